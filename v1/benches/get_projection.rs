@@ -0,0 +1,74 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// Size and deserialization time of the responses to the GETs, SELECTing only the fields of the storage's points
+// (GET_COLUMNS) or all the fields of the measurements (as with "SELECT *")
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_json::{json, Value};
+use zenoh_backend_influxdb::bench::{decode_get_response, get_columns};
+
+// number of points in a response
+const POINTS: usize = 1000;
+// number of fields not read by the storage: those of other writers, and the storage's ones not needed on GET
+// (e.g. "schema_version", "value_f")
+const EXTRA_FIELDS: usize = 8;
+
+// The response of InfluxDB to a GET on a measurement, with the columns read by the storage and `extra_fields` others
+fn response(extra_fields: usize) -> String {
+    let mut columns: Vec<String> = vec!["time".into()];
+    columns.extend(get_columns().iter().map(|c| c.to_string()));
+    columns.extend((0..extra_fields).map(|i| format!("other_{i}")));
+    let values: Vec<Value> = (0..POINTS)
+        .map(|i| {
+            let mut row = vec![json!(format!("2023-01-01T00:00:00.{i:09}Z"))];
+            row.extend(get_columns().iter().map(|c| match *c {
+                "kind" => json!("PUT"),
+                "timestamp" => json!(format!(
+                    "{}/1f2e3d4c",
+                    7_183_000_000_000_000_000_u64 + i as u64
+                )),
+                "encoding_prefix" => json!(0),
+                "encoding_suffix" => json!(""),
+                "base64" => json!(false),
+                "value" => json!(format!("{{\"temperature\": {}.5}}", i % 40)),
+                _ => Value::Null,
+            }));
+            row.extend((0..extra_fields).map(|j| json!(j as f64 * 0.5)));
+            Value::Array(row)
+        })
+        .collect();
+    json!({
+        "results": [{
+            "statement_id": 0,
+            "series": [{"name": "demo/example/a", "columns": columns, "values": values}]
+        }]
+    })
+    .to_string()
+}
+
+fn get_projection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_projection");
+    for (name, extra_fields) in [("projected", 0), ("select_all", EXTRA_FIELDS)] {
+        let body = response(extra_fields);
+        println!("{name}: {} bytes for {POINTS} points", body.len());
+        group.throughput(Throughput::Bytes(body.len() as u64));
+        group.bench_with_input(BenchmarkId::new("decode", name), &body, |b, body| {
+            b.iter(|| decode_get_response(black_box(body)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, get_projection);
+criterion_main!(benches);
@@ -0,0 +1,59 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// Time to build the point written by a PUT (the codec's encoding of the value and the write query), and to
+// serialize it in line protocol as the client does before sending it, per codec and without any InfluxDB server
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use influxdb::Query;
+use zenoh::prelude::*;
+use zenoh::time::new_reception_timestamp;
+use zenoh_backend_influxdb::bench::{put_point, PutCodec};
+
+const KEY: &str = "demo/example/a";
+
+// The values put with each codec: a JSON object (with numeric leaves for "json_flatten"), or a number
+fn values() -> Vec<(&'static str, Value)> {
+    let json = r#"{"temperature": 21.5, "position": {"x": 1.25, "y": -3.5}, "unit": "celsius"}"#;
+    vec![
+        ("string", Value::from(json)),
+        ("numeric", Value::from("21.5")),
+        (
+            "json_flatten",
+            Value::from(json).encoding(Encoding::Exact(KnownEncoding::AppJson)),
+        ),
+    ]
+}
+
+fn put_path(c: &mut Criterion) {
+    let mut group = c.benchmark_group("put_path");
+    group.throughput(Throughput::Elements(1));
+    let timestamp = new_reception_timestamp();
+    for (name, value) in values() {
+        let codec = PutCodec::new(name);
+        group.bench_with_input(BenchmarkId::new("point", name), &value, |b, value| {
+            b.iter(|| put_point(&codec, black_box(KEY), black_box(value), &timestamp))
+        });
+        group.bench_with_input(BenchmarkId::new("line", name), &value, |b, value| {
+            b.iter(|| {
+                put_point(&codec, black_box(KEY), black_box(value), &timestamp)
+                    .build()
+                    .expect("Invalid point")
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, put_path);
+criterion_main!(benches);
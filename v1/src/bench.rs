@@ -0,0 +1,83 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// Entry points to the internals of the storages, for the benchmarks of the `benches` directory only
+// (`bench` feature): not a stable API.
+
+use crate::codec::{JsonFlattenCodec, NumericCodec, StringCodec, ValueCodec};
+use crate::{
+    add_put_fields, ZenohPoint, GET_COLUMNS, NUMERIC_VALUE_FIELD, SCHEMA_VERSION,
+    SCHEMA_VERSION_FIELD,
+};
+use influxdb::integrations::serde_integration::DatabaseQueryResult;
+use influxdb::{Timestamp as InfluxTimestamp, WriteQuery};
+use zenoh::buffers::buffer::SplitBuffer;
+use zenoh::prelude::Value;
+use zenoh::time::Timestamp;
+
+// The columns SELECTed by the GETs
+pub fn get_columns() -> &'static [&'static str] {
+    GET_COLUMNS
+}
+
+// Deserialize a JSON response of InfluxDB to a GET into the storage's points (as `query_points` does),
+// returning the number of points
+pub fn decode_get_response(body: &str) -> usize {
+    let mut result: DatabaseQueryResult =
+        serde_json::from_str(body).expect("Invalid InfluxDB response");
+    let mut points = 0;
+    while !result.results.is_empty() {
+        let series = result
+            .deserialize_next::<ZenohPoint>()
+            .expect("Invalid points in InfluxDB response")
+            .series;
+        points += series.iter().map(|s| s.values.len()).sum::<usize>();
+    }
+    points
+}
+
+// A value codec (see `value_codec` property): "string", "numeric" or "json_flatten"
+pub struct PutCodec(Box<dyn ValueCodec>);
+
+impl PutCodec {
+    pub fn new(name: &str) -> PutCodec {
+        PutCodec(match name {
+            "string" => Box::new(StringCodec),
+            "numeric" => Box::new(NumericCodec),
+            "json_flatten" => Box::new(JsonFlattenCodec),
+            _ => panic!("Unknown codec {name:?}"),
+        })
+    }
+}
+
+// Build the point written by a PUT with the default properties (as `put` does once the value is accepted):
+// the codec's encoding of the value and the write query, without any InfluxDB server
+pub fn put_point(codec: &PutCodec, key: &str, value: &Value, timestamp: &Timestamp) -> WriteQuery {
+    let influx_time = timestamp.get_time().to_duration().as_nanos();
+    let encoded = codec.0.encode(value.payload.contiguous(), &value.encoding);
+    let mut query = add_put_fields(
+        WriteQuery::new(InfluxTimestamp::Nanoseconds(influx_time), key)
+            .add_field(SCHEMA_VERSION_FIELD, SCHEMA_VERSION),
+        timestamp,
+        &value.encoding,
+        encoded.base64,
+    );
+    if let Some(f) = encoded.numeric {
+        query = query.add_field(NUMERIC_VALUE_FIELD, f);
+    }
+    for (name, f) in encoded.fields {
+        query = query.add_field(name, f);
+    }
+    query.add_field("value", encoded.value)
+}
@@ -0,0 +1,596 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::InfluxDbError;
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// default size (in bytes) above which the payloads are offloaded to the blob store
+pub(crate) const DEFAULT_OFFLOAD_THRESHOLD: u64 = 1024 * 1024;
+
+// The reference of an offloaded payload, stored in its point instead of the payload
+pub(crate) struct BlobRef {
+    // the name of the blob in the store
+    pub(crate) reference: String,
+    // the SHA-256 of the payload (hex), verified when fetching it back
+    pub(crate) hash: String,
+}
+
+// The points possibly referencing an offloaded payload
+pub(crate) trait OffloadedPoint {
+    fn blob(&self) -> Option<BlobRef>;
+}
+
+// Where the payloads too large for InfluxDB are stored (see `offload` property).
+// The blobs are named after the hash of their payload, so a same payload is stored once.
+pub(crate) enum BlobStore {
+    Directory(PathBuf),
+    S3(S3Bucket),
+}
+
+impl BlobStore {
+    // Store a payload, returning its reference
+    pub(crate) async fn put(&self, payload: &[u8]) -> Result<BlobRef, InfluxDbError> {
+        let hash = to_hex(&Sha256::digest(payload));
+        // spread the blobs in sub-directories (or prefixes)
+        let reference = format!("{}/{}", &hash[..2], hash);
+        match self {
+            BlobStore::Directory(dir) => {
+                let path = dir.join(&reference);
+                if !async_std::path::Path::new(&path).exists().await {
+                    if let Some(parent) = path.parent() {
+                        async_std::fs::create_dir_all(parent).await.map_err(|e| {
+                            influx_error!(
+                                Capacity,
+                                "Failed to create directory {:?}: {}",
+                                parent,
+                                e
+                            )
+                        })?;
+                    }
+                    // write a temporary file first, so a blob is never partially written
+                    let tmp = path.with_extension("tmp");
+                    async_std::fs::write(&tmp, payload).await.map_err(|e| {
+                        influx_error!(Capacity, "Failed to write blob {:?}: {}", tmp, e)
+                    })?;
+                    async_std::fs::rename(&tmp, &path).await.map_err(|e| {
+                        influx_error!(Capacity, "Failed to write blob {:?}: {}", path, e)
+                    })?;
+                }
+            }
+            BlobStore::S3(bucket) => bucket.put_object(&reference, payload, &hash).await?,
+        }
+        Ok(BlobRef { reference, hash })
+    }
+
+    // Fetch an offloaded payload, checking its hash
+    pub(crate) async fn get(&self, blob: &BlobRef) -> Result<Vec<u8>, InfluxDbError> {
+        // the reference is read from InfluxDB: never follow one that isn't a blob name (e.g. "../..")
+        if !is_blob_reference(&blob.reference) {
+            fail!(Serialization, "Invalid blob reference {:?}", blob.reference)
+        }
+        let payload = match self {
+            BlobStore::Directory(dir) => async_std::fs::read(dir.join(&blob.reference))
+                .await
+                .map_err(|e| {
+                    influx_error!(Capacity, "Failed to read blob {}: {}", blob.reference, e)
+                })?,
+            BlobStore::S3(bucket) => bucket.get_object(&blob.reference).await?,
+        };
+        if to_hex(&Sha256::digest(&payload)) != blob.hash {
+            fail!(
+                Serialization,
+                "Corrupted blob {}: hash mismatch",
+                blob.reference
+            )
+        }
+        Ok(payload)
+    }
+
+    // The names of the blobs in the store, with their last modification time
+    pub(crate) async fn list(&self) -> Result<Vec<(String, SystemTime)>, InfluxDbError> {
+        match self {
+            BlobStore::Directory(dir) => list_files(dir).await,
+            BlobStore::S3(bucket) => bucket.list_objects().await,
+        }
+    }
+
+    // Remove a blob (nothing if it doesn't exist)
+    pub(crate) async fn remove(&self, reference: &str) -> Result<(), InfluxDbError> {
+        if !is_blob_reference(reference) {
+            fail!(Serialization, "Invalid blob reference {:?}", reference)
+        }
+        match self {
+            BlobStore::Directory(dir) => {
+                match async_std::fs::remove_file(dir.join(reference)).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        fail!(Capacity, "Failed to remove blob {}: {}", reference, e)
+                    }
+                    _ => Ok(()),
+                }
+            }
+            BlobStore::S3(bucket) => bucket.delete_object(reference).await,
+        }
+    }
+}
+
+// The blobs of a directory store: the files of its sub-directories named after the hashes
+async fn list_files(dir: &Path) -> Result<Vec<(String, SystemTime)>, InfluxDbError> {
+    let list_error =
+        |e: std::io::Error| influx_error!(Capacity, "Failed to list blobs in {:?}: {}", dir, e);
+    let mut blobs = Vec::new();
+    let mut prefixes = match async_std::fs::read_dir(dir).await {
+        Ok(prefixes) => prefixes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(blobs),
+        Err(e) => return Err(list_error(e)),
+    };
+    while let Some(prefix) = prefixes.next().await {
+        let prefix = prefix.map_err(list_error)?;
+        if !prefix.file_type().await.map_err(list_error)?.is_dir() {
+            continue;
+        }
+        let mut files = async_std::fs::read_dir(prefix.path())
+            .await
+            .map_err(list_error)?;
+        while let Some(file) = files.next().await {
+            let file = file.map_err(list_error)?;
+            let reference = format!(
+                "{}/{}",
+                prefix.file_name().to_string_lossy(),
+                file.file_name().to_string_lossy()
+            );
+            // (not the temporary files of the blobs being written)
+            if is_blob_reference(&reference) {
+                let modified = file
+                    .metadata()
+                    .await
+                    .and_then(|m| m.modified())
+                    .map_err(list_error)?;
+                blobs.push((reference, modified));
+            }
+        }
+    }
+    Ok(blobs)
+}
+
+// A bucket of an S3-compatible object store (AWS S3, MinIO...), accessed with path-style URLs
+// and AWS Signature Version 4 authentication.
+pub(crate) struct S3Bucket {
+    endpoint: surf::Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+// the SHA-256 of an empty payload, for the GET requests
+const EMPTY_PAYLOAD_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+impl S3Bucket {
+    pub(crate) fn new(
+        endpoint: surf::Url,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    ) -> S3Bucket {
+        S3Bucket {
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+        }
+    }
+
+    async fn put_object(&self, key: &str, payload: &[u8], hash: &str) -> Result<(), InfluxDbError> {
+        let (url, headers) = self.signed_request("PUT", key, &[], hash)?;
+        let mut request = surf::put(url).body(surf::Body::from_bytes(payload.to_vec()));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request
+            .await
+            .map_err(|e| influx_error!(Connection, "Failed to put blob {}: {}", key, e))?;
+        check_status(&response, key)
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, InfluxDbError> {
+        let (url, headers) = self.signed_request("GET", key, &[], EMPTY_PAYLOAD_HASH)?;
+        let mut request = surf::get(url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let mut response = request
+            .await
+            .map_err(|e| influx_error!(Connection, "Failed to get blob {}: {}", key, e))?;
+        check_status(&response, key)?;
+        response
+            .body_bytes()
+            .await
+            .map_err(|e| influx_error!(Connection, "Failed to get blob {}: {}", key, e))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), InfluxDbError> {
+        let (url, headers) = self.signed_request("DELETE", key, &[], EMPTY_PAYLOAD_HASH)?;
+        let mut request = surf::delete(url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request
+            .await
+            .map_err(|e| influx_error!(Connection, "Failed to remove blob {}: {}", key, e))?;
+        check_status(&response, key)
+    }
+
+    // The objects of the bucket named like blobs, with their last modification time (ListObjectsV2, by pages)
+    async fn list_objects(&self) -> Result<Vec<(String, SystemTime)>, InfluxDbError> {
+        let mut objects = Vec::new();
+        let mut continuation = None;
+        loop {
+            let mut params = vec![("list-type", "2".to_string())];
+            if let Some(token) = continuation.take() {
+                params.push(("continuation-token", token));
+            }
+            let (url, headers) = self.signed_request("GET", "", &params, EMPTY_PAYLOAD_HASH)?;
+            let mut request = surf::get(url);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            let mut response = request.await.map_err(|e| {
+                influx_error!(Connection, "Failed to list blobs of {}: {}", self.bucket, e)
+            })?;
+            check_status(&response, &self.bucket)?;
+            let body = response.body_string().await.map_err(|e| {
+                influx_error!(Connection, "Failed to list blobs of {}: {}", self.bucket, e)
+            })?;
+            for object in xml_elements(&body, "Contents") {
+                let key = xml_elements(object, "Key");
+                let modified = xml_elements(object, "LastModified");
+                if let (Some(key), Some(modified)) = (key.first(), modified.first()) {
+                    if is_blob_reference(key) {
+                        // (an unparsable time is taken as a recent one: the blob is kept)
+                        let modified = humantime::parse_rfc3339(modified)
+                            .unwrap_or_else(|_| SystemTime::now());
+                        objects.push((key.to_string(), modified));
+                    }
+                }
+            }
+            match (
+                xml_elements(&body, "IsTruncated").first(),
+                xml_elements(&body, "NextContinuationToken").first(),
+            ) {
+                (Some(&"true"), Some(token)) => continuation = Some(token.to_string()),
+                _ => return Ok(objects),
+            }
+        }
+    }
+
+    // The URL of an object (or of the bucket if `key` is empty) with some query parameters,
+    // and the headers signing a request on it (AWS Signature Version 4)
+    fn signed_request(
+        &self,
+        method: &str,
+        key: &str,
+        params: &[(&str, String)],
+        payload_hash: &str,
+    ) -> Result<(surf::Url, Vec<(&'static str, String)>), InfluxDbError> {
+        let mut path = format!(
+            "{}/{}",
+            self.endpoint.path().trim_end_matches('/'),
+            self.bucket
+        );
+        if !key.is_empty() {
+            path = format!("{path}/{key}");
+        }
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        // the canonical query string (sorted and encoded), also used as the URL's one
+        let mut params: Vec<String> = params
+            .iter()
+            .map(|(name, value)| format!("{}={}", uri_encode(name), uri_encode(value)))
+            .collect();
+        params.sort();
+        let query = params.join("&");
+        url.set_query(if query.is_empty() { None } else { Some(&query) });
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => fail!(Connection, "Invalid S3 endpoint {}", self.endpoint),
+        };
+
+        // e.g. "20240214T002807Z"
+        let datetime = humantime::format_rfc3339_seconds(SystemTime::now())
+            .to_string()
+            .replace(['-', ':'], "");
+        let scope = format!("{}/{}/s3/aws4_request", &datetime[..8], self.region);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let signature = sign_v4(
+            &self.secret_key,
+            &self.region,
+            "s3",
+            &datetime,
+            method,
+            url.path(),
+            &query,
+            &[
+                ("host", &host),
+                ("x-amz-content-sha256", payload_hash),
+                ("x-amz-date", &datetime),
+            ],
+            payload_hash,
+        );
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.access_key
+        );
+        Ok((
+            url,
+            vec![
+                ("x-amz-content-sha256", payload_hash.to_string()),
+                ("x-amz-date", datetime),
+                ("Authorization", authorization),
+            ],
+        ))
+    }
+}
+
+fn check_status(response: &surf::Response, key: &str) -> Result<(), InfluxDbError> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else if status == surf::StatusCode::Unauthorized || status == surf::StatusCode::Forbidden {
+        fail!(
+            Auth,
+            "Access to blob {} refused: HTTP status {}",
+            key,
+            status
+        )
+    } else {
+        fail!(
+            Connection,
+            "Failed to access blob {}: HTTP status {}",
+            key,
+            status
+        )
+    }
+}
+
+// The AWS Signature Version 4 (hex) of a request, signing the given headers (lowercase names, sorted by name)
+#[allow(clippy::too_many_arguments)]
+fn sign_v4(
+    secret_key: &str,
+    region: &str,
+    service: &str,
+    datetime: &str,
+    method: &str,
+    path: &str,
+    query: &str,
+    headers: &[(&str, &str)],
+    payload_hash: &str,
+) -> String {
+    let date = &datetime[..8];
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request =
+        format!("{method}\n{path}\n{query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{datetime}\n{date}/{region}/{service}/aws4_request\n{}",
+        to_hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let signing_key = signing_key(secret_key, date, region, service);
+    to_hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()))
+}
+
+// The key deriving the signatures of a day, region and service
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> [u8; 32] {
+    let mut key = hmac_sha256(format!("AWS4{secret_key}").as_bytes(), date.as_bytes());
+    for part in [region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    key
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+// If a reference has the "xx/<sha256-hex>" shape of the blob names, "xx" being the first 2 digits of the hash
+fn is_blob_reference(reference: &str) -> bool {
+    match reference.split_once('/') {
+        Some((prefix, hash)) => {
+            prefix.len() == 2
+                && hash.starts_with(prefix)
+                && hash.len() == 64
+                && hash
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        }
+        None => false,
+    }
+}
+
+// The URI encoding of the AWS signatures: all the bytes but the unreserved characters are percent-encoded
+fn uri_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+// The contents of the elements with a tag in an XML document (enough for the S3 responses, whose elements
+// of a same tag are never nested)
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{tag}>"), format!("</{tag}>"));
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        match rest.find(&close) {
+            Some(end) => {
+                elements.push(&rest[..end]);
+                rest = &rest[end + close.len()..];
+            }
+            None => break,
+        }
+    }
+    elements
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blob_references() {
+        let hash = to_hex(&Sha256::digest(b"payload"));
+        assert!(is_blob_reference(&format!("{}/{}", &hash[..2], hash)));
+        assert!(!is_blob_reference(&hash));
+        assert!(!is_blob_reference(&format!("00/{}", hash)));
+        assert!(!is_blob_reference(&format!(
+            "{}/{}",
+            &hash[..2],
+            &hash[..63]
+        )));
+        assert!(!is_blob_reference(&format!(
+            "{}/{}",
+            &hash[..2],
+            hash.to_uppercase()
+        )));
+        assert!(!is_blob_reference("../../etc/passwd"));
+        assert!(!is_blob_reference(&format!("{}/../{}", &hash[..2], hash)));
+    }
+
+    // https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv-create-signed-request.html
+    #[test]
+    fn aws_signing_key() {
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            to_hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    // the "GET Object" example of https://docs.aws.amazon.com/AmazonS3/latest/API/sig-v4-header-based-auth.html
+    #[test]
+    fn aws_s3_get_object_signature() {
+        let signature = sign_v4(
+            "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+            "us-east-1",
+            "s3",
+            "20130524T000000Z",
+            "GET",
+            "/test.txt",
+            "",
+            &[
+                ("host", "examplebucket.s3.amazonaws.com"),
+                ("range", "bytes=0-9"),
+                ("x-amz-content-sha256", EMPTY_PAYLOAD_HASH),
+                ("x-amz-date", "20130524T000000Z"),
+            ],
+            EMPTY_PAYLOAD_HASH,
+        );
+        assert_eq!(
+            signature,
+            "f0e8bdb87c964420e857bd35b5d6ed310bd44f0170aba48dd91039c6036bdb41"
+        );
+    }
+
+    // the "GET Bucket (List Objects)" example of the same page
+    #[test]
+    fn aws_s3_list_objects_signature() {
+        let signature = sign_v4(
+            "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+            "us-east-1",
+            "s3",
+            "20130524T000000Z",
+            "GET",
+            "/",
+            "max-keys=2&prefix=J",
+            &[
+                ("host", "examplebucket.s3.amazonaws.com"),
+                ("x-amz-content-sha256", EMPTY_PAYLOAD_HASH),
+                ("x-amz-date", "20130524T000000Z"),
+            ],
+            EMPTY_PAYLOAD_HASH,
+        );
+        assert_eq!(
+            signature,
+            "34b48302e7b5fa45bde8084f4b7868a86f0a534bc59db6670ed5711ef69dc6f7"
+        );
+    }
+
+    #[test]
+    fn list_objects_response() {
+        let xml = "<ListBucketResult><IsTruncated>true</IsTruncated>\
+            <Contents><Key>ab/abc</Key><LastModified>2009-10-12T17:50:30.000Z</LastModified></Contents>\
+            <Contents><Key>cd/cde</Key><LastModified>2009-10-12T17:50:31.000Z</LastModified></Contents>\
+            <NextContinuationToken>1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=</NextContinuationToken>\
+            </ListBucketResult>";
+        let contents = xml_elements(xml, "Contents");
+        assert_eq!(contents.len(), 2);
+        assert_eq!(xml_elements(contents[1], "Key"), vec!["cd/cde"]);
+        assert_eq!(xml_elements(xml, "IsTruncated"), vec!["true"]);
+        assert_eq!(
+            uri_encode(xml_elements(xml, "NextContinuationToken")[0]),
+            "1ueGcxLPRx1Tr%2FXYExHnhbYLgveDs2J%2Fwm36Hy4vbOwM%3D"
+        );
+        assert!(humantime::parse_rfc3339("2009-10-12T17:50:30.000Z").is_ok());
+    }
+
+    #[test]
+    fn directory_blobs() {
+        let dir = std::env::temp_dir().join(format!("zenoh-blobs-{}", uuid::Uuid::new_v4()));
+        let store = BlobStore::Directory(dir.clone());
+        async_std::task::block_on(async {
+            assert!(store.list().await.unwrap().is_empty());
+            let blob = store.put(b"payload").await.unwrap();
+            std::fs::write(dir.join(&blob.reference).with_extension("tmp"), b"partial").unwrap();
+            let blobs = store.list().await.unwrap();
+            assert_eq!(blobs.len(), 1);
+            assert_eq!(blobs[0].0, blob.reference);
+            store.remove(&blob.reference).await.unwrap();
+            store.remove(&blob.reference).await.unwrap();
+            assert!(store.list().await.unwrap().is_empty());
+            assert!(store.remove("../outside").await.is_err());
+        });
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
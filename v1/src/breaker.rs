@@ -0,0 +1,137 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::events::Events;
+use log::{info, warn};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zenoh::Result as ZResult;
+
+// A circuit breaker on the queries to InfluxDB: once the error rate over the last queries reaches the threshold,
+// the queries fail fast (open) during the cool-down. Then a single trial query is let through (half-open):
+// its success closes the breaker, its failure re-opens it.
+pub(crate) struct CircuitBreaker {
+    window: usize,
+    error_rate: f64,
+    cool_down: Duration,
+    state: Mutex<BreakerState>,
+    // if set, the state changes are published as "circuit_breaker" events
+    events: Option<Events>,
+}
+
+enum State {
+    Closed,
+    Open(Instant),
+    HalfOpen,
+}
+
+struct BreakerState {
+    state: State,
+    // outcomes of the last queries while closed (true for a failure)
+    outcomes: VecDeque<bool>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(window: usize, error_rate: f64, cool_down: Duration) -> CircuitBreaker {
+        CircuitBreaker {
+            window: window.max(1),
+            error_rate,
+            cool_down,
+            state: Mutex::new(BreakerState {
+                state: State::Closed,
+                outcomes: VecDeque::new(),
+            }),
+            events: None,
+        }
+    }
+
+    pub(crate) fn with_events(mut self, events: Option<Events>) -> CircuitBreaker {
+        self.events = events;
+        self
+    }
+
+    // Check if a query can be run, failing fast if the breaker is open
+    pub(crate) fn check(&self) -> ZResult<()> {
+        let mut s = self.state.lock().unwrap();
+        match s.state {
+            State::Closed => Ok(()),
+            State::Open(since) if since.elapsed() >= self.cool_down => {
+                s.state = State::HalfOpen;
+                self.emit("half-open");
+                Ok(())
+            }
+            State::Open(since) => fail!(
+                Capacity,
+                "InfluxDB queries are failing: circuit breaker open for {:?} more",
+                self.cool_down.saturating_sub(since.elapsed())
+            ),
+            State::HalfOpen => fail!(
+                Capacity,
+                "InfluxDB queries are failing: circuit breaker half-open, waiting for a trial query"
+            ),
+        }
+    }
+
+    // Record the outcome of a query allowed by check()
+    pub(crate) fn record(&self, failed: bool) {
+        let mut s = self.state.lock().unwrap();
+        match s.state {
+            State::HalfOpen if failed => {
+                warn!("InfluxDB trial query failed: circuit breaker re-opened");
+                s.state = State::Open(Instant::now());
+                self.emit("open");
+            }
+            State::HalfOpen => {
+                info!("InfluxDB trial query succeeded: circuit breaker closed");
+                s.state = State::Closed;
+                s.outcomes.clear();
+                self.emit("closed");
+            }
+            State::Closed => {
+                s.outcomes.push_back(failed);
+                if s.outcomes.len() > self.window {
+                    s.outcomes.pop_front();
+                }
+                let failures = s.outcomes.iter().filter(|f| **f).count();
+                if s.outcomes.len() == self.window
+                    && failures as f64 >= self.error_rate * self.window as f64
+                {
+                    warn!(
+                        "{} of the last {} InfluxDB queries failed: circuit breaker opened for {:?}",
+                        failures, self.window, self.cool_down
+                    );
+                    s.state = State::Open(Instant::now());
+                    self.emit("open");
+                }
+            }
+            // a query allowed before the opening
+            State::Open(_) => {}
+        }
+    }
+
+    fn emit(&self, state: &str) {
+        if let Some(events) = &self.events {
+            events.emit("circuit_breaker", serde_json::json!({ "state": state }));
+        }
+    }
+
+    pub(crate) fn state_name(&self) -> &'static str {
+        match self.state.lock().unwrap().state {
+            State::Closed => "closed",
+            State::Open(_) => "open",
+            State::HalfOpen => "half-open",
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::collections::{BTreeMap, HashMap};
+use zenoh::prelude::OwnedKeyExpr;
+use zenoh_backend_traits::StoredData;
+
+// A LRU cache of the latest value of the most recently used keys
+pub(crate) struct LatestCache {
+    capacity: usize,
+    // incremented at each use of an entry
+    tick: u64,
+    entries: HashMap<Option<OwnedKeyExpr>, (StoredData, u64)>,
+    // the keys by last use tick (the first one is the least recently used)
+    lru: BTreeMap<u64, Option<OwnedKeyExpr>>,
+}
+
+impl LatestCache {
+    pub(crate) fn new(capacity: usize) -> LatestCache {
+        LatestCache {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            lru: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn get(&mut self, key: &Option<OwnedKeyExpr>) -> Option<StoredData> {
+        let tick = self.next_tick();
+        let (data, last_use) = self.entries.get_mut(key)?;
+        self.lru.remove(last_use);
+        *last_use = tick;
+        self.lru.insert(tick, key.clone());
+        Some(data.clone())
+    }
+
+    // Cache the value of a key, unless a more recent one is already cached
+    pub(crate) fn put(&mut self, key: Option<OwnedKeyExpr>, data: StoredData) {
+        let tick = self.next_tick();
+        if let Some((cached, last_use)) = self.entries.get_mut(&key) {
+            if cached.timestamp < data.timestamp {
+                *cached = data;
+            }
+            self.lru.remove(last_use);
+            *last_use = tick;
+            self.lru.insert(tick, key);
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.lru.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.lru.insert(tick, key.clone());
+        self.entries.insert(key, (data, tick));
+    }
+
+    pub(crate) fn remove(&mut self, key: &Option<OwnedKeyExpr>) {
+        if let Some((_, last_use)) = self.entries.remove(key) {
+            self.lru.remove(&last_use);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.lru.clear();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
@@ -0,0 +1,222 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::events::open_session;
+use crate::{decode_row, InfluxDbError, KEY_TAG};
+use influxdb::{Client, ReadQuery};
+use log::{debug, warn};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zenoh::buffers::buffer::SplitBuffer;
+use zenoh::prelude::{OwnedKeyExpr, Value};
+use zenoh::Result as ZResult;
+use zenoh_core::AsyncResolve;
+
+// default interval between 2 polls of the new points
+pub(crate) const DEFAULT_CHANGE_FEED_INTERVAL: Duration = Duration::from_secs(5);
+// max number of points per serie read at each poll
+const CHANGE_FEED_BATCH_SIZE: usize = 1000;
+// max number of republished values not yet received back by the storage
+const MAX_ECHOES: usize = 100_000;
+
+// The configuration of the change feed (`change_feed` property)
+pub(crate) struct ChangeFeedConfig {
+    pub(crate) interval: Duration,
+    // the zenoh endpoints to connect to (by default, the session scouts for the routers)
+    pub(crate) connect: Vec<String>,
+    // the prefix of the republished keys
+    pub(crate) key_prefix: Option<OwnedKeyExpr>,
+}
+
+// The selection of the points to republish
+pub(crate) struct ChangeFeedSource {
+    pub(crate) client: Client,
+    // the FROM clause: all the measurements or the single measurement of the storage
+    pub(crate) from: String,
+    // if true, the key of the points is their "key" tag (single measurement layout), else their measurement
+    pub(crate) key_from_tag: bool,
+    // the field of the value of the points
+    pub(crate) value_field: String,
+}
+
+// A change feed republishing in zenoh the points written directly in InfluxDB by other writers than zenoh (without
+// "kind" tag), by polling the points more recent than a high-water mark. The polling stops once it's dropped.
+pub(crate) struct ChangeFeed {
+    running: Arc<AtomicBool>,
+    // the keys and payload hashes of the republished values, to ignore them when received back by the storage
+    echoes: Arc<Mutex<HashSet<(OwnedKeyExpr, u64)>>>,
+    published: Arc<AtomicU64>,
+}
+
+impl ChangeFeed {
+    pub(crate) async fn start(
+        source: ChangeFeedSource,
+        config: ChangeFeedConfig,
+    ) -> ZResult<ChangeFeed> {
+        let session = open_session(&config.connect).await?;
+        let feed = ChangeFeed {
+            running: Arc::new(AtomicBool::new(true)),
+            echoes: Arc::new(Mutex::new(HashSet::new())),
+            published: Arc::new(AtomicU64::new(0)),
+        };
+        let running = feed.running.clone();
+        let echoes = feed.echoes.clone();
+        let published = feed.published.clone();
+        async_std::task::spawn(async move {
+            // only the points written from now are republished
+            let mut high_water_mark = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos();
+            while running.load(Ordering::Relaxed) {
+                async_std::task::sleep(config.interval).await;
+                let points = match poll(&source, high_water_mark).await {
+                    Ok((points, hwm)) => {
+                        high_water_mark = hwm;
+                        points
+                    }
+                    Err(e) => {
+                        warn!("Failed to poll the change feed: {}", e);
+                        continue;
+                    }
+                };
+                for (key, value) in points {
+                    let republished = match &config.key_prefix {
+                        Some(prefix) => prefix.join(&key),
+                        None => Ok(key.clone()),
+                    };
+                    let republished = match republished {
+                        Ok(k) => k,
+                        Err(e) => {
+                            warn!("Failed to republish the change feed of {}: {}", key, e);
+                            continue;
+                        }
+                    };
+                    {
+                        let mut echoes = echoes.lock().unwrap();
+                        if echoes.len() >= MAX_ECHOES {
+                            echoes.clear();
+                        }
+                        echoes.insert((key, payload_hash(&value.payload.contiguous())));
+                    }
+                    debug!("Republish change feed value of {}", republished);
+                    match session.put(&republished, value).res_async().await {
+                        Ok(()) => {
+                            published.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(e) => warn!(
+                            "Failed to republish the change feed of {}: {}",
+                            republished, e
+                        ),
+                    }
+                }
+            }
+        });
+        Ok(feed)
+    }
+
+    // If the value was republished by the change feed, forget it and return true: the storage must ignore it
+    pub(crate) fn is_echo(&self, key: &OwnedKeyExpr, payload: &[u8]) -> bool {
+        self.echoes
+            .lock()
+            .unwrap()
+            .remove(&(key.clone(), payload_hash(payload)))
+    }
+
+    // The number of values republished
+    pub(crate) fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for ChangeFeed {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+fn payload_hash(payload: &[u8]) -> u64 {
+    xxhash_rust::xxh64::xxh64(payload, 0)
+}
+
+// Read the points without "kind" tag more recent than the high-water mark (in ns), returning their keys and values
+// with the new high-water mark. If a serie has more points than the batch size, only the points older than its last
+// read one are returned, and the others are read by the next poll.
+async fn poll(
+    source: &ChangeFeedSource,
+    high_water_mark: u128,
+) -> Result<(Vec<(OwnedKeyExpr, Value)>, u128), InfluxDbError> {
+    let query = ReadQuery::new(format!(
+        r#"SELECT * FROM {} WHERE "kind"='' AND time > {high_water_mark} GROUP BY * ORDER BY time ASC LIMIT {CHANGE_FEED_BATCH_SIZE}"#,
+        source.from
+    ));
+    debug!("Poll the change feed with Influx query: {:?}", query);
+    let series = match source.client.json_query(query).await {
+        Ok(mut result) => match result.deserialize_next_tagged::<
+            HashMap<String, String>,
+            serde_json::Map<String, serde_json::Value>,
+        >() {
+            Ok(qr) => qr.series,
+            Err(e) => fail_client!(e, "Failed to parse the change feed points"),
+        },
+        Err(e) => fail_client!(e, "Failed to read the change feed points"),
+    };
+
+    let time_of = |row: &serde_json::Map<String, serde_json::Value>| {
+        row.get("time")
+            .and_then(|t| t.as_str())
+            .and_then(|t| humantime::parse_rfc3339(t).ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|t| t.as_nanos())
+    };
+    // the new high-water mark: the time of the latest point read, or of the earliest last point of a full serie
+    let full_series_end = series
+        .iter()
+        .filter(|s| s.values.len() >= CHANGE_FEED_BATCH_SIZE)
+        .filter_map(|s| s.values.last().and_then(time_of))
+        .min();
+    let latest = series
+        .iter()
+        .filter_map(|s| s.values.last().and_then(time_of))
+        .max();
+    let new_high_water_mark = full_series_end.or(latest).unwrap_or(high_water_mark);
+
+    let mut points = Vec::new();
+    for serie in series {
+        let key = match source.key_from_tag {
+            true => serie.tags.get(KEY_TAG).cloned().unwrap_or_default(),
+            false => serie.name.clone(),
+        };
+        let key = match key.parse::<OwnedKeyExpr>() {
+            Ok(key) => key,
+            Err(e) => {
+                debug!("Skip change feed of serie {}: {}", key, e);
+                continue;
+            }
+        };
+        for row in serie.values {
+            if time_of(&row).map_or(true, |t| t > new_high_water_mark) {
+                continue;
+            }
+            match decode_row(&serie.name, row, Some(&source.value_field), false) {
+                Ok(Some(data)) => points.push((key.clone(), data.value)),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to decode change feed point of {}: {}", key, e),
+            }
+        }
+    }
+    Ok((points, new_high_water_mark))
+}
@@ -0,0 +1,273 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// default max length (in bytes) of the "value" field of a point, InfluxDB rejecting the larger strings (~64KB)
+pub(crate) const DEFAULT_CHUNK_SIZE: u64 = 60 * 1024;
+
+// The points of the values that might be split in chunks: the chunks of a value are points with
+// the same zenoh timestamp, each with the index of the chunk and the total number of chunks.
+pub(crate) trait ChunkedPoint {
+    // the chunk index and count of the point, if it's a chunk of a larger value
+    fn chunk(&self) -> Option<(usize, usize)>;
+    // the zenoh timestamp of the point, shared by all the chunks of a value
+    fn zenoh_timestamp(&self) -> &str;
+    fn take_value(&mut self) -> String;
+    fn set_value(&mut self, value: String);
+}
+
+pub(crate) enum Reassembled<T> {
+    Point(T),
+    // a value whose chunks are not all in the points (e.g. cut by a LIMIT), with its zenoh timestamp
+    Incomplete(String),
+}
+
+// Split a value in chunks of at most `size` bytes, on UTF-8 characters boundaries
+pub(crate) fn split_value(value: &str, size: usize) -> Vec<&str> {
+    let mut chunks = Vec::with_capacity(value.len() / size.max(1) + 1);
+    let mut rest = value;
+    while rest.len() > size {
+        let mut end = size;
+        while end > 0 && !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        // a chunk size smaller than a character: keep the whole character
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    // (no empty last chunk when the value ends with a character larger than the chunk size)
+    if !rest.is_empty() || chunks.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+// A value being reassembled: its first chunk, its number of chunks and its (index, chunk) received
+type PendingValue<T> = (T, usize, Vec<(usize, String)>);
+
+// Reassemble the chunks of the values, in the position of their first chunk.
+// The points that are not chunks are returned unchanged.
+pub(crate) fn reassemble<T: ChunkedPoint>(points: Vec<T>) -> Vec<Reassembled<T>> {
+    let mut values: Vec<PendingValue<T>> = Vec::new();
+    // per point: its index in `values` if it's the first chunk of a value, None if it's a following chunk
+    let mut order: Vec<Result<T, Option<usize>>> = Vec::with_capacity(points.len());
+    for mut point in points {
+        let (index, count) = match point.chunk() {
+            Some(c) => c,
+            None => {
+                order.push(Ok(point));
+                continue;
+            }
+        };
+        let chunk = point.take_value();
+        match values
+            .iter_mut()
+            .find(|(p, _, _)| p.zenoh_timestamp() == point.zenoh_timestamp())
+        {
+            Some((_, _, chunks)) => {
+                chunks.push((index, chunk));
+                order.push(Err(None));
+            }
+            None => {
+                values.push((point, count, vec![(index, chunk)]));
+                order.push(Err(Some(values.len() - 1)));
+            }
+        }
+    }
+
+    let mut values = values.into_iter().map(Some).collect::<Vec<_>>();
+    let mut result = Vec::with_capacity(order.len());
+    for entry in order {
+        match entry {
+            Ok(point) => result.push(Reassembled::Point(point)),
+            Err(None) => {}
+            Err(Some(i)) => {
+                if let Some((mut point, count, mut chunks)) = values[i].take() {
+                    chunks.sort_by_key(|(index, _)| *index);
+                    chunks.dedup_by_key(|(index, _)| *index);
+                    let complete = chunks.len() == count
+                        && chunks.iter().enumerate().all(|(i, (index, _))| i == *index);
+                    if complete {
+                        point.set_value(chunks.into_iter().map(|(_, chunk)| chunk).collect());
+                        result.push(Reassembled::Point(point));
+                    } else {
+                        result.push(Reassembled::Incomplete(point.zenoh_timestamp().into()));
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Point {
+        timestamp: &'static str,
+        chunk: Option<(usize, usize)>,
+        value: String,
+    }
+
+    impl ChunkedPoint for Point {
+        fn chunk(&self) -> Option<(usize, usize)> {
+            self.chunk
+        }
+        fn zenoh_timestamp(&self) -> &str {
+            self.timestamp
+        }
+        fn take_value(&mut self) -> String {
+            std::mem::take(&mut self.value)
+        }
+        fn set_value(&mut self, value: String) {
+            self.value = value;
+        }
+    }
+
+    fn point(timestamp: &'static str, value: &str) -> Point {
+        Point {
+            timestamp,
+            chunk: None,
+            value: value.into(),
+        }
+    }
+
+    fn chunk(timestamp: &'static str, index: usize, count: usize, value: &str) -> Point {
+        Point {
+            timestamp,
+            chunk: Some((index, count)),
+            value: value.into(),
+        }
+    }
+
+    // The reassembled points as (timestamp, value), with no value for the incomplete ones
+    fn reassembled(points: Vec<Point>) -> Vec<(String, Option<String>)> {
+        reassemble(points)
+            .into_iter()
+            .map(|r| match r {
+                Reassembled::Point(p) => (p.timestamp.to_string(), Some(p.value)),
+                Reassembled::Incomplete(timestamp) => (timestamp, None),
+            })
+            .collect()
+    }
+
+    fn expected(points: &[(&str, Option<&str>)]) -> Vec<(String, Option<String>)> {
+        points
+            .iter()
+            .map(|(t, v)| (t.to_string(), v.map(String::from)))
+            .collect()
+    }
+
+    #[test]
+    fn split_ascii() {
+        assert_eq!(split_value("", 4), vec![""]);
+        assert_eq!(split_value("abc", 4), vec!["abc"]);
+        assert_eq!(split_value("abcd", 4), vec!["abcd"]);
+        assert_eq!(split_value("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(split_value("abcdefgh", 4), vec!["abcd", "efgh"]);
+        assert_eq!(split_value("abc", 1), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn split_on_char_boundaries() {
+        // 'é' is 2 bytes, '日' is 3 bytes
+        assert_eq!(split_value("aééé", 4), vec!["aé", "éé"]);
+        assert_eq!(split_value("日本語", 4), vec!["日", "本", "語"]);
+        assert_eq!(split_value("日本語", 6), vec!["日本", "語"]);
+        // a chunk size smaller than a character keeps the whole character
+        assert_eq!(split_value("日本", 1), vec!["日", "本"]);
+        assert_eq!(split_value("a日", 2), vec!["a", "日"]);
+    }
+
+    #[test]
+    fn split_and_join() {
+        let value: String = "zenoh-é-日本-🦀-".repeat(50);
+        for size in 1..40 {
+            let chunks = split_value(&value, size);
+            assert_eq!(chunks.concat(), value);
+            for chunk in &chunks {
+                assert!(!chunk.is_empty());
+                // (only a single character can exceed the size)
+                assert!(chunk.len() <= size || chunk.chars().count() == 1);
+            }
+        }
+    }
+
+    #[test]
+    fn reassemble_unchunked() {
+        assert_eq!(
+            reassembled(vec![point("1", "a"), point("2", "b")]),
+            expected(&[("1", Some("a")), ("2", Some("b"))])
+        );
+    }
+
+    #[test]
+    fn reassemble_chunks() {
+        assert_eq!(
+            reassembled(vec![
+                point("1", "a"),
+                chunk("2", 0, 3, "ab"),
+                chunk("2", 1, 3, "cd"),
+                chunk("2", 2, 3, "e"),
+                point("3", "f"),
+            ]),
+            expected(&[("1", Some("a")), ("2", Some("abcde")), ("3", Some("f"))])
+        );
+    }
+
+    #[test]
+    fn reassemble_unordered_chunks() {
+        // the chunks are sorted by index, the value being in the position of its first received chunk
+        assert_eq!(
+            reassembled(vec![
+                chunk("2", 2, 3, "e"),
+                point("1", "a"),
+                chunk("3", 1, 2, "h"),
+                chunk("2", 0, 3, "ab"),
+                chunk("3", 0, 2, "g"),
+                chunk("2", 1, 3, "cd"),
+            ]),
+            expected(&[("2", Some("abcde")), ("1", Some("a")), ("3", Some("gh"))])
+        );
+    }
+
+    #[test]
+    fn reassemble_duplicated_chunks() {
+        assert_eq!(
+            reassembled(vec![
+                chunk("2", 0, 2, "ab"),
+                chunk("2", 1, 2, "cd"),
+                chunk("2", 1, 2, "cd"),
+            ]),
+            expected(&[("2", Some("abcd"))])
+        );
+    }
+
+    #[test]
+    fn reassemble_incomplete() {
+        assert_eq!(
+            reassembled(vec![
+                chunk("2", 0, 3, "ab"),
+                chunk("2", 2, 3, "e"),
+                point("3", "f"),
+                chunk("4", 1, 2, "h"),
+            ]),
+            expected(&[("2", None), ("3", Some("f")), ("4", None)])
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use async_trait::async_trait;
+use influxdb::integrations::serde_integration::DatabaseQueryResult;
+use influxdb::{Client, Error, ReadQuery, WriteQuery};
+
+// The InfluxDB operations run by a storage on the points of its databases, implemented by
+// `influxdb::Client` (and by the in-memory mock of the unit tests)
+#[async_trait]
+pub(crate) trait InfluxClient: Send + Sync {
+    fn database_name(&self) -> &str;
+
+    // Run a query, returning the raw response
+    async fn query(&self, query: &ReadQuery) -> Result<String, Error>;
+
+    // Write the points in a single request
+    // (a Vec: the client only writes the batches of points from a Vec, and they must not be copied)
+    #[allow(clippy::ptr_arg)]
+    async fn write(&self, points: &Vec<WriteQuery>) -> Result<String, Error>;
+
+    // Run a query, returning its results to deserialize
+    async fn json_query(&self, query: ReadQuery) -> Result<DatabaseQueryResult, Error>;
+}
+
+#[async_trait]
+impl InfluxClient for Client {
+    fn database_name(&self) -> &str {
+        Client::database_name(self)
+    }
+
+    async fn query(&self, query: &ReadQuery) -> Result<String, Error> {
+        Client::query(self, query).await
+    }
+
+    async fn write(&self, points: &Vec<WriteQuery>) -> Result<String, Error> {
+        Client::query(self, points).await
+    }
+
+    async fn json_query(&self, query: ReadQuery) -> Result<DatabaseQueryResult, Error> {
+        Client::json_query(self, query).await
+    }
+}
+
+#[cfg(test)]
+pub(crate) use mock::MockClient;
+
+#[cfg(test)]
+mod mock {
+    use super::*;
+    use influxdb::Query;
+    use std::sync::Mutex;
+
+    // An in-memory InfluxClient recording the queries (and the line protocol of the written points),
+    // replying the registered results to the matching queries, and failing the requests on demand
+    pub(crate) struct MockClient {
+        database: String,
+        queries: Mutex<Vec<String>>,
+        // the results of the queries containing a pattern (the first matching one is replied)
+        replies: Mutex<Vec<(String, serde_json::Value)>>,
+        // the number of the next requests to fail with a connection error
+        failures: Mutex<usize>,
+    }
+
+    impl MockClient {
+        pub(crate) fn new(database: &str) -> MockClient {
+            MockClient {
+                database: database.to_string(),
+                queries: Mutex::new(Vec::new()),
+                replies: Mutex::new(Vec::new()),
+                failures: Mutex::new(0),
+            }
+        }
+
+        // The queries received so far, in order
+        pub(crate) fn queries(&self) -> Vec<String> {
+            self.queries.lock().unwrap().clone()
+        }
+
+        // Reply the series (as returned by InfluxDB) to the next queries containing the pattern
+        pub(crate) fn reply(&self, pattern: &str, series: serde_json::Value) {
+            self.replies.lock().unwrap().push((
+                pattern.to_string(),
+                serde_json::json!({ "statement_id": 0, "series": series }),
+            ))
+        }
+
+        // Fail the next requests (of any kind)
+        pub(crate) fn fail_next(&self, requests: usize) {
+            *self.failures.lock().unwrap() = requests
+        }
+
+        fn record(&self, query: String) -> Result<String, Error> {
+            self.queries.lock().unwrap().push(query.clone());
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(Error::ConnectionError {
+                    error: "injected failure".to_string(),
+                });
+            }
+            Ok(query)
+        }
+    }
+
+    #[async_trait]
+    impl InfluxClient for MockClient {
+        fn database_name(&self) -> &str {
+            &self.database
+        }
+
+        async fn query(&self, query: &ReadQuery) -> Result<String, Error> {
+            self.record(query.build()?.get())?;
+            Ok(String::new())
+        }
+
+        async fn write(&self, points: &Vec<WriteQuery>) -> Result<String, Error> {
+            let lines = points
+                .iter()
+                .map(|p| p.build().map(|q| q.get()))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.record(lines.join("\n"))?;
+            Ok(String::new())
+        }
+
+        async fn json_query(&self, query: ReadQuery) -> Result<DatabaseQueryResult, Error> {
+            let query = self.record(query.build()?.get())?;
+            let result = self
+                .replies
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(pattern, _)| query.contains(pattern.as_str()))
+                .map(|(_, result)| result.clone())
+                .unwrap_or_else(|| serde_json::json!({ "statement_id": 0 }));
+            Ok(DatabaseQueryResult {
+                results: vec![result],
+            })
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::compression::Compression;
+use base64::{engine::general_purpose::STANDARD as b64_std_engine, Engine};
+use log::warn;
+use std::borrow::Cow;
+use zenoh::prelude::{Encoding, KnownEncoding};
+use zenoh::Result as ZResult;
+
+// prefix of the fields of the flattened JSON values ("json_flatten" codec)
+const JSON_FIELD_PREFIX: &str = "json.";
+// max number of fields of a flattened JSON value (the others are not flattened)
+const MAX_FLATTENED_FIELDS: usize = 64;
+
+// The fields of the point storing a value
+pub(crate) struct EncodedValue {
+    // the "value" field: the payload as a string, or base64-encoded
+    pub(crate) value: String,
+    pub(crate) base64: bool,
+    // the algorithm compressing the payload (before its base64 encoding), empty if not compressed
+    pub(crate) compression: &'static str,
+    // the payload as a float, stored in the NUMERIC_VALUE_FIELD field
+    pub(crate) numeric: Option<f64>,
+    // additional float fields derived from the payload
+    pub(crate) fields: Vec<(String, f64)>,
+}
+
+// The encoding of the values into the fields of their points (`codec` property), and back.
+// The points are self-describing (base64 and compression fields): the points written with a codec are decoded
+// with any other one, so the codec of a storage can be changed at any time.
+pub(crate) trait ValueCodec: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Encode a payload into the fields of its point
+    // (borrowed from a single slice ZBuf, or owned once copied from several slices)
+    fn encode(&self, payload: Cow<[u8]>, encoding: &Encoding) -> EncodedValue;
+
+    // Decode the payload of a point from its "value", "base64" and "compression" fields
+    fn decode(&self, value: String, base64: bool, compression: Option<&str>) -> ZResult<Vec<u8>> {
+        if !base64 {
+            return Ok(value.into_bytes());
+        }
+        let payload = b64_std_engine
+            .decode(value)
+            .map_err(|e| influx_error!(Serialization, "Invalid base64 value: {}", e))?;
+        match compression {
+            None | Some("") => Ok(payload),
+            Some(name) => match Compression::from_name(name) {
+                Some(c) => c.decompress(&payload),
+                None => fail!(Serialization, "Unknown compression {}", name),
+            },
+        }
+    }
+}
+
+// The default codec: the UTF-8 payloads as strings, and the others in base64
+pub(crate) struct StringCodec;
+
+impl ValueCodec for StringCodec {
+    fn name(&self) -> &'static str {
+        "string"
+    }
+
+    fn encode(&self, payload: Cow<[u8]>, _encoding: &Encoding) -> EncodedValue {
+        let (base64, value) = encode_string(payload);
+        EncodedValue {
+            value,
+            base64,
+            compression: "",
+            numeric: None,
+            fields: Vec::new(),
+        }
+    }
+}
+
+// Encode a payload as a string, converting it to base64 if it's not UTF-8. The payload is copied only once: into
+// the string if it's borrowed and UTF-8, while the base64 string is encoded from the payload's bytes
+pub(crate) fn encode_string(payload: Cow<[u8]>) -> (bool, String) {
+    match payload {
+        Cow::Borrowed(bytes) => match std::str::from_utf8(bytes) {
+            Ok(s) => (false, s.to_string()),
+            Err(_) => (true, b64_std_engine.encode(bytes)),
+        },
+        Cow::Owned(bytes) => match String::from_utf8(bytes) {
+            Ok(s) => (false, s),
+            Err(err) => (true, b64_std_engine.encode(err.as_bytes())),
+        },
+    }
+}
+
+// Also stores the numeric string values as floats, allowing aggregations
+pub(crate) struct NumericCodec;
+
+impl ValueCodec for NumericCodec {
+    fn name(&self) -> &'static str {
+        "numeric"
+    }
+
+    fn encode(&self, payload: Cow<[u8]>, encoding: &Encoding) -> EncodedValue {
+        let mut encoded = StringCodec.encode(payload, encoding);
+        if !encoded.base64 {
+            encoded.numeric = encoded
+                .value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite());
+        }
+        encoded
+    }
+}
+
+// Also stores the numeric leaves of the JSON objects as float fields named after their path
+// (e.g. "json.position.x"), so they can be queried with InfluxQL
+pub(crate) struct JsonFlattenCodec;
+
+impl ValueCodec for JsonFlattenCodec {
+    fn name(&self) -> &'static str {
+        "json_flatten"
+    }
+
+    fn encode(&self, payload: Cow<[u8]>, encoding: &Encoding) -> EncodedValue {
+        let mut encoded = StringCodec.encode(payload, encoding);
+        let is_json = matches!(
+            encoding.prefix(),
+            KnownEncoding::AppJson | KnownEncoding::TextJson
+        );
+        if is_json && !encoded.base64 {
+            if let Ok(json @ serde_json::Value::Object(_)) =
+                serde_json::from_str::<serde_json::Value>(&encoded.value)
+            {
+                flatten_json(
+                    &json,
+                    &mut String::from(JSON_FIELD_PREFIX),
+                    &mut encoded.fields,
+                );
+                if encoded.fields.len() > MAX_FLATTENED_FIELDS {
+                    warn!(
+                        "Only {} of the {} numeric fields of a JSON value are flattened",
+                        MAX_FLATTENED_FIELDS,
+                        encoded.fields.len()
+                    );
+                    encoded.fields.truncate(MAX_FLATTENED_FIELDS);
+                }
+            }
+        }
+        encoded
+    }
+}
+
+// Collect the numeric leaves of a JSON value, with their path from `path`
+fn flatten_json(json: &serde_json::Value, path: &mut String, fields: &mut Vec<(String, f64)>) {
+    match json {
+        serde_json::Value::Number(n) => {
+            if let Some(f) = n.as_f64().filter(|f| f.is_finite()) {
+                fields.push((path.clone(), f));
+            }
+        }
+        serde_json::Value::Object(map) => {
+            let len = path.len();
+            for (name, value) in map {
+                if len > JSON_FIELD_PREFIX.len() {
+                    path.push('.');
+                }
+                path.push_str(name);
+                flatten_json(value, path, fields);
+                path.truncate(len);
+            }
+        }
+        serde_json::Value::Array(values) => {
+            let len = path.len();
+            for (i, value) in values.iter().enumerate() {
+                path.push_str(&format!("[{i}]"));
+                flatten_json(value, path, fields);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Compresses the payloads larger than a threshold (if smaller once compressed) before their base64 encoding,
+// encoding the others with the inner codec
+pub(crate) struct CompressedCodec {
+    pub(crate) compression: Compression,
+    pub(crate) threshold: usize,
+    pub(crate) inner: Box<dyn ValueCodec>,
+}
+
+impl ValueCodec for CompressedCodec {
+    fn name(&self) -> &'static str {
+        "compressed"
+    }
+
+    fn encode(&self, payload: Cow<[u8]>, encoding: &Encoding) -> EncodedValue {
+        if payload.len() > self.threshold {
+            match self.compression.compress(&payload) {
+                Ok(z) if z.len() < payload.len() => {
+                    return EncodedValue {
+                        value: b64_std_engine.encode(z),
+                        base64: true,
+                        compression: self.compression.name(),
+                        numeric: None,
+                        fields: Vec::new(),
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Store a value uncompressed: {}", e),
+            }
+        }
+        self.inner.encode(payload, encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn text() -> Encoding {
+        Encoding::Exact(KnownEncoding::TextPlain)
+    }
+
+    fn json() -> Encoding {
+        Encoding::Exact(KnownEncoding::AppJson)
+    }
+
+    // Encode a payload and decode it back from the fields of its point
+    fn round_trip(codec: &dyn ValueCodec, payload: &[u8], encoding: &Encoding) -> EncodedValue {
+        let encoded = codec.encode(Cow::Borrowed(payload), encoding);
+        let compression = Some(encoded.compression).filter(|c| !c.is_empty());
+        let decoded = codec
+            .decode(encoded.value.clone(), encoded.base64, compression)
+            .unwrap();
+        assert_eq!(decoded, payload, "{} codec", codec.name());
+        encoded
+    }
+
+    fn sorted(mut fields: Vec<(String, f64)>) -> Vec<(String, f64)> {
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        fields
+    }
+
+    #[test]
+    fn string_codec() {
+        let encoded = round_trip(&StringCodec, b"hello", &text());
+        assert_eq!(encoded.value, "hello");
+        assert!(!encoded.base64);
+        assert_eq!(encoded.compression, "");
+        assert_eq!(encoded.numeric, None);
+        assert!(encoded.fields.is_empty());
+
+        let encoded = round_trip(&StringCodec, "é日🦀".as_bytes(), &text());
+        assert_eq!(encoded.value, "é日🦀");
+        assert!(!encoded.base64);
+
+        let encoded = round_trip(&StringCodec, b"", &text());
+        assert_eq!(encoded.value, "");
+        assert!(!encoded.base64);
+    }
+
+    #[test]
+    fn string_codec_binary() {
+        let payload = [0xff, 0x00, 0xfe, b'a'];
+        let encoded = round_trip(&StringCodec, &payload, &text());
+        assert!(encoded.base64);
+        assert_eq!(encoded.value, b64_std_engine.encode(payload));
+    }
+
+    #[test]
+    fn encode_owned_and_borrowed() {
+        for payload in [&b"hello"[..], &[0xff, 0x00, 0xfe][..]] {
+            assert_eq!(
+                encode_string(Cow::Borrowed(payload)),
+                encode_string(Cow::Owned(payload.to_vec()))
+            );
+        }
+        assert_eq!(
+            encode_string(Cow::Owned(b"hello".to_vec())),
+            (false, "hello".to_string())
+        );
+    }
+
+    #[test]
+    fn numeric_codec() {
+        assert_eq!(
+            round_trip(&NumericCodec, b"21.5", &text()).numeric,
+            Some(21.5)
+        );
+        assert_eq!(
+            round_trip(&NumericCodec, b" -3 ", &text()).numeric,
+            Some(-3.0)
+        );
+        assert_eq!(
+            round_trip(&NumericCodec, b"1e3", &text()).numeric,
+            Some(1000.0)
+        );
+        assert_eq!(round_trip(&NumericCodec, b"abc", &text()).numeric, None);
+        assert_eq!(round_trip(&NumericCodec, b"", &text()).numeric, None);
+        // the non-finite floats can't be stored in InfluxDB
+        assert_eq!(round_trip(&NumericCodec, b"NaN", &text()).numeric, None);
+        assert_eq!(round_trip(&NumericCodec, b"inf", &text()).numeric, None);
+        // nor the binary values
+        let encoded = round_trip(&NumericCodec, &[0xff, b'1'], &text());
+        assert!(encoded.base64);
+        assert_eq!(encoded.numeric, None);
+    }
+
+    #[test]
+    fn json_flatten_codec() {
+        let payload = br#"{"a": 1, "b": {"c": 2.5, "d": "x"}, "e": [3, "y", {"f": 4}], "g": null}"#;
+        let encoded = round_trip(&JsonFlattenCodec, payload, &json());
+        assert!(!encoded.base64);
+        assert_eq!(
+            sorted(encoded.fields),
+            vec![
+                ("json.a".to_string(), 1.0),
+                ("json.b.c".to_string(), 2.5),
+                ("json.e[0]".to_string(), 3.0),
+                ("json.e[2].f".to_string(), 4.0),
+            ]
+        );
+        // only the JSON objects with a JSON encoding are flattened
+        assert!(round_trip(&JsonFlattenCodec, br#"{"a": 1}"#, &text())
+            .fields
+            .is_empty());
+        assert!(round_trip(&JsonFlattenCodec, b"[1, 2]", &json())
+            .fields
+            .is_empty());
+        assert!(round_trip(&JsonFlattenCodec, b"{invalid", &json())
+            .fields
+            .is_empty());
+        let encoded = round_trip(
+            &JsonFlattenCodec,
+            br#"{"a": 1}"#,
+            &Encoding::Exact(KnownEncoding::TextJson),
+        );
+        assert_eq!(encoded.fields, vec![("json.a".to_string(), 1.0)]);
+    }
+
+    #[test]
+    fn json_flatten_max_fields() {
+        let object: serde_json::Map<String, serde_json::Value> = (0..100)
+            .map(|i| (format!("f{i:03}"), serde_json::Value::from(i)))
+            .collect();
+        let payload = serde_json::Value::Object(object).to_string();
+        let encoded = round_trip(&JsonFlattenCodec, payload.as_bytes(), &json());
+        assert_eq!(encoded.fields.len(), MAX_FLATTENED_FIELDS);
+    }
+
+    #[test]
+    fn compressed_codec() {
+        for compression in [Compression::Zstd, Compression::Lz4] {
+            let codec = CompressedCodec {
+                compression,
+                threshold: 16,
+                inner: Box::new(StringCodec),
+            };
+            // compressed above the threshold
+            let payload = "zenoh ".repeat(100);
+            let encoded = round_trip(&codec, payload.as_bytes(), &text());
+            assert!(encoded.base64);
+            assert_eq!(encoded.compression, compression.name());
+            assert!(encoded.value.len() < payload.len());
+            // encoded by the inner codec below the threshold
+            let encoded = round_trip(&codec, b"short", &text());
+            assert!(!encoded.base64);
+            assert_eq!(encoded.compression, "");
+            assert_eq!(encoded.value, "short");
+            // and when the compression doesn't reduce the size
+            let payload: Vec<u8> = (0..64_u32)
+                .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+                .collect();
+            let encoded = round_trip(&codec, &payload, &text());
+            assert_eq!(encoded.compression, "");
+        }
+    }
+
+    #[test]
+    fn decode_with_any_codec() {
+        // the points are self-describing: decoded whatever the codec
+        let codec = CompressedCodec {
+            compression: Compression::Zstd,
+            threshold: 0,
+            inner: Box::new(NumericCodec),
+        };
+        let payload = "1.5 ".repeat(100);
+        let encoded = codec.encode(Cow::Borrowed(payload.as_bytes()), &text());
+        let codecs: [&dyn ValueCodec; 3] = [&StringCodec, &NumericCodec, &JsonFlattenCodec];
+        for other in codecs {
+            assert_eq!(
+                other
+                    .decode(encoded.value.clone(), encoded.base64, Some("zstd"))
+                    .unwrap(),
+                payload.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert!(StringCodec
+            .decode("not base64!".into(), true, None)
+            .is_err());
+        let value = b64_std_engine.encode("abc");
+        assert!(StringCodec
+            .decode(value.clone(), true, Some("unknown"))
+            .is_err());
+        assert!(StringCodec
+            .decode(value.clone(), true, Some("zstd"))
+            .is_err());
+        // an empty compression field means not compressed
+        assert_eq!(
+            StringCodec.decode(value, true, Some("")).unwrap(),
+            b"abc".to_vec()
+        );
+        // the compression is ignored for the values not in base64
+        assert_eq!(
+            StringCodec
+                .decode("abc".into(), false, Some("zstd"))
+                .unwrap(),
+            b"abc".to_vec()
+        );
+    }
+
+    // All the codecs, with the compression of the small values
+    fn codecs() -> Vec<Box<dyn ValueCodec>> {
+        vec![
+            Box::new(StringCodec),
+            Box::new(NumericCodec),
+            Box::new(JsonFlattenCodec),
+            Box::new(CompressedCodec {
+                compression: Compression::Zstd,
+                threshold: 16,
+                inner: Box::new(StringCodec),
+            }),
+            Box::new(CompressedCodec {
+                compression: Compression::Lz4,
+                threshold: 0,
+                inner: Box::new(JsonFlattenCodec),
+            }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn binary_round_trip(payload in vec(any::<u8>(), 0..512)) {
+            for codec in codecs() {
+                round_trip(&*codec, &payload, &text());
+                round_trip(&*codec, &payload, &json());
+            }
+        }
+
+        #[test]
+        fn text_round_trip(payload in any::<String>()) {
+            for codec in codecs() {
+                let encoded = round_trip(&*codec, payload.as_bytes(), &text());
+                // stored as is, unless compressed
+                if encoded.compression.is_empty() {
+                    prop_assert!(!encoded.base64);
+                    prop_assert_eq!(&encoded.value, &payload);
+                }
+            }
+        }
+
+        #[test]
+        fn numeric_round_trip(f in any::<f64>().prop_filter("finite", |f| f.is_finite())) {
+            let encoded = round_trip(&NumericCodec, f.to_string().as_bytes(), &text());
+            prop_assert_eq!(encoded.numeric, Some(f));
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use zenoh::Result as ZResult;
+
+// default size (in bytes) above which the payloads are compressed
+pub(crate) const DEFAULT_COMPRESSION_THRESHOLD: u64 = 1024;
+
+// the zstd compression level (a good trade-off between speed and ratio)
+const ZSTD_LEVEL: i32 = 3;
+
+// The algorithms compressing the large payloads before their base64 encoding.
+// The algorithm's name is stored in the "compression" field of the compressed points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Compression {
+    Zstd,
+    Lz4,
+}
+
+impl Compression {
+    pub(crate) fn from_name(name: &str) -> Option<Compression> {
+        match name {
+            "zstd" => Some(Compression::Zstd),
+            "lz4" => Some(Compression::Lz4),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Compression::Zstd => "zstd",
+            Compression::Lz4 => "lz4",
+        }
+    }
+
+    pub(crate) fn compress(&self, payload: &[u8]) -> ZResult<Vec<u8>> {
+        match self {
+            Compression::Zstd => zstd::stream::encode_all(payload, ZSTD_LEVEL).map_err(|e| {
+                influx_error!(Serialization, "Failed to compress payload with zstd: {}", e).into()
+            }),
+            Compression::Lz4 => Ok(lz4_flex::compress_prepend_size(payload)),
+        }
+    }
+
+    pub(crate) fn decompress(&self, payload: &[u8]) -> ZResult<Vec<u8>> {
+        match self {
+            Compression::Zstd => zstd::stream::decode_all(payload).map_err(|e| {
+                influx_error!(Serialization, "Failed to decompress zstd payload: {}", e).into()
+            }),
+            Compression::Lz4 => lz4_flex::decompress_size_prepended(payload).map_err(|e| {
+                influx_error!(Serialization, "Failed to decompress lz4 payload: {}", e).into()
+            }),
+        }
+    }
+}
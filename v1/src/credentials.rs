@@ -0,0 +1,239 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::client::InfluxClient;
+use influxdb::Client;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use zenoh::Result as ZResult;
+use zenoh_core::zerror;
+
+// The credentials of the clients, with their generation (incremented on each rotation)
+struct Credentials {
+    value: Option<(String, String)>,
+    generation: u64,
+}
+
+// An InfluxDB client whose credentials can be rotated at runtime.
+// A snapshot of the client with the current credentials is taken for each request, so a rotation
+// applies to the requests started afterwards. The clients derived for other databases share
+// the same credentials.
+#[derive(Clone)]
+pub(crate) struct SharedClient {
+    // the client without credentials
+    client: Client,
+    credentials: Arc<RwLock<Credentials>>,
+    // the client with the credentials of the given generation, rebuilt after a rotation only
+    // (adding the credentials to a client copies its parameters)
+    authenticated: Arc<RwLock<(u64, Arc<Client>)>>,
+    // the HTTP client with specific TLS settings, if any (see `tls` storage property)
+    http_client: Option<surf::Client>,
+    // if set, the operations on the points are run by this client rather than by InfluxDB (e.g. a mock)
+    data_client: Option<Arc<dyn InfluxClient>>,
+}
+
+impl SharedClient {
+    pub(crate) fn new(client: Client, credentials: Option<(String, String)>) -> SharedClient {
+        let authenticated = authenticate(&client, &credentials);
+        SharedClient {
+            client,
+            credentials: Arc::new(RwLock::new(Credentials {
+                value: credentials,
+                generation: 0,
+            })),
+            authenticated: Arc::new(RwLock::new((0, Arc::new(authenticated)))),
+            http_client: None,
+            data_client: None,
+        }
+    }
+
+    // The same client, using an HTTP client with specific TLS settings
+    pub(crate) fn with_http_client(self, http_client: surf::Client) -> SharedClient {
+        SharedClient::from_parts(
+            self.client.with_http_client(http_client.clone()),
+            self.credentials,
+            Some(http_client),
+        )
+    }
+
+    // A client on another database, with the same (rotated) credentials and HTTP client
+    pub(crate) fn for_database(&self, db: &str) -> SharedClient {
+        let client = Client::new(self.client.database_url(), db);
+        SharedClient::from_parts(
+            match &self.http_client {
+                Some(http_client) => client.with_http_client(http_client.clone()),
+                None => client,
+            },
+            self.credentials.clone(),
+            self.http_client.clone(),
+        )
+    }
+
+    fn from_parts(
+        client: Client,
+        credentials: Arc<RwLock<Credentials>>,
+        http_client: Option<surf::Client>,
+    ) -> SharedClient {
+        let authenticated = {
+            let credentials = credentials.read().unwrap();
+            (
+                credentials.generation,
+                Arc::new(authenticate(&client, &credentials.value)),
+            )
+        };
+        SharedClient {
+            client,
+            credentials,
+            authenticated: Arc::new(RwLock::new(authenticated)),
+            http_client,
+            data_client: None,
+        }
+    }
+
+    pub(crate) fn get(&self) -> Client {
+        Client::clone(&self.current())
+    }
+
+    // The client of the operations on the points of the database
+    pub(crate) fn data(&self) -> Arc<dyn InfluxClient> {
+        match &self.data_client {
+            Some(client) => client.clone(),
+            None => self.current(),
+        }
+    }
+
+    // The same client, running the operations on the points with another client
+    #[cfg(test)]
+    pub(crate) fn with_data_client(mut self, data_client: Arc<dyn InfluxClient>) -> SharedClient {
+        self.data_client = Some(data_client);
+        self
+    }
+
+    // The client with the current credentials
+    fn current(&self) -> Arc<Client> {
+        let credentials = self.credentials.read().unwrap();
+        {
+            let authenticated = self.authenticated.read().unwrap();
+            if authenticated.0 == credentials.generation {
+                return authenticated.1.clone();
+            }
+        }
+        let client = Arc::new(authenticate(&self.client, &credentials.value));
+        *self.authenticated.write().unwrap() = (credentials.generation, client.clone());
+        client
+    }
+
+    pub(crate) fn username(&self) -> Option<String> {
+        self.credentials
+            .read()
+            .unwrap()
+            .value
+            .as_ref()
+            .map(|(username, _)| username.clone())
+    }
+
+    // Replace the credentials, returning true if they changed
+    pub(crate) fn set_credentials(&self, username: &str, password: &str) -> bool {
+        let mut credentials = self.credentials.write().unwrap();
+        match &credentials.value {
+            Some((u, p)) if u == username && p == password => false,
+            _ => {
+                credentials.value = Some((username.to_string(), password.to_string()));
+                credentials.generation += 1;
+                true
+            }
+        }
+    }
+}
+
+// The client with the credentials, if any
+fn authenticate(client: &Client, credentials: &Option<(String, String)>) -> Client {
+    match credentials {
+        Some((username, password)) => client.clone().with_auth(username, password),
+        None => client.clone(),
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CredentialsFile {
+    username: String,
+    password: String,
+}
+
+// Read the credentials from a JSON file: {"username": "...", "password": "..."}
+pub(crate) fn read_credentials_file(path: &Path) -> ZResult<(String, String)> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        zerror!(
+            "Failed to read InfluxDB credentials file {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+    let file: CredentialsFile = serde_json::from_str(&content).map_err(|e| {
+        zerror!(
+            "Invalid InfluxDB credentials file {} (expecting {{\"username\": \"...\", \"password\": \"...\"}}): {}",
+            path.display(),
+            e
+        )
+    })?;
+    Ok((file.username, file.password))
+}
+
+// Write the credentials in a JSON file (only readable by its owner, on unix)
+pub(crate) fn write_credentials_file(path: &Path, username: &str, password: &str) -> ZResult<()> {
+    let content = serde_json::to_string(&CredentialsFile {
+        username: username.to_string(),
+        password: password.to_string(),
+    })
+    .map_err(|e| zerror!("Failed to serialize InfluxDB credentials: {}", e))?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    std::io::Write::write_all(
+        &mut options.open(path).map_err(|e| {
+            zerror!(
+                "Failed to create InfluxDB credentials file {}: {}",
+                path.display(),
+                e
+            )
+        })?,
+        content.as_bytes(),
+    )
+    .map_err(|e| {
+        zerror!(
+            "Failed to write InfluxDB credentials file {}: {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+// Re-read a credentials file and apply its content to the client, if changed
+pub(crate) async fn reload_credentials(path: PathBuf, client: SharedClient) {
+    match read_credentials_file(&path) {
+        Ok((username, password)) => {
+            if client.set_credentials(&username, &password) {
+                info!(
+                    "Rotated InfluxDB credentials from {} (user {})",
+                    path.display(),
+                    username
+                );
+            }
+        }
+        Err(e) => warn!("{}", e),
+    }
+}
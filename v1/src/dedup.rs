@@ -0,0 +1,122 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use zenoh::prelude::{OwnedKeyExpr, Value};
+use zenoh::time::Timestamp;
+
+// default max time between 2 written points of a key with an unchanged value (the keyframes interval)
+pub(crate) const DEFAULT_DEDUP_INTERVAL: Duration = Duration::from_secs(60);
+
+// What is compared to the latest written value of a key: the hash of the payload and encoding and,
+// with a deadband, the numeric value
+#[derive(Clone, Copy)]
+pub(crate) struct Fingerprint {
+    hash: u64,
+    number: Option<f64>,
+}
+
+// The latest value written for each key (and its timestamp), to skip the PUTs of a value identical to the
+// previous one, or with a deadband of a numeric value that changed by at most the deadband. A point (keyframe) is
+// still written once per interval, so that the key doesn't look stale and a GET over any longer window finds it.
+pub(crate) struct PutDedup {
+    interval: Duration,
+    deadband: Option<f64>,
+    latest: Mutex<HashMap<Option<OwnedKeyExpr>, (Fingerprint, Timestamp)>>,
+    skipped: AtomicU64,
+}
+
+impl PutDedup {
+    pub(crate) fn new(interval: Duration, deadband: Option<f64>) -> PutDedup {
+        PutDedup {
+            interval,
+            deadband,
+            latest: Mutex::new(HashMap::new()),
+            skipped: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn fingerprint(&self, value: &Value) -> Fingerprint {
+        let payload = value.payload.contiguous();
+        let number = match self.deadband {
+            Some(_) => std::str::from_utf8(&payload)
+                .ok()
+                .and_then(|s| s.trim().parse::<f64>().ok())
+                .filter(|f| f.is_finite()),
+            None => None,
+        };
+        let mut bytes = payload.to_vec();
+        bytes.extend_from_slice(value.encoding.to_string().as_bytes());
+        Fingerprint {
+            hash: xxhash_rust::xxh64::xxh64(&bytes, 0),
+            number,
+        }
+    }
+
+    // True (and counted as skipped) if the value is identical (or within the deadband) to the latest one
+    // written for the key, less than the interval before
+    pub(crate) fn is_duplicate(
+        &self,
+        key: &Option<OwnedKeyExpr>,
+        fingerprint: &Fingerprint,
+        timestamp: &Timestamp,
+    ) -> bool {
+        let duplicate = match self.latest.lock().unwrap().get(key) {
+            Some((latest_fingerprint, latest)) => {
+                let unchanged = latest_fingerprint.hash == fingerprint.hash
+                    || match (self.deadband, latest_fingerprint.number, fingerprint.number) {
+                        (Some(deadband), Some(a), Some(b)) => (a - b).abs() <= deadband,
+                        _ => false,
+                    };
+                unchanged
+                    && latest < timestamp
+                    && timestamp.get_time().to_duration() - latest.get_time().to_duration()
+                        < self.interval
+            }
+            None => false,
+        };
+        if duplicate {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        duplicate
+    }
+
+    // Record a written value (ignored if older than the recorded one)
+    pub(crate) fn record(
+        &self,
+        key: Option<OwnedKeyExpr>,
+        fingerprint: Fingerprint,
+        timestamp: Timestamp,
+    ) {
+        let mut latest = self.latest.lock().unwrap();
+        match latest.get(&key) {
+            Some((_, t)) if *t >= timestamp => {}
+            _ => {
+                latest.insert(key, (fingerprint, timestamp));
+            }
+        }
+    }
+
+    // Forget the latest value of a deleted key: the next PUT is always written
+    pub(crate) fn forget(&self, key: &Option<OwnedKeyExpr>) {
+        self.latest.lock().unwrap().remove(key);
+    }
+
+    pub(crate) fn skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+}
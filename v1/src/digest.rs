@@ -0,0 +1,68 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::collections::BTreeMap;
+use zenoh::time::Timestamp;
+
+// The default number of key chunks grouping the entries of a digest
+pub(crate) const DEFAULT_DIGEST_DEPTH: usize = 1;
+
+// The digest of a set of entries: the XOR of the checksums of their key and timestamp, so that it doesn't depend on
+// the order of the entries, and that 2 storages with different entries have different digests (but for collisions)
+#[derive(Default)]
+struct Digest {
+    checksum: u64,
+    entries: u64,
+}
+
+impl Digest {
+    fn add(&mut self, checksum: u64) {
+        self.checksum ^= checksum;
+        self.entries += 1;
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "checksum": format!("{:016x}", self.checksum),
+            "entries": self.entries,
+        })
+    }
+}
+
+fn entry_checksum(key: &str, timestamp: &Timestamp) -> u64 {
+    xxhash_rust::xxh64::xxh64(format!("{key}@{timestamp}").as_bytes(), 0)
+}
+
+// The digest of the entries (with their latest timestamp), and the digests of their groups by the first `depth`
+// chunks of their key: comparing the digests of 2 storages, then of the groups that differ with a larger depth, the
+// entries to align are found without listing all of them.
+pub(crate) fn digest(entries: &[(String, Timestamp)], depth: usize) -> serde_json::Value {
+    let mut root = Digest::default();
+    let mut children: BTreeMap<String, Digest> = BTreeMap::new();
+    for (key, timestamp) in entries {
+        let checksum = entry_checksum(key, timestamp);
+        root.add(checksum);
+        if depth > 0 {
+            let prefix = key.split('/').take(depth).collect::<Vec<_>>().join("/");
+            children.entry(prefix).or_default().add(checksum);
+        }
+    }
+    let mut result = root.to_json();
+    result["children"] = children
+        .into_iter()
+        .map(|(prefix, digest)| (prefix, digest.to_json()))
+        .collect::<serde_json::Map<_, _>>()
+        .into();
+    result
+}
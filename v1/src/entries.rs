@@ -0,0 +1,97 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime};
+use zenoh::prelude::OwnedKeyExpr;
+use zenoh::time::Timestamp;
+
+// The latest timestamp of each key of the storage, as replied by get_all_entries(): updated on each PUT and
+// DELETE of the storage, and reconciled with InfluxDB (for the points written or removed by others) once expired
+pub(crate) struct EntriesCache {
+    refresh_interval: Duration,
+    // the entries, and the time of their last reconciliation (None if never reconciled)
+    state: RwLock<(HashMap<Option<OwnedKeyExpr>, Timestamp>, Option<Instant>)>,
+}
+
+impl EntriesCache {
+    pub(crate) fn new(refresh_interval: Duration) -> EntriesCache {
+        EntriesCache {
+            refresh_interval,
+            state: RwLock::new((HashMap::new(), None)),
+        }
+    }
+
+    // The cached entries, or None if they must be reconciled with InfluxDB first
+    pub(crate) fn entries(&self) -> Option<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
+        let state = self.state.read().unwrap();
+        match state.1 {
+            Some(reconciled) if reconciled.elapsed() < self.refresh_interval => Some(
+                state
+                    .0
+                    .iter()
+                    .map(|(key, timestamp)| (key.clone(), *timestamp))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    // Record a PUT or DELETE of the key (ignored if older than the cached one)
+    pub(crate) fn update(&self, key: Option<OwnedKeyExpr>, timestamp: Timestamp) {
+        let mut state = self.state.write().unwrap();
+        match state.0.get(&key) {
+            Some(latest) if *latest >= timestamp => {}
+            _ => {
+                state.0.insert(key, timestamp);
+            }
+        }
+    }
+
+    // Replace the entries by the ones read from InfluxDB since `started`, keeping the entries updated since
+    pub(crate) fn reconcile(
+        &self,
+        entries: &[(Option<OwnedKeyExpr>, Timestamp)],
+        started: (Instant, SystemTime),
+    ) {
+        let mut state = self.state.write().unwrap();
+        let mut reconciled: HashMap<Option<OwnedKeyExpr>, Timestamp> = HashMap::new();
+        for (key, timestamp) in entries {
+            match reconciled.get(key) {
+                Some(latest) if latest >= timestamp => {}
+                _ => {
+                    reconciled.insert(key.clone(), *timestamp);
+                }
+            }
+        }
+        for (key, timestamp) in state.0.drain() {
+            if timestamp.get_time().to_system_time() > started.1
+                && reconciled.get(&key).map_or(true, |t| *t < timestamp)
+            {
+                reconciled.insert(key, timestamp);
+            }
+        }
+        *state = (reconciled, Some(started.0));
+    }
+
+    // Reconcile the entries with InfluxDB on the next listing (e.g. once points were removed by a trim)
+    pub(crate) fn invalidate(&self) {
+        self.state.write().unwrap().1 = None;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.state.read().unwrap().0.len()
+    }
+}
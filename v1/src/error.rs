@@ -0,0 +1,121 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::fmt;
+
+// Return early with an InfluxDbError of the given kind, e.g. `fail!(Connection, "... {}", e)`
+macro_rules! fail {
+    ($kind:ident, $($arg:tt)+) => {
+        return Err($crate::error::InfluxDbError::$kind(format!($($arg)+)).into())
+    };
+}
+
+// Return early with the classified error of the InfluxDB client, with a context message,
+// e.g. `fail_client!(e, "Failed to list measurements of {}", db)`
+macro_rules! fail_client {
+    ($err:expr, $($arg:tt)+) => {
+        return Err($crate::error::InfluxDbError::from_client(format!($($arg)+), $err).into())
+    };
+}
+
+// Build an InfluxDbError of the given kind, e.g. `.map_err(|e| influx_error!(Serialization, "... {}", e))`
+macro_rules! influx_error {
+    ($kind:ident, $($arg:tt)+) => {
+        $crate::error::InfluxDbError::$kind(format!($($arg)+))
+    };
+}
+
+// The errors of the InfluxDB storages operations, returned boxed as `zenoh::Error`: use
+// `downcast_ref::<InfluxDbError>()` (or `is_retryable()`) to know if an operation is worth retrying.
+// The configuration errors are not of this type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InfluxDbError {
+    // InfluxDB is unreachable, or the connection failed
+    Connection(String),
+    // InfluxDB refused the credentials, or the user lacks a privilege
+    Auth(String),
+    // A key, a value or a stored point doesn't match the storage's schema
+    Schema(String),
+    // A query (or the selector it's built from) is invalid, or was refused by InfluxDB
+    QueryParse(String),
+    // A result of InfluxDB or a value can't be (de)serialized
+    Serialization(String),
+    // A limit of the storage is reached (max series, buffer, open circuit breaker...)
+    Capacity(String),
+}
+
+impl InfluxDbError {
+    // The kind of error, as reported in the storage's admin status
+    pub fn kind(&self) -> &'static str {
+        match self {
+            InfluxDbError::Connection(_) => "connection",
+            InfluxDbError::Auth(_) => "auth",
+            InfluxDbError::Schema(_) => "schema",
+            InfluxDbError::QueryParse(_) => "query_parse",
+            InfluxDbError::Serialization(_) => "serialization",
+            InfluxDbError::Capacity(_) => "capacity",
+        }
+    }
+
+    // If true, the same operation may succeed later (once InfluxDB is reachable, or below the limit)
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            InfluxDbError::Connection(_) | InfluxDbError::Capacity(_)
+        )
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            InfluxDbError::Connection(m)
+            | InfluxDbError::Auth(m)
+            | InfluxDbError::Schema(m)
+            | InfluxDbError::QueryParse(m)
+            | InfluxDbError::Serialization(m)
+            | InfluxDbError::Capacity(m) => m,
+        }
+    }
+
+    // Classify an error of the InfluxDB client, prefixing its message with the context
+    pub(crate) fn from_client(context: impl fmt::Display, e: influxdb::Error) -> InfluxDbError {
+        let message = format!("{context} : {e}");
+        match e {
+            influxdb::Error::ConnectionError { .. }
+            | influxdb::Error::ProtocolError { .. }
+            | influxdb::Error::UrlConstructionError { .. } => InfluxDbError::Connection(message),
+            influxdb::Error::AuthenticationError | influxdb::Error::AuthorizationError => {
+                InfluxDbError::Auth(message)
+            }
+            influxdb::Error::InvalidQueryError { .. } | influxdb::Error::DatabaseError { .. } => {
+                InfluxDbError::QueryParse(message)
+            }
+            influxdb::Error::DeserializationError { .. } => InfluxDbError::Serialization(message),
+        }
+    }
+}
+
+impl fmt::Display for InfluxDbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for InfluxDbError {}
+
+// If true, the error returned by an InfluxDB storage operation is worth retrying
+// (e.g. `is_retryable(err.as_ref())` with a `zenoh::Error`)
+pub fn is_retryable(e: &(dyn std::error::Error + 'static)) -> bool {
+    e.downcast_ref::<InfluxDbError>()
+        .map_or(false, InfluxDbError::is_retryable)
+}
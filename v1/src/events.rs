@@ -0,0 +1,93 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use async_std::channel::{bounded, Sender, TrySendError};
+use log::{debug, warn};
+use std::time::SystemTime;
+use zenoh::prelude::OwnedKeyExpr;
+use zenoh::Result as ZResult;
+use zenoh::Session;
+use zenoh_core::{zerror, AsyncResolve};
+
+// max number of events waiting to be published
+const EVENTS_QUEUE_SIZE: usize = 1024;
+
+// Open a zenoh session connected to the endpoints if any (or else scouting for the routers)
+pub(crate) async fn open_session(connect: &[String]) -> ZResult<Session> {
+    let mut config = zenoh::config::Config::default();
+    if !connect.is_empty() {
+        config
+            .insert_json5("mode", r#""client""#)
+            .and_then(|_| {
+                config.insert_json5(
+                    "connect/endpoints",
+                    &serde_json::to_string(connect).unwrap(),
+                )
+            })
+            .map_err(|e| zerror!("Invalid zenoh endpoints {:?}: {}", connect, e))?;
+    }
+    zenoh::open(config).res_async().await
+}
+
+// The significant events of a storage (errors, failed buffer flushes, dropped measurements, garbage collections,
+// circuit breaker state changes...), published as JSON values on a zenoh key (`events` property).
+// The events are dropped if they can't be published fast enough. The publication stops once all clones are dropped.
+#[derive(Clone)]
+pub(crate) struct Events {
+    storage: String,
+    tx: Sender<serde_json::Value>,
+}
+
+impl Events {
+    pub(crate) async fn start(
+        storage: String,
+        key: OwnedKeyExpr,
+        connect: &[String],
+    ) -> ZResult<Events> {
+        let session = open_session(connect).await?;
+        let (tx, rx) = bounded::<serde_json::Value>(EVENTS_QUEUE_SIZE);
+        async_std::task::spawn(async move {
+            while let Ok(event) = rx.recv().await {
+                if let Err(e) = session.put(&key, event.to_string()).res_async().await {
+                    warn!("Failed to publish InfluxDB storage event on {}: {}", key, e);
+                }
+            }
+        });
+        Ok(Events { storage, tx })
+    }
+
+    // Publish an event, e.g. `events.emit("gc", json!({"db": db}))`
+    pub(crate) fn emit(&self, event: &str, details: serde_json::Value) {
+        let mut value = serde_json::json!({
+            "storage": self.storage,
+            "event": event,
+            "time": humantime::format_rfc3339(SystemTime::now()).to_string(),
+        });
+        if let (Some(value), serde_json::Value::Object(details)) = (value.as_object_mut(), details)
+        {
+            value.extend(details);
+        }
+        debug!("Publish InfluxDB storage event {}", value);
+        match self.tx.try_send(value) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                warn!(
+                    "Too many InfluxDB storage events: dropping event '{}'",
+                    event
+                )
+            }
+            Err(TrySendError::Closed(_)) => {}
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use log::debug;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Mutex;
+use std::time::Duration;
+use zenoh::Result as ZResult;
+use zenoh_core::bail;
+
+// Properties of the "fault_injection" storage object
+const PROP_WRITE_FAILURE_RATE: &str = "write_failure_rate";
+const PROP_READ_FAILURE_RATE: &str = "read_failure_rate";
+const PROP_RESET_RATE: &str = "reset_rate";
+const PROP_LATENCY: &str = "latency";
+const PROP_SEED: &str = "seed";
+
+// Simulates InfluxDB failures before the queries of a storage (only built with the "fault_injection" feature).
+// With a seed, the sequence of failures is reproducible.
+pub(crate) struct FaultInjector {
+    write_failure_rate: f64,
+    read_failure_rate: f64,
+    reset_rate: f64,
+    latency: Option<Duration>,
+    rng: Mutex<StdRng>,
+}
+
+impl FaultInjector {
+    pub(crate) fn from_config(config: &serde_json::Value) -> ZResult<FaultInjector> {
+        let config = match config.as_object() {
+            Some(c) => c,
+            None => bail!("fault_injection configuration must be an object"),
+        };
+        let rate = |name: &str| -> ZResult<f64> {
+            match config.get(name) {
+                None => Ok(0.0),
+                Some(v) => match v.as_f64() {
+                    Some(r) if (0.0..=1.0).contains(&r) => Ok(r),
+                    _ => bail!(
+                        "fault_injection `{}` must be a number between 0 and 1",
+                        name
+                    ),
+                },
+            }
+        };
+        let latency = match config.get(PROP_LATENCY) {
+            None => None,
+            Some(serde_json::Value::String(s)) => match humantime::parse_duration(s) {
+                Ok(d) => Some(d),
+                Err(e) => bail!("Invalid fault_injection `{}`: {}", PROP_LATENCY, e),
+            },
+            Some(_) => bail!(
+                "fault_injection `{}` must be a duration string",
+                PROP_LATENCY
+            ),
+        };
+        let rng = match config.get(PROP_SEED) {
+            None => StdRng::from_entropy(),
+            Some(v) => match v.as_u64() {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => bail!("fault_injection `{}` must be an integer", PROP_SEED),
+            },
+        };
+        Ok(FaultInjector {
+            write_failure_rate: rate(PROP_WRITE_FAILURE_RATE)?,
+            read_failure_rate: rate(PROP_READ_FAILURE_RATE)?,
+            reset_rate: rate(PROP_RESET_RATE)?,
+            latency,
+            rng: Mutex::new(rng),
+        })
+    }
+
+    // To be called before a write query: adds the latency and possibly fails
+    pub(crate) async fn before_write(&self) -> ZResult<()> {
+        self.inject(self.write_failure_rate, "write").await
+    }
+
+    // To be called before a read query: adds the latency and possibly fails
+    pub(crate) async fn before_read(&self) -> ZResult<()> {
+        self.inject(self.read_failure_rate, "read").await
+    }
+
+    async fn inject(&self, failure_rate: f64, op: &str) -> ZResult<()> {
+        if let Some(latency) = self.latency {
+            async_std::task::sleep(latency).await;
+        }
+        let (reset, failure) = {
+            let mut rng = self.rng.lock().unwrap();
+            (rng.gen_bool(self.reset_rate), rng.gen_bool(failure_rate))
+        };
+        if reset {
+            debug!("Injecting a connection reset on {}", op);
+            bail!("Simulated connection reset by InfluxDB (fault injection)")
+        }
+        if failure {
+            debug!("Injecting a failure on {}", op);
+            bail!("Simulated InfluxDB {} failure (fault injection)", op)
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use async_std::sync::{Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
+use influxdb::WriteQuery;
+use log::{info, warn};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::SystemTime;
+
+// The connectivity to InfluxDB of a volume, as tracked by its periodic health check
+// and shared with its storages
+pub(crate) struct Health {
+    up: AtomicBool,
+    state: RwLock<HealthState>,
+}
+
+struct HealthState {
+    // time of the last check
+    last_check: Option<SystemTime>,
+    // time of the last transition up or down
+    since: SystemTime,
+    last_error: Option<String>,
+    // the InfluxDB server version, as reported by the last successful check
+    version: Option<String>,
+    // the databases visible with the admin credentials, as listed by the last successful check
+    databases: Option<Vec<String>>,
+}
+
+impl Health {
+    pub(crate) fn new() -> Health {
+        Health {
+            up: AtomicBool::new(true),
+            state: RwLock::new(HealthState {
+                last_check: None,
+                since: SystemTime::now(),
+                last_error: None,
+                version: None,
+                databases: None,
+            }),
+        }
+    }
+
+    pub(crate) fn is_up(&self) -> bool {
+        self.up.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn last_error(&self) -> Option<String> {
+        self.state.read().unwrap().last_error.clone()
+    }
+
+    pub(crate) fn report_up(&self, version: String, databases: Option<Vec<String>>) {
+        let mut state = self.state.write().unwrap();
+        state.version = Some(version);
+        if databases.is_some() {
+            state.databases = databases;
+        }
+        let now = SystemTime::now();
+        state.last_check = Some(now);
+        if !self.up.swap(true, Ordering::Relaxed) {
+            info!("InfluxDB is reachable again");
+            state.since = now;
+        }
+    }
+
+    pub(crate) fn report_down(&self, error: String) {
+        let mut state = self.state.write().unwrap();
+        let now = SystemTime::now();
+        state.last_check = Some(now);
+        if self.up.swap(false, Ordering::Relaxed) {
+            warn!("InfluxDB is unreachable : {}", error);
+            state.since = now;
+        }
+        state.last_error = Some(error);
+    }
+
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        let state = self.state.read().unwrap();
+        serde_json::json!({
+            "up": self.is_up(),
+            "since": humantime::format_rfc3339_millis(state.since).to_string(),
+            "last_check": state
+                .last_check
+                .map(|t| humantime::format_rfc3339_millis(t).to_string()),
+            "last_error": state.last_error,
+            "version": state.version,
+        })
+    }
+
+    pub(crate) fn databases(&self) -> Option<Vec<String>> {
+        self.state.read().unwrap().databases.clone()
+    }
+}
+
+// The priority of the points of a key in the write buffer (`priorities` property)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Priority {
+    Low = 0,
+    Normal = 1,
+    High = 2,
+}
+
+impl Priority {
+    pub(crate) fn from_name(name: &str) -> Option<Priority> {
+        match name {
+            "low" => Some(Priority::Low),
+            "normal" => Some(Priority::Normal),
+            "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+}
+
+// The points written by a storage while InfluxDB is unreachable, to be flushed once it's back.
+// The points of higher priority are flushed first and, when the buffer is full, replace the oldest points
+// of lower priority: bulk telemetry never delays nor evicts the high priority keys.
+pub(crate) struct WriteBuffer {
+    capacity: usize,
+    // the points of each priority, indexed by Priority
+    points: Mutex<[VecDeque<WriteQuery>; 3]>,
+    // held while points are flushed, so a DELETE is only run once the points taken before it are written
+    flushing: AsyncMutex<()>,
+}
+
+impl WriteBuffer {
+    pub(crate) fn new(capacity: usize) -> WriteBuffer {
+        WriteBuffer {
+            capacity,
+            points: Mutex::new(Default::default()),
+            flushing: AsyncMutex::new(()),
+        }
+    }
+
+    // Wait for the end of a flush in progress, and prevent the others until the guard is dropped
+    pub(crate) async fn lock_flush(&self) -> AsyncMutexGuard<'_, ()> {
+        self.flushing.lock().await
+    }
+
+    // Returns false if the buffer is full of points of the same or higher priority
+    pub(crate) fn push(&self, query: WriteQuery, priority: Priority) -> bool {
+        let mut points = self.points.lock().unwrap();
+        if points.iter().map(VecDeque::len).sum::<usize>() >= self.capacity {
+            match points[..priority as usize]
+                .iter_mut()
+                .find(|p| !p.is_empty())
+            {
+                Some(lower) => {
+                    lower.pop_front();
+                    warn!("Buffer of InfluxDB points is full: dropped the oldest point of lower priority");
+                }
+                None => return false,
+            }
+        }
+        points[priority as usize].push_back(query);
+        true
+    }
+
+    // Take the oldest points of the highest priorities, at most max
+    pub(crate) fn take(&self, max: usize) -> Vec<(Priority, WriteQuery)> {
+        let mut points = self.points.lock().unwrap();
+        let mut taken = Vec::new();
+        for priority in [Priority::High, Priority::Normal, Priority::Low] {
+            let queue = &mut points[priority as usize];
+            let n = (max - taken.len()).min(queue.len());
+            taken.extend(queue.drain(..n).map(|q| (priority, q)));
+        }
+        taken
+    }
+
+    // Put back points that failed to be flushed, as the oldest ones
+    pub(crate) fn restore(&self, queries: Vec<(Priority, WriteQuery)>) {
+        let mut points = self.points.lock().unwrap();
+        for (priority, q) in queries.into_iter().rev() {
+            points[priority as usize].push_front(q);
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.points.lock().unwrap().iter().map(VecDeque::len).sum()
+    }
+}
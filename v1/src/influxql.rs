@@ -0,0 +1,431 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::fmt;
+
+// Quote an identifier (database, measurement, user...) for an InfluxQL query
+pub(crate) fn quote_ident(name: &str) -> String {
+    quote(name, '"')
+}
+
+// Quote a string literal (e.g. a tag value) for an InfluxQL query
+pub(crate) fn quote_literal(value: &str) -> String {
+    quote(value, '\'')
+}
+
+// Surround a string with quote characters, escaping them, the backslashes and the line feeds it contains
+// (InfluxQL refuses the unescaped line feeds in quoted strings)
+fn quote(s: &str, q: char) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push(q);
+    for c in s.chars() {
+        match c {
+            '\n' => result.push_str("\\n"),
+            c if c == q || c == '\\' => {
+                result.push('\\');
+                result.push(c);
+            }
+            c => result.push(c),
+        }
+    }
+    result.push(q);
+    result
+}
+
+// The FROM clause of a query: the measurements to select, quoted when built from their names
+#[derive(Clone, Debug)]
+pub(crate) struct Source(String);
+
+impl Source {
+    pub(crate) fn measurement(name: &str) -> Source {
+        Source(quote_ident(name))
+    }
+
+    // A measurement of a retention policy (the default one if empty)
+    pub(crate) fn in_retention_policy(rp: &str, name: &str) -> Source {
+        match rp {
+            "" => Source::measurement(name),
+            rp => Source(format!("{}.{}", quote_ident(rp), quote_ident(name))),
+        }
+    }
+
+    // The measurements matching an InfluxQL regex (as built by key_exprs_to_influx_regex), or a measurement name
+    // already quoted
+    pub(crate) fn expr(expr: String) -> Source {
+        Source(expr)
+    }
+
+    // All the measurements of the database
+    pub(crate) fn all() -> Source {
+        Source("/.*/".to_string())
+    }
+
+    pub(crate) fn list(sources: Vec<Source>) -> Source {
+        Source(
+            sources
+                .into_iter()
+                .map(|s| s.0)
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// The comparison operators of the conditions
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Cmp {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Cmp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Cmp::Lt => "<",
+            Cmp::Le => "<=",
+            Cmp::Eq => "=",
+            Cmp::Ge => ">=",
+            Cmp::Gt => ">",
+        }
+    }
+}
+
+// A condition of a WHERE clause, with its identifiers and literals quoted
+#[derive(Clone, Debug)]
+pub(crate) struct Condition(String);
+
+impl Condition {
+    pub(crate) fn tag_eq(tag: &str, value: &str) -> Condition {
+        Condition(format!("{}={}", quote_ident(tag), quote_literal(value)))
+    }
+
+    pub(crate) fn tag_ne(tag: &str, value: &str) -> Condition {
+        Condition(format!("{}!={}", quote_ident(tag), quote_literal(value)))
+    }
+
+    pub(crate) fn field_ne(field: &str, value: &str) -> Condition {
+        Condition(format!("{}!={}", quote_ident(field), quote_literal(value)))
+    }
+
+    // The tag matches an InfluxQL regex (as built by key_exprs_to_influx_regex)
+    pub(crate) fn tag_matches(tag: &str, regex: &str) -> Condition {
+        Condition(format!("{} =~ {regex}", quote_ident(tag)))
+    }
+
+    // Compare the time of the points to a time literal (in nanoseconds, a quoted RFC3339 time, or relative to now())
+    pub(crate) fn time(cmp: Cmp, time: impl fmt::Display) -> Condition {
+        Condition(format!("time {} {time}", cmp.as_str()))
+    }
+}
+
+// A WHERE clause: the conjunction of its conditions (nothing if there's none)
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Where(Vec<Condition>);
+
+impl Where {
+    pub(crate) fn new() -> Where {
+        Where(Vec::new())
+    }
+
+    pub(crate) fn and(mut self, condition: Condition) -> Where {
+        self.0.push(condition);
+        self
+    }
+
+    pub(crate) fn push(&mut self, condition: Condition) {
+        self.0.push(condition);
+    }
+}
+
+impl fmt::Display for Where {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, condition) in self.0.iter().enumerate() {
+            f.write_str(if i == 0 { "WHERE " } else { " AND " })?;
+            f.write_str(&condition.0)?;
+        }
+        Ok(())
+    }
+}
+
+// A SELECT query
+#[derive(Clone, Debug)]
+pub(crate) struct Select {
+    columns: String,
+    from: Source,
+    filter: Where,
+    group_by: Option<String>,
+    order: Option<&'static str>,
+    limit: Option<u64>,
+    slimit: Option<(u64, u64)>,
+}
+
+impl Select {
+    // (the columns are expressions of the storage, e.g. `last("timestamp") AS "timestamp"`)
+    pub(crate) fn new(columns: &str, from: Source) -> Select {
+        Select {
+            columns: columns.to_string(),
+            from,
+            filter: Where::new(),
+            group_by: None,
+            order: None,
+            limit: None,
+            slimit: None,
+        }
+    }
+
+    pub(crate) fn filter(mut self, filter: Where) -> Select {
+        self.filter = filter;
+        self
+    }
+
+    pub(crate) fn group_by_tag(mut self, tag: &str) -> Select {
+        self.group_by = Some(quote_ident(tag));
+        self
+    }
+
+    // ("ASC" or "DESC")
+    pub(crate) fn order_by_time(mut self, order: &'static str) -> Select {
+        self.order = Some(if order.eq_ignore_ascii_case("DESC") {
+            "DESC"
+        } else {
+            "ASC"
+        });
+        self
+    }
+
+    pub(crate) fn limit(mut self, n: u64) -> Select {
+        self.limit = Some(n);
+        self
+    }
+
+    // Select a page of `n` series from `offset`
+    pub(crate) fn series_page(mut self, n: u64, offset: u64) -> Select {
+        self.slimit = Some((n, offset));
+        self
+    }
+}
+
+impl fmt::Display for Select {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SELECT {} FROM {}", self.columns, self.from)?;
+        if !self.filter.0.is_empty() {
+            write!(f, " {}", self.filter)?;
+        }
+        if let Some(group_by) = &self.group_by {
+            write!(f, " GROUP BY {group_by}")?;
+        }
+        if let Some(order) = self.order {
+            write!(f, " ORDER BY time {order}")?;
+        }
+        if let Some(n) = self.limit {
+            write!(f, " LIMIT {n}")?;
+        }
+        if let Some((n, offset)) = self.slimit {
+            write!(f, " SLIMIT {n} SOFFSET {offset}")?;
+        }
+        Ok(())
+    }
+}
+
+// A DELETE query
+#[derive(Clone, Debug)]
+pub(crate) struct Delete {
+    from: Source,
+    filter: Where,
+}
+
+impl Delete {
+    pub(crate) fn new(from: Source, filter: Where) -> Delete {
+        Delete { from, filter }
+    }
+}
+
+impl fmt::Display for Delete {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DELETE FROM {}", self.from)?;
+        if !self.filter.0.is_empty() {
+            write!(f, " {}", self.filter)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_identifiers() {
+        assert_eq!(quote_ident("demo/example/a"), r#""demo/example/a""#);
+        assert_eq!(quote_ident(""), r#""""#);
+        assert_eq!(quote_ident(r#"a"b"#), r#""a\"b""#);
+        assert_eq!(quote_ident("a'b"), r#""a'b""#);
+        assert_eq!(quote_ident(r"a\b"), r#""a\\b""#);
+        assert_eq!(quote_ident(r#"a\"b"#), r#""a\\\"b""#);
+        assert_eq!(quote_ident("a\nb"), r#""a\nb""#);
+        assert_eq!(quote_ident("a\rb"), "\"a\rb\"");
+        assert_eq!(quote_ident("é/日本 ,="), "\"é/日本 ,=\"");
+        // an identifier can't be closed early
+        assert_eq!(
+            quote_ident(r#"x"; DROP DATABASE "db"#),
+            r#""x\"; DROP DATABASE \"db""#
+        );
+    }
+
+    #[test]
+    fn quote_literals() {
+        assert_eq!(quote_literal("PUT"), "'PUT'");
+        assert_eq!(quote_literal(""), "''");
+        assert_eq!(quote_literal("it's"), r"'it\'s'");
+        assert_eq!(quote_literal(r#"a"b"#), r#"'a"b'"#);
+        assert_eq!(quote_literal(r"a\b"), r"'a\\b'");
+        assert_eq!(quote_literal(r"a\'b"), r"'a\\\'b'");
+        assert_eq!(quote_literal(r"trailing\"), r"'trailing\\'");
+        assert_eq!(quote_literal("a\nb"), r"'a\nb'");
+        // a literal "\n" (backslash and n) is distinct from a line feed
+        assert_eq!(quote_literal(r"a\nb"), r"'a\\nb'");
+        assert_eq!(quote_literal("x' OR 'a'='a"), r"'x\' OR \'a\'=\'a'");
+    }
+
+    #[test]
+    fn sources() {
+        assert_eq!(Source::measurement("demo/a").to_string(), r#""demo/a""#);
+        assert_eq!(Source::measurement(r#"a"b"#).to_string(), r#""a\"b""#);
+        assert_eq!(
+            Source::in_retention_policy("", "demo/a").to_string(),
+            r#""demo/a""#
+        );
+        assert_eq!(
+            Source::in_retention_policy("one_week", "demo/a").to_string(),
+            r#""one_week"."demo/a""#
+        );
+        assert_eq!(
+            Source::in_retention_policy(r#"r"p"#, "m").to_string(),
+            r#""r\"p"."m""#
+        );
+        // the regexes are written as-is
+        assert_eq!(
+            Source::expr(r"/^(?:demo\/[^\/@][^\/]*)$/".to_string()).to_string(),
+            r"/^(?:demo\/[^\/@][^\/]*)$/"
+        );
+        assert_eq!(Source::all().to_string(), "/.*/");
+        assert_eq!(
+            Source::list(vec![
+                Source::measurement("a"),
+                Source::measurement("b\"c"),
+                Source::expr("/^d$/".to_string())
+            ])
+            .to_string(),
+            r#""a", "b\"c", /^d$/"#
+        );
+    }
+
+    #[test]
+    fn conditions() {
+        assert_eq!(Where::new().to_string(), "");
+        assert_eq!(
+            Where::new()
+                .and(Condition::tag_eq("kind", "PUT"))
+                .to_string(),
+            r#"WHERE "kind"='PUT'"#
+        );
+        assert_eq!(
+            Where::new()
+                .and(Condition::tag_ne("kind", "DEL"))
+                .and(Condition::tag_matches("key", r"/^a\/b$/"))
+                .and(Condition::time(Cmp::Ge, "now() - 500ms"))
+                .and(Condition::time(Cmp::Lt, 1_000_u64))
+                .to_string(),
+            r#"WHERE "kind"!='DEL' AND "key" =~ /^a\/b$/ AND time >= now() - 500ms AND time < 1000"#
+        );
+        let mut filter = Where::new().and(Condition::tag_eq(r#"t"a"g"#, "it's"));
+        filter.push(Condition::time(Cmp::Le, "'2023-01-01T00:00:00Z'"));
+        filter.push(Condition::time(Cmp::Gt, 0));
+        filter.push(Condition::time(Cmp::Eq, 5));
+        assert_eq!(
+            filter.to_string(),
+            r#"WHERE "t\"a\"g"='it\'s' AND time <= '2023-01-01T00:00:00Z' AND time > 0 AND time = 5"#
+        );
+        assert_eq!(
+            Where::new()
+                .and(Condition::tag_eq("key", "a\nb"))
+                .to_string(),
+            r#"WHERE "key"='a\nb'"#
+        );
+    }
+
+    #[test]
+    fn selects() {
+        assert_eq!(
+            Select::new("*", Source::measurement("m")).to_string(),
+            r#"SELECT * FROM "m""#
+        );
+        assert_eq!(
+            Select::new(r#""timestamp", "value""#, Source::all())
+                .filter(Where::new().and(Condition::tag_ne("kind", "DEL")))
+                .group_by_tag("key")
+                .order_by_time("desc")
+                .limit(1)
+                .series_page(100, 200)
+                .to_string(),
+            r#"SELECT "timestamp", "value" FROM /.*/ WHERE "kind"!='DEL' GROUP BY "key" ORDER BY time DESC LIMIT 1 SLIMIT 100 SOFFSET 200"#
+        );
+        assert_eq!(
+            Select::new("count(\"value\")", Source::measurement("m"))
+                .order_by_time("ASC")
+                .to_string(),
+            r#"SELECT count("value") FROM "m" ORDER BY time ASC"#
+        );
+        // anything else than DESC is ascending
+        assert_eq!(
+            Select::new("*", Source::measurement("m"))
+                .order_by_time("random")
+                .to_string(),
+            r#"SELECT * FROM "m" ORDER BY time ASC"#
+        );
+        assert_eq!(
+            Select::new("*", Source::measurement("m"))
+                .group_by_tag(r#"k"ey"#)
+                .to_string(),
+            r#"SELECT * FROM "m" GROUP BY "k\"ey""#
+        );
+    }
+
+    #[test]
+    fn deletes() {
+        assert_eq!(
+            Delete::new(Source::measurement("demo/a"), Where::new()).to_string(),
+            r#"DELETE FROM "demo/a""#
+        );
+        assert_eq!(
+            Delete::new(
+                Source::measurement("zenoh"),
+                Where::new()
+                    .and(Condition::tag_eq("key", "demo/it's"))
+                    .and(Condition::time(Cmp::Lt, 1_700_000_000_000_000_000_u64))
+            )
+            .to_string(),
+            r#"DELETE FROM "zenoh" WHERE "key"='demo/it\'s' AND time < 1700000000000000000"#
+        );
+    }
+}
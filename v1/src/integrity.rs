@@ -0,0 +1,77 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use sha2::{Digest, Sha256};
+
+// The algorithms of the hashes of the values, verified on GET (see `value_hash` property).
+// The hash is stored as "<algorithm>:<hex>", so it can be verified whatever the current setting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ValueHash {
+    Sha256,
+    XxHash64,
+}
+
+// The result of the verification of a value against its stored hash
+pub(crate) enum Verification {
+    Valid,
+    Corrupted,
+    // the hash is of an unknown algorithm
+    Unknown,
+}
+
+impl ValueHash {
+    pub(crate) fn from_name(name: &str) -> Option<ValueHash> {
+        match name {
+            "sha256" => Some(ValueHash::Sha256),
+            "xxhash64" => Some(ValueHash::XxHash64),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ValueHash::Sha256 => "sha256",
+            ValueHash::XxHash64 => "xxhash64",
+        }
+    }
+
+    // The hash of a payload, as stored in the points
+    pub(crate) fn compute(&self, payload: &[u8]) -> String {
+        let hex = match self {
+            ValueHash::Sha256 => Sha256::digest(payload)
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect(),
+            ValueHash::XxHash64 => format!("{:016x}", xxhash_rust::xxh64::xxh64(payload, 0)),
+        };
+        format!("{}:{}", self.name(), hex)
+    }
+}
+
+// Verify a payload against its stored hash
+pub(crate) fn verify(hash: &str, payload: &[u8]) -> Verification {
+    let algorithm = hash
+        .split_once(':')
+        .and_then(|(a, _)| ValueHash::from_name(a));
+    match algorithm {
+        Some(a) if a.compute(payload) == hash => Verification::Valid,
+        Some(_) => Verification::Corrupted,
+        None => Verification::Unknown,
+    }
+}
+
+// The points possibly storing the hash of their value
+pub(crate) trait HashedPoint {
+    fn value_hash(&self) -> Option<String>;
+}
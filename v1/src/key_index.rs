@@ -0,0 +1,169 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::{quote_ident, quote_literal, InfluxDbError, KEY_TAG};
+use influxdb::{Client, ReadQuery, Timestamp as InfluxTimestamp, WriteQuery};
+use log::warn;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::SystemTime;
+use zenoh::prelude::keyexpr;
+use zenoh::time::Timestamp;
+
+// The measurement of the key index
+pub(crate) const KEY_INDEX_MEASUREMENT: &str = "@@zenoh_keys@@";
+
+// The index of the keys written by a storage (`key_index` property): a measurement with 1 point per key (at time 0,
+// so it's overwritten on each update) with the key as "key" tag, and the timestamps of its first and last PUT or DELETE
+// as "first" and "last" fields. It's loaded when the storage starts and kept in memory, so the keys are listed without
+// scanning the measurements: the points written or removed by others than the storage are not indexed.
+pub(crate) struct KeyIndex {
+    // the first and last timestamps of each key
+    keys: RwLock<HashMap<String, (Timestamp, Timestamp)>>,
+}
+
+impl KeyIndex {
+    // Load the index from the databases of the storage
+    pub(crate) async fn load(clients: &[Client]) -> Result<KeyIndex, InfluxDbError> {
+        #[derive(Deserialize, Debug)]
+        struct KeyTag {
+            key: String,
+        }
+        #[derive(Deserialize, Debug)]
+        struct IndexPoint {
+            first: String,
+            last: String,
+        }
+
+        let mut keys = HashMap::new();
+        for client in clients {
+            let query = ReadQuery::new(format!(
+                r#"SELECT "first", "last" FROM {} GROUP BY "{KEY_TAG}""#,
+                quote_ident(KEY_INDEX_MEASUREMENT)
+            ));
+            let series = match client.json_query(query).await {
+                Ok(mut result) => match result.deserialize_next_tagged::<KeyTag, IndexPoint>() {
+                    Ok(qr) => qr.series,
+                    Err(e) => fail_client!(
+                        e,
+                        "Failed to parse the key index of InfluxDb database {}",
+                        client.database_name()
+                    ),
+                },
+                Err(e) => fail_client!(
+                    e,
+                    "Failed to load the key index of InfluxDb database {}",
+                    client.database_name()
+                ),
+            };
+            for serie in series {
+                for point in serie.values {
+                    match (point.first.parse(), point.last.parse()) {
+                        (Ok(first), Ok(last)) => {
+                            keys.insert(serie.tags.key.clone(), (first, last));
+                        }
+                        _ => warn!(
+                            "Invalid timestamps in key index of {} in InfluxDb database {}",
+                            serie.tags.key,
+                            client.database_name()
+                        ),
+                    }
+                }
+            }
+        }
+        Ok(KeyIndex {
+            keys: RwLock::new(keys),
+        })
+    }
+
+    // The index point to write with a point of the key, if it changes its first or last timestamps
+    pub(crate) fn point(&self, key: &str, timestamp: &Timestamp) -> Option<WriteQuery> {
+        let (first, last) = match self.keys.read().unwrap().get(key) {
+            Some((first, last)) if first <= timestamp && timestamp <= last => return None,
+            Some((first, last)) => ((*first).min(*timestamp), (*last).max(*timestamp)),
+            None => (*timestamp, *timestamp),
+        };
+        Some(
+            WriteQuery::new(InfluxTimestamp::Nanoseconds(0), KEY_INDEX_MEASUREMENT)
+                .add_tag(KEY_TAG, key)
+                .add_field("first", first.to_string())
+                .add_field("last", last.to_string()),
+        )
+    }
+
+    // Record a point of the key, once written
+    pub(crate) fn record(&self, key: &str, timestamp: &Timestamp) {
+        let mut keys = self.keys.write().unwrap();
+        match keys.get_mut(key) {
+            Some((first, last)) => {
+                *first = (*first).min(*timestamp);
+                *last = (*last).max(*timestamp);
+            }
+            None => {
+                keys.insert(key.to_string(), (*timestamp, *timestamp));
+            }
+        }
+    }
+
+    // Remove a key from the index, returning the query removing its index point
+    pub(crate) fn remove(&self, key: &str) -> ReadQuery {
+        self.keys.write().unwrap().remove(key);
+        ReadQuery::new(format!(
+            r#"DROP SERIES FROM {} WHERE "{KEY_TAG}"={}"#,
+            quote_ident(KEY_INDEX_MEASUREMENT),
+            quote_literal(key)
+        ))
+    }
+
+    // The keys whose last point is older than the time (e.g. with no point left after a trim)
+    pub(crate) fn older_keys(&self, time: SystemTime) -> Vec<String> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, (_, last))| last.get_time().to_system_time() < time)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    // The keys intersecting the key expression (all by default), with their last timestamp
+    pub(crate) fn entries(&self, key_expr: Option<&keyexpr>) -> Vec<(String, Timestamp)> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(key, _)| match key_expr {
+                Some(ke) => keyexpr::new(key.as_str()).map_or(false, |k| ke.intersects(k)),
+                None => true,
+            })
+            .map(|(key, (_, last))| (key.clone(), *last))
+            .collect()
+    }
+
+    // The keys intersecting the key expression, with their first and last timestamps
+    pub(crate) fn coverage(&self, key_expr: &keyexpr) -> Vec<(String, Timestamp, Timestamp)> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(key, _)| keyexpr::new(key.as_str()).map_or(false, |k| key_expr.intersects(k)))
+            .map(|(key, (first, last))| (key.clone(), *first, *last))
+            .collect()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.keys.read().unwrap().len()
+    }
+}
@@ -0,0 +1,278 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::RwLock;
+use zenoh::prelude::{keyexpr, OwnedKeyExpr};
+use zenoh::Result as ZResult;
+
+// The mapping of the keys to the InfluxDB measurements (and key tags) of their points, and back.
+// It's derived from the `layout` and `max_measurement_length` properties of the storage.
+pub(crate) trait KeyMapper: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // The measurement of the points of a key
+    fn measurement(&self, key: &str) -> String;
+
+    // The key tag of the points of a key, if their measurement is shared with other keys
+    fn key_tag<'a>(&self, key: &'a str) -> Option<&'a str>;
+
+    // The key of a serie, named after its measurement (or its key tag in a shared measurement)
+    fn key(&self, serie: &str) -> String;
+}
+
+// Each key is a measurement ("measurement_per_key" layout)
+pub(crate) struct PerKeyMapper;
+
+impl KeyMapper for PerKeyMapper {
+    fn name(&self) -> &'static str {
+        "measurement_per_key"
+    }
+
+    fn measurement(&self, key: &str) -> String {
+        key.to_string()
+    }
+
+    fn key_tag<'a>(&self, _key: &'a str) -> Option<&'a str> {
+        None
+    }
+
+    fn key(&self, serie: &str) -> String {
+        serie.to_string()
+    }
+}
+
+// All keys are in the same measurement, with the key as a tag ("single_measurement" layout)
+pub(crate) struct SingleMeasurementMapper {
+    pub(crate) measurement: String,
+}
+
+impl KeyMapper for SingleMeasurementMapper {
+    fn name(&self) -> &'static str {
+        "single_measurement"
+    }
+
+    fn measurement(&self, _key: &str) -> String {
+        self.measurement.clone()
+    }
+
+    fn key_tag<'a>(&self, key: &'a str) -> Option<&'a str> {
+        Some(key)
+    }
+
+    fn key(&self, serie: &str) -> String {
+        serie.to_string()
+    }
+}
+
+// Each key is a measurement, the keys too long to be used as measurement names being stored in measurements
+// named with a hash ("measurement_per_key" layout with `max_measurement_length`)
+pub(crate) struct HashingMapper {
+    pub(crate) max_length: usize,
+    // hashed measurement name -> key
+    pub(crate) names: RwLock<HashMap<String, String>>,
+}
+
+impl HashingMapper {
+    // Returns the measurement name for a key if it's too long to be used as-is:
+    // the key truncated to fit max_length, with a suffix made of its hash
+    pub(crate) fn hashed_name(&self, key: &str) -> Option<String> {
+        if key.len() <= self.max_length {
+            return None;
+        }
+        let hash = Sha256::digest(key.as_bytes());
+        let suffix: String = hash[..8].iter().map(|b| format!("{b:02x}")).collect();
+        let mut end = self.max_length - suffix.len() - 1;
+        while !key.is_char_boundary(end) {
+            end -= 1;
+        }
+        Some(format!("{}~{}", &key[..end], suffix))
+    }
+}
+
+impl KeyMapper for HashingMapper {
+    fn name(&self) -> &'static str {
+        "hashing"
+    }
+
+    fn measurement(&self, key: &str) -> String {
+        self.hashed_name(key).unwrap_or_else(|| key.to_string())
+    }
+
+    fn key_tag<'a>(&self, _key: &'a str) -> Option<&'a str> {
+        None
+    }
+
+    fn key(&self, serie: &str) -> String {
+        match self.names.read().unwrap().get(serie) {
+            Some(key) => key.clone(),
+            None => serie.to_string(),
+        }
+    }
+}
+
+// The key expression of a serie, named after its measurement (or its key tag in a shared measurement),
+// None for the points with no key
+pub(crate) fn keyexpr_from_serie(
+    mapper: &dyn KeyMapper,
+    none_key: &keyexpr,
+    serie_name: &str,
+) -> ZResult<Option<OwnedKeyExpr>> {
+    let key = mapper.key(serie_name);
+    if key.eq(none_key.as_str()) {
+        Ok(None)
+    } else {
+        match OwnedKeyExpr::from_str(&key) {
+            Ok(key) => Ok(Some(key)),
+            Err(e) => Err(format!("{}", e).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NONE_KEY;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    // the max_measurement_length of the hashing mapper
+    const MAX_LENGTH: usize = 64;
+
+    // The keys to map and back, with the generated ones: some are longer than MAX_LENGTH (with multi-bytes
+    // characters where they're truncated)
+    fn keys(generated: Vec<String>) -> Vec<String> {
+        let mut keys: Vec<String> = [
+            "a",
+            "demo/example/a",
+            "demo/example/with space",
+            "demo/example/with,comma=equal\"quote'",
+            "demo/é/ü/日本",
+            "demo/example/@meta",
+        ]
+        .iter()
+        .map(|k| k.to_string())
+        .collect();
+        keys.push(format!("demo/example/{}", "x".repeat(200)));
+        keys.push(format!("demo/{}", "é".repeat(100)));
+        keys.push(format!("demo/{}/a", "y".repeat(MAX_LENGTH)));
+        keys.push(format!("demo/{}/b", "y".repeat(MAX_LENGTH)));
+        keys.extend(generated);
+        keys
+    }
+
+    // Keys of 1 to 6 chunks of up to 30 characters (some multi-bytes)
+    fn generated_keys() -> impl Strategy<Value = Vec<String>> {
+        vec(
+            vec("[abcXYZ019_. é日-]{1,30}", 1..7).prop_map(|chunks| chunks.join("/")),
+            1..50,
+        )
+    }
+
+    // The serie of the points of a key, as read back: its key tag in a shared measurement, else its measurement
+    fn serie(mapper: &dyn KeyMapper, key: &str) -> String {
+        match mapper.key_tag(key) {
+            Some(tag) => tag.to_string(),
+            None => mapper.measurement(key),
+        }
+    }
+
+    fn assert_round_trip(mapper: &dyn KeyMapper, keys: &[String]) {
+        let none_key = keyexpr::new(NONE_KEY).unwrap();
+        for key in keys {
+            let ke = keyexpr_from_serie(mapper, none_key, &serie(mapper, key)).unwrap();
+            assert_eq!(
+                ke.as_ref().map(|k| k.as_str()),
+                Some(key.as_str()),
+                "{} mapper doesn't map back key {:?}",
+                mapper.name(),
+                key
+            );
+        }
+        assert_eq!(
+            keyexpr_from_serie(mapper, none_key, &serie(mapper, NONE_KEY)).unwrap(),
+            None
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn per_key_round_trip(generated in generated_keys()) {
+            let keys = keys(generated);
+            for key in &keys {
+                assert_eq!(PerKeyMapper.measurement(key), *key);
+                assert_eq!(PerKeyMapper.key_tag(key), None);
+            }
+            assert_round_trip(&PerKeyMapper, &keys);
+        }
+
+        #[test]
+        fn single_measurement_round_trip(generated in generated_keys()) {
+            let mapper = SingleMeasurementMapper {
+                measurement: "zenoh".into(),
+            };
+            let keys = keys(generated);
+            for key in &keys {
+                assert_eq!(mapper.measurement(key), "zenoh");
+                assert_eq!(mapper.key_tag(key), Some(key.as_str()));
+            }
+            assert_round_trip(&mapper, &keys);
+        }
+
+        #[test]
+        fn hashing_round_trip(generated in generated_keys()) {
+            let mapper = HashingMapper {
+                max_length: MAX_LENGTH,
+                names: RwLock::new(HashMap::new()),
+            };
+            let keys = keys(generated);
+            // register the hashed names of the long keys, as the storage does on PUT
+            for key in &keys {
+                match mapper.hashed_name(key) {
+                    Some(name) => {
+                        assert!(key.len() > MAX_LENGTH);
+                        assert!(name.len() <= MAX_LENGTH, "too long name {:?}", name);
+                        mapper.names.write().unwrap().insert(name, key.clone());
+                    }
+                    None => {
+                        assert!(key.len() <= MAX_LENGTH);
+                        assert_eq!(mapper.measurement(key), *key);
+                    }
+                }
+            }
+            assert_round_trip(&mapper, &keys);
+
+            // distinct keys have distinct measurements (even with a common prefix longer than MAX_LENGTH)
+            let unique: HashSet<&String> = keys.iter().collect();
+            let measurements: HashSet<String> =
+                unique.iter().map(|k| mapper.measurement(k)).collect();
+            assert_eq!(measurements.len(), unique.len());
+        }
+    }
+
+    #[test]
+    fn hashing_unregistered_name() {
+        let mapper = HashingMapper {
+            max_length: MAX_LENGTH,
+            names: RwLock::new(HashMap::new()),
+        };
+        // the measurements not registered as hashed names are the keys themselves
+        assert_eq!(mapper.key("demo/example/a"), "demo/example/a");
+        let name = mapper.hashed_name(&"z".repeat(100)).unwrap();
+        assert_eq!(mapper.key(&name), name);
+    }
+}
@@ -12,24 +12,32 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
+use async_std::io::prelude::BufReadExt;
+use async_std::sync::Mutex as AsyncMutex;
 use async_std::task;
 use async_trait::async_trait;
-use base64::{engine::general_purpose::STANDARD as b64_std_engine, Engine};
+use futures::StreamExt;
 use influxdb::{
     Client, ReadQuery as InfluxRQuery, Timestamp as InfluxTimestamp, WriteQuery as InfluxWQuery,
 };
 use log::{debug, error, warn};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::convert::{TryFrom, TryInto};
+use std::path::{Component, Path, PathBuf};
 use std::str::FromStr;
-use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, Weak};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "tracing")]
+use tracing::field::Empty;
 use uuid::Uuid;
 use zenoh::buffers::{buffer::SplitBuffer, ZBuf};
 use zenoh::prelude::*;
 use zenoh::properties::Properties;
 use zenoh::selector::TimeExpr;
-use zenoh::time::Timestamp;
+use zenoh::time::{new_reception_timestamp, Timestamp, NTP64};
 use zenoh::Result as ZResult;
 use zenoh_backend_traits::config::{
     PrivacyGetResult, PrivacyTransparentGet, StorageConfig, VolumeConfig,
@@ -40,35 +48,470 @@ use zenoh_core::{bail, zerror};
 use zenoh_plugin_trait::{plugin_long_version, plugin_version, Plugin};
 use zenoh_util::{Timed, TimedEvent, TimedHandle, Timer};
 
+#[macro_use]
+mod error;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod blobs;
+mod breaker;
+mod cache;
+mod change_feed;
+mod chunking;
+mod client;
+mod codec;
+mod compression;
+mod credentials;
+mod dedup;
+mod digest;
+mod entries;
+mod events;
+#[cfg(feature = "fault_injection")]
+mod fault_injection;
+mod health;
+mod influxql;
+mod integrity;
+mod key_index;
+mod keymap;
+mod line_protocol;
+mod maintenance;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod mirror;
+#[cfg(feature = "otlp")]
+mod otlp;
+mod parameters;
+mod rate_limit;
+mod replay;
+mod secrets;
+mod stats;
+mod tls;
+mod v2_target;
+use blobs::{BlobRef, BlobStore, OffloadedPoint, S3Bucket, DEFAULT_OFFLOAD_THRESHOLD};
+use breaker::CircuitBreaker;
+use cache::LatestCache;
+use change_feed::{ChangeFeed, ChangeFeedConfig, ChangeFeedSource, DEFAULT_CHANGE_FEED_INTERVAL};
+use chunking::{reassemble, split_value, ChunkedPoint, Reassembled, DEFAULT_CHUNK_SIZE};
+use client::InfluxClient;
+use codec::{
+    CompressedCodec, EncodedValue, JsonFlattenCodec, NumericCodec, StringCodec, ValueCodec,
+};
+use compression::{Compression, DEFAULT_COMPRESSION_THRESHOLD};
+use credentials::{
+    read_credentials_file, reload_credentials, write_credentials_file, SharedClient,
+};
+use dedup::{PutDedup, DEFAULT_DEDUP_INTERVAL};
+use digest::{digest, DEFAULT_DIGEST_DEPTH};
+use entries::EntriesCache;
+pub use error::{is_retryable, InfluxDbError};
+use events::Events;
+use health::{Health, Priority, WriteBuffer};
+use influxql::{quote_ident, quote_literal, Cmp, Condition, Delete, Select, Source, Where};
+use integrity::{HashedPoint, ValueHash, Verification};
+use key_index::{KeyIndex, KEY_INDEX_MEASUREMENT};
+use keymap::{keyexpr_from_serie, HashingMapper, KeyMapper, PerKeyMapper, SingleMeasurementMapper};
+use line_protocol::{
+    context_database, export_header, parse_line, to_line, LineSplitter, EXPORT_CHUNK_SIZE,
+};
+use maintenance::{MaintenanceQueue, DEFAULT_MAINTENANCE_QUEUE_SIZE, DEFAULT_MAINTENANCE_THREADS};
+use mirror::Mirror;
+use parameters::GetParameters;
+use rate_limit::RateLimiter;
+use replay::{ReplayPacing, ReplayedPoint, Replayer};
+use secrets::{default_secret_provider, VaultKv2};
+pub use secrets::{set_secret_provider, SecretProvider};
+use stats::StorageStats;
+use tls::TlsConfig;
+use v2_target::{v2_line, V2Target};
+
 // Properties used by the Backend
 pub const PROP_BACKEND_URL: &str = "url";
 pub const PROP_BACKEND_USERNAME: &str = "username";
 pub const PROP_BACKEND_PASSWORD: &str = "password";
+pub const PROP_BACKEND_MAINTENANCE_THREADS: &str = "maintenance_threads";
+pub const PROP_BACKEND_MAINTENANCE_QUEUE_SIZE: &str = "maintenance_queue_size";
+pub const PROP_BACKEND_HISTORY: &str = "history";
+pub const PROP_BACKEND_HEALTH_CHECK_INTERVAL: &str = "health_check_interval";
+pub const PROP_BACKEND_CREDENTIALS_FILE: &str = "credentials_file";
+pub const PROP_BACKEND_VAULT: &str = "vault";
+pub const PROP_BACKEND_METRICS_LISTEN: &str = "metrics_listen";
+pub const PROP_BACKEND_OTLP: &str = "otlp";
+pub const PROP_BACKEND_REQUIRE_ADMIN: &str = "require_admin";
 
 // Properties used by the Storage
 pub const PROP_STORAGE_DB: &str = "db";
+pub const PROP_STORAGE_URL: &str = PROP_BACKEND_URL;
+pub const PROP_STORAGE_TLS: &str = "tls";
 pub const PROP_STORAGE_CREATE_DB: &str = "create_db";
+pub const PROP_STORAGE_PROVISION_CREDENTIALS: &str = "provision_credentials";
 pub const PROP_STORAGE_ON_CLOSURE: &str = "on_closure";
+pub const PROP_STORAGE_TOMBSTONE_WINDOW: &str = "tombstone_window";
+pub const PROP_STORAGE_KEY_TAGS: &str = "key_tags";
+pub const PROP_STORAGE_VERIFY_INTERVAL: &str = "verify_interval";
+pub const PROP_STORAGE_ON_DRIFT: &str = "on_drift";
+pub const PROP_STORAGE_LAYOUT: &str = "layout";
+pub const PROP_STORAGE_MEASUREMENT: &str = "measurement";
+pub const PROP_STORAGE_MAX_SERIES: &str = "max_series";
+pub const PROP_STORAGE_ON_MAX_SERIES: &str = "on_max_series";
+pub const PROP_STORAGE_CARDINALITY_CHECK_INTERVAL: &str = "cardinality_check_interval";
+pub const PROP_STORAGE_STATS_INTERVAL: &str = "stats_interval";
+pub const PROP_STORAGE_SCHEMA: &str = "schema";
+pub const PROP_STORAGE_MAX_MEASUREMENT_LENGTH: &str = "max_measurement_length";
+pub const PROP_STORAGE_DEDUP_WINDOW: &str = "dedup_window";
+pub const PROP_STORAGE_READ_EXTERNAL: &str = "read_external";
+pub const PROP_STORAGE_EXTERNAL_VALUE_FIELD: &str = "external_value_field";
+pub const PROP_STORAGE_FAULT_INJECTION: &str = "fault_injection";
+pub const PROP_STORAGE_STRICT_SCHEMA: &str = "strict_schema";
+pub const PROP_STORAGE_MAX_POINTS_PER_CHUNK: &str = "max_points_per_chunk";
+pub const PROP_STORAGE_NUMERIC_VALUES: &str = "numeric_values";
+pub const PROP_STORAGE_STRICT_PARAMETERS: &str = "strict_parameters";
+pub const PROP_STORAGE_LATEST_CACHE_SIZE: &str = "latest_cache_size";
+pub const PROP_STORAGE_RETENTION: &str = "retention";
+pub const PROP_STORAGE_MAX_HISTORY_PER_KEY: &str = "max_history_per_key";
+pub const PROP_STORAGE_HISTORY_TRIM_INTERVAL: &str = "history_trim_interval";
+pub const PROP_STORAGE_TTL: &str = "ttl";
+pub const PROP_STORAGE_GC_INTERVAL: &str = "gc_interval";
+pub const PROP_STORAGE_TOMBSTONE_TTL: &str = "tombstone_ttl";
+pub const PROP_STORAGE_DROP_MEASUREMENT_TIMEOUT: &str = "drop_measurement_timeout";
+pub const PROP_STORAGE_DOWNSAMPLING: &str = "downsampling";
+pub const PROP_STORAGE_ARCHIVE: &str = "archive";
+pub const PROP_STORAGE_REPLICAS: &str = "replicas";
+pub const PROP_STORAGE_WRITE_ONLY: &str = "write_only";
+pub const PROP_STORAGE_BUFFER_SIZE: &str = "buffer_size";
+pub const PROP_STORAGE_CIRCUIT_BREAKER: &str = "circuit_breaker";
+pub const PROP_STORAGE_SHUTDOWN_TIMEOUT: &str = "shutdown_timeout";
+pub const PROP_STORAGE_ON_CLOSURE_PROTECTION: &str = "on_closure_protection";
+pub const PROP_STORAGE_ADMIN_OPERATIONS: &str = "admin_operations";
+pub const PROP_STORAGE_ALLOW_RAW_QUERIES: &str = "allow_raw_queries";
+pub const PROP_STORAGE_EXPORT_DIR: &str = "export_dir";
+pub const PROP_STORAGE_IMPORT_DIR: &str = "import_dir";
+pub const PROP_STORAGE_SLOW_QUERY_MS: &str = "slow_query_ms";
+pub const PROP_STORAGE_NONE_KEY: &str = "none_key";
+pub const PROP_STORAGE_ROUTING: &str = "routing";
+pub const PROP_STORAGE_COMPRESSION: &str = "compression";
+pub const PROP_STORAGE_COMPRESSION_THRESHOLD: &str = "compression_threshold";
+pub const PROP_STORAGE_CODEC: &str = "codec";
+pub const PROP_STORAGE_CHUNK_SIZE: &str = "chunk_size";
+pub const PROP_STORAGE_OFFLOAD: &str = "offload";
+pub const PROP_STORAGE_VALUE_HASH: &str = "value_hash";
+pub const PROP_STORAGE_ON_CORRUPTED: &str = "on_corrupted";
+pub const PROP_STORAGE_MIGRATE_SCHEMA: &str = "migrate_schema";
+pub const PROP_STORAGE_MIGRATE_V2: &str = "migrate_v2";
+pub const PROP_STORAGE_ALIGNMENT_CONCURRENCY: &str = "alignment_concurrency";
+pub const PROP_STORAGE_ENTRIES_CACHE: &str = "entries_cache";
+pub const PROP_STORAGE_CHANGE_FEED: &str = "change_feed";
+pub const PROP_STORAGE_EVENTS: &str = "events";
+pub const PROP_STORAGE_REPLAY: &str = "replay";
+pub const PROP_STORAGE_KEY_INDEX: &str = "key_index";
+pub const PROP_STORAGE_MAX_POINTS_PER_SEC: &str = "max_points_per_sec";
+pub const PROP_STORAGE_MAX_BYTES_PER_SEC: &str = "max_bytes_per_sec";
+pub const PROP_STORAGE_ON_RATE_LIMIT: &str = "on_rate_limit";
+pub const PROP_STORAGE_PRIORITIES: &str = "priorities";
+pub const PROP_STORAGE_DEDUP: &str = "dedup";
+pub const PROP_STORAGE_DEDUP_INTERVAL: &str = "dedup_interval";
+pub const PROP_STORAGE_DEADBAND: &str = "deadband";
+pub const PROP_STORAGE_MAX_FUTURE_SKEW: &str = "max_future_skew";
+pub const PROP_STORAGE_MAX_PAST_SKEW: &str = "max_past_skew";
+pub const PROP_STORAGE_ON_CLOCK_SKEW: &str = "on_clock_skew";
+pub const PROP_STORAGE_CONFLICT_POLICY: &str = "conflict_policy";
+pub const PROP_STORAGE_DELETE_STRATEGY: &str = "delete_strategy";
 pub const PROP_STORAGE_USERNAME: &str = PROP_BACKEND_USERNAME;
 pub const PROP_STORAGE_PASSWORD: &str = PROP_BACKEND_PASSWORD;
+pub const PROP_STORAGE_CREDENTIALS_FILE: &str = PROP_BACKEND_CREDENTIALS_FILE;
 
-// Special key for None (when the prefix being stripped exactly matches the key)
+// Selector parameters
+pub const PARAM_TIME: &str = "_time";
+pub const PARAM_COVERAGE: &str = "_coverage";
+pub const PARAM_LIMIT: &str = "_limit";
+pub const PARAM_ORDER: &str = "_order";
+pub const PARAM_AGG: &str = "_agg";
+pub const PARAM_WINDOW: &str = "_window";
+pub const PARAM_MAX_SAMPLES: &str = "_max_samples";
+pub const PARAM_INCLUDE_DELETES: &str = "_include_deletes";
+pub const PARAM_AT: &str = "_at";
+
+// Encoding suffix marking the deletions in the replies with "_include_deletes"
+// (StoredData has no kind to indicate them)
+pub const DELETE_ENCODING_SUFFIX: &str = "zenoh-delete";
+// encoding suffix of the values replied with a hash mismatch (with "on_corrupted": "flag")
+pub const CORRUPTED_ENCODING_SUFFIX: &str = "zenoh-corrupted";
+
+// Default special key for None (when the prefix being stripped exactly matches the key)
 pub const NONE_KEY: &str = "@@none_key@@";
 
-// delay after deletion to drop a measurement
-const DROP_MEASUREMENT_TIMEOUT_MS: u64 = 5000;
+// Default measurement name for the "single_measurement" layout
+const DEFAULT_MEASUREMENT: &str = "zenoh";
+
+// Default name of the retention policy created with the `retention` property
+const DEFAULT_RETENTION_POLICY: &str = "zenoh";
+
+// Defaults for the forwarding of the points to the archive and replica databases
+const DEFAULT_MIRROR_BATCH_SIZE: u64 = 100;
+const DEFAULT_MIRROR_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_MIRROR_QUEUE_SIZE: u64 = 10_000;
+
+// Defaults for the circuit breaker on the GET queries
+const DEFAULT_BREAKER_WINDOW: u64 = 20;
+const DEFAULT_BREAKER_ERROR_RATE: f64 = 0.5;
+const DEFAULT_BREAKER_COOL_DOWN: Duration = Duration::from_secs(10);
+
+// By default, a downsampling tier is read by the GETs on time ranges of at least this number of its intervals
+const DEFAULT_TIER_MIN_INTERVALS: u32 = 1000;
+
+// Tag storing the key in the "single_measurement" layout
+const KEY_TAG: &str = "key";
+
+// Measurement mapping the hashed measurement names of too long keys to the keys
+const KEY_NAMES_MEASUREMENT: &str = "@@key_names@@";
+
+// Prefix of the keys triggering a maintenance operation on GET (e.g. "@admin/flush"), if enabled
+const ADMIN_KEY_PREFIX: &str = "@admin/";
+// Key running the read-only InfluxQL query given as "q" parameter on GET (e.g. "@raw?q=SHOW MEASUREMENTS"), if enabled
+const RAW_QUERY_KEY: &str = "@raw";
+
+// Default field used as value for the points not written by zenoh (see `read_external` property)
+const DEFAULT_EXTERNAL_VALUE_FIELD: &str = "value";
+
+// Minimal value for max_measurement_length (to keep some readable chars before the hash suffix)
+const MIN_MEASUREMENT_LENGTH: u64 = 32;
+
+// Field storing the numeric values as floats (see `numeric_values` property)
+const NUMERIC_VALUE_FIELD: &str = "value_f";
+
+// The aggregation functions supported by the "_agg" selector parameter
+const AGGREGATIONS: &[&str] = &["mean", "max", "min", "count", "sum", "first", "last"];
+
+// Tag of the points kept besides a point of another writer at the same time (see `conflict_policy` property)
+const CONFLICT_TAG: &str = "conflict";
+
+// Field storing the encoding as a string in the points written by previous versions
+const LEGACY_ENCODING_FIELD: &str = "encoding";
+
+// Field storing the algorithm compressing the value (see `compression` property)
+const COMPRESSION_FIELD: &str = "compression";
+
+// Tag and field of the chunks of the values split in several points (see `chunk_size` property).
+// The index is a tag, so that the chunks written at the same time are distinct points.
+const CHUNK_INDEX_TAG: &str = "chunk_index";
+const CHUNK_COUNT_FIELD: &str = "chunk_count";
+
+// Fields referencing the payload offloaded to the blob store (see `offload` property)
+const BLOB_REF_FIELD: &str = "blob_ref";
+const BLOB_HASH_FIELD: &str = "blob_hash";
+
+// Field storing the hash of the value (see `value_hash` property)
+const VALUE_HASH_FIELD: &str = "value_hash";
+
+// Field storing the version of the schema of the points, written on each point:
+// - 1 (points without the field): the encoding is stored as a string in the "encoding" field
+// - 2: the encoding is stored in the "encoding_prefix" and "encoding_suffix" fields
+const SCHEMA_VERSION_FIELD: &str = "schema_version";
+const SCHEMA_VERSION: u64 = 2;
+
+// max number of points per serie read (and rewritten) at once by a schema migration
+const MIGRATION_BATCH_SIZE: usize = 10000;
+// max number of points per serie replayed at once (loaded in memory)
+const MAX_REPLAYED_POINTS: usize = 1_000_000;
+
+// Names of the tags and fields of the points written by zenoh
+const ZENOH_COLUMNS: &[&str] = &[
+    KEY_TAG,
+    "kind",
+    "timestamp",
+    "encoding_prefix",
+    "encoding_suffix",
+    LEGACY_ENCODING_FIELD,
+    "base64",
+    COMPRESSION_FIELD,
+    CHUNK_INDEX_TAG,
+    CHUNK_COUNT_FIELD,
+    BLOB_REF_FIELD,
+    BLOB_HASH_FIELD,
+    VALUE_HASH_FIELD,
+    SCHEMA_VERSION_FIELD,
+    "value",
+    NUMERIC_VALUE_FIELD,
+    "tombstone_start",
+];
+
+// Names of the tags and fields read on GET (i.e. the ones of `ZenohPoint`)
+const GET_COLUMNS: &[&str] = &[
+    "kind",
+    "timestamp",
+    "encoding_prefix",
+    "encoding_suffix",
+    LEGACY_ENCODING_FIELD,
+    "base64",
+    COMPRESSION_FIELD,
+    CHUNK_INDEX_TAG,
+    CHUNK_COUNT_FIELD,
+    BLOB_REF_FIELD,
+    BLOB_HASH_FIELD,
+    VALUE_HASH_FIELD,
+    "value",
+];
+
+// max number of measurements in the FROM clause of a query of get_all_entries()
+const GET_ALL_ENTRIES_BATCH_SIZE: usize = 100;
+// number of measurements (or of series with a single measurement) listed at once by get_all_entries()
+const GET_ALL_ENTRIES_PAGE_SIZE: usize = 1000;
+// max number of indexed measurements listed in the FROM clause of a GET on a key expression
+// (instead of a regex), with the `key_index` property
+const MAX_INDEXED_MEASUREMENTS: usize = 100;
+// max length of a zenoh timestamp as a string (a 64 bits time, '/' and an id of up to 16 bytes in hex)
+const TIMESTAMP_FIELD_CAPACITY: usize = 20 + 1 + 32;
+// default max number of concurrent queries of get_all_entries()
+const DEFAULT_ALIGNMENT_CONCURRENCY: u64 = 4;
+
+// default number of (key, timestamp) remembered to deduplicate the replies of a GET (no dedup by default)
+const DEFAULT_DEDUP_WINDOW: u64 = 0;
+
+// default interval between 2 checks of the series cardinality
+const DEFAULT_CARDINALITY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// default interval between 2 countings of the measurements, series and points of a storage
+const DEFAULT_STATS_INTERVAL: Duration = Duration::from_secs(300);
+// default interval between 2 trimmings of the history (with `max_history_per_key`)
+const DEFAULT_HISTORY_TRIM_INTERVAL: Duration = Duration::from_secs(60);
+// default interval between 2 garbage collections of the expired points (with `ttl` or `tombstone_ttl`)
+const DEFAULT_GC_INTERVAL: Duration = Duration::from_secs(600);
+// min age of the unreferenced blobs removed by the garbage collection (their points might still be buffered)
+const BLOB_GC_GRACE: Duration = Duration::from_secs(24 * 3600);
+
+// default interval between 2 checks of the connectivity to InfluxDB
+const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+// max number of buffered points written at once, once InfluxDB is reachable again
+const BUFFER_FLUSH_BATCH_SIZE: usize = 100;
+
+// default max time to flush the pending writes when a storage is closed
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+// default mount path of the Vault KV v2 secrets engine
+const DEFAULT_VAULT_MOUNT: &str = "secret";
 
-lazy_static::lazy_static!(
-    static ref INFLUX_REGEX_ALL: String = key_exprs_to_influx_regex(&["**".try_into().unwrap()]);
-);
+// default delay after deletion to drop a measurement
+const DEFAULT_DROP_MEASUREMENT_TIMEOUT: Duration = Duration::from_secs(5);
 
-fn get_private_conf<'a>(
-    config: &'a serde_json::Map<String, serde_json::Value>,
+// The path of a JSON file with the credentials ({"username": "...", "password": "..."}),
+// exclusive with the `username` and `password` properties
+fn get_credentials_file_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    has_credentials: bool,
+) -> ZResult<Option<PathBuf>> {
+    match config.get(PROP_BACKEND_CREDENTIALS_FILE) {
+        None => Ok(None),
+        Some(serde_json::Value::String(path)) if !has_credentials => Ok(Some(path.into())),
+        Some(serde_json::Value::String(_)) => bail!(
+            "Property `{}` can't be used with `{}` and `{}`",
+            PROP_BACKEND_CREDENTIALS_FILE,
+            PROP_BACKEND_USERNAME,
+            PROP_BACKEND_PASSWORD
+        ),
+        Some(_) => bail!(
+            "Property `{}` must be a string",
+            PROP_BACKEND_CREDENTIALS_FILE
+        ),
+    }
+}
+
+// The TLS settings of a storage connecting to its own InfluxDB URL:
+// a CA certificate to trust and/or a client certificate and key (PEM files)
+fn get_tls_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+) -> ZResult<Option<TlsConfig>> {
+    let tls = match config.get(PROP_STORAGE_TLS) {
+        None => return Ok(None),
+        Some(serde_json::Value::Object(tls)) => tls,
+        Some(_) => bail!(
+            "`{}` property of storage `{}` must be an object",
+            PROP_STORAGE_TLS,
+            storage_name
+        ),
+    };
+    let path = |name: &str| -> ZResult<Option<PathBuf>> {
+        match tls.get(name) {
+            None => Ok(None),
+            Some(serde_json::Value::String(path)) => Ok(Some(path.into())),
+            Some(_) => bail!(
+                "`{}.{}` property of storage `{}` must be a string",
+                PROP_STORAGE_TLS,
+                name,
+                storage_name
+            ),
+        }
+    };
+    let ca_file = path("ca_file")?;
+    let client_cert = match (path("cert_file")?, path("key_file")?) {
+        (Some(cert), Some(key)) => Some((cert, key)),
+        (None, None) => None,
+        _ => bail!(
+            "`{}.cert_file` and `{}.key_file` properties of storage `{}` must coexist",
+            PROP_STORAGE_TLS,
+            PROP_STORAGE_TLS,
+            storage_name
+        ),
+    };
+    Ok(Some(TlsConfig {
+        ca_file,
+        client_cert,
+    }))
+}
+
+// Resolve a credential value: either a literal string, or a reference to a file
+// (`{"file": "/run/secrets/influx_pass"}`), to an environment variable (`{"env": "INFLUX_PASSWORD"}`)
+// or to a secret of the volume's secret provider (`{"secret": "influxdb/prod#password"}`)
+fn resolve_secret(
+    value: &serde_json::Value,
+    credit: &str,
+    secrets: Option<&dyn SecretProvider>,
+) -> ZResult<String> {
+    match value {
+        serde_json::Value::String(v) => Ok(v.clone()),
+        serde_json::Value::Object(reference) if reference.len() == 1 => {
+            match reference.iter().next() {
+                Some((k, serde_json::Value::String(path))) if k == "file" => {
+                    let secret = std::fs::read_to_string(path).map_err(|e| {
+                        zerror!("Failed to read `{}` from file {}: {}", credit, path, e)
+                    })?;
+                    Ok(secret.trim_end_matches(['\n', '\r']).to_string())
+                }
+                Some((k, serde_json::Value::String(var))) if k == "env" => Ok(std::env::var(var)
+                    .map_err(|e| {
+                    zerror!(
+                        "Failed to read `{}` from environment variable {}: {}",
+                        credit,
+                        var,
+                        e
+                    )
+                })?),
+                Some((k, serde_json::Value::String(reference))) if k == "secret" => match secrets {
+                    Some(secrets) => secrets.get_secret(reference),
+                    None => bail!(
+                        "Optional property `{}` references a secret, but no secret provider is configured (see `{}`)",
+                        credit,
+                        PROP_BACKEND_VAULT
+                    ),
+                },
+                _ => bail!(
+                    r#"Optional property `{}` must be a string, or a {{"file": "<path>"}}, {{"env": "<variable>"}} or {{"secret": "<reference>"}} reference"#,
+                    credit
+                ),
+            }
+        }
+        _ => bail!(
+            r#"Optional property `{}` must be a string, or a {{"file": "<path>"}}, {{"env": "<variable>"}} or {{"secret": "<reference>"}} reference"#,
+            credit
+        ),
+    }
+}
+
+fn get_private_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
     credit: &str,
-) -> ZResult<Option<&'a String>> {
+    secrets: Option<&dyn SecretProvider>,
+) -> ZResult<Option<String>> {
     match config.get_private(credit) {
         PrivacyGetResult::NotFound => Ok(None),
-        PrivacyGetResult::Private(serde_json::Value::String(v)) => Ok(Some(v)),
+        PrivacyGetResult::Private(v) => Ok(Some(resolve_secret(v, credit, secrets)?)),
         PrivacyGetResult::Public(serde_json::Value::String(v)) => {
             log::warn!(
                 r#"Value "{}" is given for `{}` publicly (i.e. is visible by anyone who can fetch the router configuration). You may want to replace `{}: "{}"` with `private: {{{}: "{}"}}`"#,
@@ -79,8 +522,10 @@ fn get_private_conf<'a>(
                 credit,
                 v
             );
-            Ok(Some(v))
+            Ok(Some(v.clone()))
         }
+        // a reference is not a secret by itself: no need to make it private
+        PrivacyGetResult::Public(v) => Ok(Some(resolve_secret(v, credit, secrets)?)),
         PrivacyGetResult::Both {
             public: serde_json::Value::String(public),
             private: serde_json::Value::String(private),
@@ -91,11 +536,994 @@ fn get_private_conf<'a>(
                 credit,
                 if public == private { "" } else { "not " }
             );
-            Ok(Some(private))
+            Ok(Some(private.clone()))
         }
-        _ => {
-            bail!("Optional property `{}` must be a string", credit)
+        PrivacyGetResult::Both { private, .. } => {
+            Ok(Some(resolve_secret(private, credit, secrets)?))
+        }
+    }
+}
+
+// Parse the `vault` property: a HashiCorp Vault KV v2 secrets engine, e.g.
+// {"address": "https://vault:8200", "token": {"env": "VAULT_TOKEN"}, "mount": "secret"}
+fn get_vault_conf(
+    vault: &serde_json::Map<String, serde_json::Value>,
+) -> ZResult<Arc<dyn SecretProvider>> {
+    let address = match vault.get("address") {
+        Some(serde_json::Value::String(address)) => address.clone(),
+        _ => bail!(
+            "Property `{}` for InfluxDb Backend requires an `address` string",
+            PROP_BACKEND_VAULT
+        ),
+    };
+    let token = match get_private_conf(vault, "token", None)? {
+        Some(token) => token,
+        None => bail!(
+            "Property `{}` for InfluxDb Backend requires a `token`",
+            PROP_BACKEND_VAULT
+        ),
+    };
+    let mount = match vault.get("mount") {
+        Some(serde_json::Value::String(mount)) => mount.clone(),
+        None => DEFAULT_VAULT_MOUNT.to_string(),
+        Some(_) => bail!("`{}.mount` must be a string", PROP_BACKEND_VAULT),
+    };
+    let namespace = match vault.get("namespace") {
+        Some(serde_json::Value::String(namespace)) => Some(namespace.clone()),
+        None => None,
+        Some(_) => bail!("`{}.namespace` must be a string", PROP_BACKEND_VAULT),
+    };
+    Ok(Arc::new(VaultKv2::new(address, token, mount, namespace)))
+}
+
+fn get_u64_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    name: &str,
+    default: u64,
+) -> ZResult<u64> {
+    match config.get(name) {
+        None => Ok(default),
+        Some(serde_json::Value::Number(n)) if n.is_u64() => Ok(n.as_u64().unwrap()),
+        Some(_) => bail!(
+            "Optional property `{}` must be a non-negative integer",
+            name
+        ),
+    }
+}
+
+fn get_duration_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    name: &str,
+) -> ZResult<Option<Duration>> {
+    match config.get(name) {
+        None => Ok(None),
+        Some(serde_json::Value::String(s)) => match humantime::parse_duration(s) {
+            Ok(d) => Ok(Some(d)),
+            Err(e) => bail!("Invalid duration for property `{}`: {}", name, e),
+        },
+        Some(_) => bail!(
+            r#"Optional property `{}` must be a duration string (e.g. "30s", "1h")"#,
+            name
+        ),
+    }
+}
+
+// Parse the `key_tags` property: a map of key chunk indexes to InfluxDB tag names
+fn get_key_tags_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+) -> ZResult<Vec<(usize, String)>> {
+    let mut result = Vec::new();
+    match config.get(PROP_STORAGE_KEY_TAGS) {
+        None => {}
+        Some(serde_json::Value::Object(map)) => {
+            for (index, tag) in map {
+                match (index.parse::<usize>(), tag) {
+                    (Ok(i), serde_json::Value::String(t))
+                        if !t.is_empty() && !ZENOH_COLUMNS.contains(&t.as_str()) =>
+                    {
+                        result.push((i, t.clone()))
+                    }
+                    _ => bail!(
+                        r#"Invalid entry "{}": {} in `{}` property of storage `{}`: it must map a chunk index to a tag name (e.g. {{"1": "robot"}}), which must not be one of {:?}"#,
+                        index,
+                        tag,
+                        PROP_STORAGE_KEY_TAGS,
+                        storage_name,
+                        ZENOH_COLUMNS
+                    ),
+                }
+            }
+        }
+        Some(_) => bail!(
+            r#"`{}` property of storage `{}` must be a map of chunk indexes to tag names (e.g. {{"1": "robot"}})"#,
+            PROP_STORAGE_KEY_TAGS,
+            storage_name
+        ),
+    }
+    Ok(result)
+}
+
+// A retention policy to create (or alter) on the storage's database
+#[derive(Clone)]
+struct RetentionPolicy {
+    name: String,
+    // None for an infinite duration
+    duration: Option<Duration>,
+    replication: u64,
+    shard_duration: Option<Duration>,
+    // if true, set as the default retention policy of the database (where the points are written)
+    default: bool,
+}
+
+impl RetentionPolicy {
+    // The DURATION, REPLICATION and SHARD DURATION clauses of CREATE/ALTER RETENTION POLICY
+    fn clauses(&self) -> String {
+        let mut result = match self.duration {
+            Some(d) => format!("DURATION {}", influx_duration(d)),
+            None => "DURATION INF".to_string(),
+        };
+        result.push_str(&format!(" REPLICATION {}", self.replication));
+        if let Some(d) = self.shard_duration {
+            result.push_str(&format!(" SHARD DURATION {}", influx_duration(d)));
+        }
+        result
+    }
+}
+
+// Parse the `retention` property, e.g. {"duration": "30d", "replication": 1, "shard_duration": "1d"}
+fn get_retention_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+) -> ZResult<Option<RetentionPolicy>> {
+    let rp = match config.get(PROP_STORAGE_RETENTION) {
+        None => return Ok(None),
+        Some(serde_json::Value::Object(rp)) => rp,
+        Some(_) => bail!(
+            r#"`{}` property of storage `{}` must be an object (e.g. {{"duration": "30d"}})"#,
+            PROP_STORAGE_RETENTION,
+            storage_name
+        ),
+    };
+    let name = match rp.get("name") {
+        None => DEFAULT_RETENTION_POLICY.to_string(),
+        Some(serde_json::Value::String(n)) if !n.is_empty() => n.clone(),
+        Some(_) => bail!(
+            "`{}.name` property of storage `{}` must be a non-empty string",
+            PROP_STORAGE_RETENTION,
+            storage_name
+        ),
+    };
+    let duration = match rp.get("duration") {
+        Some(serde_json::Value::String(d)) if d.eq_ignore_ascii_case("inf") => None,
+        Some(_) => get_duration_conf(rp, "duration")?,
+        None => bail!(
+            "`{}.duration` property of storage `{}` is required",
+            PROP_STORAGE_RETENTION,
+            storage_name
+        ),
+    };
+    let replication = match get_u64_conf(rp, "replication", 1)? {
+        0 => bail!(
+            "`{}.replication` property of storage `{}` must be at least 1",
+            PROP_STORAGE_RETENTION,
+            storage_name
+        ),
+        n => n,
+    };
+    let shard_duration = get_duration_conf(rp, "shard_duration")?;
+    Ok(Some(RetentionPolicy {
+        name,
+        duration,
+        replication,
+        shard_duration,
+        default: true,
+    }))
+}
+
+// A downsampling tier: a continuous query writing the last point of each key per interval
+// into a dedicated retention policy
+struct Tier {
+    interval: Duration,
+    // the GETs on time ranges at least this long read this tier
+    min_range: Duration,
+    retention: RetentionPolicy,
+}
+
+impl Tier {
+    // The name of both the retention policy and the continuous query
+    fn name(interval: Duration) -> String {
+        format!("zenoh_{}", influx_duration(interval))
+    }
+}
+
+// Parse the `downsampling` property, e.g. [{"interval": "1m", "duration": "90d"}, {"interval": "1h"}]
+fn get_downsampling_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+) -> ZResult<Vec<Tier>> {
+    let tiers = match config.get(PROP_STORAGE_DOWNSAMPLING) {
+        None => return Ok(Vec::new()),
+        Some(serde_json::Value::Array(tiers)) => tiers,
+        Some(_) => bail!(
+            r#"`{}` property of storage `{}` must be an array of tiers (e.g. [{{"interval": "1m"}}])"#,
+            PROP_STORAGE_DOWNSAMPLING,
+            storage_name
+        ),
+    };
+    let mut result: Vec<Tier> = Vec::new();
+    for tier in tiers {
+        let tier = match tier {
+            serde_json::Value::Object(t) => t,
+            _ => bail!(
+                r#"Invalid tier {} in `{}` property of storage `{}`: it must be an object (e.g. {{"interval": "1m"}})"#,
+                tier,
+                PROP_STORAGE_DOWNSAMPLING,
+                storage_name
+            ),
+        };
+        let interval = match get_duration_conf(tier, "interval")? {
+            Some(i) if i >= Duration::from_secs(1) => i,
+            _ => bail!(
+                "`{}.interval` property of storage `{}` is required, and must be at least 1s",
+                PROP_STORAGE_DOWNSAMPLING,
+                storage_name
+            ),
+        };
+        if result.iter().any(|t| t.interval == interval) {
+            bail!(
+                "`{}` property of storage `{}` has several tiers with interval {:?}",
+                PROP_STORAGE_DOWNSAMPLING,
+                storage_name,
+                interval
+            )
+        }
+        let duration = match tier.get("duration") {
+            None => None,
+            Some(serde_json::Value::String(d)) if d.eq_ignore_ascii_case("inf") => None,
+            Some(_) => get_duration_conf(tier, "duration")?,
+        };
+        let min_range =
+            get_duration_conf(tier, "min_range")?.unwrap_or(interval * DEFAULT_TIER_MIN_INTERVALS);
+        result.push(Tier {
+            interval,
+            min_range,
+            retention: RetentionPolicy {
+                name: Tier::name(interval),
+                duration,
+                replication: 1,
+                shard_duration: None,
+                default: false,
+            },
+        });
+    }
+    result.sort_by_key(|t| t.interval);
+    Ok(result)
+}
+
+// Parse the configuration of a mirror database (`archive` property or `replicas` item),
+// e.g. {"url": "http://archive:8086", "db": "zenoh_archive"}, returning the Mirror forwarding to it
+fn get_mirror_conf(
+    mirror: &serde_json::Map<String, serde_json::Value>,
+    prop: &str,
+    storage_name: &str,
+    secrets: Option<&dyn SecretProvider>,
+) -> ZResult<Mirror> {
+    let (url, db) = match (mirror.get(PROP_BACKEND_URL), mirror.get(PROP_STORAGE_DB)) {
+        (Some(serde_json::Value::String(url)), Some(serde_json::Value::String(db))) => (url, db),
+        _ => bail!(
+            "`{}` property of storage `{}` requires `{}` and `{}` strings",
+            prop,
+            storage_name,
+            PROP_BACKEND_URL,
+            PROP_STORAGE_DB
+        ),
+    };
+    let mut client = Client::new(url, db);
+    match (
+        get_private_conf(mirror, PROP_STORAGE_USERNAME, secrets)?,
+        get_private_conf(mirror, PROP_STORAGE_PASSWORD, secrets)?,
+    ) {
+        (Some(username), Some(password)) => client = client.with_auth(username, password),
+        (None, None) => {}
+        _ => bail!(
+            "Properties `{}.{}` and `{}.{}` of storage `{}` must coexist",
+            prop,
+            PROP_STORAGE_USERNAME,
+            prop,
+            PROP_STORAGE_PASSWORD,
+            storage_name
+        ),
+    }
+    let batch_size = get_u64_conf(mirror, "batch_size", DEFAULT_MIRROR_BATCH_SIZE)?;
+    let max_bytes = match mirror.get("batch_max_bytes") {
+        None => None,
+        Some(serde_json::Value::Number(n)) if n.as_u64().map_or(false, |n| n > 0) => {
+            n.as_u64().map(|n| n as usize)
+        }
+        Some(_) => bail!(
+            "`{}.batch_max_bytes` property of storage `{}` must be a positive integer",
+            prop,
+            storage_name
+        ),
+    };
+    let flush_interval =
+        get_duration_conf(mirror, "flush_interval")?.unwrap_or(DEFAULT_MIRROR_FLUSH_INTERVAL);
+    let linger = get_duration_conf(mirror, "linger")?;
+    let queue_size = get_u64_conf(mirror, "queue_size", DEFAULT_MIRROR_QUEUE_SIZE)?;
+    Ok(Mirror::new(
+        client,
+        batch_size as usize,
+        max_bytes,
+        flush_interval,
+        linger,
+        queue_size as usize,
+    ))
+}
+
+// Parse the `offload` property: the blob store of the payloads larger than a threshold, e.g.
+// {"threshold": 1048576, "dir": "/var/lib/zenoh/blobs"} or
+// {"s3": {"endpoint": "http://minio:9000", "bucket": "zenoh", "access_key": "...", "secret_key": "..."}},
+// and if the unreferenced blobs are collected
+fn get_offload_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+    secrets: Option<&dyn SecretProvider>,
+) -> ZResult<Option<(BlobStore, usize, bool)>> {
+    let offload = match config.get(PROP_STORAGE_OFFLOAD) {
+        None => return Ok(None),
+        Some(serde_json::Value::Object(o)) => o,
+        Some(_) => bail!(
+            "`{}` property of storage `{}` must be an object",
+            PROP_STORAGE_OFFLOAD,
+            storage_name
+        ),
+    };
+    let threshold = get_u64_conf(offload, "threshold", DEFAULT_OFFLOAD_THRESHOLD)?;
+    let collect = match offload.get("collect") {
+        None => true,
+        Some(serde_json::Value::Bool(b)) => *b,
+        Some(_) => bail!(
+            "`{}.collect` property of storage `{}` must be a boolean",
+            PROP_STORAGE_OFFLOAD,
+            storage_name
+        ),
+    };
+    let store = match (offload.get("dir"), offload.get("s3")) {
+        (Some(serde_json::Value::String(dir)), None) => BlobStore::Directory(PathBuf::from(dir)),
+        (None, Some(serde_json::Value::Object(s3))) => {
+            let str_conf = |name: &str| match s3.get(name) {
+                Some(serde_json::Value::String(s)) => Ok(s.clone()),
+                _ => bail!(
+                    "`{}.s3` property of storage `{}` requires a `{}` string",
+                    PROP_STORAGE_OFFLOAD,
+                    storage_name,
+                    name
+                ),
+            };
+            let endpoint = str_conf("endpoint")?;
+            let endpoint = surf::Url::parse(&endpoint).map_err(|e| {
+                zerror!(
+                    "Invalid `{}.s3.endpoint` of storage `{}`: {}",
+                    PROP_STORAGE_OFFLOAD,
+                    storage_name,
+                    e
+                )
+            })?;
+            let region = match s3.get("region") {
+                None => "us-east-1".to_string(),
+                Some(_) => str_conf("region")?,
+            };
+            match (
+                get_private_conf(s3, "access_key", secrets)?,
+                get_private_conf(s3, "secret_key", secrets)?,
+            ) {
+                (Some(access_key), Some(secret_key)) => BlobStore::S3(S3Bucket::new(
+                    endpoint,
+                    str_conf("bucket")?,
+                    region,
+                    access_key,
+                    secret_key,
+                )),
+                _ => bail!(
+                    "`{}.s3` property of storage `{}` requires `access_key` and `secret_key`",
+                    PROP_STORAGE_OFFLOAD,
+                    storage_name
+                ),
+            }
+        }
+        _ => bail!(
+            "`{}` property of storage `{}` requires either a `dir` string or an `s3` object",
+            PROP_STORAGE_OFFLOAD,
+            storage_name
+        ),
+    };
+    Ok(Some((store, threshold as usize, collect)))
+}
+
+// Parse the `migrate_v2` property: the InfluxDB 2.x bucket where the `migrate_v2` admin operation rewrites the points,
+// e.g. {"url": "http://influxdb2:8086", "org_id": "...", "db": "zenoh", "token": "..."}
+fn get_v2_target_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+    secrets: Option<&dyn SecretProvider>,
+) -> ZResult<Option<V2Target>> {
+    let target = match config.get(PROP_STORAGE_MIGRATE_V2) {
+        None => return Ok(None),
+        Some(serde_json::Value::Object(t)) => t,
+        Some(_) => bail!(
+            "`{}` property of storage `{}` must be an object",
+            PROP_STORAGE_MIGRATE_V2,
+            storage_name
+        ),
+    };
+    match (
+        target.get(PROP_BACKEND_URL),
+        target.get("org_id"),
+        target.get(PROP_STORAGE_DB),
+        get_private_conf(target, "token", secrets)?,
+    ) {
+        (
+            Some(serde_json::Value::String(url)),
+            Some(serde_json::Value::String(org_id)),
+            Some(serde_json::Value::String(bucket)),
+            Some(token),
+        ) => Ok(Some(V2Target::new(
+            url.clone(),
+            org_id.clone(),
+            bucket.clone(),
+            token,
+        ))),
+        _ => bail!(
+            "`{}` property of storage `{}` requires `{}`, `org_id`, `{}` and `token` strings",
+            PROP_STORAGE_MIGRATE_V2,
+            storage_name,
+            PROP_BACKEND_URL,
+            PROP_STORAGE_DB
+        ),
+    }
+}
+
+// Parse the `connect` field of a property: the zenoh endpoints to connect to, e.g. ["tcp/localhost:7447"]
+fn get_connect_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    prop: &str,
+    storage_name: &str,
+) -> ZResult<Vec<String>> {
+    match config.get("connect") {
+        None => Ok(Vec::new()),
+        Some(serde_json::Value::Array(endpoints)) => {
+            let mut connect = Vec::with_capacity(endpoints.len());
+            for endpoint in endpoints {
+                match endpoint {
+                    serde_json::Value::String(e) => connect.push(e.clone()),
+                    _ => bail!(
+                        "`{}.connect` property of storage `{}` must be an array of strings",
+                        prop,
+                        storage_name
+                    ),
+                }
+            }
+            Ok(connect)
+        }
+        Some(_) => bail!(
+            "`{}.connect` property of storage `{}` must be an array of strings",
+            prop,
+            storage_name
+        ),
+    }
+}
+
+// Parse the `events` property: `true`, the key expression where the storage events are published,
+// or e.g. {"key": "demo/influxdb/events", "connect": ["tcp/localhost:7447"]}
+fn get_events_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+) -> ZResult<Option<(OwnedKeyExpr, Vec<String>)>> {
+    let (key, connect) = match config.get(PROP_STORAGE_EVENTS) {
+        None | Some(serde_json::Value::Bool(false)) => return Ok(None),
+        Some(serde_json::Value::Bool(true)) => (None, Vec::new()),
+        Some(serde_json::Value::String(key)) => (Some(key), Vec::new()),
+        Some(serde_json::Value::Object(events)) => match events.get("key") {
+            None => (
+                None,
+                get_connect_conf(events, PROP_STORAGE_EVENTS, storage_name)?,
+            ),
+            Some(serde_json::Value::String(key)) => (
+                Some(key),
+                get_connect_conf(events, PROP_STORAGE_EVENTS, storage_name)?,
+            ),
+            Some(_) => bail!(
+                "`{}.key` property of storage `{}` must be a string",
+                PROP_STORAGE_EVENTS,
+                storage_name
+            ),
+        },
+        Some(_) => bail!(
+            "`{}` property of storage `{}` must be a boolean, a string or an object",
+            PROP_STORAGE_EVENTS,
+            storage_name
+        ),
+    };
+    let key = match key {
+        Some(key) => key.clone(),
+        None => format!("@influxdb/{storage_name}/events"),
+    };
+    match OwnedKeyExpr::from_str(&key) {
+        Ok(key) => Ok(Some((key, connect))),
+        Err(e) => bail!(
+            "Invalid key `{}` for the events of storage `{}`: {}",
+            key,
+            storage_name,
+            e
+        ),
+    }
+}
+
+// Parse the `priorities` property: the priorities of the points of some keys in the write buffer,
+// e.g. {"robot/*/alarm/**": "high", "robot/*/telemetry/**": "low"}
+fn get_priorities_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+) -> ZResult<Vec<(OwnedKeyExpr, Priority)>> {
+    let priorities = match config.get(PROP_STORAGE_PRIORITIES) {
+        None => return Ok(Vec::new()),
+        Some(serde_json::Value::Object(p)) => p,
+        Some(_) => bail!(
+            "`{}` property of storage `{}` must be an object",
+            PROP_STORAGE_PRIORITIES,
+            storage_name
+        ),
+    };
+    let mut result = Vec::with_capacity(priorities.len());
+    for (key_expr, priority) in priorities {
+        let key_expr = match OwnedKeyExpr::from_str(key_expr) {
+            Ok(ke) => ke,
+            Err(e) => bail!(
+                "Invalid key expression `{}` in `{}` property of storage `{}`: {}",
+                key_expr,
+                PROP_STORAGE_PRIORITIES,
+                storage_name,
+                e
+            ),
+        };
+        match priority.as_str().and_then(Priority::from_name) {
+            Some(priority) => result.push((key_expr, priority)),
+            None => bail!(
+                r#"Priority of `{}` in `{}` property of storage `{}` must be one of "high", "normal" and "low""#,
+                key_expr,
+                PROP_STORAGE_PRIORITIES,
+                storage_name
+            ),
+        }
+    }
+    Ok(result)
+}
+
+// Parse the `replay` property: `true`, or the zenoh endpoints to replay the stored points on,
+// e.g. {"connect": ["tcp/localhost:7447"]}
+fn get_replay_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+) -> ZResult<Option<Vec<String>>> {
+    match config.get(PROP_STORAGE_REPLAY) {
+        None | Some(serde_json::Value::Bool(false)) => Ok(None),
+        Some(serde_json::Value::Bool(true)) => Ok(Some(Vec::new())),
+        Some(serde_json::Value::Object(replay)) => Ok(Some(get_connect_conf(
+            replay,
+            PROP_STORAGE_REPLAY,
+            storage_name,
+        )?)),
+        Some(_) => bail!(
+            "`{}` property of storage `{}` must be a boolean or an object",
+            PROP_STORAGE_REPLAY,
+            storage_name
+        ),
+    }
+}
+
+// Parse the `change_feed` property: `true`, or the polling of the points written by other writers than zenoh,
+// e.g. {"interval": "5s", "connect": ["tcp/localhost:7447"], "key_prefix": "demo/example"}
+fn get_change_feed_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+    strip_prefix: Option<&OwnedKeyExpr>,
+) -> ZResult<Option<ChangeFeedConfig>> {
+    let feed = match config.get(PROP_STORAGE_CHANGE_FEED) {
+        None | Some(serde_json::Value::Bool(false)) => return Ok(None),
+        Some(serde_json::Value::Bool(true)) => serde_json::Map::new(),
+        Some(serde_json::Value::Object(f)) => f.clone(),
+        Some(_) => bail!(
+            "`{}` property of storage `{}` must be a boolean or an object",
+            PROP_STORAGE_CHANGE_FEED,
+            storage_name
+        ),
+    };
+    let interval = get_duration_conf(&feed, "interval")?.unwrap_or(DEFAULT_CHANGE_FEED_INTERVAL);
+    let connect = get_connect_conf(&feed, PROP_STORAGE_CHANGE_FEED, storage_name)?;
+    let key_prefix = match feed.get("key_prefix") {
+        None => strip_prefix.cloned(),
+        Some(serde_json::Value::String(p)) => match OwnedKeyExpr::from_str(p) {
+            Ok(p) => Some(p),
+            Err(e) => bail!(
+                "Invalid `{}.key_prefix` property of storage `{}`: {}",
+                PROP_STORAGE_CHANGE_FEED,
+                storage_name,
+                e
+            ),
+        },
+        Some(_) => bail!(
+            "`{}.key_prefix` property of storage `{}` must be a string",
+            PROP_STORAGE_CHANGE_FEED,
+            storage_name
+        ),
+    };
+    Ok(Some(ChangeFeedConfig {
+        interval,
+        connect,
+        key_prefix,
+    }))
+}
+
+// Parse the `archive` property: the database where the PUT points are also forwarded
+fn get_archive_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+    secrets: Option<&dyn SecretProvider>,
+) -> ZResult<Option<Mirror>> {
+    match config.get(PROP_STORAGE_ARCHIVE) {
+        None => Ok(None),
+        Some(serde_json::Value::Object(a)) => Ok(Some(get_mirror_conf(
+            a,
+            PROP_STORAGE_ARCHIVE,
+            storage_name,
+            secrets,
+        )?)),
+        Some(_) => bail!(
+            r#"`{}` property of storage `{}` must be an object (e.g. {{"url": "http://archive:8086", "db": "zenoh_archive"}})"#,
+            PROP_STORAGE_ARCHIVE,
+            storage_name
+        ),
+    }
+}
+
+// Parse the `replicas` property: the databases where the PUT and DELETE are mirrored
+fn get_replicas_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+    secrets: Option<&dyn SecretProvider>,
+) -> ZResult<Vec<Mirror>> {
+    match config.get(PROP_STORAGE_REPLICAS) {
+        None => Ok(Vec::new()),
+        Some(serde_json::Value::Array(replicas)) => replicas
+            .iter()
+            .map(|r| match r {
+                serde_json::Value::Object(r) => {
+                    get_mirror_conf(r, PROP_STORAGE_REPLICAS, storage_name, secrets)
+                }
+                _ => bail!(
+                    "Invalid replica {} in `{}` property of storage `{}`: it must be an object",
+                    r,
+                    PROP_STORAGE_REPLICAS,
+                    storage_name
+                ),
+            })
+            .collect(),
+        Some(_) => bail!(
+            r#"`{}` property of storage `{}` must be an array (e.g. [{{"url": "http://dc:8086", "db": "zenoh"}}])"#,
+            PROP_STORAGE_REPLICAS,
+            storage_name
+        ),
+    }
+}
+
+// Parse the `circuit_breaker` property, e.g. {"window": 20, "error_rate": 0.5, "cool_down": "10s"}
+fn get_circuit_breaker_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+) -> ZResult<Option<CircuitBreaker>> {
+    let breaker = match config.get(PROP_STORAGE_CIRCUIT_BREAKER) {
+        None => return Ok(None),
+        Some(serde_json::Value::Object(b)) => b,
+        Some(_) => bail!(
+            r#"`{}` property of storage `{}` must be an object (e.g. {{"error_rate": 0.5}})"#,
+            PROP_STORAGE_CIRCUIT_BREAKER,
+            storage_name
+        ),
+    };
+    let window = get_u64_conf(breaker, "window", DEFAULT_BREAKER_WINDOW)?;
+    let error_rate = match breaker.get("error_rate") {
+        None => DEFAULT_BREAKER_ERROR_RATE,
+        Some(serde_json::Value::Number(n)) if n.as_f64().map_or(false, |r| r > 0.0 && r <= 1.0) => {
+            n.as_f64().unwrap()
+        }
+        Some(_) => bail!(
+            "`{}.error_rate` property of storage `{}` must be a number in ]0, 1]",
+            PROP_STORAGE_CIRCUIT_BREAKER,
+            storage_name
+        ),
+    };
+    let cool_down = get_duration_conf(breaker, "cool_down")?.unwrap_or(DEFAULT_BREAKER_COOL_DOWN);
+    Ok(Some(CircuitBreaker::new(
+        window as usize,
+        error_rate,
+        cool_down,
+    )))
+}
+
+// Safeguards against dropping data by mistake when a storage is removed
+struct ClosureProtection {
+    // the database can only be dropped if its name matches this pattern (with '*' wildcards)...
+    db_pattern: Option<String>,
+    // ... or if explicitly confirmed
+    confirm_drop: bool,
+    // if true, the drops are only logged
+    dry_run: bool,
+}
+
+impl ClosureProtection {
+    fn allows_drop_db(&self, db: &str) -> bool {
+        self.confirm_drop
+            || self
+                .db_pattern
+                .as_ref()
+                .map_or(false, |p| wildcard_match(p, db))
+    }
+}
+
+// Parse the `on_closure_protection` property, e.g. {"db_pattern": "zenoh_*", "dry_run": true}
+fn get_closure_protection_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+) -> ZResult<Option<ClosureProtection>> {
+    let protection = match config.get(PROP_STORAGE_ON_CLOSURE_PROTECTION) {
+        None => return Ok(None),
+        Some(serde_json::Value::Object(p)) => p,
+        Some(_) => bail!(
+            r#"`{}` property of storage `{}` must be an object (e.g. {{"db_pattern": "zenoh_*"}})"#,
+            PROP_STORAGE_ON_CLOSURE_PROTECTION,
+            storage_name
+        ),
+    };
+    let db_pattern = match protection.get("db_pattern") {
+        None => None,
+        Some(serde_json::Value::String(p)) => Some(p.clone()),
+        Some(_) => bail!(
+            "`{}.db_pattern` property of storage `{}` must be a string",
+            PROP_STORAGE_ON_CLOSURE_PROTECTION,
+            storage_name
+        ),
+    };
+    let confirm_drop = match protection.get("confirm_drop") {
+        None => false,
+        Some(serde_json::Value::Bool(b)) => *b,
+        Some(_) => bail!(
+            "`{}.confirm_drop` property of storage `{}` must be a boolean",
+            PROP_STORAGE_ON_CLOSURE_PROTECTION,
+            storage_name
+        ),
+    };
+    let dry_run = match protection.get("dry_run") {
+        None => false,
+        Some(serde_json::Value::Bool(b)) => *b,
+        Some(_) => bail!(
+            "`{}.dry_run` property of storage `{}` must be a boolean",
+            PROP_STORAGE_ON_CLOSURE_PROTECTION,
+            storage_name
+        ),
+    };
+    Ok(Some(ClosureProtection {
+        db_pattern,
+        confirm_drop,
+        dry_run,
+    }))
+}
+
+// A routing of the keys matching a key expression to another database of the InfluxDB server
+struct RouteConf {
+    key_expr: OwnedKeyExpr,
+    db: String,
+    // if set, created (or altered) as the default retention policy of the database
+    retention: Option<RetentionPolicy>,
+}
+
+fn get_routing_conf(
+    config: &serde_json::Map<String, serde_json::Value>,
+    storage_name: &str,
+) -> ZResult<Vec<RouteConf>> {
+    let routes = match config.get(PROP_STORAGE_ROUTING) {
+        None => return Ok(Vec::new()),
+        Some(serde_json::Value::Array(routes)) => routes,
+        Some(_) => bail!(
+            r#"`{}` property of storage `{}` must be an array (e.g. [{{"key_expr": "**/image/**", "db": "images"}}])"#,
+            PROP_STORAGE_ROUTING,
+            storage_name
+        ),
+    };
+    let mut result = Vec::with_capacity(routes.len());
+    for route in routes {
+        let route = match route {
+            serde_json::Value::Object(route) => route,
+            _ => bail!(
+                "Invalid route {} in `{}` property of storage `{}`: it must be an object",
+                route,
+                PROP_STORAGE_ROUTING,
+                storage_name
+            ),
+        };
+        let key_expr = match route.get("key_expr") {
+            Some(serde_json::Value::String(k)) => match OwnedKeyExpr::from_str(k) {
+                Ok(k) => k,
+                Err(e) => bail!(
+                    "Invalid `{}.key_expr` property of storage `{}`: {}",
+                    PROP_STORAGE_ROUTING,
+                    storage_name,
+                    e
+                ),
+            },
+            _ => bail!(
+                "`{}.key_expr` property of storage `{}` is required and must be a string",
+                PROP_STORAGE_ROUTING,
+                storage_name
+            ),
+        };
+        let db = match route.get("db") {
+            Some(serde_json::Value::String(db)) if !db.is_empty() => db.clone(),
+            _ => bail!(
+                "`{}.db` property of storage `{}` is required and must be a non-empty string",
+                PROP_STORAGE_ROUTING,
+                storage_name
+            ),
+        };
+        let retention = get_retention_conf(route, storage_name)?;
+        result.push(RouteConf {
+            key_expr,
+            db,
+            retention,
+        });
+    }
+    Ok(result)
+}
+
+// Match a name against a pattern where '*' matches any sequence of characters
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // the first part must be a prefix, the last a suffix, and the others found in order in between
+    let first = parts.next().unwrap_or("");
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    match parts.split_last() {
+        None => rest.is_empty(),
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(i) => rest = &rest[i + part.len()..],
+                    None => return false,
+                }
+            }
+            rest.len() >= last.len() && rest.ends_with(last)
+        }
+    }
+}
+
+// The storage properties that can be changed at runtime (see `InfluxDbVolume::reconfigure_storage`)
+struct Settings {
+    // if set, a deletion only covers this window before its timestamp (allowing backfill before)
+    tombstone_window: Option<Duration>,
+    // tags to add to each point, from the key's chunk at the given index
+    key_tags: Vec<(usize, String)>,
+    // if false, the points with missing zenoh fields are decoded with default values for those
+    strict_schema: bool,
+    // if set, the GET queries on a time range are paginated with this number of points per serie
+    max_points_per_chunk: Option<usize>,
+    // if true, the unsupported selector parameters starting with '_' are refused
+    strict_parameters: bool,
+    // max number of (key, timestamp) remembered to deduplicate the replies of a GET (0 = no dedup)
+    dedup_window: usize,
+    // the points older than this are expired (removed periodically)
+    ttl: Option<Duration>,
+    // the deletion markers older than this are removed (periodically)
+    tombstone_ttl: Option<Duration>,
+    // the delay before dropping a measurement whose key has been deleted
+    drop_measurement_timeout: Duration,
+    // max time to flush the pending writes on shutdown
+    shutdown_timeout: Duration,
+    // if true, the PUTs are not checked against the deletions and GET is refused
+    write_only: bool,
+    // if set, the queries lasting longer are logged as warnings
+    slow_query: Option<Duration>,
+}
+
+// The settings of a storage, shared with its periodic tasks and its volume (for reconfiguration)
+type SharedSettings = Arc<RwLock<Arc<Settings>>>;
+
+impl Settings {
+    fn from_config(
+        volume_cfg: &serde_json::Map<String, serde_json::Value>,
+        storage_name: &str,
+        latest_only: bool,
+    ) -> ZResult<Settings> {
+        let tombstone_window = get_duration_conf(volume_cfg, PROP_STORAGE_TOMBSTONE_WINDOW)?;
+        let key_tags = get_key_tags_conf(volume_cfg, storage_name)?;
+        let strict_schema = match volume_cfg.get(PROP_STORAGE_STRICT_SCHEMA) {
+            Some(serde_json::Value::Bool(b)) => *b,
+            None => true,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_STRICT_SCHEMA,
+                storage_name
+            ),
+        };
+        let max_points_per_chunk = match volume_cfg.get(PROP_STORAGE_MAX_POINTS_PER_CHUNK) {
+            None => None,
+            Some(_) => match get_u64_conf(volume_cfg, PROP_STORAGE_MAX_POINTS_PER_CHUNK, 0)? {
+                0 => bail!(
+                    "`{}` property of storage `{}` must be a positive integer",
+                    PROP_STORAGE_MAX_POINTS_PER_CHUNK,
+                    storage_name
+                ),
+                n => Some(n as usize),
+            },
+        };
+        let strict_parameters = match volume_cfg.get(PROP_STORAGE_STRICT_PARAMETERS) {
+            Some(serde_json::Value::Bool(b)) => *b,
+            None => false,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_STRICT_PARAMETERS,
+                storage_name
+            ),
+        };
+        let dedup_window =
+            get_u64_conf(volume_cfg, PROP_STORAGE_DEDUP_WINDOW, DEFAULT_DEDUP_WINDOW)? as usize;
+        let ttl = get_duration_conf(volume_cfg, PROP_STORAGE_TTL)?;
+        let tombstone_ttl = get_duration_conf(volume_cfg, PROP_STORAGE_TOMBSTONE_TTL)?;
+        let drop_measurement_timeout =
+            get_duration_conf(volume_cfg, PROP_STORAGE_DROP_MEASUREMENT_TIMEOUT)?
+                .unwrap_or(DEFAULT_DROP_MEASUREMENT_TIMEOUT);
+        let shutdown_timeout = get_duration_conf(volume_cfg, PROP_STORAGE_SHUTDOWN_TIMEOUT)?
+            .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT);
+        let write_only = match volume_cfg.get(PROP_STORAGE_WRITE_ONLY) {
+            Some(serde_json::Value::Bool(b)) => *b,
+            None => false,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_WRITE_ONLY,
+                storage_name
+            ),
+        };
+        let slow_query = match volume_cfg.get(PROP_STORAGE_SLOW_QUERY_MS) {
+            None => None,
+            Some(_) => Some(Duration::from_millis(get_u64_conf(
+                volume_cfg,
+                PROP_STORAGE_SLOW_QUERY_MS,
+                0,
+            )?)),
+        };
+        if write_only && latest_only {
+            bail!(
+                r#"`{}` property of storage `{}` is not supported with the "latest" history of the volume"#,
+                PROP_STORAGE_WRITE_ONLY,
+                storage_name
+            )
         }
+        Ok(Settings {
+            tombstone_window,
+            key_tags,
+            strict_schema,
+            max_points_per_chunk,
+            strict_parameters,
+            dedup_window,
+            ttl,
+            tombstone_ttl,
+            drop_measurement_timeout,
+            shutdown_timeout,
+            write_only,
+            slow_query,
+        })
     }
 }
 
@@ -131,18 +1559,22 @@ impl Plugin for InfluxDbBackend {
             }
         };
 
-        // The InfluxDB client used for administration purposes (show/create/drop databases)
-        let mut admin_client = Client::new(url, "");
+        // The provider of the secrets referenced in the configuration, if any
+        let secrets = match config.rest.get(PROP_BACKEND_VAULT) {
+            Some(serde_json::Value::Object(vault)) => Some(get_vault_conf(vault)?),
+            None => default_secret_provider(),
+            Some(_) => bail!(
+                "Property `{}` for InfluxDb Backend must be an object",
+                PROP_BACKEND_VAULT
+            ),
+        };
 
         // Note: remove username/password from properties to not re-expose them in admin_status
         let credentials = match (
-            get_private_conf(&config.rest, PROP_BACKEND_USERNAME)?,
-            get_private_conf(&config.rest, PROP_BACKEND_PASSWORD)?,
+            get_private_conf(&config.rest, PROP_BACKEND_USERNAME, secrets.as_deref())?,
+            get_private_conf(&config.rest, PROP_BACKEND_PASSWORD, secrets.as_deref())?,
         ) {
-            (Some(username), Some(password)) => {
-                admin_client = admin_client.with_auth(username, password);
-                Some((username.clone(), password.clone()))
-            }
+            (Some(username), Some(password)) => Some((username, password)),
             (None, None) => None,
             _ => {
                 bail!(
@@ -152,61 +1584,315 @@ impl Plugin for InfluxDbBackend {
                 )
             }
         };
+        let credentials_file = get_credentials_file_conf(&config.rest, credentials.is_some())?;
+        let credentials = match &credentials_file {
+            Some(path) => Some(read_credentials_file(path)?),
+            None => credentials,
+        };
 
-        // Check connectivity to InfluxDB, trying to list databases
-        match async_std::task::block_on(async { show_databases(&admin_client).await }) {
-            Ok(dbs) => {
-                // trick: if "_internal" db is not shown, it means the credentials are not for an admin
-                if !dbs.iter().any(|e| e == "_internal") {
-                    warn!("The InfluxDB credentials are not for an admin user; the volume won't be able to create or drop any database")
+        // The InfluxDB client used for administration purposes (show/create/drop databases)
+        let admin_client = SharedClient::new(Client::new(url, ""), credentials);
+
+        // Without admin credentials, the storages can't run the admin operations (create/drop databases...)
+        let require_admin = match config.rest.get(PROP_BACKEND_REQUIRE_ADMIN) {
+            Some(serde_json::Value::Bool(b)) => *b,
+            None => true,
+            Some(_) => bail!(
+                "Property `{}` for InfluxDb Backend must be a boolean",
+                PROP_BACKEND_REQUIRE_ADMIN
+            ),
+        };
+
+        if require_admin {
+            // Check connectivity to InfluxDB, trying to list databases
+            match async_std::task::block_on(async { show_databases(&admin_client.get()).await }) {
+                Ok(dbs) => {
+                    // trick: if "_internal" db is not shown, it means the credentials are not for an admin
+                    if !dbs.iter().any(|e| e == "_internal") {
+                        warn!("The InfluxDB credentials are not for an admin user; the volume won't be able to create or drop any database")
+                    }
                 }
+                Err(e) => bail!("Failed to create InfluxDb Volume : {}", e),
+            }
+        } else {
+            // Check connectivity to InfluxDB only (/ping doesn't require any privilege)
+            if let Err(e) = async_std::task::block_on(async { admin_client.get().ping().await }) {
+                bail!("Failed to create InfluxDb Volume : {}", e)
+            }
+        }
+
+        // The queue running background jobs for all the storages of this volume
+        let maintenance = MaintenanceQueue::new(
+            get_u64_conf(
+                &config.rest,
+                PROP_BACKEND_MAINTENANCE_THREADS,
+                DEFAULT_MAINTENANCE_THREADS as u64,
+            )? as usize,
+            get_u64_conf(
+                &config.rest,
+                PROP_BACKEND_MAINTENANCE_QUEUE_SIZE,
+                DEFAULT_MAINTENANCE_QUEUE_SIZE as u64,
+            )? as usize,
+        )?;
+
+        // Keep all the history, or only the latest point per key
+        let latest_only = match config.rest.get(PROP_BACKEND_HISTORY) {
+            Some(serde_json::Value::String(x)) if x == "all" => false,
+            Some(serde_json::Value::String(x)) if x == "latest" => true,
+            None => false,
+            Some(_) => bail!(
+                r#"Property `{}` for InfluxDb Backend must be one of "all" (default) and "latest""#,
+                PROP_BACKEND_HISTORY
+            ),
+        };
+
+        // periodically check the connectivity to InfluxDB
+        let health_check_interval =
+            get_duration_conf(&config.rest, PROP_BACKEND_HEALTH_CHECK_INTERVAL)?
+                .unwrap_or(DEFAULT_HEALTH_CHECK_INTERVAL);
+        let health = Arc::new(Health::new());
+        let timer = Timer::default();
+        task::block_on(timer.add_async(TimedEvent::periodic(
+            health_check_interval,
+            TimedHealthCheck {
+                client: admin_client.clone(),
+                health: health.clone(),
+                maintenance: maintenance.clone(),
+            },
+        )));
+
+        // periodically re-read the credentials file, to apply the rotated credentials
+        if let Some(path) = credentials_file {
+            task::block_on(timer.add_async(TimedEvent::periodic(
+                health_check_interval,
+                TimedCredentialsReload {
+                    path,
+                    client: admin_client.clone(),
+                    maintenance: maintenance.clone(),
+                },
+            )));
+        }
+
+        // serve the metrics of the storages over HTTP
+        match config.rest.get(PROP_BACKEND_METRICS_LISTEN) {
+            #[cfg(feature = "metrics")]
+            Some(serde_json::Value::String(address)) => {
+                task::spawn(metrics::serve(address.clone()));
             }
-            Err(e) => bail!("Failed to create InfluxDb Volume : {}", e),
+            #[cfg(not(feature = "metrics"))]
+            Some(serde_json::Value::String(_)) => warn!(
+                "Property `{}` for InfluxDb Backend is ignored: the backend is not built with the \"metrics\" feature",
+                PROP_BACKEND_METRICS_LISTEN
+            ),
+            None => (),
+            Some(_) => bail!(
+                "Property `{}` for InfluxDb Backend must be a string (e.g. \"127.0.0.1:9464\")",
+                PROP_BACKEND_METRICS_LISTEN
+            ),
+        }
+
+        // export the tracing spans to an OpenTelemetry collector
+        if let Some(_otlp) = config.rest.get(PROP_BACKEND_OTLP) {
+            #[cfg(feature = "otlp")]
+            otlp::install(_otlp)?;
+            #[cfg(not(feature = "otlp"))]
+            warn!(
+                "Property `{}` for InfluxDb Backend is ignored: the backend is not built with the \"otlp\" feature",
+                PROP_BACKEND_OTLP
+            );
         }
 
         Ok(Box::new(InfluxDbVolume {
             admin_status: config,
             admin_client,
-            credentials,
+            secrets,
+            maintenance,
+            latest_only,
+            require_admin,
+            health,
+            health_check_interval,
+            timer,
+            storages: RwLock::new(HashMap::new()),
         }))
     }
 }
 
 pub struct InfluxDbVolume {
     admin_status: VolumeConfig,
-    admin_client: Client,
-    credentials: Option<(String, String)>,
+    // with the admin credentials, shared with the storages' admin clients
+    admin_client: SharedClient,
+    // resolving the `{"secret": ...}` references of the storages' credentials
+    secrets: Option<Arc<dyn SecretProvider>>,
+    maintenance: MaintenanceQueue,
+    // if true, the storages only keep the latest point per key
+    latest_only: bool,
+    // if false, the storages are refused the configurations requiring admin privileges
+    require_admin: bool,
+    // the connectivity to InfluxDB, shared with the storages
+    health: Arc<Health>,
+    health_check_interval: Duration,
+    // running the health check (stopped on drop)
+    #[allow(dead_code)]
+    timer: Timer,
+    // the settings and clients of the storages, by storage name
+    storages: RwLock<HashMap<String, StorageHandle>>,
 }
 
-#[async_trait]
-impl Volume for InfluxDbVolume {
-    fn get_admin_status(&self) -> serde_json::Value {
-        self.admin_status.to_json_value()
+// What a volume keeps of one of its storages, to reconfigure it at runtime and shut it down
+struct StorageHandle {
+    settings: Weak<RwLock<Arc<Settings>>>,
+    client: SharedClient,
+    storage: Weak<AsyncMutex<InfluxDbStorage>>,
+}
+
+impl InfluxDbVolume {
+    // Shut down the running storages (each one within its `shutdown_timeout`), then stop the background jobs
+    // of the volume: the maintenance queue doesn't accept new jobs, and its threads stop once the already
+    // queued ones are completed.
+    // The storages not shut down this way are shut down when dropped.
+    pub async fn shutdown(&self) {
+        debug!("Shutting down InfluxDB volume");
+        let storages: Vec<_> = self
+            .storages
+            .read()
+            .unwrap()
+            .values()
+            .filter_map(|h| h.storage.upgrade())
+            .collect();
+        futures::future::join_all(storages.iter().map(|storage| async move {
+            let mut storage = storage.lock().await;
+            let deadline = Instant::now() + storage.settings().shutdown_timeout;
+            storage.shutdown(deadline).await;
+        }))
+        .await;
+        self.maintenance.close();
     }
 
-    fn get_capability(&self) -> Capability {
-        Capability {
-            persistence: Persistence::Durable,
-            history: History::All,
-            read_cost: 1,
+    // Replace the admin credentials of the volume and its storages (e.g. after a password rotation),
+    // for the requests started afterwards
+    pub fn rotate_credentials(&self, username: &str, password: &str) {
+        if self.admin_client.set_credentials(username, password) {
+            debug!("Rotated the admin credentials of the InfluxDB volume");
         }
     }
 
-    async fn create_storage(&self, mut config: StorageConfig) -> ZResult<Box<dyn Storage>> {
-        let volume_cfg = match config.volume_cfg.as_object() {
+    // Apply an updated volume-specific configuration to a running storage, without re-creating it
+    // (and thus without applying its `on_closure` strategy). Only the runtime settings and the
+    // credentials are changed (e.g. `key_tags`, `ttl`, `write_only`, `password`...): the other
+    // properties require to re-create the storage.
+    // The new settings are applied atomically, to the operations started afterwards.
+    pub fn reconfigure_storage(
+        &self,
+        storage_name: &str,
+        volume_cfg: &serde_json::Value,
+    ) -> ZResult<()> {
+        let volume_cfg = match volume_cfg.as_object() {
             Some(v) => v,
             None => bail!("InfluxDB backed storages need some volume-specific configuration"),
         };
-        let on_closure = match volume_cfg.get(PROP_STORAGE_ON_CLOSURE) {
-            Some(serde_json::Value::String(x)) if x == "drop_series" => OnClosure::DropSeries,
-            Some(serde_json::Value::String(x)) if x == "drop_db" => OnClosure::DropDb,
-            Some(serde_json::Value::String(x)) if x == "do_nothing" => OnClosure::DoNothing,
-            None => OnClosure::DoNothing,
-            Some(_) => {
+        let settings = Settings::from_config(volume_cfg, storage_name, self.latest_only)?;
+        let credentials = match (
+            get_private_conf(volume_cfg, PROP_STORAGE_USERNAME, self.secrets.as_deref())?,
+            get_private_conf(volume_cfg, PROP_STORAGE_PASSWORD, self.secrets.as_deref())?,
+        ) {
+            (Some(username), Some(password)) => Some((username, password)),
+            (None, None) => None,
+            _ => {
                 bail!(
-                    r#"`{}` property of storage `{}` must be one of "do_nothing" (default), "drop_db" and "drop_series""#,
-                    PROP_STORAGE_ON_CLOSURE,
-                    &config.name
+                    "Optional properties `{}` and `{}` must coexist",
+                    PROP_STORAGE_USERNAME,
+                    PROP_STORAGE_PASSWORD
+                )
+            }
+        };
+        let mut storages = self.storages.write().unwrap();
+        let handle = storages.get(storage_name);
+        match handle.and_then(|h| h.settings.upgrade().map(|s| (s, &h.client))) {
+            Some((current, client)) => {
+                *current.write().unwrap() = Arc::new(settings);
+                if let Some((username, password)) = credentials {
+                    if client.set_credentials(&username, &password) {
+                        debug!(
+                            "Rotated the credentials of InfluxDB storage {}",
+                            storage_name
+                        );
+                    }
+                }
+                debug!("Reconfigured InfluxDB storage {}", storage_name);
+                Ok(())
+            }
+            None => {
+                storages.remove(storage_name);
+                bail!("No running InfluxDB storage named {}", storage_name)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Volume for InfluxDbVolume {
+    fn get_admin_status(&self) -> serde_json::Value {
+        let mut status = self.admin_status.to_json_value();
+        if let Some(obj) = status.as_object_mut() {
+            obj.insert("health".into(), self.health.to_json());
+            #[cfg(feature = "metrics")]
+            obj.insert("metrics".into(), metrics::render().into());
+        }
+        // the databases visible to the admin user, with the storages mapped to each of them
+        if let (Some(databases), Some(obj)) = (self.health.databases(), status.as_object_mut()) {
+            let mut managed: HashMap<String, Vec<String>> = HashMap::new();
+            for (name, handle) in self.storages.read().unwrap().iter() {
+                if handle.settings.strong_count() > 0 {
+                    managed
+                        .entry(handle.client.get().database_name().to_string())
+                        .or_default()
+                        .push(name.clone());
+                }
+            }
+            obj.insert(
+                "databases".into(),
+                databases
+                    .into_iter()
+                    .map(|db| {
+                        let storages = managed.remove(&db).unwrap_or_default();
+                        serde_json::json!({ "name": db, "storages": storages })
+                    })
+                    .collect(),
+            );
+        }
+        status
+    }
+
+    fn get_capability(&self) -> Capability {
+        Capability {
+            persistence: Persistence::Durable,
+            history: if self.latest_only {
+                History::Latest
+            } else {
+                History::All
+            },
+            read_cost: 1,
+        }
+    }
+
+    async fn create_storage(&self, mut config: StorageConfig) -> ZResult<Box<dyn Storage>> {
+        let volume_cfg = match config.volume_cfg.as_object() {
+            Some(v) => v,
+            None => bail!("InfluxDB backed storages need some volume-specific configuration"),
+        };
+        let on_closure = match volume_cfg.get(PROP_STORAGE_ON_CLOSURE) {
+            Some(serde_json::Value::String(x)) if x == "drop_series" => OnClosure::DropSeries,
+            Some(serde_json::Value::String(x)) if x == "drop_db" => OnClosure::DropDb,
+            Some(serde_json::Value::String(x)) if x == "drop_created_series" => {
+                OnClosure::DropCreatedSeries
+            }
+            Some(serde_json::Value::String(x)) if x == "do_nothing" => OnClosure::DoNothing,
+            None => OnClosure::DoNothing,
+            Some(_) => {
+                bail!(
+                    r#"`{}` property of storage `{}` must be one of "do_nothing" (default), "drop_db", "drop_series" and "drop_created_series""#,
+                    PROP_STORAGE_ON_CLOSURE,
+                    &config.name
                 )
             }
         };
@@ -223,19 +1909,513 @@ impl Volume for InfluxDbVolume {
             Some(v) => bail!("Invalid value for ${PROP_STORAGE_DB} config property: ${v}"),
         };
 
-        // The Influx client on database used to write/query on this storage
-        // (using the same URL than backend's admin_client, but with storage credentials)
-        let mut client = Client::new(self.admin_client.database_url(), &db);
+        let settings = Settings::from_config(volume_cfg, &config.name, self.latest_only)?;
+        let retention = get_retention_conf(volume_cfg, &config.name)?;
+        let tiers = get_downsampling_conf(volume_cfg, &config.name)?;
+        if !self.require_admin {
+            if volume_cfg.get(PROP_STORAGE_DB).is_none() {
+                bail!(
+                    "`{}` property of storage `{}` is required when the volume has `{}: false` (a generated database must be created)",
+                    PROP_STORAGE_DB,
+                    &config.name,
+                    PROP_BACKEND_REQUIRE_ADMIN
+                )
+            }
+            let admin_property = if createdb {
+                Some(PROP_STORAGE_CREATE_DB)
+            } else if retention.is_some() {
+                Some(PROP_STORAGE_RETENTION)
+            } else if !tiers.is_empty() {
+                Some(PROP_STORAGE_DOWNSAMPLING)
+            } else if let OnClosure::DropDb = on_closure {
+                Some(PROP_STORAGE_ON_CLOSURE)
+            } else {
+                None
+            };
+            if let Some(property) = admin_property {
+                bail!(
+                    "`{}` property of storage `{}` requires admin privileges, but the volume has `{}: false`",
+                    property,
+                    &config.name,
+                    PROP_BACKEND_REQUIRE_ADMIN
+                )
+            }
+        }
+        let archive = get_archive_conf(volume_cfg, &config.name, self.secrets.as_deref())?;
+        let (offload, collect_blobs) =
+            match get_offload_conf(volume_cfg, &config.name, self.secrets.as_deref())? {
+                Some((store, threshold, collect)) => (Some((Arc::new(store), threshold)), collect),
+                None => (None, false),
+            };
+        let v2_target = get_v2_target_conf(volume_cfg, &config.name, self.secrets.as_deref())?;
+        let change_feed =
+            get_change_feed_conf(volume_cfg, &config.name, config.strip_prefix.as_ref())?;
+        let replicas = get_replicas_conf(volume_cfg, &config.name, self.secrets.as_deref())?;
+        let replayer = match get_replay_conf(volume_cfg, &config.name)? {
+            Some(connect) => Some(Replayer::start(&connect).await?),
+            None => None,
+        };
+        let events = match get_events_conf(volume_cfg, &config.name)? {
+            Some((key, connect)) => Some(Events::start(config.name.clone(), key, &connect).await?),
+            None => None,
+        };
+        let breaker = get_circuit_breaker_conf(volume_cfg, &config.name)?
+            .map(|b| b.with_events(events.clone()));
+        let buffer = match get_u64_conf(volume_cfg, PROP_STORAGE_BUFFER_SIZE, 0)? {
+            0 => None,
+            n => Some(Arc::new(WriteBuffer::new(n as usize))),
+        };
+        let priorities = get_priorities_conf(volume_cfg, &config.name)?;
+        let layout = match volume_cfg.get(PROP_STORAGE_LAYOUT) {
+            Some(serde_json::Value::String(x)) if x == "measurement_per_key" => {
+                Layout::MeasurementPerKey
+            }
+            Some(serde_json::Value::String(x)) if x == "single_measurement" => {
+                match volume_cfg.get(PROP_STORAGE_MEASUREMENT) {
+                    None => Layout::SingleMeasurement(DEFAULT_MEASUREMENT.into()),
+                    Some(serde_json::Value::String(m)) if !m.is_empty() => {
+                        Layout::SingleMeasurement(m.clone())
+                    }
+                    Some(_) => bail!(
+                        "`{}` property of storage `{}` must be a non-empty string",
+                        PROP_STORAGE_MEASUREMENT,
+                        &config.name
+                    ),
+                }
+            }
+            None => Layout::MeasurementPerKey,
+            Some(_) => {
+                bail!(
+                    r#"`{}` property of storage `{}` must be one of "measurement_per_key" (default) and "single_measurement""#,
+                    PROP_STORAGE_LAYOUT,
+                    &config.name
+                )
+            }
+        };
+        if let (OnClosure::DropCreatedSeries, Layout::SingleMeasurement(_)) = (&on_closure, &layout)
+        {
+            bail!(
+                r#"`{}` property of storage `{}` can't be "drop_created_series" with the "single_measurement" layout"#,
+                PROP_STORAGE_ON_CLOSURE,
+                &config.name
+            )
+        }
+        let closure_protection = get_closure_protection_conf(volume_cfg, &config.name)?;
+        let routing = get_routing_conf(volume_cfg, &config.name)?;
+        let verify_interval = get_duration_conf(volume_cfg, PROP_STORAGE_VERIFY_INTERVAL)?;
+        let on_drift = match volume_cfg.get(PROP_STORAGE_ON_DRIFT) {
+            Some(serde_json::Value::String(x)) if x == "warn" => OnDrift::Warn,
+            Some(serde_json::Value::String(x)) if x == "repair" => OnDrift::Repair,
+            None => OnDrift::Warn,
+            Some(_) => {
+                bail!(
+                    r#"`{}` property of storage `{}` must be one of "warn" (default) and "repair""#,
+                    PROP_STORAGE_ON_DRIFT,
+                    &config.name
+                )
+            }
+        };
+        let value_hash = match volume_cfg.get(PROP_STORAGE_VALUE_HASH) {
+            None => None,
+            Some(serde_json::Value::String(x)) if x == "none" => None,
+            Some(serde_json::Value::String(x)) if ValueHash::from_name(x).is_some() => {
+                ValueHash::from_name(x)
+            }
+            Some(_) => bail!(
+                r#"`{}` property of storage `{}` must be one of "none" (default), "sha256" and "xxhash64""#,
+                PROP_STORAGE_VALUE_HASH,
+                &config.name
+            ),
+        };
+        let on_corrupted = match volume_cfg.get(PROP_STORAGE_ON_CORRUPTED) {
+            Some(serde_json::Value::String(x)) if x == "skip" => OnCorrupted::Skip,
+            Some(serde_json::Value::String(x)) if x == "flag" => OnCorrupted::Flag,
+            None => OnCorrupted::Skip,
+            Some(_) => {
+                bail!(
+                    r#"`{}` property of storage `{}` must be one of "skip" (default) and "flag""#,
+                    PROP_STORAGE_ON_CORRUPTED,
+                    &config.name
+                )
+            }
+        };
+        let migrate_schema = match volume_cfg.get(PROP_STORAGE_MIGRATE_SCHEMA) {
+            Some(serde_json::Value::Bool(b)) => *b,
+            None => false,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_MIGRATE_SCHEMA,
+                &config.name
+            ),
+        };
+        let read_external = match volume_cfg.get(PROP_STORAGE_READ_EXTERNAL) {
+            Some(serde_json::Value::Bool(true)) => {
+                Some(match volume_cfg.get(PROP_STORAGE_EXTERNAL_VALUE_FIELD) {
+                    Some(serde_json::Value::String(f)) => f.clone(),
+                    None => DEFAULT_EXTERNAL_VALUE_FIELD.to_string(),
+                    Some(_) => bail!(
+                        "`{}` property of storage `{}` must be a string",
+                        PROP_STORAGE_EXTERNAL_VALUE_FIELD,
+                        &config.name
+                    ),
+                })
+            }
+            Some(serde_json::Value::Bool(false)) | None => None,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_READ_EXTERNAL,
+                &config.name
+            ),
+        };
+        let none_key = match volume_cfg.get(PROP_STORAGE_NONE_KEY) {
+            Some(serde_json::Value::String(k)) => match OwnedKeyExpr::from_str(k) {
+                Ok(k) if !k.is_wild() && check_key(k.as_str()).is_ok() => k,
+                _ => bail!(
+                    "`{}` property of storage `{}` must be a key without wildcards",
+                    PROP_STORAGE_NONE_KEY,
+                    &config.name
+                ),
+            },
+            None => OwnedKeyExpr::from_str(NONE_KEY).unwrap(),
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a string",
+                PROP_STORAGE_NONE_KEY,
+                &config.name
+            ),
+        };
+        #[cfg(feature = "fault_injection")]
+        let faults = match volume_cfg.get(PROP_STORAGE_FAULT_INJECTION) {
+            Some(c) => Some(fault_injection::FaultInjector::from_config(c)?),
+            None => None,
+        };
+        #[cfg(not(feature = "fault_injection"))]
+        {
+            if volume_cfg.contains_key(PROP_STORAGE_FAULT_INJECTION) {
+                warn!(
+                    "`{}` property of storage `{}` is ignored: the backend is not built with the \"fault_injection\" feature",
+                    PROP_STORAGE_FAULT_INJECTION,
+                    &config.name
+                );
+            }
+        }
+        let admin_operations = match volume_cfg.get(PROP_STORAGE_ADMIN_OPERATIONS) {
+            Some(serde_json::Value::Bool(b)) => *b,
+            None => false,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_ADMIN_OPERATIONS,
+                &config.name
+            ),
+        };
+        let export_dir = match volume_cfg.get(PROP_STORAGE_EXPORT_DIR) {
+            Some(serde_json::Value::String(dir)) => Some(PathBuf::from(dir)),
+            None => None,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a string",
+                PROP_STORAGE_EXPORT_DIR,
+                &config.name
+            ),
+        };
+        let import_dir = match volume_cfg.get(PROP_STORAGE_IMPORT_DIR) {
+            Some(serde_json::Value::String(dir)) => Some(PathBuf::from(dir)),
+            None => None,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a string",
+                PROP_STORAGE_IMPORT_DIR,
+                &config.name
+            ),
+        };
+        let allow_raw_queries = match volume_cfg.get(PROP_STORAGE_ALLOW_RAW_QUERIES) {
+            Some(serde_json::Value::Bool(b)) => *b,
+            None => false,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_ALLOW_RAW_QUERIES,
+                &config.name
+            ),
+        };
+        let mut numeric_values = match volume_cfg.get(PROP_STORAGE_NUMERIC_VALUES) {
+            Some(serde_json::Value::Bool(b)) => *b,
+            None => false,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_NUMERIC_VALUES,
+                &config.name
+            ),
+        };
+        let mut compression = match volume_cfg.get(PROP_STORAGE_COMPRESSION) {
+            None => None,
+            Some(serde_json::Value::String(x)) if x == "none" => None,
+            Some(serde_json::Value::String(x)) if Compression::from_name(x).is_some() => {
+                Compression::from_name(x)
+            }
+            Some(_) => bail!(
+                r#"`{}` property of storage `{}` must be one of "none" (default), "zstd" and "lz4""#,
+                PROP_STORAGE_COMPRESSION,
+                &config.name
+            ),
+        };
+        let compression_threshold = get_u64_conf(
+            volume_cfg,
+            PROP_STORAGE_COMPRESSION_THRESHOLD,
+            DEFAULT_COMPRESSION_THRESHOLD,
+        )? as usize;
+        // the codec of the values (by default, the one of the `numeric_values` property), compressing the
+        // large payloads with the `compression` property (or zstd with the "compressed" codec)
+        let codec: Box<dyn ValueCodec> = match volume_cfg.get(PROP_STORAGE_CODEC) {
+            None if numeric_values => Box::new(NumericCodec),
+            None => Box::new(StringCodec),
+            Some(serde_json::Value::String(x)) if numeric_values && x != "numeric" => bail!(
+                r#"`{}` property of storage `{}` must be "numeric" with `{}` property"#,
+                PROP_STORAGE_CODEC,
+                &config.name,
+                PROP_STORAGE_NUMERIC_VALUES
+            ),
+            Some(serde_json::Value::String(x)) if x == "string" => Box::new(StringCodec),
+            Some(serde_json::Value::String(x)) if x == "compressed" => {
+                compression = compression.or(Some(Compression::Zstd));
+                Box::new(StringCodec)
+            }
+            Some(serde_json::Value::String(x)) if x == "numeric" => {
+                numeric_values = true;
+                Box::new(NumericCodec)
+            }
+            Some(serde_json::Value::String(x)) if x == "json_flatten" => Box::new(JsonFlattenCodec),
+            Some(_) => bail!(
+                r#"`{}` property of storage `{}` must be one of "string" (default), "numeric", "json_flatten" and "compressed""#,
+                PROP_STORAGE_CODEC,
+                &config.name
+            ),
+        };
+        let codec: Box<dyn ValueCodec> = match compression {
+            Some(compression) => Box::new(CompressedCodec {
+                compression,
+                threshold: compression_threshold,
+                inner: codec,
+            }),
+            None => codec,
+        };
+        let chunk_size =
+            get_u64_conf(volume_cfg, PROP_STORAGE_CHUNK_SIZE, DEFAULT_CHUNK_SIZE)? as usize;
+        let alignment_concurrency = get_u64_conf(
+            volume_cfg,
+            PROP_STORAGE_ALIGNMENT_CONCURRENCY,
+            DEFAULT_ALIGNMENT_CONCURRENCY,
+        )?
+        .max(1) as usize;
+        let latest_cache = match get_u64_conf(volume_cfg, PROP_STORAGE_LATEST_CACHE_SIZE, 0)? {
+            0 => None,
+            n => Some(LatestCache::new(n as usize)),
+        };
+        let entries_cache =
+            get_duration_conf(volume_cfg, PROP_STORAGE_ENTRIES_CACHE)?.map(EntriesCache::new);
+        let deadband = match volume_cfg.get(PROP_STORAGE_DEADBAND) {
+            None => None,
+            Some(serde_json::Value::Number(n)) if n.as_f64().map_or(false, |d| d >= 0.0) => {
+                n.as_f64()
+            }
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a positive number",
+                PROP_STORAGE_DEADBAND,
+                &config.name
+            ),
+        };
+        let dedup = match (volume_cfg.get(PROP_STORAGE_DEDUP), deadband) {
+            (None | Some(serde_json::Value::Bool(false)), None) => None,
+            // the deadband implies the deduplication: refuse to silently ignore an explicit `false`
+            (Some(serde_json::Value::Bool(false)), Some(_)) => bail!(
+                "`{}` property of storage `{}` requires `{}` (it can't be `false`)",
+                PROP_STORAGE_DEADBAND,
+                &config.name,
+                PROP_STORAGE_DEDUP
+            ),
+            (None | Some(serde_json::Value::Bool(true)), _) => Some(PutDedup::new(
+                get_duration_conf(volume_cfg, PROP_STORAGE_DEDUP_INTERVAL)?
+                    .unwrap_or(DEFAULT_DEDUP_INTERVAL),
+                deadband,
+            )),
+            (Some(_), _) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_DEDUP,
+                &config.name
+            ),
+        };
+        let max_series = match volume_cfg.get(PROP_STORAGE_MAX_SERIES) {
+            None => None,
+            Some(_) => Some(get_u64_conf(volume_cfg, PROP_STORAGE_MAX_SERIES, 0)?),
+        };
+        let rate_limiter = RateLimiter::new(
+            match volume_cfg.get(PROP_STORAGE_MAX_POINTS_PER_SEC) {
+                None => None,
+                Some(_) => Some(get_u64_conf(
+                    volume_cfg,
+                    PROP_STORAGE_MAX_POINTS_PER_SEC,
+                    0,
+                )?),
+            },
+            match volume_cfg.get(PROP_STORAGE_MAX_BYTES_PER_SEC) {
+                None => None,
+                Some(_) => Some(get_u64_conf(volume_cfg, PROP_STORAGE_MAX_BYTES_PER_SEC, 0)?),
+            },
+        );
+        let clock_skew = (
+            get_duration_conf(volume_cfg, PROP_STORAGE_MAX_FUTURE_SKEW)?,
+            get_duration_conf(volume_cfg, PROP_STORAGE_MAX_PAST_SKEW)?,
+        );
+        let conflict_policy = match volume_cfg.get(PROP_STORAGE_CONFLICT_POLICY) {
+            Some(serde_json::Value::String(x)) if x == "newest-wins" => ConflictPolicy::NewestWins,
+            Some(serde_json::Value::String(x)) if x == "first-wins" => ConflictPolicy::FirstWins,
+            Some(serde_json::Value::String(x)) if x == "keep-both" => ConflictPolicy::KeepBoth,
+            None => ConflictPolicy::LastWritten,
+            Some(_) => {
+                bail!(
+                    r#"`{}` property of storage `{}` must be one of "newest-wins", "first-wins" and "keep-both""#,
+                    PROP_STORAGE_CONFLICT_POLICY,
+                    &config.name
+                )
+            }
+        };
+        let on_clock_skew = match volume_cfg.get(PROP_STORAGE_ON_CLOCK_SKEW) {
+            Some(serde_json::Value::String(x)) if x == "reject" => OnClockSkew::Reject,
+            Some(serde_json::Value::String(x)) if x == "clamp" => OnClockSkew::Clamp,
+            None => OnClockSkew::Reject,
+            Some(_) => {
+                bail!(
+                    r#"`{}` property of storage `{}` must be one of "reject" (default) and "clamp""#,
+                    PROP_STORAGE_ON_CLOCK_SKEW,
+                    &config.name
+                )
+            }
+        };
+        let delete_strategy = match volume_cfg.get(PROP_STORAGE_DELETE_STRATEGY) {
+            Some(serde_json::Value::String(x)) if x == "tombstone" => DeleteStrategy::Tombstone,
+            Some(serde_json::Value::String(x)) if x == "drop" => DeleteStrategy::Drop,
+            Some(serde_json::Value::String(x)) if x == "mark" => DeleteStrategy::Mark,
+            None => DeleteStrategy::Tombstone,
+            Some(_) => {
+                bail!(
+                    r#"`{}` property of storage `{}` must be one of "tombstone" (default), "drop" and "mark""#,
+                    PROP_STORAGE_DELETE_STRATEGY,
+                    &config.name
+                )
+            }
+        };
+        if delete_strategy == DeleteStrategy::Mark && settings.tombstone_ttl.is_some() {
+            warn!(
+                r#"`{}` property of storage `{}` is ignored with the "mark" `{}`: the deletion markers are kept"#,
+                PROP_STORAGE_TOMBSTONE_TTL, &config.name, PROP_STORAGE_DELETE_STRATEGY
+            );
+        }
+        let on_rate_limit = match volume_cfg.get(PROP_STORAGE_ON_RATE_LIMIT) {
+            Some(serde_json::Value::String(x)) if x == "refuse" => OnRateLimit::Refuse,
+            Some(serde_json::Value::String(x)) if x == "drop" => OnRateLimit::Drop,
+            None => OnRateLimit::Refuse,
+            Some(_) => {
+                bail!(
+                    r#"`{}` property of storage `{}` must be one of "refuse" (default) and "drop""#,
+                    PROP_STORAGE_ON_RATE_LIMIT,
+                    &config.name
+                )
+            }
+        };
+        let on_max_series = match volume_cfg.get(PROP_STORAGE_ON_MAX_SERIES) {
+            Some(serde_json::Value::String(x)) if x == "warn" => OnMaxSeries::Warn,
+            Some(serde_json::Value::String(x)) if x == "refuse" => OnMaxSeries::Refuse,
+            None => OnMaxSeries::Warn,
+            Some(_) => {
+                bail!(
+                    r#"`{}` property of storage `{}` must be one of "warn" (default) and "refuse""#,
+                    PROP_STORAGE_ON_MAX_SERIES,
+                    &config.name
+                )
+            }
+        };
+        let schema = match volume_cfg.get(PROP_STORAGE_SCHEMA) {
+            Some(serde_json::Value::String(x)) if x == "current" => Schema::Current,
+            Some(serde_json::Value::String(x)) if x == "compat" => Schema::Compat,
+            None => Schema::Current,
+            Some(_) => {
+                bail!(
+                    r#"`{}` property of storage `{}` must be one of "current" (default) and "compat""#,
+                    PROP_STORAGE_SCHEMA,
+                    &config.name
+                )
+            }
+        };
+        let long_keys = match volume_cfg.get(PROP_STORAGE_MAX_MEASUREMENT_LENGTH) {
+            None => None,
+            Some(_) => {
+                let max_length = get_u64_conf(volume_cfg, PROP_STORAGE_MAX_MEASUREMENT_LENGTH, 0)?;
+                if max_length < MIN_MEASUREMENT_LENGTH {
+                    bail!(
+                        "`{}` property of storage `{}` must be at least {}",
+                        PROP_STORAGE_MAX_MEASUREMENT_LENGTH,
+                        &config.name,
+                        MIN_MEASUREMENT_LENGTH
+                    )
+                }
+                if let Layout::SingleMeasurement(_) = layout {
+                    bail!(
+                        "`{}` property of storage `{}` is not supported with the \"single_measurement\" layout",
+                        PROP_STORAGE_MAX_MEASUREMENT_LENGTH,
+                        &config.name
+                    )
+                }
+                Some(max_length as usize)
+            }
+        };
+        let stats_interval = get_duration_conf(volume_cfg, PROP_STORAGE_STATS_INTERVAL)?
+            .unwrap_or(DEFAULT_STATS_INTERVAL);
+        let cardinality_check_interval =
+            get_duration_conf(volume_cfg, PROP_STORAGE_CARDINALITY_CHECK_INTERVAL)?
+                .unwrap_or(DEFAULT_CARDINALITY_CHECK_INTERVAL);
+        let max_history_per_key = match volume_cfg.get(PROP_STORAGE_MAX_HISTORY_PER_KEY) {
+            None => None,
+            Some(_) => match get_u64_conf(volume_cfg, PROP_STORAGE_MAX_HISTORY_PER_KEY, 0)? {
+                0 => bail!(
+                    "`{}` property of storage `{}` must be a positive integer",
+                    PROP_STORAGE_MAX_HISTORY_PER_KEY,
+                    &config.name
+                ),
+                n => Some(n),
+            },
+        };
+        let history_trim_interval =
+            get_duration_conf(volume_cfg, PROP_STORAGE_HISTORY_TRIM_INTERVAL)?
+                .unwrap_or(DEFAULT_HISTORY_TRIM_INTERVAL);
+        let gc_interval =
+            get_duration_conf(volume_cfg, PROP_STORAGE_GC_INTERVAL)?.unwrap_or(DEFAULT_GC_INTERVAL);
 
+        // The InfluxDB server of this storage: the backend's one, or its own URL
+        let url = match volume_cfg.get(PROP_STORAGE_URL) {
+            None => None,
+            Some(serde_json::Value::String(url)) => Some(url.clone()),
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a string",
+                PROP_STORAGE_URL,
+                &config.name
+            ),
+        };
+        let tls = get_tls_conf(volume_cfg, &config.name)?;
+        let http_client = match (&url, &tls) {
+            (Some(_), Some(tls)) => Some(tls.http_client()?),
+            (None, Some(_)) => bail!(
+                "`{}` property of storage `{}` requires its own `{}`",
+                PROP_STORAGE_TLS,
+                &config.name,
+                PROP_STORAGE_URL
+            ),
+            (_, None) => None,
+        };
+
+        // The Influx client on database used to write/query on this storage
+        // (using the same URL than backend's admin_client, or the storage's one, with storage credentials)
         // Use credentials if specified in storage's volume config
-        let storage_username = match (
-            get_private_conf(volume_cfg, PROP_STORAGE_USERNAME)?,
-            get_private_conf(volume_cfg, PROP_STORAGE_PASSWORD)?,
+        let credentials = match (
+            get_private_conf(volume_cfg, PROP_STORAGE_USERNAME, self.secrets.as_deref())?,
+            get_private_conf(volume_cfg, PROP_STORAGE_PASSWORD, self.secrets.as_deref())?,
         ) {
-            (Some(username), Some(password)) => {
-                client = client.with_auth(username, password);
-                Some(username.clone())
-            }
+            (Some(username), Some(password)) => Some((username, password)),
             (None, None) => None,
             _ => {
                 bail!(
@@ -245,12 +2425,121 @@ impl Volume for InfluxDbVolume {
                 )
             }
         };
+        let credentials_file = get_credentials_file_conf(volume_cfg, credentials.is_some())?;
+        let provision = match volume_cfg.get(PROP_STORAGE_PROVISION_CREDENTIALS) {
+            None | Some(serde_json::Value::Bool(false)) => false,
+            Some(serde_json::Value::Bool(true)) => true,
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_PROVISION_CREDENTIALS,
+                &config.name
+            ),
+        };
+        let credentials = match &credentials_file {
+            // with provisioning, the file is written once the credentials are created
+            Some(path) if provision && !path.exists() => None,
+            Some(path) => Some(read_credentials_file(path)?),
+            None => credentials,
+        };
+        // The client used for the server-wide operations (databases, users, retention policies...):
+        // the backend's admin_client, or on the storage's own server a client with the storage credentials
+        let own_server = url.is_some();
+        let server_admin = match url {
+            Some(url) => {
+                let server_admin = SharedClient::new(Client::new(url, ""), credentials.clone());
+                match &http_client {
+                    Some(http_client) => server_admin.with_http_client(http_client.clone()),
+                    None => server_admin,
+                }
+            }
+            None => self.admin_client.clone(),
+        };
+        let client = SharedClient::new(
+            Client::new(server_admin.get().database_url(), &db),
+            credentials,
+        );
+        let client = match http_client {
+            Some(http_client) => client.with_http_client(http_client),
+            None => client,
+        };
+
+        // with provisioning, create a user only granted on the database if the storage creates it
+        // (and has no credentials of its own), and keep its credentials privately
+        let mut provisioned = None;
+        if provision
+            && createdb
+            && client.username().is_none()
+            && !is_db_existing(&server_admin.get(), &db).await?
+        {
+            let (username, password) = provision_user(&server_admin.get(), &db).await?;
+            if let Some(path) = &credentials_file {
+                write_credentials_file(path, &username, &password)?;
+            }
+            client.set_credentials(&username, &password);
+            provisioned = Some((username, password));
+        }
+        let storage_username = client.username();
+
+        // warn early if the database won't be dropped on closure
+        if let (OnClosure::DropDb, Some(protection)) = (&on_closure, &closure_protection) {
+            if !protection.allows_drop_db(&db) {
+                warn!(
+                    r#"Database '{}' of storage `{}` matches neither `{}.db_pattern` nor `{}.confirm_drop`: it won't be dropped on closure"#,
+                    db,
+                    &config.name,
+                    PROP_STORAGE_ON_CLOSURE_PROTECTION,
+                    PROP_STORAGE_ON_CLOSURE_PROTECTION
+                );
+            }
+        }
+
+        // The InfluxDB objects created by this storage
+        let mut managed = ManagedObjects::default();
+
+        // the databases of the routed keys, created if needed (and applied the `on_closure` of the storage)
+        let mut routes = Vec::with_capacity(routing.len());
+        for route in routing {
+            let route_client = client.for_database(&route.db);
+            if !is_db_existing(&route_client.get(), &route.db).await? {
+                if createdb {
+                    create_db(&server_admin.get(), &route.db, storage_username.clone()).await?;
+                } else {
+                    bail!(
+                        "Database '{}' of `{}` property doesn't exist in InfluxDb",
+                        route.db,
+                        PROP_STORAGE_ROUTING
+                    )
+                }
+            }
+            if let (Some(_), false) = (&route.retention, self.require_admin) {
+                bail!(
+                    "`{}.retention` property of storage `{}` requires admin privileges, but the volume has `{}: false`",
+                    PROP_STORAGE_ROUTING,
+                    &config.name,
+                    PROP_BACKEND_REQUIRE_ADMIN
+                )
+            }
+            if let Some(rp) = &route.retention {
+                let admin_client = server_admin.for_database(&route.db);
+                set_retention_policy(&admin_client.get(), &route.db, rp).await?;
+                managed
+                    .retention_policies
+                    .push((route.db.clone(), rp.clone()));
+            }
+            routes.push(Route {
+                key_expr: route.key_expr,
+                client: route_client,
+                admin_client: server_admin.for_database(&route.db),
+            });
+        }
 
         // Check if the database exists (using storages credentials)
-        if !is_db_existing(&client, &db).await? {
+        if !is_db_existing(&client.get(), &db).await? {
             if createdb {
                 // create db using backend's credentials
-                create_db(&self.admin_client, &db, storage_username).await?;
+                create_db(&server_admin.get(), &db, storage_username.clone()).await?;
+                managed.database = Some(db.clone());
+                managed.storage_username = storage_username;
             } else {
                 bail!("Database '{}' doesn't exist in InfluxDb", db)
             }
@@ -263,46 +2552,441 @@ impl Volume for InfluxDbVolume {
             .unwrap()
             .entry(PROP_STORAGE_DB)
             .or_insert(db.clone().into());
+        // insert the provisioned credentials (if not written in the credentials file) as private properties
+        let provisioned_user = match provisioned {
+            Some((username, password)) => {
+                if credentials_file.is_none() {
+                    let private = config
+                        .volume_cfg
+                        .as_object_mut()
+                        .unwrap()
+                        .entry("private")
+                        .or_insert_with(|| serde_json::Value::Object(Default::default()));
+                    if let Some(private) = private.as_object_mut() {
+                        private.insert(PROP_STORAGE_USERNAME.into(), username.clone().into());
+                        private.insert(PROP_STORAGE_PASSWORD.into(), password.into());
+                    }
+                }
+                Some(username)
+            }
+            None => None,
+        };
 
         // The Influx client on database with backend's credentials (admin), to drop measurements and database
-        let mut admin_client = Client::new(self.admin_client.database_url(), db);
-        if let Some((username, password)) = &self.credentials {
-            admin_client = admin_client.with_auth(username, password);
+        let admin_client = server_admin.for_database(&db);
+
+        // create or update the retention policy, as the default one so all points are written into it
+        if let Some(rp) = &retention {
+            set_retention_policy(&admin_client.get(), &db, rp).await?;
+            managed.retention_policies.push((db.clone(), rp.clone()));
         }
 
-        Ok(Box::new(InfluxDbStorage {
-            config,
-            admin_client,
-            client,
-            on_closure,
-            timer: Timer::default(),
-        }))
-    }
+        // create the downsampling tiers: their retention policies and continuous queries
+        for tier in &tiers {
+            set_retention_policy(&admin_client.get(), &db, &tier.retention).await?;
+            let query = downsampling_continuous_query(
+                &db,
+                tier,
+                &layout,
+                schema == Schema::Compat,
+                numeric_values,
+            );
+            set_continuous_query(&admin_client.get(), &db, &tier.retention.name, &query).await?;
+            managed
+                .retention_policies
+                .push((db.clone(), tier.retention.clone()));
+            managed.continuous_queries.push(ContinuousQuery {
+                db: db.clone(),
+                name: tier.retention.name.clone(),
+                select: query,
+            });
+        }
 
-    fn incoming_data_interceptor(&self) -> Option<Arc<dyn Fn(Sample) -> Sample + Send + Sync>> {
-        None
-    }
+        // with the "drop_created_series" closure, the measurements which must be kept
+        let created_measurements = match on_closure {
+            OnClosure::DropCreatedSeries => {
+                let mut existing = HashMap::new();
+                for c in std::iter::once(&client).chain(routes.iter().map(|r| &r.client)) {
+                    let c = c.get();
+                    let measurements = show_measurements(&c).await?.into_iter().collect();
+                    existing.insert(c.database_name().to_string(), measurements);
+                }
+                Some(CreatedMeasurements {
+                    existing,
+                    created: HashMap::new(),
+                })
+            }
+            _ => None,
+        };
 
-    fn outgoing_data_interceptor(&self) -> Option<Arc<dyn Fn(Sample) -> Sample + Send + Sync>> {
-        None
-    }
-}
+        // load the index of the keys written in the storage's databases, if enabled
+        let key_index = match volume_cfg.get(PROP_STORAGE_KEY_INDEX) {
+            None | Some(serde_json::Value::Bool(false)) => None,
+            Some(serde_json::Value::Bool(true)) => {
+                let mut clients = vec![client.get()];
+                clients.extend(routes.iter().map(|r| r.client.get()));
+                Some(KeyIndex::load(&clients).await?)
+            }
+            Some(_) => bail!(
+                "`{}` property of storage `{}` must be a boolean",
+                PROP_STORAGE_KEY_INDEX,
+                &config.name
+            ),
+        };
 
-enum OnClosure {
-    DropDb,
-    DropSeries,
-    DoNothing,
-}
+        // load the mapping of the hashed measurement names to their keys
+        let long_keys = match long_keys {
+            Some(max_length) => Some(Arc::new(HashingMapper {
+                max_length,
+                names: RwLock::new(load_long_keys(&client.get()).await?),
+            })),
+            None => None,
+        };
+        let key_mapper: Arc<dyn KeyMapper> = match (&layout, &long_keys) {
+            (Layout::SingleMeasurement(m), _) => Arc::new(SingleMeasurementMapper {
+                measurement: m.clone(),
+            }),
+            (Layout::MeasurementPerKey, Some(long_keys)) => long_keys.clone(),
+            (Layout::MeasurementPerKey, None) => Arc::new(PerKeyMapper),
+        };
 
-impl TryFrom<&Properties> for OnClosure {
-    type Error = zenoh_core::Error;
-    fn try_from(p: &Properties) -> ZResult<OnClosure> {
+        // periodically verify that the created objects still exist in InfluxDB
+        let timer = Timer::default();
+        if let Some(interval) = verify_interval {
+            if !managed.is_empty() {
+                timer
+                    .add_async(TimedEvent::periodic(
+                        interval,
+                        TimedVerification {
+                            client: server_admin.clone(),
+                            managed,
+                            on_drift,
+                            maintenance: self.maintenance.clone(),
+                        },
+                    ))
+                    .await;
+            }
+        }
+
+        // the clients of the storage's databases (its own one and the ones of its routes), maintained by the
+        // periodic jobs
+        let mut db_clients = vec![client.clone()];
+        for route in &routes {
+            let db = route.client.data();
+            if !db_clients
+                .iter()
+                .any(|c| c.data().database_name() == db.database_name())
+            {
+                db_clients.push(route.client.clone());
+            }
+        }
+
+        // track the series cardinality, if limited
+        let series_cardinality = Arc::new(AtomicU64::new(0));
+        if max_series.is_some() {
+            let clients: Vec<_> = db_clients.iter().map(|c| c.data()).collect();
+            match get_total_series_cardinality(&clients).await {
+                Ok(n) => series_cardinality.store(n, Ordering::Relaxed),
+                Err(e) => warn!("{}", e),
+            }
+            timer
+                .add_async(TimedEvent::periodic(
+                    cardinality_check_interval,
+                    TimedCardinalityCheck {
+                        clients: db_clients.clone(),
+                        series_cardinality: series_cardinality.clone(),
+                        maintenance: self.maintenance.clone(),
+                    },
+                ))
+                .await;
+        }
+
+        // periodically count the measurements, series and points of the storage
+        let stats = Arc::new(StorageStats::new());
+        #[cfg(feature = "metrics")]
+        let metrics = metrics::StorageMetrics::register(&config.name);
+        timer
+            .add_async(TimedEvent::periodic(
+                stats_interval,
+                TimedStatsRefresh {
+                    clients: db_clients.clone(),
+                    measurement: match &layout {
+                        Layout::MeasurementPerKey => None,
+                        Layout::SingleMeasurement(m) => Some(m.clone()),
+                    },
+                    stats: stats.clone(),
+                    maintenance: self.maintenance.clone(),
+                },
+            ))
+            .await;
+
+        // periodically trim the history of each key, if limited
+        let trimmed_points = Arc::new(AtomicU64::new(0));
+        if let Some(max_history) = max_history_per_key {
+            for db_client in &db_clients {
+                timer
+                    .add_async(TimedEvent::periodic(
+                        history_trim_interval,
+                        TimedHistoryTrim {
+                            client: db_client.clone(),
+                            measurement: match &layout {
+                                Layout::MeasurementPerKey => None,
+                                Layout::SingleMeasurement(m) => Some(m.clone()),
+                            },
+                            max_history,
+                            trimmed_points: trimmed_points.clone(),
+                            maintenance: self.maintenance.clone(),
+                        },
+                    ))
+                    .await;
+            }
+        }
+
+        // periodically remove the expired points and/or deletion markers, if a TTL is set
+        // (always scheduled, as the TTLs can be reconfigured)
+        let settings: SharedSettings = Arc::new(RwLock::new(Arc::new(settings)));
+        let last_gc = Arc::new(RwLock::new(None));
+        for db_client in &db_clients {
+            timer
+                .add_async(TimedEvent::periodic(
+                    gc_interval,
+                    TimedGarbageCollection {
+                        client: db_client.clone(),
+                        measurement: match &layout {
+                            Layout::MeasurementPerKey => None,
+                            Layout::SingleMeasurement(m) => Some(m.clone()),
+                        },
+                        settings: settings.clone(),
+                        last_gc: last_gc.clone(),
+                        keep_markers: delete_strategy == DeleteStrategy::Mark,
+                        events: events.clone(),
+                        maintenance: self.maintenance.clone(),
+                    },
+                ))
+                .await;
+        }
+        // and the offloaded payloads no longer referenced by the points
+        if let (Some((store, _)), true) = (&offload, collect_blobs) {
+            timer
+                .add_async(TimedEvent::periodic(
+                    gc_interval,
+                    TimedBlobCollection {
+                        clients: db_clients.clone(),
+                        measurement: match &layout {
+                            Layout::MeasurementPerKey => None,
+                            Layout::SingleMeasurement(m) => Some(m.clone()),
+                        },
+                        store: store.clone(),
+                        maintenance: self.maintenance.clone(),
+                    },
+                ))
+                .await;
+        }
+
+        // on its own server, the storage periodically checks the connectivity to it
+        let health = if own_server {
+            let health = Arc::new(Health::new());
+            timer
+                .add_async(TimedEvent::periodic(
+                    self.health_check_interval,
+                    TimedHealthCheck {
+                        client: server_admin.clone(),
+                        health: health.clone(),
+                        maintenance: self.maintenance.clone(),
+                    },
+                ))
+                .await;
+            health
+        } else {
+            self.health.clone()
+        };
+
+        // periodically re-read the credentials file, to apply the rotated credentials
+        if let Some(path) = credentials_file {
+            timer
+                .add_async(TimedEvent::periodic(
+                    self.health_check_interval,
+                    TimedCredentialsReload {
+                        path,
+                        client: client.clone(),
+                        maintenance: self.maintenance.clone(),
+                    },
+                ))
+                .await;
+        }
+
+        // flush the points buffered while InfluxDB was unreachable
+        if let Some(buffer) = &buffer {
+            timer
+                .add_async(TimedEvent::periodic(
+                    self.health_check_interval,
+                    TimedBufferFlush {
+                        client: client.clone(),
+                        health: health.clone(),
+                        buffer: buffer.clone(),
+                        events: events.clone(),
+                        #[cfg(feature = "metrics")]
+                        metrics: metrics.clone(),
+                        maintenance: self.maintenance.clone(),
+                    },
+                ))
+                .await;
+        }
+
+        // migrate in background the points written with a previous schema version
+        if migrate_schema {
+            let clients =
+                std::iter::once(client.data()).chain(routes.iter().map(|r| r.client.data()));
+            for client in clients {
+                let measurement = match &layout {
+                    Layout::MeasurementPerKey => None,
+                    Layout::SingleMeasurement(m) => Some(m.clone()),
+                };
+                self.maintenance
+                    .submit_waiting(
+                        format!("migrate points of {}", client.database_name()),
+                        async move {
+                            match migrate_points(&*client, measurement).await {
+                                Ok(n) => debug!(
+                                    "Migrated {} points of InfluxDb database {}",
+                                    n,
+                                    client.database_name()
+                                ),
+                                Err(e) => warn!("{}", e),
+                            }
+                        },
+                    )
+                    .await;
+            }
+        }
+
+        // republish in zenoh the points written directly in InfluxDB by other writers
+        let change_feed = match change_feed {
+            Some(feed_config) => {
+                let source = ChangeFeedSource {
+                    client: client.get(),
+                    from: match &layout {
+                        Layout::MeasurementPerKey => "/.*/".to_string(),
+                        Layout::SingleMeasurement(m) => quote_ident(m),
+                    },
+                    key_from_tag: matches!(layout, Layout::SingleMeasurement(_)),
+                    value_field: read_external
+                        .clone()
+                        .unwrap_or_else(|| DEFAULT_EXTERNAL_VALUE_FIELD.to_string()),
+                };
+                Some(ChangeFeed::start(source, feed_config).await?)
+            }
+            None => None,
+        };
+
+        let storage_name = config.name.clone();
+        let handle_settings = Arc::downgrade(&settings);
+        let handle_client = client.clone();
+        let storage = Arc::new(AsyncMutex::new(InfluxDbStorage {
+            config,
+            admin_client,
+            client,
+            routes,
+            on_closure,
+            closure_protection,
+            provisioned_user,
+            created_measurements,
+            layout,
+            settings,
+            max_series,
+            on_max_series,
+            rate_limiter,
+            on_rate_limit,
+            clock_skew,
+            on_clock_skew,
+            delete_strategy,
+            conflict_policy,
+            priorities,
+            series_cardinality,
+            max_history_per_key,
+            trimmed_points,
+            stats,
+            #[cfg(feature = "metrics")]
+            metrics,
+            last_gc,
+            tiers,
+            archive,
+            replicas,
+            health,
+            buffer,
+            breaker,
+            closed: false,
+            pending_drops: HashMap::new(),
+            schema,
+            long_keys,
+            key_mapper,
+            read_external,
+            none_key,
+            numeric_values,
+            compression,
+            codec,
+            chunk_size,
+            alignment_concurrency,
+            offload,
+            value_hash,
+            on_corrupted,
+            latest_cache,
+            entries_cache,
+            dedup,
+            change_feed,
+            replayer,
+            key_index,
+            events,
+            latest_only: self.latest_only,
+            admin_operations,
+            export_dir,
+            import_dir,
+            v2_target,
+            allow_raw_queries,
+            #[cfg(feature = "fault_injection")]
+            faults,
+            timer,
+            maintenance: self.maintenance.clone(),
+        }));
+        self.storages.write().unwrap().insert(
+            storage_name,
+            StorageHandle {
+                settings: handle_settings,
+                client: handle_client,
+                storage: Arc::downgrade(&storage),
+            },
+        );
+        Ok(Box::new(SharedStorage(storage)))
+    }
+
+    fn incoming_data_interceptor(&self) -> Option<Arc<dyn Fn(Sample) -> Sample + Send + Sync>> {
+        None
+    }
+
+    fn outgoing_data_interceptor(&self) -> Option<Arc<dyn Fn(Sample) -> Sample + Send + Sync>> {
+        None
+    }
+}
+
+enum OnClosure {
+    DropDb,
+    DropSeries,
+    // drop only the measurements created by this storage instance
+    DropCreatedSeries,
+    DoNothing,
+}
+
+impl TryFrom<&Properties> for OnClosure {
+    type Error = zenoh_core::Error;
+    fn try_from(p: &Properties) -> ZResult<OnClosure> {
         match p.get(PROP_STORAGE_ON_CLOSURE) {
             Some(s) => {
                 if s == "drop_db" {
                     Ok(OnClosure::DropDb)
                 } else if s == "drop_series" {
                     Ok(OnClosure::DropSeries)
+                } else if s == "drop_created_series" {
+                    Ok(OnClosure::DropCreatedSeries)
                 } else {
                     bail!("Unsupported value for 'on_closure' property: {}", s)
                 }
@@ -312,12 +2996,267 @@ impl TryFrom<&Properties> for OnClosure {
     }
 }
 
+// How the keys are mapped to InfluxDB measurements
+enum Layout {
+    // each key is a measurement
+    MeasurementPerKey,
+    // all keys are in the same measurement, with the key as a tag
+    SingleMeasurement(String),
+}
+
+enum OnMaxSeries {
+    Warn,
+    Refuse,
+}
+
+// The policy for a PUT at the same time as a stored point with a different timestamp (i.e. from another writer)
+#[derive(PartialEq)]
+enum ConflictPolicy {
+    // the last written point replaces the stored one (InfluxDB's behaviour, without extra query)
+    LastWritten,
+    // the point with the greatest timestamp is kept
+    NewestWins,
+    // the stored point is kept
+    FirstWins,
+    // both points are kept, the new one with the id of its timestamp in the "conflict" tag
+    KeepBoth,
+}
+
+#[derive(PartialEq)]
+enum DeleteStrategy {
+    // delete the older points, insert a deletion marker and drop the measurement later if it's empty
+    Tombstone,
+    // drop the measurement (or the key's series) at once, without deletion marker
+    Drop,
+    // only insert a deletion marker, keeping the older points
+    Mark,
+}
+
+enum OnClockSkew {
+    // fail the PUT or DELETE with a Schema error
+    Reject,
+    // replace the timestamp by the closest bound
+    Clamp,
+}
+
+enum OnRateLimit {
+    // fail the PUT with a Capacity error
+    Refuse,
+    // ignore the PUT (replying it as outdated)
+    Drop,
+}
+
+// Detects the duplicated (key, timestamp) among the last `window` ones, i.e. the same sample
+// returned by several series (a bounded window is enough as duplicates are usually close in the results)
+struct ReplyDedup {
+    window: usize,
+    seen: HashSet<(Option<OwnedKeyExpr>, Timestamp)>,
+    order: VecDeque<(Option<OwnedKeyExpr>, Timestamp)>,
+}
+
+impl ReplyDedup {
+    fn new(window: usize) -> Self {
+        ReplyDedup {
+            window,
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    // Returns true if this (key, timestamp) was already seen within the window
+    fn is_duplicate(&mut self, key: &Option<OwnedKeyExpr>, timestamp: &Timestamp) -> bool {
+        if self.window == 0 {
+            return false;
+        }
+        let entry = (key.clone(), *timestamp);
+        if self.seen.contains(&entry) {
+            return true;
+        }
+        if self.order.len() >= self.window {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.seen.insert(entry.clone());
+        self.order.push_back(entry);
+        false
+    }
+}
+
+// The schema of the written points
+#[derive(PartialEq)]
+enum Schema {
+    // encoding stored in "encoding_prefix" and "encoding_suffix" fields
+    Current,
+    // also store the encoding in the legacy "encoding" field, for routers running a previous version
+    Compat,
+}
+
+#[derive(Clone, Copy)]
+enum OnDrift {
+    Warn,
+    Repair,
+}
+
+// What to do with the values read with a hash mismatch
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OnCorrupted {
+    Skip,
+    // reply them with the CORRUPTED_ENCODING_SUFFIX encoding suffix
+    Flag,
+}
+
+// The InfluxDB objects a storage has created and expects to still exist
+#[derive(Clone, Default)]
+struct ManagedObjects {
+    database: Option<String>,
+    // the user granted access on the database at its creation
+    storage_username: Option<String>,
+    // the retention policies set on the storage's database (and on the routes' ones), by database
+    retention_policies: Vec<(String, RetentionPolicy)>,
+    // the continuous queries of the downsampling tiers
+    continuous_queries: Vec<ContinuousQuery>,
+}
+
+impl ManagedObjects {
+    fn is_empty(&self) -> bool {
+        self.database.is_none()
+            && self.retention_policies.is_empty()
+            && self.continuous_queries.is_empty()
+    }
+}
+
+#[derive(Clone)]
+struct ContinuousQuery {
+    db: String,
+    name: String,
+    // the SELECT ... INTO statement run by the continuous query
+    select: String,
+}
+
+// The measurements created by a storage instance (with the "drop_created_series" closure)
+struct CreatedMeasurements {
+    // the measurements existing in each database when the storage was created
+    existing: HashMap<String, HashSet<String>>,
+    // the measurements created in each database
+    created: HashMap<String, HashSet<String>>,
+}
+
+// The keys matching a key expression, stored in another database than the storage's one
+struct Route {
+    key_expr: OwnedKeyExpr,
+    client: SharedClient,
+    admin_client: SharedClient,
+}
+
 struct InfluxDbStorage {
     config: StorageConfig,
-    admin_client: Client,
-    client: Client,
+    admin_client: SharedClient,
+    client: SharedClient,
+    // the first route including a key gives its database (by default the storage's one)
+    routes: Vec<Route>,
     on_closure: OnClosure,
+    closure_protection: Option<ClosureProtection>,
+    // the user created for the database (see `provision_credentials` property), dropped with it
+    provisioned_user: Option<String>,
+    created_measurements: Option<CreatedMeasurements>,
+    layout: Layout,
+    // the properties that can be changed at runtime
+    settings: SharedSettings,
+    max_series: Option<u64>,
+    on_max_series: OnMaxSeries,
+    // if set, the PUT exceeding the rate limits are refused or dropped
+    rate_limiter: Option<RateLimiter>,
+    on_rate_limit: OnRateLimit,
+    // the max durations of the timestamps of the PUT and DELETE in the future and in the past
+    clock_skew: (Option<Duration>, Option<Duration>),
+    on_clock_skew: OnClockSkew,
+    conflict_policy: ConflictPolicy,
+    delete_strategy: DeleteStrategy,
+    // the priorities of the points of some keys in the write buffer (the highest one of the matching key expressions)
+    priorities: Vec<(OwnedKeyExpr, Priority)>,
+    // last known number of series in the database (refreshed periodically if max_series is set)
+    series_cardinality: Arc<AtomicU64>,
+    // the max number of PUT points kept per key (trimmed periodically)
+    max_history_per_key: Option<u64>,
+    // the activity and data size, reported in the admin status
+    stats: Arc<StorageStats>,
+    // the total number of points removed by the history trimming
+    trimmed_points: Arc<AtomicU64>,
+    // the time and error (if any) of the last garbage collection
+    last_gc: Arc<RwLock<Option<(SystemTime, Option<String>)>>>,
+    // the downsampling tiers, sorted by interval
+    tiers: Vec<Tier>,
+    // the archive database where the PUT points are also forwarded (best-effort)
+    archive: Option<Mirror>,
+    // the databases where the PUT and DELETE are also mirrored (best-effort)
+    replicas: Vec<Mirror>,
+    // the connectivity to InfluxDB, checked by the volume
+    health: Arc<Health>,
+    // if set, the PUT points are buffered while InfluxDB is unreachable
+    buffer: Option<Arc<WriteBuffer>>,
+    // if set, the GET queries fail fast while too many of them are failing
+    breaker: Option<CircuitBreaker>,
+    // set once shut down: no more operations are accepted
+    closed: bool,
+    // the scheduled drops of measurements per deleted key
+    pending_drops: HashMap<String, PendingDrop>,
+    schema: Schema,
+    // the hashed measurement names of the too long keys (`max_measurement_length` property)
+    long_keys: Option<Arc<HashingMapper>>,
+    // the measurements (and key tags) of the keys' points
+    key_mapper: Arc<dyn KeyMapper>,
+    // if set, GET also serves points not written by zenoh, using this field as value
+    read_external: Option<String>,
+    // the measurement (or key tag) of the points with no key
+    none_key: OwnedKeyExpr,
+    // if true, the numeric values are also stored as floats, allowing aggregations
+    numeric_values: bool,
+    // if set, the payloads larger than `compression_threshold` bytes are compressed before their base64 encoding
+    compression: Option<Compression>,
+    // the encoding of the values into the fields of their points
+    codec: Box<dyn ValueCodec>,
+    // the values longer than this are split in several points (0 to never split them)
+    chunk_size: usize,
+    // the max number of concurrent queries listing the latest timestamps of the keys (get_all_entries)
+    alignment_concurrency: usize,
+    // if set, the payloads larger than the threshold are stored in this blob store, and only referenced in InfluxDB
+    offload: Option<(Arc<BlobStore>, usize)>,
+    // if set, the hash of each value is stored, and verified on GET
+    value_hash: Option<ValueHash>,
+    on_corrupted: OnCorrupted,
+    // if set, the latest value of the most recently used keys, to reply to GET without time range
+    latest_cache: Option<LatestCache>,
+    // if set, the latest timestamp of each key, to reply to get_all_entries() without querying InfluxDB
+    entries_cache: Option<EntriesCache>,
+    // if set, the PUTs of a value identical to the previous one of the key are skipped
+    dedup: Option<PutDedup>,
+    // if set, the points written directly in InfluxDB by other writers are republished in zenoh
+    change_feed: Option<ChangeFeed>,
+    // if set, the stored points can be replayed in zenoh (`replay` admin operation)
+    replayer: Option<Replayer>,
+    // if set, the keys written by the storage are indexed and listed from the index
+    key_index: Option<KeyIndex>,
+    // if set, the significant events of the storage are published in zenoh
+    events: Option<Events>,
+    // if true, only the latest point per key is kept (each PUT replaces the previous point)
+    latest_only: bool,
+    // if true, a GET on "@admin/<operation>" runs a maintenance operation
+    admin_operations: bool,
+    // if set, the export operation can write files under this directory
+    export_dir: Option<PathBuf>,
+    // if set, the import operation can read files under this directory
+    import_dir: Option<PathBuf>,
+    // the InfluxDB 2.x bucket of the "migrate_v2" admin operation
+    v2_target: Option<V2Target>,
+    // if true, a GET on "@raw?q=<query>" runs a read-only InfluxQL query
+    allow_raw_queries: bool,
+    #[cfg(feature = "fault_injection")]
+    faults: Option<fault_injection::FaultInjector>,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<metrics::StorageMetrics>,
     timer: Timer,
+    maintenance: MaintenanceQueue,
 }
 
 impl InfluxDbStorage {
@@ -327,10 +3266,15 @@ impl InfluxDbStorage {
             timestamp: String,
         }
 
-        let query = InfluxRQuery::new(format!(
-            r#"SELECT "timestamp" FROM "{measurement}" WHERE kind='DEL' ORDER BY time DESC LIMIT 1"#
-        ));
-        match self.client.json_query(query).await {
+        let (from, filter) = self.key_selection(measurement);
+        let query = InfluxRQuery::new(
+            Select::new(r#""timestamp""#, from)
+                .filter(filter.and(Condition::tag_eq("kind", "DEL")))
+                .order_by_time("DESC")
+                .limit(1)
+                .to_string(),
+        );
+        match self.client_for(measurement).json_query(query).await {
             Ok(mut result) => match result.deserialize_next::<QueryResult>() {
                 Ok(qr) => {
                     if !qr.series.is_empty() && !qr.series[0].values.is_empty() {
@@ -338,602 +3282,6896 @@ impl InfluxDbStorage {
                             .timestamp
                             .parse::<Timestamp>()
                             .map_err(|err| {
-                                zerror!(
-                                "Failed to parse the latest timestamp for deletion of measurement {} : {}",
-                                measurement, err.cause)
+                                influx_error!(
+                                    Serialization,
+                                    "Failed to parse the latest timestamp for deletion of measurement {} : {}",
+                                    measurement,
+                                    err.cause
+                                )
                             })?;
                         Ok(Some(ts))
                     } else {
                         Ok(None)
                     }
                 }
-                Err(err) => bail!(
-                    "Failed to get latest timestamp for deletion of measurement {} : {}",
-                    measurement,
-                    err
+                Err(err) => fail_client!(
+                    err,
+                    "Failed to get latest timestamp for deletion of measurement {}",
+                    measurement
                 ),
             },
-            Err(err) => bail!(
-                "Failed to get latest timestamp for deletion of measurement {} : {}",
-                measurement,
-                err
+            Err(err) => fail_client!(
+                err,
+                "Failed to get latest timestamp for deletion of measurement {}",
+                measurement
             ),
         }
     }
 
-    async fn schedule_measurement_drop(&self, measurement: &str) -> TimedHandle {
-        let event = TimedEvent::once(
-            Instant::now() + Duration::from_millis(DROP_MEASUREMENT_TIMEOUT_MS),
-            TimedMeasurementDrop {
-                client: self.admin_client.clone(),
-                measurement: measurement.to_string(),
-            },
-        );
-        let handle = event.get_handle();
-        self.timer.add_async(event).await;
-        handle
-    }
-
-    fn keyexpr_from_serie(&self, serie_name: &str) -> ZResult<Option<OwnedKeyExpr>> {
-        if serie_name.eq(NONE_KEY) {
-            Ok(None)
-        } else {
-            match OwnedKeyExpr::from_str(serie_name) {
-                Ok(key) => Ok(Some(key)),
-                Err(e) => Err(format!("{}", e).into()),
-            }
+    // Returns true if a point (PUT or DEL) more recent than this timestamp is stored for the measurement
+    async fn has_newer_point(&self, measurement: &str, timestamp: &Timestamp) -> ZResult<bool> {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct QueryResult {
+            timestamp: String,
         }
-    }
-}
-
-#[async_trait]
-impl Storage for InfluxDbStorage {
-    fn get_admin_status(&self) -> serde_json::Value {
-        // TODO: possibly add more properties in returned Value for more information about this storage
-        self.config.to_json_value()
-    }
 
-    async fn put(
-        &mut self,
-        key: Option<OwnedKeyExpr>,
-        value: Value,
-        timestamp: Timestamp,
-    ) -> ZResult<StorageInsertionResult> {
-        let measurement = key.unwrap_or_else(|| OwnedKeyExpr::from_str(NONE_KEY).unwrap());
-
-        // Note: assume that uhlc timestamp was generated by a clock using UNIX_EPOCH (that's the case by default)
         let influx_time = timestamp.get_time().to_duration().as_nanos();
-
-        // get timestamp of deletion of this measurement, if any
-        if let Some(del_time) = self.get_deletion_timestamp(measurement.as_str()).await? {
-            // ignore sample if oldest than the deletion
-            if timestamp < del_time {
-                debug!(
-                    "Received a value for {:?} with timestamp older than its deletion; ignore it",
+        let (from, filter) = self.key_selection(measurement);
+        let query = InfluxRQuery::new(
+            Select::new(r#""timestamp""#, from)
+                .filter(filter.and(Condition::time(Cmp::Ge, influx_time)))
+                .to_string(),
+        );
+        match self.client_for(measurement).json_query(query).await {
+            Ok(mut result) => match result.deserialize_next::<QueryResult>() {
+                Ok(qr) => Ok(qr.series.iter().flat_map(|s| &s.values).any(|p| {
+                    p.timestamp
+                        .parse::<Timestamp>()
+                        .map(|t| t >= *timestamp)
+                        .unwrap_or(false)
+                })),
+                Err(err) => fail_client!(
+                    err,
+                    "Failed to get latest timestamp of measurement {}",
                     measurement
-                );
-                return Ok(StorageInsertionResult::Outdated);
-            }
-        }
-
-        // encode the value as a string to be stored in InfluxDB, converting to base64 if the buffer is not a UTF-8 string
-        let (base64, strvalue) = match String::from_utf8(value.payload.contiguous().into_owned()) {
-            Ok(s) => (false, s),
-            Err(err) => (true, b64_std_engine.encode(err.into_bytes())),
-        };
-
-        // Note: tags are stored as strings in InfluxDB, while fileds are typed.
-        // For simpler/faster deserialization, we store encoding, timestamp and base64 as fields.
-        // while the kind is stored as a tag to be indexed by InfluxDB and have faster queries on it.
-        let query = InfluxWQuery::new(
-            InfluxTimestamp::Nanoseconds(influx_time),
-            measurement.clone(),
-        )
-        .add_tag("kind", "PUT")
-        .add_field("timestamp", timestamp.to_string())
-        .add_field("encoding_prefix", u8::from(*value.encoding.prefix()))
-        .add_field("encoding_suffix", value.encoding.suffix())
-        .add_field("base64", base64)
-        .add_field("value", strvalue);
-        debug!("Put {:?} with Influx query: {:?}", measurement, query);
-        if let Err(e) = self.client.query(&query).await {
-            bail!(
-                "Failed to put Value for {:?} in InfluxDb storage : {}",
-                measurement,
-                e
-            )
-        } else {
-            Ok(StorageInsertionResult::Inserted)
+                ),
+            },
+            Err(err) => fail_client!(
+                err,
+                "Failed to get latest timestamp of measurement {}",
+                measurement
+            ),
         }
     }
 
-    async fn delete(
-        &mut self,
-        key: Option<OwnedKeyExpr>,
-        timestamp: Timestamp,
-    ) -> ZResult<StorageInsertionResult> {
-        let measurement = key.unwrap_or_else(|| OwnedKeyExpr::from_str(NONE_KEY).unwrap());
+    // Returns the timestamps of the points of the measurement stored at the same time as this timestamp
+    async fn timestamps_at(
+        &self,
+        measurement: &str,
+        timestamp: &Timestamp,
+    ) -> ZResult<Vec<Timestamp>> {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct QueryResult {
+            timestamp: String,
+        }
 
-        // Note: assume that uhlc timestamp was generated by a clock using UNIX_EPOCH (that's the case by default)
         let influx_time = timestamp.get_time().to_duration().as_nanos();
-
-        // delete all points from the measurement that are older than this DELETE message
-        // (in case more recent PUT have been recevived un-ordered)
-        let query = InfluxRQuery::new(format!(
-            r#"DELETE FROM "{}" WHERE time < {}"#,
-            measurement, influx_time
-        ));
-        debug!("Delete {:?} with Influx query: {:?}", measurement, query);
-        if let Err(e) = self.client.query(&query).await {
-            bail!(
-                "Failed to delete points for measurement '{}' from InfluxDb storage : {}",
-                measurement,
-                e
-            )
-        }
-        // store a point (with timestamp) with "delete" tag, thus we don't re-introduce an older point later
-        let query = InfluxWQuery::new(
-            InfluxTimestamp::Nanoseconds(influx_time),
-            measurement.clone(),
-        )
-        .add_tag("kind", "DEL")
-        .add_field("timestamp", timestamp.to_string())
-        .add_field("encoding_prefix", 0_u8)
-        .add_field("encoding_suffix", "")
-        .add_field("base64", false)
-        .add_field("value", "");
-        debug!(
-            "Mark measurement {} as deleted at time {}",
-            measurement, influx_time
+        let (from, filter) = self.key_selection(measurement);
+        let query = InfluxRQuery::new(
+            Select::new(r#""timestamp""#, from)
+                .filter(
+                    filter
+                        .and(Condition::tag_eq("kind", "PUT"))
+                        .and(Condition::time(Cmp::Eq, influx_time)),
+                )
+                .to_string(),
         );
-        if let Err(e) = self.client.query(&query).await {
-            bail!(
-                "Failed to mark measurement {:?} as deleted : {}",
-                measurement,
-                e
-            )
+        match self.client_for(measurement).json_query(query).await {
+            Ok(mut result) => match result.deserialize_next::<QueryResult>() {
+                Ok(qr) => Ok(qr
+                    .series
+                    .iter()
+                    .flat_map(|s| &s.values)
+                    .filter_map(|p| p.timestamp.parse::<Timestamp>().ok())
+                    .collect()),
+                Err(err) => fail_client!(
+                    err,
+                    "Failed to get points at the same time in measurement {}",
+                    measurement
+                ),
+            },
+            Err(err) => fail_client!(
+                err,
+                "Failed to get points at the same time in measurement {}",
+                measurement
+            ),
         }
-        // schedule the drop of measurement later in the future, if it's empty
-        let _ = self.schedule_measurement_drop(measurement.as_str()).await;
-        Ok(StorageInsertionResult::Deleted)
     }
 
-    async fn get(
-        &mut self,
-        key: Option<OwnedKeyExpr>,
-        parameters: &str,
-    ) -> ZResult<Vec<StoredData>> {
-        let measurement = match key.clone() {
-            Some(k) => k,
-            None => OwnedKeyExpr::from_str(NONE_KEY).unwrap(),
-        };
-        // convert the key expression into an Influx regex
-        let regex = key_exprs_to_influx_regex(&[&KeyExpr::from(measurement)]);
-
-        // construct the Influx query clauses from the parameters
-        let clauses = clauses_from_parameters(parameters)?;
-
-        // the Influx query
-        let influx_query_str = format!("SELECT * FROM {regex} {clauses}");
-        let influx_query = InfluxRQuery::new(&influx_query_str);
+    // Returns true if a point with this timestamp is covered by a deletion of the measurement
+    // (i.e. it must not be re-inserted)
+    async fn is_deleted(&self, measurement: &str, timestamp: &Timestamp) -> ZResult<bool> {
+        if self.settings().tombstone_window.is_none() {
+            return match self.get_deletion_timestamp(measurement).await? {
+                Some(del_time) => Ok(*timestamp < del_time),
+                None => Ok(false),
+            };
+        }
 
-        // the expected JSon type resulting from the query
-        #[derive(Deserialize, Debug)]
-        struct ZenohPoint {
-            #[allow(dead_code)]
-            // NOTE: "kind" is present within InfluxDB and used in query clauses, but not read in Rust...
-            kind: String,
+        // with bounded tombstones, look at all the deletions more recent than the point
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct QueryResult {
             timestamp: String,
-            encoding_prefix: u8,
-            encoding_suffix: String,
-            base64: bool,
-            value: String,
+            // not set for deletions recorded without tombstone_window (unbounded)
+            #[serde(default)]
+            tombstone_start: Option<i64>,
         }
-        debug!("Get {:?} with Influx query: {}", key, influx_query_str);
-        let mut result = Vec::new();
-        match self.client.json_query(influx_query).await {
-            Ok(mut query_result) => {
-                while !query_result.results.is_empty() {
-                    match query_result.deserialize_next::<ZenohPoint>() {
-                        Ok(retn) => {
-                            // for each serie
-                            for serie in retn.series {
-                                // get the key expression from the serie name
-                                let ke = match self.keyexpr_from_serie(&serie.name) {
-                                    Ok(k) => k,
-                                    Err(e) => {
-                                        error!(
-                                            "Error replying with serie '{}' : {}",
-                                            serie.name, e
-                                        );
-                                        continue;
-                                    }
-                                };
-                                debug!("Replying {} values for {:?}", serie.values.len(), ke);
-                                // for each point
-                                for zpoint in serie.values {
-                                    // get the encoding
-                                    let encoding_prefix =
-                                        zpoint.encoding_prefix.try_into().map_err(|_| {
-                                            zerror!("Unknown encoding {}", zpoint.encoding_prefix)
-                                        })?;
-                                    let encoding = if zpoint.encoding_suffix.is_empty() {
-                                        Encoding::Exact(encoding_prefix)
-                                    } else {
-                                        Encoding::WithSuffix(
-                                            encoding_prefix,
-                                            zpoint.encoding_suffix.into(),
-                                        )
-                                    };
-                                    // get the payload
-                                    let payload = if zpoint.base64 {
-                                        match b64_std_engine.decode(zpoint.value) {
-                                            Ok(v) => ZBuf::from(v),
-                                            Err(e) => {
-                                                warn!(
-                                                    r#"Failed to decode zenoh base64 Value from Influx point {} with timestamp="{}": {}"#,
-                                                    serie.name, zpoint.timestamp, e
-                                                );
-                                                continue;
-                                            }
-                                        }
-                                    } else {
-                                        ZBuf::from(zpoint.value.into_bytes())
-                                    };
-                                    // get the timestamp
-                                    let timestamp = match Timestamp::from_str(&zpoint.timestamp) {
-                                        Ok(t) => t,
-                                        Err(e) => {
-                                            warn!(
-                                                r#"Failed to decode zenoh Timestamp from Influx point {} with timestamp="{}": {:?}"#,
-                                                serie.name, zpoint.timestamp, e
-                                            );
-                                            continue;
-                                        }
-                                    };
-                                    let value = Value::new(payload).encoding(encoding);
-                                    result.push(StoredData { value, timestamp });
-                                }
+
+        let influx_time = timestamp.get_time().to_duration().as_nanos();
+        let (from, filter) = self.key_selection(measurement);
+        let query = InfluxRQuery::new(
+            Select::new(r#""timestamp", "tombstone_start""#, from)
+                .filter(
+                    filter
+                        .and(Condition::tag_eq("kind", "DEL"))
+                        .and(Condition::time(Cmp::Ge, influx_time)),
+                )
+                .to_string(),
+        );
+        match self.client_for(measurement).json_query(query).await {
+            Ok(mut result) => match result.deserialize_next::<QueryResult>() {
+                Ok(qr) => {
+                    for serie in qr.series {
+                        for del in serie.values {
+                            let del_time = del.timestamp.parse::<Timestamp>().map_err(|err| {
+                                influx_error!(
+                                    Serialization,
+                                    "Failed to parse the timestamp for deletion of measurement {} : {}",
+                                    measurement,
+                                    err.cause
+                                )
+                            })?;
+                            let in_window = match del.tombstone_start {
+                                Some(start) => start as u128 <= influx_time,
+                                None => true,
+                            };
+                            if *timestamp < del_time && in_window {
+                                return Ok(true);
                             }
                         }
-                        Err(e) => {
-                            bail!(
-                                "Failed to parse result of InfluxDB query '{}': {}",
-                                influx_query_str,
-                                e
-                            )
-                        }
                     }
+                    Ok(false)
                 }
-            }
-            Err(e) => bail!(
-                "Failed to query InfluxDb with '{}' : {}",
-                influx_query_str,
-                e
+                Err(err) => fail_client!(
+                    err,
+                    "Failed to get deletions of measurement {}",
+                    measurement
+                ),
+            },
+            Err(err) => fail_client!(
+                err,
+                "Failed to get deletions of measurement {}",
+                measurement
             ),
         }
-        Ok(result)
     }
 
-    async fn get_all_entries(&self) -> ZResult<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
-        let mut result = Vec::new();
+    // The route of a key, if any
+    fn route_of(&self, key: &str) -> Option<&Route> {
+        let key = keyexpr::new(key).ok()?;
+        self.routes.iter().find(|r| r.key_expr.includes(key))
+    }
 
-        // the Influx query: 1 entry == 1 measurement => get only 1 point per measurement (the more recent timestamp)
-        let influx_query_str = format!(
-            "SELECT * FROM {} ORDER BY time DESC LIMIT 1",
-            *INFLUX_REGEX_ALL
-        );
-        let influx_query = InfluxRQuery::new(&influx_query_str);
+    // The client of the database where the points of a key are stored
+    fn client_for(&self, key: &str) -> Arc<dyn InfluxClient> {
+        match self.route_of(key) {
+            Some(route) => route.client.data(),
+            None => self.client.data(),
+        }
+    }
 
-        // the expected JSon type resulting from the query
-        #[derive(Deserialize, Debug)]
-        struct ZenohPoint {
-            #[allow(dead_code)]
-            // NOTE: "kind" is present within InfluxDB and used in query clauses, but not read in Rust...
-            kind: String,
-            timestamp: String,
+    // The clients of the databases of the storage and of its routes
+    fn all_clients(&self) -> Vec<Arc<dyn InfluxClient>> {
+        let mut clients = vec![self.client.data()];
+        for route in &self.routes {
+            let client = route.client.data();
+            if !clients
+                .iter()
+                .any(|c| c.database_name() == client.database_name())
+            {
+                clients.push(client);
+            }
         }
-        debug!("Get all entries with Influx query: {}", influx_query_str);
-        match self.client.json_query(influx_query).await {
-            Ok(mut query_result) => {
-                while !query_result.results.is_empty() {
-                    match query_result.deserialize_next::<ZenohPoint>() {
-                        Ok(retn) => {
-                            // for each serie
-                            for serie in retn.series {
-                                // get the key expression from the serie name
-                                match self.keyexpr_from_serie(&serie.name) {
-                                    Ok(ke) => {
-                                        debug!(
-                                            "Replying {} values for {:?}",
-                                            serie.values.len(),
-                                            ke
-                                        );
-                                        // for each point in the serie
-                                        for zpoint in serie.values {
-                                            // get the timestamp (ignore the point if failing)
-                                            match Timestamp::from_str(&zpoint.timestamp) {
-                                                Ok(timestamp) => {
-                                                    result.push((ke.clone(), timestamp))
-                                                }
-                                                Err(e) => warn!(
-                                                    r#"Failed to decode zenoh Timestamp from Influx point {} with timestamp="{}": {:?}"#,
-                                                    serie.name, zpoint.timestamp, e
-                                                ),
-                                            };
-                                        }
-                                    }
-                                    Err(e) => {
-                                        error!("Error replying with serie '{}' : {}", serie.name, e)
-                                    }
-                                };
-                            }
-                        }
-                        Err(e) => {
-                            bail!(
-                                "Failed to parse result of InfluxDB query '{}': {}",
-                                influx_query_str,
-                                e
-                            )
-                        }
-                    }
+        clients
+    }
+
+    // The clients of the databases which may store keys matching the key expression:
+    // the ones of the routes intersecting it, and the storage's one unless a route includes it
+    fn read_clients(&self, key_expr: &keyexpr) -> Vec<Arc<dyn InfluxClient>> {
+        let mut clients: Vec<Arc<dyn InfluxClient>> = Vec::new();
+        let mut routed = false;
+        for route in &self.routes {
+            if route.key_expr.intersects(key_expr) {
+                let client = route.client.data();
+                if !clients
+                    .iter()
+                    .any(|c| c.database_name() == client.database_name())
+                {
+                    clients.push(client);
                 }
-                Ok(result)
+                routed |= route.key_expr.includes(key_expr);
             }
-            Err(e) => bail!(
-                "Failed to query InfluxDb with '{}' : {}",
-                influx_query_str,
-                e
+        }
+        let client = self.client.data();
+        if !routed
+            && !clients
+                .iter()
+                .any(|c| c.database_name() == client.database_name())
+        {
+            clients.push(client);
+        }
+        clients
+    }
+
+    // The FROM clause and the WHERE clause selecting the points of a key, depending on the layout
+    fn key_selection(&self, key: &str) -> (Source, Where) {
+        let from = Source::measurement(&self.key_mapper.measurement(key));
+        match self.key_mapper.key_tag(key) {
+            Some(tag) => (from, Where::new().and(Condition::tag_eq(KEY_TAG, tag))),
+            None => (from, Where::new()),
+        }
+    }
+
+    // Remember the measurement of a key, if it's created by this storage
+    fn record_created(&mut self, key: &str) {
+        if self.created_measurements.is_none() {
+            return;
+        }
+        let db = self.client_for(key).database_name().to_string();
+        let name = self.key_mapper.measurement(key);
+        if let Some(measurements) = &mut self.created_measurements {
+            if !measurements
+                .existing
+                .get(&db)
+                .map_or(false, |e| e.contains(&name))
+            {
+                measurements.created.entry(db).or_default().insert(name);
+            }
+        }
+    }
+
+    // If the key is too long to be used as measurement name, and its hashed measurement name is not yet known,
+    // returns this name and the point to record it in the lookup measurement
+    fn long_key_point(&self, key: &str) -> Option<(String, InfluxWQuery)> {
+        let long_keys = self.long_keys.as_ref()?;
+        let name = long_keys.hashed_name(key)?;
+        if long_keys.names.read().unwrap().contains_key(&name) {
+            return None;
+        }
+        let query = InfluxWQuery::new(InfluxTimestamp::Nanoseconds(0), KEY_NAMES_MEASUREMENT)
+            .add_tag("measurement", name.as_str())
+            .add_field("key", key);
+        Some((name, query))
+    }
+
+    // Mark the hashed measurement name of a long key as known
+    fn add_long_key(&self, name: String, key: &str) {
+        if let Some(long_keys) = &self.long_keys {
+            long_keys
+                .names
+                .write()
+                .unwrap()
+                .insert(name, key.to_string());
+        }
+    }
+
+    // Check the timestamp of a PUT or DELETE against the `max_future_skew` and `max_past_skew` bounds,
+    // returning it (or the closest bound with `"on_clock_skew": "clamp"`)
+    fn police_timestamp(
+        &self,
+        key: &Option<OwnedKeyExpr>,
+        timestamp: Timestamp,
+    ) -> ZResult<Timestamp> {
+        let time = timestamp.get_time().to_duration();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let bound = match self.clock_skew {
+            (Some(skew), _) if time > now + skew => now + skew,
+            (_, Some(skew)) if time + skew < now => now.saturating_sub(skew),
+            _ => return Ok(timestamp),
+        };
+        self.stats.record_clock_skewed();
+        self.count_metric("clock_skewed");
+        match self.on_clock_skew {
+            OnClockSkew::Reject => fail!(
+                Schema,
+                "Timestamp {} of {:?} on storage {} is out of the `{}` and `{}` bounds",
+                timestamp,
+                key,
+                self.config.name,
+                PROP_STORAGE_MAX_FUTURE_SKEW,
+                PROP_STORAGE_MAX_PAST_SKEW
             ),
+            OnClockSkew::Clamp => {
+                debug!(
+                    "Clamp timestamp {} of {:?} out of the skew bounds",
+                    timestamp, key
+                );
+                Ok(Timestamp::new(NTP64::from(bound), *timestamp.get_id()))
+            }
         }
     }
-}
 
-impl Drop for InfluxDbStorage {
-    fn drop(&mut self) {
-        debug!("Closing InfluxDB storage");
+    // The priority of the points of a key in the write buffer
+    fn priority_of(&self, key: &keyexpr) -> Priority {
+        self.priorities
+            .iter()
+            .filter(|(ke, _)| ke.includes(key))
+            .map(|(_, p)| *p)
+            .max()
+            .unwrap_or(Priority::Normal)
+    }
+
+    // If the key is too long to be used as measurement name,
+    // record its hashed measurement name in the lookup measurement (if not yet known)
+    async fn register_long_key(&self, key: &str) -> ZResult<()> {
+        if let Some((name, query)) = self.long_key_point(key) {
+            debug!("Register measurement {} for key {}", name, key);
+            if let Err(e) = self.client.data().query(&query).await {
+                fail_client!(e, "Failed to register measurement {} for key {}", name, key)
+            }
+            self.add_long_key(name, key);
+        }
+        Ok(())
+    }
+
+    // The current settings (swapped as a whole on reconfiguration)
+    fn settings(&self) -> Arc<Settings> {
+        self.settings.read().unwrap().clone()
+    }
+
+    // Refuse the operations once shut down
+    fn check_open(&self) -> ZResult<()> {
+        if self.closed {
+            bail!("Storage {} is shut down", self.config.name)
+        }
+        Ok(())
+    }
+
+    // Gracefully shut down the storage: stop accepting operations, cancel the scheduled drops of measurements,
+    // flush the buffered points and the mirrors (until the deadline), then apply the `on_closure` strategy.
+    // Called by the volume's shutdown, or else on drop (within the `shutdown_timeout`).
+    async fn shutdown(&mut self, deadline: Instant) {
+        if self.closed {
+            return;
+        }
+        self.closed = true;
+        debug!("Shutting down InfluxDB storage {}", self.config.name);
+
+        for (_, pending) in self.pending_drops.drain() {
+            pending.handle.defuse();
+        }
+        if let Some(buffer) = &self.buffer {
+            if self.health.is_up() {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if async_std::future::timeout(
+                    remaining,
+                    flush_buffer(&*self.client.data(), buffer, self.events.as_ref()),
+                )
+                .await
+                .is_err()
+                {
+                    warn!(
+                        "Timeout flushing the buffered points of storage {}",
+                        self.config.name
+                    );
+                }
+            }
+            if buffer.len() > 0 {
+                warn!(
+                    "Storage {} shut down with {} buffered points not written",
+                    self.config.name,
+                    buffer.len()
+                );
+            }
+        }
+        for mirror in self.archive.iter_mut().chain(self.replicas.iter_mut()) {
+            mirror
+                .close(deadline.saturating_duration_since(Instant::now()))
+                .await;
+        }
+        self.apply_on_closure().await;
+    }
+
+    // The clients (and admin clients) of the storage's databases: its own one and the ones of its routes
+    fn closure_clients(&self) -> Vec<(Client, Client)> {
+        let mut clients = vec![(self.client.get(), self.admin_client.get())];
+        for route in &self.routes {
+            let client = route.client.get();
+            if !clients
+                .iter()
+                .any(|(c, _)| c.database_name() == client.database_name())
+            {
+                clients.push((client, route.admin_client.get()));
+            }
+        }
+        clients
+    }
+
+    async fn apply_on_closure(&self) {
+        let dry_run = self
+            .closure_protection
+            .as_ref()
+            .map_or(false, |p| p.dry_run);
         match self.on_closure {
             OnClosure::DropDb => {
-                task::block_on(async move {
-                    let db = self.admin_client.database_name();
+                for (_, admin_client) in self.closure_clients() {
+                    let db = admin_client.database_name();
+                    if let Some(protection) = &self.closure_protection {
+                        if !protection.allows_drop_db(db) {
+                            error!(
+                                "Refuse to drop InfluxDb database '{}' on closure of storage {}: not allowed by `{}`",
+                                db, self.config.name, PROP_STORAGE_ON_CLOSURE_PROTECTION
+                            );
+                            continue;
+                        }
+                    }
+                    if dry_run {
+                        warn!(
+                            "Close InfluxDB storage (dry run): would drop database {}",
+                            db
+                        );
+                        continue;
+                    }
                     debug!("Close InfluxDB storage, dropping database {}", db);
-                    let query = InfluxRQuery::new(format!(r#"DROP DATABASE "{db}""#));
-                    if let Err(e) = self.admin_client.query(&query).await {
+                    let query = InfluxRQuery::new(format!("DROP DATABASE {}", quote_ident(db)));
+                    if let Err(e) = admin_client.query(&query).await {
                         error!("Failed to drop InfluxDb database '{}' : {}", db, e)
                     }
-                });
+                }
+                if dry_run {
+                    return;
+                }
+                if let Some(username) = &self.provisioned_user {
+                    debug!("Close InfluxDB storage, dropping user {}", username);
+                    let query = InfluxRQuery::new(format!("DROP USER {}", quote_ident(username)));
+                    if let Err(e) = self.admin_client.get().query(&query).await {
+                        error!("Failed to drop InfluxDb user '{}' : {}", username, e)
+                    }
+                }
             }
             OnClosure::DropSeries => {
-                task::block_on(async move {
-                    let db = self.client.database_name();
+                for (client, _) in self.closure_clients() {
+                    let db = client.database_name();
+                    if dry_run {
+                        warn!(
+                            "Close InfluxDB storage (dry run): would drop all series from database {}",
+                            db
+                        );
+                        continue;
+                    }
                     debug!(
                         "Close InfluxDB storage, dropping all series from database {}",
                         db
                     );
                     let query = InfluxRQuery::new("DROP SERIES FROM /.*/");
-                    if let Err(e) = self.client.query(&query).await {
+                    if let Err(e) = client.query(&query).await {
                         error!(
                             "Failed to drop all series from InfluxDb database '{}' : {}",
                             db, e
                         )
                     }
-                });
+                }
+            }
+            OnClosure::DropCreatedSeries => {
+                for (_, admin_client) in self.closure_clients() {
+                    let db = admin_client.database_name();
+                    let mut created: Vec<&String> = match self
+                        .created_measurements
+                        .as_ref()
+                        .and_then(|m| m.created.get(db))
+                    {
+                        Some(created) => created.iter().collect(),
+                        None => Vec::new(),
+                    };
+                    // skip the measurements already dropped (e.g. after a deletion)
+                    match show_measurements(&admin_client).await {
+                        Ok(current) => created.retain(|m| current.contains(m)),
+                        Err(e) => warn!("{}", e),
+                    }
+                    debug!(
+                        "Close InfluxDB storage, dropping the {} measurements it created in database {}",
+                        created.len(),
+                        db
+                    );
+                    for measurement in created {
+                        if dry_run {
+                            warn!(
+                                "Close InfluxDB storage (dry run): would drop measurement {} from database {}",
+                                measurement, db
+                            );
+                            continue;
+                        }
+                        let query = InfluxRQuery::new(format!(
+                            "DROP MEASUREMENT {}",
+                            quote_ident(measurement)
+                        ));
+                        if let Err(e) = admin_client.query(&query).await {
+                            error!(
+                                "Failed to drop measurement {} from InfluxDb database '{}' : {}",
+                                measurement, db, e
+                            )
+                        }
+                    }
+                }
             }
             OnClosure::DoNothing => {
                 debug!(
                     "Close InfluxDB storage, keeping database {} as it is",
-                    self.client.database_name()
+                    self.client.get().database_name()
                 );
             }
         }
     }
-}
-
-// Scheduled dropping of a measurement after a timeout, if it's empty
-struct TimedMeasurementDrop {
-    client: Client,
-    measurement: String,
-}
 
-#[async_trait]
-impl Timed for TimedMeasurementDrop {
-    async fn run(&mut self) {
-        #[derive(Deserialize, Debug, PartialEq)]
-        struct QueryResult {
-            kind: String,
-        }
-
-        // check if there is at least 1 point without "DEL" kind in the measurement
-        let query = InfluxRQuery::new(format!(
-            r#"SELECT "kind" FROM "{}" WHERE kind!='DEL' LIMIT 1"#,
-            self.measurement
-        ));
-        match self.client.json_query(query).await {
-            Ok(mut result) => match result.deserialize_next::<QueryResult>() {
-                Ok(qr) => {
-                    if !qr.series.is_empty() {
-                        debug!("Measurement {} contains new values inserted after deletion; don't drop it", self.measurement);
-                        return;
+    // Run a maintenance operation requested by a GET on "@admin/<operation>", returning its result as JSON:
+    // - "stats": the admin status of the storage
+    // - "flush": write the points buffered while InfluxDB was unreachable
+    // - "compact": remove the deletion markers (older than `tombstone_ttl` if set)
+    // - "trim?before=<time>": remove the points older than the time (e.g. "now(-30d)" or a RFC3339 date)
+    // - "migrate": migrate in place the points written with a previous schema version
+    // - "migrate_v2": rewrite all the points into the InfluxDB 2.x bucket of the `migrate_v2` property
+    // - "export?path=<file>&key=<key expr>&range=<time range>": dump the points as annotated line protocol, to the
+    //   file or replied in text chunks
+    // - "import?path=<file>&format=<lp|csv>": write the points of an annotated line protocol or CSV file
+    // - "digest?key=<key expr>&depth=<n>": the checksum of the keys with their latest timestamp, and of their groups
+    //   by their first n chunks, to find the keys to align without listing them all
+    // - "entries?key=<key expr>": the keys with their latest timestamp
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "influxdb.admin",
+            skip_all,
+            fields(storage = %self.config.name, operation = %operation, latency_us = Empty)
+        )
+    )]
+    async fn admin_operation(
+        &mut self,
+        operation: &str,
+        parameters: &str,
+    ) -> ZResult<Vec<StoredData>> {
+        self.check_open()?;
+        let from = match &self.layout {
+            Layout::MeasurementPerKey => "/.*/".to_string(),
+            Layout::SingleMeasurement(m) => quote_ident(m),
+        };
+        debug!(
+            "Run admin operation {:?} on storage {}",
+            operation, self.config.name
+        );
+        let result = match operation {
+            "stats" => self.get_admin_status(),
+            "flush" => {
+                self.check_health()?;
+                match &self.buffer {
+                    Some(buffer) => {
+                        let before = buffer.len();
+                        for _ in 0..flush_buffer(&*self.client.data(), buffer, self.events.as_ref()).await {
+                            self.count_metric("buffer_flushes");
+                        }
+                        serde_json::json!({
+                            "flushed_points": before.saturating_sub(buffer.len()),
+                            "buffered_points": buffer.len(),
+                        })
                     }
+                    None => serde_json::json!({"flushed_points": 0, "buffered_points": 0}),
                 }
-                Err(e) => {
-                    warn!(
-                        "Failed to check if measurement '{}' is empty (can't drop it) : {}",
-                        self.measurement, e
-                    );
+            }
+            "compact" => {
+                self.check_health()?;
+                // with the "mark" strategy, the deletion markers are the only record of the deletions
+                if self.delete_strategy == DeleteStrategy::Mark {
+                    fail!(
+                        QueryParse,
+                        "The compact operation is not supported with the \"mark\" `{}` of storage {}",
+                        PROP_STORAGE_DELETE_STRATEGY,
+                        self.config.name
+                    )
                 }
-            },
-            Err(e) => {
-                warn!(
-                    "Failed to check if measurement '{}' is empty (can't drop it) : {}",
-                    self.measurement, e
-                );
-                return;
+                // only the markers older than a horizon are removed: a late PUT older than a removed marker would
+                // be stored again
+                let mut query = format!("DELETE FROM {from} WHERE kind='DEL' AND time < ");
+                match (
+                    parameters
+                        .split('&')
+                        .find_map(|kv| kv.strip_prefix("before=")),
+                    self.settings().tombstone_ttl,
+                ) {
+                    (Some(before), _) => {
+                        let time = TimeExpr::from_str(before).map_err(|e| {
+                            influx_error!(
+                                QueryParse,
+                                "Invalid `before` time for compact operation: {}",
+                                e
+                            )
+                        })?;
+                        write_timeexpr(&mut query, &time);
+                    }
+                    (None, Some(ttl)) => {
+                        query.push_str(&format!("now() - {}", influx_duration(ttl)));
+                    }
+                    (None, None) => fail!(
+                        QueryParse,
+                        "The compact operation requires a `before=<time>` parameter (or the `{}` property)",
+                        PROP_STORAGE_TOMBSTONE_TTL
+                    ),
+                }
+                let query = InfluxRQuery::new(query);
+                debug!("Compact with Influx query: {:?}", query);
+                let mut error = None;
+                for client in self.all_clients() {
+                    if let Err(e) = client.query(&query).await {
+                        error.get_or_insert(e);
+                    }
+                }
+                *self.last_gc.write().unwrap() =
+                    Some((SystemTime::now(), error.as_ref().map(|e| e.to_string())));
+                if let Some(e) = error {
+                    fail_client!(
+                        e,
+                        "Failed to remove the deletion markers of storage {}",
+                        self.config.name
+                    )
+                }
+                serde_json::json!({ "compacted": true })
             }
-        }
-
-        // drop the measurement
-        let query = InfluxRQuery::new(format!(r#"DROP MEASUREMENT "{}""#, self.measurement));
-        debug!(
-            "Drop measurement {} after timeout with Influx query: {:?}",
-            self.measurement, query
-        );
-        if let Err(e) = self.client.query(&query).await {
-            warn!(
-                "Failed to drop measurement '{}' from InfluxDb storage : {}",
-                self.measurement, e
-            );
-        }
-    }
-}
-
-fn generate_db_name() -> String {
-    format!("zenoh_db_{}", Uuid::new_v4().simple())
-}
-
-async fn show_databases(client: &Client) -> ZResult<Vec<String>> {
-    #[derive(Deserialize)]
-    struct Database {
-        name: String,
-    }
-    let query = InfluxRQuery::new("SHOW DATABASES");
-    debug!("List databases with Influx query: {:?}", query);
-    match client.json_query(query).await {
-        Ok(mut result) => match result.deserialize_next::<Database>() {
-            Ok(dbs) => {
-                let mut result: Vec<String> = Vec::new();
-                for serie in dbs.series {
-                    for db in serie.values {
-                        result.push(db.name);
+            "trim" => {
+                self.check_health()?;
+                let before = match parameters
+                    .split('&')
+                    .find_map(|kv| kv.strip_prefix("before="))
+                {
+                    Some(before) => before,
+                    None => fail!(
+                        QueryParse,
+                        "The trim operation requires a `before=<time>` parameter"
+                    ),
+                };
+                let time = TimeExpr::from_str(before)
+                    .map_err(|e| {
+                        influx_error!(QueryParse, "Invalid `before` time for trim operation: {}", e)
+                    })?;
+                // only the zenoh points (not the lookup of hashed measurement names, nor the external points)
+                let mut query = format!("DELETE FROM {from} WHERE kind!='' AND time < ");
+                write_timeexpr(&mut query, &time);
+                let query = InfluxRQuery::new(query);
+                debug!("Trim with Influx query: {:?}", query);
+                for client in self.all_clients() {
+                    if let Err(e) = client.query(&query).await {
+                        fail_client!(
+                            e,
+                            "Failed to trim the points of storage {} in InfluxDb database {}",
+                            self.config.name,
+                            client.database_name()
+                        )
                     }
                 }
-                Ok(result)
+                for replica in &self.replicas {
+                    replica.push_query(query.clone());
+                }
+                if let Some(cache) = &mut self.latest_cache {
+                    cache.clear();
+                }
+                // the keys with no point left are listed again from InfluxDB, and removed from the key index
+                if let Some(cache) = &self.entries_cache {
+                    cache.invalidate();
+                }
+                if let Some(index) = &self.key_index {
+                    for key in index.older_keys(resolve_timeexpr(&time)) {
+                        let query = index.remove(&key);
+                        if let Err(e) = self.client_for(&key).query(&query).await {
+                            warn!(
+                                "Failed to remove {:?} from the key index of storage {}: {}",
+                                key, self.config.name, e
+                            );
+                        }
+                    }
+                }
+                serde_json::json!({ "trimmed_before": before })
             }
-            Err(e) => bail!(
-                "Failed to parse list of existing InfluxDb databases : {}",
-                e
-            ),
-        },
-        Err(e) => bail!("Failed to list existing InfluxDb databases : {}", e),
-    }
-}
-
-async fn is_db_existing(client: &Client, db_name: &str) -> ZResult<bool> {
-    let dbs = show_databases(client).await?;
-    Ok(dbs.iter().any(|e| e == db_name))
-}
-
-async fn create_db(
-    client: &Client,
-    db_name: &str,
-    storage_username: Option<String>,
-) -> ZResult<()> {
-    let query = InfluxRQuery::new(format!(r#"CREATE DATABASE "{db_name}""#));
-    debug!("Create Influx database: {}", db_name);
-    if let Err(e) = client.query(&query).await {
-        bail!(
-            "Failed to create new InfluxDb database '{}' : {}",
-            db_name,
-            e
-        )
-    }
-
-    // is a username is specified for storage access, grant him access to the database
-    if let Some(username) = storage_username {
-        let query = InfluxRQuery::new(format!(r#"GRANT ALL ON "{db_name}" TO "{username}""#));
-        debug!(
-            "Grant access to {} on Influx database: {}",
-            username, db_name
-        );
-        if let Err(e) = client.query(&query).await {
-            bail!(
-                "Failed grant access to {} on Influx database '{}' : {}",
-                username,
-                db_name,
-                e
-            )
-        }
-    }
-    Ok(())
-}
-
-// Returns an InfluxDB regex (see https://docs.influxdata.com/influxdb/v1.8/query_language/explore-data/#regular-expressions)
-// corresponding to the list of path expressions. I.e.:
-// Replace "**" with ".*", "*" with "[^\/]*"  and "/" with "\/".
-// Concat each with "|", and surround the result with '/^' and '$/'.
-fn key_exprs_to_influx_regex(path_exprs: &[&keyexpr]) -> String {
-    let mut result = String::with_capacity(2 * path_exprs[0].len());
-    result.push_str("/^");
-    for (i, path_expr) in path_exprs.iter().enumerate() {
-        if i != 0 {
-            result.push('|');
-        }
-        let mut chars = path_expr.chars().peekable();
-        while let Some(c) = chars.next() {
-            match c {
-                '*' => {
-                    if let Some(c2) = chars.peek() {
-                        if c2 == &'*' {
-                            result.push_str(".*");
-                            chars.next();
-                        } else {
-                            result.push_str(".*")
+            "migrate" => {
+                self.check_health()?;
+                let measurement = match &self.layout {
+                    Layout::MeasurementPerKey => None,
+                    Layout::SingleMeasurement(m) => Some(m.clone()),
+                };
+                let mut migrated = 0;
+                for client in self.all_clients() {
+                    migrated += migrate_points(&client, measurement.clone()).await?;
+                }
+                serde_json::json!({ "migrated_points": migrated })
+            }
+            "migrate_v2" => {
+                self.check_health()?;
+                let target = match &self.v2_target {
+                    Some(t) => t,
+                    None => fail!(
+                        QueryParse,
+                        "The migrate_v2 operation requires the `{}` property on storage {}",
+                        PROP_STORAGE_MIGRATE_V2,
+                        self.config.name
+                    ),
+                };
+                let migrated = self.migrate_to_v2(target).await?;
+                serde_json::json!({ "bucket": target.bucket(), "migrated_points": migrated })
+            }
+            "export" => {
+                self.check_health()?;
+                return self.export(parameters).await;
+            }
+            "import" => {
+                self.check_health()?;
+                self.import(parameters).await?
+            }
+            "undelete" => {
+                self.check_health()?;
+                self.undelete(parameters).await?
+            }
+            "replay" => {
+                self.check_health()?;
+                self.replay(parameters).await?
+            }
+            "digest" | "entries" => {
+                self.check_health()?;
+                let mut key_expr = None;
+                let mut depth = DEFAULT_DIGEST_DEPTH;
+                for kv in parameters.split('&').filter(|kv| !kv.is_empty()) {
+                    match kv.split_once('=') {
+                        Some(("key", v)) => {
+                            key_expr = Some(OwnedKeyExpr::from_str(&percent_decode(v)).map_err(|e| {
+                                influx_error!(QueryParse, "Invalid `key` for {} operation: {}", operation, e)
+                            })?)
+                        }
+                        Some(("depth", v)) if operation == "digest" => {
+                            depth = v.parse().map_err(|e| {
+                                influx_error!(QueryParse, "Invalid `depth` for digest operation: {}", e)
+                            })?
                         }
+                        _ => fail!(
+                            QueryParse,
+                            "Invalid parameter {:?} for {} operation",
+                            kv,
+                            operation
+                        ),
                     }
                 }
-                '/' => result.push_str(r"\/"),
-                _ => result.push(c),
+                let entries: Vec<(String, Timestamp)> = self
+                    .latest_entries(key_expr.as_ref())
+                    .await?
+                    .into_iter()
+                    .map(|(key, timestamp)| {
+                        (key.unwrap_or_else(|| self.none_key.clone()).to_string(), timestamp)
+                    })
+                    .collect();
+                match operation {
+                    "digest" => digest(&entries, depth),
+                    _ => entries
+                        .into_iter()
+                        .map(|(key, timestamp)| {
+                            serde_json::json!({"key": key, "timestamp": timestamp.to_string()})
+                        })
+                        .collect(),
+                }
             }
-        }
+            _ => fail!(
+                QueryParse,
+                "Unknown admin operation {:?} on storage {} (supported: stats, flush, compact, trim, migrate, migrate_v2, export, import, undelete, replay, digest, entries)",
+                operation,
+                self.config.name
+            ),
+        };
+        Ok(vec![StoredData {
+            value: json_value(result),
+            timestamp: new_reception_timestamp(),
+        }])
     }
-    result.push_str("$/");
-    result
-}
 
-fn clauses_from_parameters(p: &str) -> ZResult<String> {
-    use zenoh::selector::{TimeBound, TimeRange};
-    let time_range = p.time_range()?;
-    let mut result = String::with_capacity(256);
-    result.push_str("WHERE kind!='DEL'");
-    match time_range {
-        Some(TimeRange(start, stop)) => {
-            match start {
-                TimeBound::Inclusive(t) => {
-                    result.push_str(" AND time >= ");
-                    write_timeexpr(&mut result, t);
+    // Replay in zenoh the values of the keys matching `key` (all by default) stored in the time `range`, on their keys
+    // (or under the `prefix` key expression instead of the storage's `strip_prefix`), with their original inter-sample
+    // timing divided by `speed`, at most `max_rate` values per second and only the first value per key in each `window`.
+    // With `stop`, `pause`, `resume` or `status`, control the running replays instead.
+    // Returns the number of values to replay.
+    async fn replay(&self, parameters: &str) -> ZResult<serde_json::Value> {
+        let replayer = match &self.replayer {
+            Some(replayer) => replayer,
+            None => fail!(
+                QueryParse,
+                "The replay operation requires the `{}` property of storage {}",
+                PROP_STORAGE_REPLAY,
+                self.config.name
+            ),
+        };
+        let mut key_expr = None;
+        let mut time_range = None;
+        let mut speed = 1.0;
+        let mut max_rate = None;
+        let mut window: Option<Duration> = None;
+        let mut prefix = None;
+        for kv in parameters.split('&').filter(|kv| !kv.is_empty()) {
+            match kv.split_once('=').unwrap_or((kv, "")) {
+                ("stop", _) => {
+                    let running = replayer.running();
+                    replayer.stop();
+                    return Ok(serde_json::json!({ "stopped": running }));
                 }
-                TimeBound::Exclusive(t) => {
-                    result.push_str(" AND time > ");
-                    write_timeexpr(&mut result, t);
+                ("pause", _) => {
+                    replayer.pause(true);
+                    return Ok(replayer.progress());
                 }
-                TimeBound::Unbounded => {}
-            }
-            match stop {
-                TimeBound::Inclusive(t) => {
-                    result.push_str(" AND time <= ");
-                    write_timeexpr(&mut result, t);
+                ("resume", _) => {
+                    replayer.pause(false);
+                    return Ok(replayer.progress());
+                }
+                ("status", _) => return Ok(replayer.progress()),
+                ("max_rate", v) => match v.parse::<f64>() {
+                    Ok(r) if r > 0.0 && r.is_finite() => max_rate = Some(r),
+                    _ => fail!(
+                        QueryParse,
+                        "Invalid `max_rate` for replay operation: {:?} (must be a positive number)",
+                        v
+                    ),
+                },
+                ("window", v) => match humantime::parse_duration(&percent_decode(v)) {
+                    Ok(w) if !w.is_zero() => window = Some(w),
+                    _ => fail!(
+                        QueryParse,
+                        "Invalid `window` for replay operation: {:?} (must be a non-zero duration)",
+                        v
+                    ),
+                },
+                ("key", v) => {
+                    key_expr = Some(OwnedKeyExpr::from_str(&percent_decode(v)).map_err(|e| {
+                        influx_error!(QueryParse, "Invalid `key` for replay operation: {}", e)
+                    })?)
+                }
+                ("range", v) => {
+                    time_range = Some(
+                        zenoh::selector::TimeRange::from_str(&percent_decode(v)).map_err(|e| {
+                            influx_error!(QueryParse, "Invalid `range` for replay operation: {}", e)
+                        })?,
+                    )
                 }
-                TimeBound::Exclusive(t) => {
-                    result.push_str(" AND time < ");
-                    write_timeexpr(&mut result, t);
+                ("speed", v) => match v.parse::<f64>() {
+                    Ok(s) if s > 0.0 && s.is_finite() => speed = s,
+                    _ => fail!(
+                        QueryParse,
+                        "Invalid `speed` for replay operation: {:?} (must be a positive number)",
+                        v
+                    ),
+                },
+                ("prefix", v) => {
+                    prefix = Some(OwnedKeyExpr::from_str(&percent_decode(v)).map_err(|e| {
+                        influx_error!(QueryParse, "Invalid `prefix` for replay operation: {}", e)
+                    })?)
                 }
-                TimeBound::Unbounded => {}
+                _ => fail!(
+                    QueryParse,
+                    "Invalid parameter {:?} for replay operation (supported: key, range, speed, max_rate, window, prefix, stop, pause, resume, status)",
+                    kv
+                ),
             }
         }
-        None => {
-            //No time selection, return only latest values
-            result.push_str(" ORDER BY time DESC LIMIT 1");
-        }
-    }
-    Ok(result)
-}
+        let time_range = match time_range {
+            Some(range) => range,
+            None => fail!(
+                QueryParse,
+                "The replay operation requires a `range=<time range>` parameter"
+            ),
+        };
+        let key_expr = match key_expr {
+            Some(k) => k,
+            None => OwnedKeyExpr::from_str("**")?,
+        };
 
-fn write_timeexpr(s: &mut String, t: TimeExpr) {
-    use humantime::format_rfc3339;
-    use std::fmt::Write;
-    match t {
-        TimeExpr::Fixed(t) => write!(s, "'{}'", format_rfc3339(t)),
-        TimeExpr::Now { offset_secs } => write!(s, "now(){offset_secs:+}s"),
-    }
-    .unwrap()
+        // read the values in time order, at most MAX_REPLAYED_POINTS per key
+        let get_parameters = GetParameters {
+            time_range: Some(time_range),
+            limit: Some(MAX_REPLAYED_POINTS as u64 + 1),
+            order: Some("ASC"),
+            agg: None,
+            window: None,
+            max_samples: None,
+            coverage: false,
+            include_deletes: false,
+            at: None,
+        };
+        let regex = self.key_regex(&key_expr);
+        let columns = self.get_columns();
+        let query = match &self.layout {
+            Layout::MeasurementPerKey => {
+                select_from_parameters(&columns, Source::expr(regex), &get_parameters, None)
+            }
+            Layout::SingleMeasurement(m) => select_from_parameters(
+                &columns,
+                Source::measurement(m),
+                &get_parameters,
+                Some(&regex),
+            ),
+        }
+        .to_string();
+        debug!("Read the values to replay with Influx query: {}", query);
+        let mut points = Vec::new();
+        for (serie_name, count, data) in self
+            .query_stored_data(&self.read_clients(&key_expr), &query)
+            .await?
+        {
+            if count > MAX_REPLAYED_POINTS {
+                fail!(
+                    Capacity,
+                    "Serie {} has more than {} points to replay: reduce the time range",
+                    serie_name,
+                    MAX_REPLAYED_POINTS
+                )
+            }
+            let key = match self.keyexpr_from_serie(&serie_name) {
+                Ok(Some(k)) => k,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Error replaying serie '{}' : {}", serie_name, e);
+                    continue;
+                }
+            };
+            // published on the original key: the storage receives it back, and ignores it
+            let (published, echo) = match (&prefix, &self.config.strip_prefix) {
+                (Some(p), _) => (p.join(&key)?, None),
+                (None, Some(p)) => (p.join(&key)?, Some(key)),
+                (None, None) => (key.clone(), Some(key)),
+            };
+            // downsampled: only the first value of each window (the values being in time order)
+            let mut last_window = None;
+            for d in data {
+                if let Some(w) = window {
+                    let n = d.timestamp.get_time().to_duration().as_nanos() / w.as_nanos();
+                    if last_window == Some(n) {
+                        continue;
+                    }
+                    last_window = Some(n);
+                }
+                points.push(ReplayedPoint {
+                    key: published.clone(),
+                    echo: echo.clone(),
+                    timestamp: d.timestamp,
+                    value: d.value,
+                });
+            }
+        }
+        points.sort_by_key(|p| p.timestamp);
+        let replayed = points.len();
+        replayer.replay(
+            format!("of {} in storage {}", key_expr, self.config.name),
+            points,
+            ReplayPacing { speed, max_rate },
+        );
+        Ok(serde_json::json!({ "replayed": replayed, "speed": speed, "max_rate": max_rate }))
+    }
+
+    // Remove the deletion markers of the keys matching `key` (only those older than `before` if set): the points kept by
+    // the "mark" delete strategy are replied again by GET, and the older points can be re-inserted.
+    // Returns the number of removed markers.
+    async fn undelete(&mut self, parameters: &str) -> ZResult<serde_json::Value> {
+        #[derive(Deserialize, Debug)]
+        struct Count {
+            count: u64,
+        }
+
+        let mut key_expr = None;
+        let mut before = None;
+        for kv in parameters.split('&').filter(|kv| !kv.is_empty()) {
+            match kv.split_once('=') {
+                Some(("key", v)) => {
+                    key_expr = Some(OwnedKeyExpr::from_str(&percent_decode(v)).map_err(|e| {
+                        influx_error!(QueryParse, "Invalid `key` for undelete operation: {}", e)
+                    })?)
+                }
+                Some(("before", v)) => {
+                    before = Some(TimeExpr::from_str(&percent_decode(v)).map_err(|e| {
+                        influx_error!(
+                            QueryParse,
+                            "Invalid `before` time for undelete operation: {}",
+                            e
+                        )
+                    })?)
+                }
+                _ => fail!(
+                    QueryParse,
+                    "Invalid parameter {:?} for undelete operation (supported: key, before)",
+                    kv
+                ),
+            }
+        }
+        let key_expr = match key_expr {
+            Some(k) => k,
+            None => fail!(
+                QueryParse,
+                "The undelete operation requires a `key=<key expr>` parameter"
+            ),
+        };
+        let regex = self.key_regex(&key_expr);
+        let mut selection = match &self.layout {
+            Layout::MeasurementPerKey => format!("FROM {regex} WHERE kind='DEL'"),
+            Layout::SingleMeasurement(m) => format!(
+                r#"FROM {} WHERE kind='DEL' AND "{KEY_TAG}" =~ {regex}"#,
+                quote_ident(m)
+            ),
+        };
+        if let Some(time) = &before {
+            selection.push_str(" AND time < ");
+            write_timeexpr(&mut selection, time);
+        }
+
+        let mut undeleted = 0;
+        for client in self.read_clients(&key_expr) {
+            let query = InfluxRQuery::new(format!(r#"SELECT count("timestamp") {selection}"#));
+            undeleted += match client.json_query(query).await {
+                Ok(mut result) => match result.deserialize_next::<Count>() {
+                    Ok(qr) => qr
+                        .series
+                        .iter()
+                        .flat_map(|s| s.values.iter())
+                        .map(|c| c.count)
+                        .sum::<u64>(),
+                    Err(e) => fail_client!(
+                        e,
+                        "Failed to count the deletion markers of {} in storage {}",
+                        key_expr,
+                        self.config.name
+                    ),
+                },
+                Err(e) => fail_client!(
+                    e,
+                    "Failed to count the deletion markers of {} in storage {}",
+                    key_expr,
+                    self.config.name
+                ),
+            };
+            let query = InfluxRQuery::new(format!("DELETE {selection}"));
+            debug!("Undelete {} with Influx query: {:?}", key_expr, query);
+            if let Err(e) = client.query(&query).await {
+                fail_client!(
+                    e,
+                    "Failed to remove the deletion markers of {} in storage {}",
+                    key_expr,
+                    self.config.name
+                )
+            }
+            for replica in &self.replicas {
+                replica.push_query(query.clone());
+            }
+        }
+        // the cached latest values don't include the restored points
+        if let Some(cache) = &mut self.latest_cache {
+            cache.clear();
+        }
+        Ok(serde_json::json!({ "undeleted": undeleted }))
+    }
+
+    // Import the points of the `path` file, as annotated line protocol (e.g. from the export operation) or as CSV with
+    // "key,timestamp,value" rows (`format` parameter, by default from the file extension). The points are decoded and
+    // written in timestamp order through put and delete, as if received from zenoh: the values covered by a deletion
+    // are ignored. The `path` is relative to the `import_dir` directory, and read line by line. Returns the numbers of
+    // imported and ignored points.
+    async fn import(&mut self, parameters: &str) -> ZResult<serde_json::Value> {
+        let mut path = None;
+        let mut format = None;
+        for kv in parameters.split('&').filter(|kv| !kv.is_empty()) {
+            match kv.split_once('=') {
+                Some(("path", v)) => {
+                    path = Some(confined_path(
+                        self.import_dir.as_deref(),
+                        &percent_decode(v),
+                        PROP_STORAGE_IMPORT_DIR,
+                        "import",
+                    )?)
+                }
+                Some(("format", v)) => format = Some(percent_decode(v)),
+                _ => fail!(
+                    QueryParse,
+                    "Invalid parameter {:?} for import operation (supported: path, format)",
+                    kv
+                ),
+            }
+        }
+        let path = match path {
+            Some(path) => path,
+            None => fail!(
+                QueryParse,
+                "The import operation requires a `path=<file>` parameter"
+            ),
+        };
+        let csv = match format.as_deref() {
+            Some("csv") => true,
+            Some("lp") => false,
+            Some(f) => fail!(
+                QueryParse,
+                "Invalid `format` {:?} for import operation (supported: lp, csv)",
+                f
+            ),
+            None => path.extension().map_or(false, |e| e == "csv"),
+        };
+        let read_error = |e: std::io::Error| {
+            influx_error!(QueryParse, "Failed to read import file {:?}: {}", path, e)
+        };
+        let file = async_std::fs::File::open(&path).await.map_err(read_error)?;
+        let mut lines = async_std::io::BufReader::new(file).lines().enumerate();
+
+        let mut invalid = 0u64;
+        // the points to write with their key (None for the points with no key)
+        let mut points: Vec<(Option<OwnedKeyExpr>, StoredData)> = Vec::new();
+        if csv {
+            while let Some((i, row)) = lines.next().await {
+                let row = row.map_err(read_error)?;
+                let row = row.as_str();
+                match parse_csv_row(row) {
+                    Some((key, _, _)) if i == 0 && key == "key" => {}
+                    Some((key, timestamp, value)) => {
+                        let key = match key {
+                            "" => Ok(None),
+                            k => OwnedKeyExpr::from_str(k).map(Some),
+                        };
+                        let timestamp = Timestamp::from_str(timestamp)
+                            .map_err(|e| format!("{e:?}"))
+                            .or_else(|_| timestamp_from_influx_time(timestamp));
+                        match (key, timestamp) {
+                            (Ok(key), Ok(timestamp)) => points.push((
+                                key,
+                                StoredData {
+                                    value: Value::from(value),
+                                    timestamp,
+                                },
+                            )),
+                            _ => {
+                                warn!("Invalid CSV row {:?} in import file {:?}", row, path);
+                                invalid += 1;
+                            }
+                        }
+                    }
+                    None => {
+                        warn!("Invalid CSV row {:?} in import file {:?}", row, path);
+                        invalid += 1;
+                    }
+                }
+            }
+        } else {
+            // the points of each serie, and the keys of the hashed measurement names
+            let mut series: Vec<(String, Vec<serde_json::Map<String, serde_json::Value>>)> =
+                Vec::new();
+            let mut serie_index: HashMap<String, usize> = HashMap::new();
+            let mut long_keys: HashMap<String, String> = HashMap::new();
+            let mut splitter = LineSplitter::default();
+            let mut eof = false;
+            while !eof {
+                let line = match lines.next().await {
+                    Some((_, line)) => splitter.push(&line.map_err(read_error)?),
+                    None => {
+                        eof = true;
+                        std::mem::take(&mut splitter).finish()
+                    }
+                };
+                let line = match line {
+                    Some(line) => line,
+                    None => continue,
+                };
+                let line = match parse_line(&line) {
+                    Ok(line) => line,
+                    Err(e) => {
+                        warn!("Invalid line in import file {:?}: {}", path, e);
+                        invalid += 1;
+                        continue;
+                    }
+                };
+                if line.measurement == KEY_NAMES_MEASUREMENT {
+                    if let (Some(name), Some(serde_json::Value::String(key))) =
+                        (line.tags.get("measurement"), line.fields.get("key"))
+                    {
+                        long_keys.insert(name.clone(), key.clone());
+                    }
+                    continue;
+                }
+                // (the key index is updated by the imported points)
+                if line.measurement == KEY_INDEX_MEASUREMENT {
+                    continue;
+                }
+                let serie_name = match &self.layout {
+                    Layout::MeasurementPerKey => line.measurement,
+                    Layout::SingleMeasurement(_) => match line.tags.get(KEY_TAG) {
+                        Some(key) => key.clone(),
+                        None => self.none_key.to_string(),
+                    },
+                };
+                let mut row = line.fields;
+                for (tag, value) in line.tags {
+                    row.insert(tag, value.into());
+                }
+                let index = *serie_index.entry(serie_name.clone()).or_insert_with(|| {
+                    series.push((serie_name, Vec::new()));
+                    series.len() - 1
+                });
+                series[index].1.push(row);
+            }
+            let clients = [self.client.data()];
+            let series = self
+                .decode_series(&clients, series, |serie_name, row| {
+                    decode_row(serie_name, row, None, true)
+                })
+                .await?;
+            for (serie_name, _, data) in series {
+                let key: ZResult<Option<OwnedKeyExpr>> = match long_keys.get(&serie_name) {
+                    Some(key) => match OwnedKeyExpr::from_str(key) {
+                        Ok(key) => Ok(Some(key)),
+                        Err(e) => Err(format!("{}", e).into()),
+                    },
+                    None => self.keyexpr_from_serie(&serie_name),
+                };
+                match key {
+                    Ok(key) => points.extend(data.into_iter().map(|d| (key.clone(), d))),
+                    Err(e) => {
+                        warn!("Skip import of serie '{}' : {}", serie_name, e)
+                    }
+                }
+            }
+        }
+
+        points.sort_by(|(_, a), (_, b)| a.timestamp.cmp(&b.timestamp));
+        let mut imported = 0u64;
+        let mut ignored = 0u64;
+        for (key, d) in points {
+            let result = if d.value.encoding.suffix() == DELETE_ENCODING_SUFFIX {
+                self.delete(key, d.timestamp).await?
+            } else {
+                self.put(key, d.value, d.timestamp).await?
+            };
+            match result {
+                StorageInsertionResult::Outdated => ignored += 1,
+                _ => imported += 1,
+            }
+        }
+        Ok(serde_json::json!({
+            "imported_points": imported,
+            "ignored_points": ignored,
+            "invalid_lines": invalid,
+        }))
+    }
+
+    // The keys with their latest timestamp (only those intersecting `key_expr` if set)
+    async fn latest_entries(
+        &self,
+        key_expr: Option<&OwnedKeyExpr>,
+    ) -> ZResult<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
+        // with the key index, list the keys from it without querying InfluxDB
+        if let Some(index) = &self.key_index {
+            return Ok(index
+                .entries(key_expr.map(|ke| &**ke))
+                .into_iter()
+                .filter_map(|(key, timestamp)| {
+                    if key == self.none_key.as_str() {
+                        // (the none key never intersects a key expression)
+                        return match key_expr {
+                            Some(_) => None,
+                            None => Some((None, timestamp)),
+                        };
+                    }
+                    match OwnedKeyExpr::from_str(&key) {
+                        Ok(key) => Some((Some(key), timestamp)),
+                        Err(e) => {
+                            warn!("Invalid key {:?} in key index: {}", key, e);
+                            None
+                        }
+                    }
+                })
+                .collect());
+        }
+        let mut result = Vec::new();
+        for client in &self.all_clients() {
+            // the Influx queries: 1 entry == 1 key => get only the more recent timestamp per key, by pages
+            // of GET_ALL_ENTRIES_PAGE_SIZE keys to never run a giant query.
+            match &self.layout {
+                Layout::MeasurementPerKey => {
+                    // list the measurements (from the index, without scanning the data) and query them by batches,
+                    // running up to `alignment_concurrency` queries at once
+                    let mut offset = 0;
+                    loop {
+                        let measurements =
+                            show_measurements_page(client, GET_ALL_ENTRIES_PAGE_SIZE, offset)
+                                .await?;
+                        let influx_queries = measurements
+                            .iter()
+                            .filter(|m| m.as_str() != KEY_NAMES_MEASUREMENT)
+                            .filter(|m| m.as_str() != KEY_INDEX_MEASUREMENT)
+                            .filter(|m| match (key_expr, self.keyexpr_from_serie(m)) {
+                                (Some(ke), Ok(Some(key))) => ke.intersects(&key),
+                                (Some(_), Ok(None)) => false,
+                                _ => true,
+                            })
+                            .map(|m| Source::measurement(m))
+                            .collect::<Vec<_>>()
+                            .chunks(GET_ALL_ENTRIES_BATCH_SIZE)
+                            .map(|measurements| {
+                                Select::new(
+                                    r#"last("timestamp") AS "timestamp""#,
+                                    Source::list(measurements.to_vec()),
+                                )
+                                .to_string()
+                            })
+                            .collect::<Vec<_>>();
+                        let entries = futures::stream::iter(&influx_queries)
+                            .map(|q| self.latest_timestamps(client, q))
+                            .buffered(self.alignment_concurrency)
+                            .collect::<Vec<_>>()
+                            .await;
+                        for entries in entries {
+                            result.append(&mut entries?.1);
+                        }
+                        if measurements.len() < GET_ALL_ENTRIES_PAGE_SIZE {
+                            break;
+                        }
+                        offset += GET_ALL_ENTRIES_PAGE_SIZE;
+                    }
+                }
+                Layout::SingleMeasurement(m) => {
+                    let filter = match key_expr {
+                        Some(ke) => Where::new().and(Condition::tag_matches(
+                            KEY_TAG,
+                            &key_exprs_to_influx_regex(&[ke]),
+                        )),
+                        None => Where::new(),
+                    };
+                    let mut offset = 0;
+                    loop {
+                        let influx_query_str = Select::new(
+                            r#"last("timestamp") AS "timestamp""#,
+                            Source::measurement(m),
+                        )
+                        .filter(filter.clone())
+                        .group_by_tag(KEY_TAG)
+                        .series_page(GET_ALL_ENTRIES_PAGE_SIZE as u64, offset as u64)
+                        .to_string();
+                        let (series, mut entries) =
+                            self.latest_timestamps(client, &influx_query_str).await?;
+                        result.append(&mut entries);
+                        if series < GET_ALL_ENTRIES_PAGE_SIZE {
+                            break;
+                        }
+                        offset += GET_ALL_ENTRIES_PAGE_SIZE;
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    // Run a query of the latest timestamps of some keys. Returns the number of series and the entries
+    async fn latest_timestamps(
+        &self,
+        client: &dyn InfluxClient,
+        influx_query_str: &str,
+    ) -> ZResult<(usize, Vec<(Option<OwnedKeyExpr>, Timestamp)>)> {
+        // the expected JSon type resulting from the query
+        #[derive(Deserialize, Debug)]
+        struct LastPoint {
+            timestamp: String,
+        }
+
+        debug!("Get all entries with Influx query: {}", influx_query_str);
+        let series = self
+            .query_points_on::<LastPoint>(client, influx_query_str)
+            .await?;
+        let count = series.len();
+        let mut result = Vec::new();
+        // for each serie
+        for (serie_name, points) in series {
+            // get the key expression from the serie name
+            match self.keyexpr_from_serie(&serie_name) {
+                Ok(ke) => {
+                    debug!("Replying {} values for {:?}", points.len(), ke);
+                    // for each point in the serie
+                    for zpoint in points {
+                        // get the timestamp (ignore the point if failing)
+                        match Timestamp::from_str(&zpoint.timestamp) {
+                            Ok(timestamp) => result.push((ke.clone(), timestamp)),
+                            Err(e) => warn!(
+                                r#"Failed to decode zenoh Timestamp from Influx point {} with timestamp="{}": {:?}"#,
+                                serie_name, zpoint.timestamp, e
+                            ),
+                        };
+                    }
+                }
+                Err(e) => {
+                    error!("Error replying with serie '{}' : {}", serie_name, e)
+                }
+            };
+        }
+        Ok((count, result))
+    }
+
+    // The Influx regex matching the measurements of a key expression (or the hashed measurement name of a too long key).
+    // With wildcards, the hashed measurement names of the matching too long keys are added to the regex: it doesn't
+    // match them otherwise, since they're truncated.
+    fn key_regex(&self, key_expr: &OwnedKeyExpr) -> String {
+        let long_keys = match &self.long_keys {
+            Some(long_keys) => long_keys,
+            None => return key_exprs_to_influx_regex(&[&KeyExpr::from(key_expr.clone())]),
+        };
+        if let Some(name) = long_keys.hashed_name(key_expr) {
+            return quote_ident(&name);
+        }
+        let regex = key_exprs_to_influx_regex(&[&KeyExpr::from(key_expr.clone())]);
+        if !key_expr.is_wild() {
+            return regex;
+        }
+        let names = long_keys.names.read().unwrap();
+        let mut hashed = names
+            .iter()
+            .filter(|(_, key)| keyexpr::new(key.as_str()).map_or(false, |k| k.intersects(key_expr)))
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        if hashed.is_empty() {
+            return regex;
+        }
+        hashed.sort_unstable();
+        let mut result = regex.strip_suffix(")$/").unwrap_or(&regex).to_string();
+        for name in hashed {
+            result.push('|');
+            push_escaped_regex(&mut result, name);
+        }
+        result.push_str(")$/");
+        result
+    }
+
+    // Rewrite all the points of the storage, with the deletion markers, into an InfluxDB 2.x bucket with the schema of
+    // the InfluxDB 2.x backend (the decoded values being re-encoded). Returns the number of migrated points.
+    async fn migrate_to_v2(&self, target: &V2Target) -> ZResult<u64> {
+        let (from, group_by) = match &self.layout {
+            Layout::MeasurementPerKey => ("/.*/".to_string(), String::new()),
+            Layout::SingleMeasurement(m) => (quote_ident(m), format!(r#" GROUP BY "{KEY_TAG}""#)),
+        };
+        let columns = self.get_columns();
+        let mut migrated = 0;
+        // the timestamp of the last migrated point of each serie, to skip a chunked value read in 2 pages
+        let mut last_timestamps: HashMap<String, Timestamp> = HashMap::new();
+        for client in self.all_clients() {
+            let clients = [client];
+            let mut offset = 0;
+            loop {
+                let influx_query_str = format!(
+                    "SELECT {columns} FROM {from} WHERE kind!=''{group_by} LIMIT {MIGRATION_BATCH_SIZE} OFFSET {offset}"
+                );
+                debug!(
+                    "Migrate to InfluxDB 2.x points read with Influx query: {}",
+                    influx_query_str
+                );
+                let page = self.query_stored_data(&clients, &influx_query_str).await?;
+                let is_last_page = page
+                    .iter()
+                    .all(|(_, count, _)| *count < MIGRATION_BATCH_SIZE);
+                let mut lines = Vec::new();
+                for (serie_name, _, data) in page {
+                    let measurement = match self.keyexpr_from_serie(&serie_name) {
+                        Ok(Some(ke)) => ke.to_string(),
+                        Ok(None) => NONE_KEY.to_string(),
+                        Err(e) => {
+                            warn!("Skip migration of serie '{}' : {}", serie_name, e);
+                            continue;
+                        }
+                    };
+                    for d in data {
+                        if last_timestamps.get(&serie_name) == Some(&d.timestamp) {
+                            continue;
+                        }
+                        last_timestamps.insert(serie_name.clone(), d.timestamp);
+                        lines.push(v2_line(&measurement, &d)?);
+                    }
+                }
+                if !lines.is_empty() {
+                    target
+                        .write(&lines)
+                        .await
+                        .map_err(|e| self.record_error(e))?;
+                    migrated += lines.len() as u64;
+                }
+                if is_last_page {
+                    break;
+                }
+                offset += MIGRATION_BATCH_SIZE;
+            }
+        }
+        Ok(migrated)
+    }
+
+    // Export the points of the storage (with the deletion markers and the lookup of hashed measurement names), as
+    // annotated line protocol importable with `influx -import`. The `key` (a key expression) and `range` (a zenoh time
+    // range) parameters filter the exported points. The lines are written to the `path` file if set, or else replied
+    // in chunks of at most EXPORT_CHUNK_SIZE bytes. The `path` is relative to the `export_dir` directory.
+    async fn export(&self, parameters: &str) -> ZResult<Vec<StoredData>> {
+        let mut path = None;
+        let mut key = None;
+        let mut time_range = None;
+        for kv in parameters.split('&').filter(|kv| !kv.is_empty()) {
+            match kv.split_once('=') {
+                Some(("path", v)) => {
+                    path = Some(confined_path(
+                        self.export_dir.as_deref(),
+                        &percent_decode(v),
+                        PROP_STORAGE_EXPORT_DIR,
+                        "export",
+                    )?)
+                }
+                Some(("key", v)) => {
+                    key = Some(OwnedKeyExpr::from_str(&percent_decode(v)).map_err(|e| {
+                        influx_error!(QueryParse, "Invalid `key` for export operation: {}", e)
+                    })?)
+                }
+                Some(("range", v)) => {
+                    time_range = Some(
+                        zenoh::selector::TimeRange::from_str(&percent_decode(v)).map_err(|e| {
+                            influx_error!(QueryParse, "Invalid `range` for export operation: {}", e)
+                        })?,
+                    )
+                }
+                _ => fail!(
+                    QueryParse,
+                    "Invalid parameter {:?} for export operation (supported: path, key, range)",
+                    kv
+                ),
+            }
+        }
+        let get_parameters = GetParameters {
+            time_range,
+            limit: None,
+            order: None,
+            agg: None,
+            window: None,
+            max_samples: None,
+            coverage: false,
+            include_deletes: true,
+            at: None,
+        };
+        let regex = key.as_ref().map(|k| self.key_regex(k));
+        let (from, key_regex) = match &self.layout {
+            Layout::MeasurementPerKey => (regex.unwrap_or_else(|| "/.*/".to_string()), None),
+            Layout::SingleMeasurement(m) => (quote_ident(m), regex.as_deref()),
+        };
+        let mut selections = vec![(from, where_from_parameters(&get_parameters, key_regex))];
+        if key.is_none() && self.long_keys.is_some() {
+            selections.push((quote_ident(KEY_NAMES_MEASUREMENT), Where::new()));
+        }
+
+        let clients = self.all_clients();
+        let databases: Vec<String> = clients
+            .iter()
+            .map(|c| c.database_name().to_string())
+            .collect();
+        let mut file = match &path {
+            Some(path) => Some(async_std::fs::File::create(path).await.map_err(|e| {
+                influx_error!(QueryParse, "Failed to create export file {:?}: {}", path, e)
+            })?),
+            None => None,
+        };
+        let mut lines = export_header(&databases);
+        let mut chunks = Vec::new();
+        let mut exported = 0u64;
+        for client in clients {
+            lines.push_str(&context_database(client.database_name()));
+            let field_types = show_field_keys(&client).await?;
+            for (from, where_clause) in &selections {
+                let mut offset = 0;
+                loop {
+                    let query = InfluxRQuery::new(format!(
+                        "SELECT * FROM {from} {where_clause} GROUP BY * LIMIT {MIGRATION_BATCH_SIZE} OFFSET {offset}"
+                    ));
+                    debug!("Export points read with Influx query: {:?}", query);
+                    let series = match client.json_query(query).await {
+                        Ok(mut result) => match result.deserialize_next_tagged::<
+                            HashMap<String, String>,
+                            serde_json::Map<String, serde_json::Value>,
+                        >() {
+                            Ok(qr) => qr.series,
+                            Err(e) => fail_client!(
+                                e,
+                                "Failed to parse points of InfluxDb database {} to export",
+                                client.database_name()
+                            ),
+                        },
+                        Err(e) => fail_client!(
+                            e,
+                            "Failed to read points of InfluxDb database {} to export",
+                            client.database_name()
+                        ),
+                    };
+                    let is_last_batch =
+                        series.iter().all(|s| s.values.len() < MIGRATION_BATCH_SIZE);
+                    for serie in series {
+                        for row in &serie.values {
+                            if let Some(line) = to_line(
+                                &serie.name,
+                                &serie.tags,
+                                row,
+                                field_types.get(&serie.name),
+                            )? {
+                                lines.push_str(&line);
+                                lines.push('\n');
+                                exported += 1;
+                            }
+                        }
+                    }
+                    match &mut file {
+                        Some(file) => {
+                            async_std::io::WriteExt::write_all(file, lines.as_bytes())
+                                .await
+                                .map_err(|e| {
+                                    influx_error!(
+                                        QueryParse,
+                                        "Failed to write export file {:?}: {}",
+                                        path,
+                                        e
+                                    )
+                                })?;
+                            lines.clear();
+                        }
+                        None if lines.len() >= EXPORT_CHUNK_SIZE => {
+                            chunks.push(std::mem::take(&mut lines));
+                        }
+                        None => {}
+                    }
+                    if is_last_batch {
+                        break;
+                    }
+                    offset += MIGRATION_BATCH_SIZE;
+                }
+            }
+        }
+        match file {
+            Some(mut file) => {
+                async_std::io::WriteExt::flush(&mut file)
+                    .await
+                    .map_err(|e| {
+                        influx_error!(QueryParse, "Failed to write export file {:?}: {}", path, e)
+                    })?;
+                Ok(vec![StoredData {
+                    value: json_value(serde_json::json!({
+                        "path": path,
+                        "exported_points": exported,
+                    })),
+                    timestamp: new_reception_timestamp(),
+                }])
+            }
+            None => {
+                if !lines.is_empty() {
+                    chunks.push(lines);
+                }
+                Ok(chunks
+                    .into_iter()
+                    .map(|chunk| StoredData {
+                        value: Value::from(chunk),
+                        timestamp: new_reception_timestamp(),
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    // Run the read-only InfluxQL query given by a GET on "@raw?q=<query>", returning InfluxDB's JSON result
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "influxdb.raw_query",
+            skip_all,
+            fields(storage = %self.config.name, query = Empty, latency_us = Empty)
+        )
+    )]
+    async fn raw_query(&mut self, parameters: &str) -> ZResult<Vec<StoredData>> {
+        self.check_open()?;
+        self.check_health()?;
+        let query = match parameters.split('&').find_map(|kv| kv.strip_prefix("q=")) {
+            Some(q) => percent_decode(q),
+            None => fail!(QueryParse, "A raw query requires a `q=<query>` parameter"),
+        };
+        check_read_only_query(&query)?;
+        debug!("Run raw query on storage {}: {}", self.config.name, query);
+        trace_field("query", &query);
+        let start = Instant::now();
+        let result = self
+            .client
+            .get()
+            .query(&InfluxRQuery::new(query.as_str()))
+            .await;
+        let latency = self.observe_query_latency(start);
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => fail_client!(e, "Failed to run raw query {:?}", query),
+        };
+        self.log_slow_query(latency, &query, Some(result.len()));
+        let result: serde_json::Value = serde_json::from_str(&result).map_err(|e| {
+            influx_error!(
+                Serialization,
+                "Invalid JSON result of raw query {:?} : {}",
+                query,
+                e
+            )
+        })?;
+        Ok(vec![StoredData {
+            value: json_value(result),
+            timestamp: new_reception_timestamp(),
+        }])
+    }
+
+    // Fail fast if InfluxDB is known to be unreachable
+    fn check_health(&self) -> ZResult<()> {
+        if !self.health.is_up() {
+            fail!(
+                Connection,
+                "InfluxDB is unreachable for storage {} : {}",
+                self.config.name,
+                self.health.last_error().unwrap_or_default()
+            )
+        }
+        Ok(())
+    }
+
+    // Create a write query for a point of a key, depending on the layout
+    fn new_write_query(&self, influx_time: u128, key: &str) -> InfluxWQuery {
+        let mut query = InfluxWQuery::new(
+            InfluxTimestamp::Nanoseconds(influx_time),
+            self.key_mapper.measurement(key),
+        );
+        if let Some(tag) = self.key_mapper.key_tag(key) {
+            query = query.add_tag(KEY_TAG, tag);
+        }
+        self.add_key_tags(query, key)
+            .add_field(SCHEMA_VERSION_FIELD, SCHEMA_VERSION)
+    }
+
+    // Add the tags derived from the key's chunks (see `key_tags` property) to a write query
+    fn add_key_tags(&self, mut query: InfluxWQuery, key: &str) -> InfluxWQuery {
+        let settings = self.settings();
+        if !settings.key_tags.is_empty() {
+            let chunks: Vec<&str> = key.split('/').collect();
+            for (index, tag) in &settings.key_tags {
+                if let Some(chunk) = chunks.get(*index) {
+                    query = query.add_tag(tag.as_str(), *chunk);
+                }
+            }
+        }
+        query
+    }
+
+    // Returns true if there are already PUT points for this key
+    async fn has_series(&self, measurement: &str) -> ZResult<bool> {
+        #[derive(Deserialize, Debug)]
+        struct QueryResult {
+            #[allow(dead_code)]
+            key: String,
+        }
+
+        let (from, filter) = self.key_selection(measurement);
+        let query = InfluxRQuery::new(format!(
+            "SHOW SERIES FROM {from} {} LIMIT 1",
+            filter.and(Condition::tag_eq("kind", "PUT"))
+        ));
+        match self.client_for(measurement).json_query(query).await {
+            Ok(mut result) => match result.deserialize_next::<QueryResult>() {
+                Ok(qr) => Ok(qr.series.iter().any(|s| !s.values.is_empty())),
+                Err(err) => fail_client!(err, "Failed to get series of {}", measurement),
+            },
+            Err(err) => fail_client!(err, "Failed to get series of {}", measurement),
+        }
+    }
+
+    // Check that writing a new key won't exceed the max_series limit
+    async fn check_max_series(&self, measurement: &str) -> ZResult<()> {
+        if let Some(max) = self.max_series {
+            let cardinality = self.series_cardinality.load(Ordering::Relaxed);
+            if cardinality >= max && !self.has_series(measurement).await? {
+                match self.on_max_series {
+                    OnMaxSeries::Warn => warn!(
+                        "Storage {} has {} series, exceeding `{}`={} ; still writing new key {}",
+                        self.config.name, cardinality, PROP_STORAGE_MAX_SERIES, max, measurement
+                    ),
+                    OnMaxSeries::Refuse => fail!(
+                        Capacity,
+                        "Storage {} has {} series, exceeding `{}`={} ; refuse to write new key {}",
+                        self.config.name,
+                        cardinality,
+                        PROP_STORAGE_MAX_SERIES,
+                        max,
+                        measurement
+                    ),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Cancel the scheduled drop of a deleted key on a new PUT, or wait for the end of a running one,
+    // so the new point is never written just before the DROP
+    async fn cancel_measurement_drop(&mut self, key: &str) {
+        if let Some(pending) = self.pending_drops.remove(key) {
+            pending.handle.defuse();
+            let mut state = pending.state.lock().await;
+            if *state == DropState::Scheduled {
+                debug!("Cancel the scheduled drop of {:?} on new PUT", key);
+                *state = DropState::Cancelled;
+            }
+        }
+    }
+
+    // The measurement to drop for a deleted key and, with a single measurement, the key of the series to drop
+    fn drop_target(&self, key: &str) -> (String, Option<String>) {
+        (
+            self.key_mapper.measurement(key),
+            self.key_mapper.key_tag(key).map(String::from),
+        )
+    }
+
+    async fn schedule_measurement_drop(
+        &self,
+        measurement: &str,
+        state: Arc<AsyncMutex<DropState>>,
+    ) -> TimedHandle {
+        let (measurement, key) = self.drop_target(measurement);
+        let event = TimedEvent::once(
+            Instant::now() + self.settings().drop_measurement_timeout,
+            TimedMeasurementDrop {
+                client: self.admin_client.clone(),
+                measurement,
+                key,
+                state,
+                events: self.events.clone(),
+                maintenance: self.maintenance.clone(),
+            },
+        );
+        let handle = event.get_handle();
+        self.timer.add_async(event).await;
+        handle
+    }
+
+    // The columns to SELECT on GET: only the ones to decode, not those possibly added by other writers
+    fn get_column_names(&self) -> Vec<&str> {
+        let mut columns: Vec<&str> = GET_COLUMNS.to_vec();
+        if let Some(value_field) = &self.read_external {
+            if !columns.contains(&value_field.as_str()) {
+                columns.push(value_field);
+            }
+        }
+        columns
+    }
+
+    fn get_columns(&self) -> String {
+        self.get_column_names()
+            .iter()
+            .map(|c| quote_ident(c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // The query returning at most `max_samples` points per key over the "_time" range:
+    // the first point of each of the `max_samples` time windows
+    fn downsampling_query(
+        &self,
+        regex: &str,
+        parameters: &GetParameters,
+        max_samples: u64,
+    ) -> ZResult<String> {
+        let (interval, offset) = downsampling_interval(parameters, max_samples)?;
+        // "kind" is a tag that can't be selected with first(), and is not read anyway
+        let columns = self
+            .get_column_names()
+            .iter()
+            .filter(|c| **c != "kind")
+            .map(|c| format!("first({0}) AS {0}", quote_ident(c)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (from, key_regex, group_by_key) = match &self.layout {
+            Layout::MeasurementPerKey => (regex.to_string(), None, String::new()),
+            Layout::SingleMeasurement(m) => {
+                (quote_ident(m), Some(regex), format!(r#", "{KEY_TAG}""#))
+            }
+        };
+        Ok(format!(
+            "SELECT {columns} FROM {from} {} GROUP BY time({}, {}){group_by_key} fill(none)",
+            where_from_parameters(parameters, key_regex),
+            influx_duration(interval),
+            influx_duration(offset)
+        ))
+    }
+
+    // The coarsest downsampling tier to read for the time range of a GET (None to read the raw points)
+    fn select_tier(&self, parameters: &GetParameters) -> Option<&Tier> {
+        use zenoh::selector::{TimeBound, TimeRange};
+        let TimeRange(start, stop) = parameters.time_range.as_ref()?;
+        let length = match start {
+            TimeBound::Inclusive(t) | TimeBound::Exclusive(t) => {
+                let stop = match stop {
+                    TimeBound::Inclusive(t) | TimeBound::Exclusive(t) => resolve_timeexpr(t),
+                    TimeBound::Unbounded => SystemTime::now(),
+                };
+                stop.duration_since(resolve_timeexpr(t)).unwrap_or_default()
+            }
+            // the whole history
+            TimeBound::Unbounded => Duration::MAX,
+        };
+        self.tiers.iter().rev().find(|t| length >= t.min_range)
+    }
+
+    // Run a GET query, returning for each serie its number of points and the decoded ones
+    async fn query_stored_data(
+        &self,
+        clients: &[Arc<dyn InfluxClient>],
+        influx_query_str: &str,
+    ) -> ZResult<Vec<(String, usize, Vec<StoredData>)>> {
+        let strict_schema = self.settings().strict_schema;
+        if self.read_external.is_none() && strict_schema {
+            let series = self
+                .query_points::<ZenohPoint>(clients, influx_query_str)
+                .await?;
+            self.decode_series(clients, series, decode_zenoh_point)
+                .await
+        } else {
+            // points written by other tools, or with missing fields, are accepted too
+            let value_field = self.read_external.as_deref();
+            let series = self
+                .query_points::<serde_json::Map<String, serde_json::Value>>(
+                    clients,
+                    influx_query_str,
+                )
+                .await?;
+            self.decode_series(clients, series, |serie_name, row| {
+                decode_row(serie_name, row, value_field, strict_schema)
+            })
+            .await
+        }
+    }
+
+    // Decode the points of each serie, after the reassembly of their chunks, with their offloaded payloads.
+    // Returns for each serie its number of points and the decoded ones.
+    async fn decode_series<T, F>(
+        &self,
+        clients: &[Arc<dyn InfluxClient>],
+        series: Vec<(String, Vec<T>)>,
+        decode: F,
+    ) -> ZResult<Vec<(String, usize, Vec<StoredData>)>>
+    where
+        T: ChunkedPoint + OffloadedPoint + HashedPoint + DeserializeOwned + Send,
+        F: Fn(&str, T) -> ZResult<Option<StoredData>> + Send + Sync,
+    {
+        let mut result = Vec::new();
+        for (serie_name, count, points) in self.reassemble_series(clients, series).await? {
+            let mut data = Vec::with_capacity(points.len());
+            for point in points {
+                let blob = point.blob();
+                let hash = point.value_hash();
+                let mut d = match decode(&serie_name, point)? {
+                    Some(d) => d,
+                    None => continue,
+                };
+                if let Some(blob) = blob {
+                    match self.fetch_blob(&blob).await {
+                        Some(payload) => {
+                            d.value =
+                                Value::new(ZBuf::from(payload)).encoding(d.value.encoding.clone())
+                        }
+                        None => continue,
+                    }
+                }
+                if let Some(hash) = hash {
+                    if !self.verify_value(&serie_name, &hash, &mut d) {
+                        continue;
+                    }
+                }
+                data.push(d);
+            }
+            result.push((serie_name, count, data));
+        }
+        Ok(result)
+    }
+
+    // Verify a decoded value against its stored hash. Returns false if it's corrupted and must be skipped,
+    // or flags it (with "on_corrupted": "flag")
+    fn verify_value(&self, serie_name: &str, hash: &str, data: &mut StoredData) -> bool {
+        match integrity::verify(hash, &data.value.payload.contiguous()) {
+            Verification::Valid => true,
+            Verification::Unknown => {
+                debug!(
+                    r#"Unknown hash algorithm of value of {} with timestamp="{}": not verified"#,
+                    serie_name, data.timestamp
+                );
+                true
+            }
+            Verification::Corrupted => {
+                warn!(
+                    r#"Corrupted value of {} with timestamp="{}": hash mismatch"#,
+                    serie_name, data.timestamp
+                );
+                self.stats.record_corrupted();
+                self.count_metric("corrupted_values");
+                match self.on_corrupted {
+                    OnCorrupted::Skip => false,
+                    OnCorrupted::Flag => {
+                        let prefix = *data.value.encoding.prefix();
+                        data.value.encoding =
+                            Encoding::WithSuffix(prefix, CORRUPTED_ENCODING_SUFFIX.into());
+                        true
+                    }
+                }
+            }
+        }
+    }
+
+    // Fetch a payload offloaded to the blob store. Returns None (with a warning) if it can't be fetched
+    async fn fetch_blob(&self, blob: &BlobRef) -> Option<Vec<u8>> {
+        match &self.offload {
+            Some((store, _)) => match store.get(blob).await {
+                Ok(payload) => Some(payload),
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch offloaded payload {} : {}",
+                        blob.reference, e
+                    );
+                    None
+                }
+            },
+            None => {
+                warn!(
+                    "Point with offloaded payload {}, but no `{}` configured for storage {}",
+                    blob.reference, PROP_STORAGE_OFFLOAD, self.config.name
+                );
+                None
+            }
+        }
+    }
+
+    // Reassemble the values split in chunks (see `chunk_size` property) in the points of each serie,
+    // querying all the chunks of the values cut by a LIMIT (e.g. the latest value, or a page).
+    // Returns for each serie its number of points and the reassembled ones.
+    async fn reassemble_series<T>(
+        &self,
+        clients: &[Arc<dyn InfluxClient>],
+        series: Vec<(String, Vec<T>)>,
+    ) -> ZResult<Vec<(String, usize, Vec<T>)>>
+    where
+        T: ChunkedPoint + DeserializeOwned + Send,
+    {
+        let mut result = Vec::with_capacity(series.len());
+        for (serie_name, points) in series {
+            let count = points.len();
+            let mut data = Vec::with_capacity(count);
+            for point in reassemble(points) {
+                match point {
+                    Reassembled::Point(p) => data.push(p),
+                    Reassembled::Incomplete(timestamp) => data.extend(
+                        self.query_chunks::<T>(clients, &serie_name, &timestamp)
+                            .await?,
+                    ),
+                }
+            }
+            result.push((serie_name, count, data));
+        }
+        Ok(result)
+    }
+
+    // Query all the chunks of the value of a serie with the given zenoh timestamp, returning the reassembled value
+    async fn query_chunks<T>(
+        &self,
+        clients: &[Arc<dyn InfluxClient>],
+        serie_name: &str,
+        timestamp: &str,
+    ) -> ZResult<Option<T>>
+    where
+        T: ChunkedPoint + DeserializeOwned + Send,
+    {
+        let influx_time = match Timestamp::from_str(timestamp) {
+            Ok(t) => t.get_time().to_duration().as_nanos(),
+            Err(e) => {
+                warn!(
+                    r#"Failed to decode zenoh Timestamp of chunked value of {} with timestamp="{}": {:?}"#,
+                    serie_name, timestamp, e
+                );
+                return Ok(None);
+            }
+        };
+        let (from, filter) = self.key_selection(serie_name);
+        let mut select = Select::new(&self.get_columns(), from).filter(
+            filter
+                .and(Condition::tag_ne(CHUNK_INDEX_TAG, ""))
+                .and(Condition::time(Cmp::Eq, influx_time)),
+        );
+        // in a shared measurement the points are read with their key tag (see `query_points_on`)
+        if self.key_mapper.key_tag(serie_name).is_some() {
+            select = select.group_by_tag(KEY_TAG);
+        }
+        let influx_query_str = select.to_string();
+        debug!(
+            "Get chunks of {} with Influx query: {}",
+            serie_name, influx_query_str
+        );
+        let points = self
+            .query_points::<T>(clients, &influx_query_str)
+            .await?
+            .into_iter()
+            .filter(|(name, _)| name == serie_name)
+            .flat_map(|(_, points)| points)
+            .filter(|p| p.zenoh_timestamp() == timestamp)
+            .collect::<Vec<_>>();
+        match reassemble(points).into_iter().next() {
+            Some(Reassembled::Point(p)) => Ok(Some(p)),
+            _ => {
+                warn!(
+                    r#"Missing chunks of the value of {} with timestamp="{}""#,
+                    serie_name, timestamp
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    // Simulated failure before a write query (only with the "fault_injection" feature)
+    async fn inject_write_fault(&self) -> ZResult<()> {
+        #[cfg(feature = "fault_injection")]
+        {
+            if let Some(faults) = &self.faults {
+                faults.before_write().await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Simulated failure before a read query (only with the "fault_injection" feature)
+    async fn inject_read_fault(&self) -> ZResult<()> {
+        #[cfg(feature = "fault_injection")]
+        {
+            if let Some(faults) = &self.faults {
+                faults.before_read().await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Count an operation in the metrics (only with the "metrics" feature)
+    fn count_metric(&self, _name: &str) {
+        #[cfg(feature = "metrics")]
+        self.metrics.inc(_name);
+    }
+
+    // Record the latency of a query started at `start` in the metrics (only with the "metrics" feature)
+    // and in the current tracing span (only with the "tracing" feature)
+    fn observe_query_latency(&self, start: Instant) -> Duration {
+        let latency = start.elapsed();
+        #[cfg(feature = "metrics")]
+        self.metrics.observe_latency(latency);
+        trace_field("latency_us", &latency.as_micros());
+        latency
+    }
+
+    // Log the query as a warning if it lasted longer than `slow_query_ms`, with its result size if any
+    fn log_slow_query(
+        &self,
+        latency: Duration,
+        query: &dyn std::fmt::Display,
+        size: Option<usize>,
+    ) {
+        match self.settings().slow_query {
+            Some(threshold) if latency > threshold => match size {
+                Some(size) => warn!(
+                    "Slow InfluxDB query on storage {} ({:?}, result size: {}): {}",
+                    self.config.name, latency, size, query
+                ),
+                None => warn!(
+                    "Slow InfluxDB query on storage {} ({:?}): {}",
+                    self.config.name, latency, query
+                ),
+            },
+            _ => (),
+        }
+    }
+
+    // Record a failed query in the statistics and the metrics, returning the error
+    fn record_error(&self, error: InfluxDbError) -> InfluxDbError {
+        self.stats.record_error(error.clone());
+        self.count_metric("errors");
+        if let Some(events) = &self.events {
+            events.emit(
+                "error",
+                serde_json::json!({"kind": error.kind(), "message": error.to_string()}),
+            );
+        }
+        error
+    }
+
+    // Run a query on each of the databases, returning the points per key of all of them
+    async fn query_points<T>(
+        &self,
+        clients: &[Arc<dyn InfluxClient>],
+        influx_query_str: &str,
+    ) -> ZResult<Vec<(String, Vec<T>)>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let mut result = Vec::new();
+        for client in clients {
+            result.append(&mut self.query_points_on(client, influx_query_str).await?);
+        }
+        Ok(result)
+    }
+
+    // Run a query, returning the points per key (i.e. per measurement, or per key tag with a single measurement)
+    async fn query_points_on<T>(
+        &self,
+        client: &dyn InfluxClient,
+        influx_query_str: &str,
+    ) -> ZResult<Vec<(String, Vec<T>)>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        #[derive(Deserialize, Debug)]
+        struct KeyTag {
+            key: String,
+        }
+
+        self.inject_read_fault().await?;
+        if let Some(breaker) = &self.breaker {
+            breaker.check()?;
+        }
+        let mut result = Vec::new();
+        trace_field("query", &influx_query_str);
+        let start = Instant::now();
+        let query_result = client.json_query(InfluxRQuery::new(influx_query_str)).await;
+        let latency = self.observe_query_latency(start);
+        if let Some(breaker) = &self.breaker {
+            breaker.record(query_result.is_err());
+        }
+        match query_result {
+            Ok(mut query_result) => {
+                while !query_result.results.is_empty() {
+                    let series = match self.layout {
+                        Layout::MeasurementPerKey => {
+                            query_result.deserialize_next::<T>().map(|r| {
+                                r.series
+                                    .into_iter()
+                                    .map(|s| (s.name, s.values))
+                                    .collect::<Vec<_>>()
+                            })
+                        }
+                        Layout::SingleMeasurement(_) => query_result
+                            .deserialize_next_tagged::<KeyTag, T>()
+                            .map(|r| {
+                                r.series
+                                    .into_iter()
+                                    .map(|s| (s.tags.key, s.values))
+                                    .collect::<Vec<_>>()
+                            }),
+                    };
+                    match series {
+                        Ok(mut series) => result.append(&mut series),
+                        Err(e) => fail_client!(
+                            e,
+                            "Failed to parse result of InfluxDB query '{}'",
+                            influx_query_str
+                        ),
+                    }
+                }
+                self.stats.record_read();
+                let points = result.iter().map(|(_, p)| p.len()).sum::<usize>();
+                trace_field("points", &points);
+                self.log_slow_query(latency, &influx_query_str, Some(points));
+                Ok(result)
+            }
+            Err(e) => Err(self
+                .record_error(InfluxDbError::from_client(
+                    format!("Failed to query InfluxDb with '{influx_query_str}'"),
+                    e,
+                ))
+                .into()),
+        }
+    }
+
+    // Returns for each key matching the key expression the timestamps of its first and last points, as JSON.
+    // They're read from the key index, without scanning the points: with a "_time" range, only the keys having
+    // points before the end of the range and after its start are replied.
+    fn get_coverage(
+        &self,
+        key_expr: &keyexpr,
+        parameters: &GetParameters<'_>,
+    ) -> ZResult<Vec<StoredData>> {
+        use zenoh::selector::{TimeBound, TimeRange};
+        let index = match &self.key_index {
+            Some(index) => index,
+            None => fail!(
+                QueryParse,
+                "{} selector parameter is only supported by storages with `{}` property",
+                PARAM_COVERAGE,
+                PROP_STORAGE_KEY_INDEX
+            ),
+        };
+        let (start, stop) = match &parameters.time_range {
+            Some(TimeRange(start, stop)) => (start, stop),
+            None => (&TimeBound::Unbounded, &TimeBound::Unbounded),
+        };
+        let mut result = Vec::new();
+        for (key, first, last) in index.coverage(key_expr) {
+            let first_time = first.get_time().to_system_time();
+            let last_time = last.get_time().to_system_time();
+            let in_range = match start {
+                TimeBound::Inclusive(t) => last_time >= resolve_timeexpr(t),
+                TimeBound::Exclusive(t) => last_time > resolve_timeexpr(t),
+                TimeBound::Unbounded => true,
+            } && match stop {
+                TimeBound::Inclusive(t) => first_time <= resolve_timeexpr(t),
+                TimeBound::Exclusive(t) => first_time < resolve_timeexpr(t),
+                TimeBound::Unbounded => true,
+            };
+            if !in_range {
+                continue;
+            }
+            debug!("Reply coverage of {} from key index", key);
+            let value = json_value(serde_json::json!({
+                "first": first.to_string(),
+                "last": last.to_string(),
+            }));
+            result.push(StoredData {
+                value,
+                timestamp: last,
+            })
+        }
+        Ok(result)
+    }
+
+    // Returns for each key matching the regex the aggregation of its numeric values per time window
+    // (see "_agg" and "_window" selector parameters)
+    async fn get_aggregation(
+        &self,
+        clients: &[Arc<dyn InfluxClient>],
+        regex: &str,
+        parameters: &GetParameters<'_>,
+        agg: &str,
+        window: Duration,
+    ) -> ZResult<Vec<StoredData>> {
+        #[derive(Deserialize, Debug)]
+        struct Aggregate {
+            time: String,
+            value: Option<f64>,
+        }
+
+        if !self.numeric_values {
+            fail!(
+                QueryParse,
+                "Aggregations are only supported by storages with `{}` property",
+                PROP_STORAGE_NUMERIC_VALUES
+            )
+        }
+
+        let (from, key_regex) = match &self.layout {
+            Layout::MeasurementPerKey => (regex.to_string(), None),
+            Layout::SingleMeasurement(m) => (quote_ident(m), Some(regex)),
+        };
+        let mut influx_query_str = format!(
+            r#"SELECT {agg}("{NUMERIC_VALUE_FIELD}") AS "value" FROM {from} {} GROUP BY time({})"#,
+            where_from_parameters(parameters, key_regex),
+            influx_duration(window)
+        );
+        if key_regex.is_some() {
+            influx_query_str.push_str(&format!(r#", "{KEY_TAG}""#));
+        }
+        influx_query_str.push_str(" fill(none)");
+        debug!("Get aggregation with Influx query: {}", influx_query_str);
+        let mut result = Vec::new();
+        for (serie_name, points) in self
+            .query_points::<Aggregate>(clients, &influx_query_str)
+            .await?
+        {
+            for point in points {
+                let f = match point.value {
+                    Some(f) => f,
+                    None => continue,
+                };
+                match timestamp_from_influx_time(&point.time) {
+                    Ok(timestamp) => {
+                        let value = if agg == "count" {
+                            Value::from(f as i64)
+                        } else {
+                            Value::from(f)
+                        };
+                        result.push(StoredData { value, timestamp })
+                    }
+                    Err(e) => warn!(
+                        r#"Failed to decode time of Influx point {} with time="{}": {}"#,
+                        serie_name, point.time, e
+                    ),
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn keyexpr_from_serie(&self, serie_name: &str) -> ZResult<Option<OwnedKeyExpr>> {
+        keyexpr_from_serie(&*self.key_mapper, &self.none_key, serie_name)
+    }
+}
+
+#[async_trait]
+impl Storage for InfluxDbStorage {
+    fn get_admin_status(&self) -> serde_json::Value {
+        let mut status = self.config.to_json_value();
+        if let Some(obj) = status.as_object_mut() {
+            obj.insert("stats".into(), self.stats.to_json());
+            obj.insert("none_key".into(), self.none_key.as_str().into());
+            obj.insert("codec".into(), self.codec.name().into());
+            obj.insert("key_mapper".into(), self.key_mapper.name().into());
+        }
+        if let (Some(cache), Some(obj)) = (&self.latest_cache, status.as_object_mut()) {
+            obj.insert("latest_cache_entries".into(), cache.len().into());
+        }
+        if let (Some(cache), Some(obj)) = (&self.entries_cache, status.as_object_mut()) {
+            obj.insert("cached_entries".into(), cache.len().into());
+        }
+        if let (Some(dedup), Some(obj)) = (&self.dedup, status.as_object_mut()) {
+            obj.insert("dedup_skipped".into(), dedup.skipped().into());
+        }
+        if let (Some(replayer), Some(obj)) = (&self.replayer, status.as_object_mut()) {
+            obj.insert("replays_running".into(), replayer.running().into());
+            obj.insert("replay_published".into(), replayer.published().into());
+            obj.insert("replay_progress".into(), replayer.progress());
+        }
+        if let (Some(index), Some(obj)) = (&self.key_index, status.as_object_mut()) {
+            obj.insert("indexed_keys".into(), index.len().into());
+        }
+        if let (Some(feed), Some(obj)) = (&self.change_feed, status.as_object_mut()) {
+            obj.insert("change_feed_published".into(), feed.published().into());
+        }
+        if self.max_series.is_some() {
+            if let Some(obj) = status.as_object_mut() {
+                obj.insert(
+                    "series_cardinality".into(),
+                    self.series_cardinality.load(Ordering::Relaxed).into(),
+                );
+            }
+        }
+        if self.max_history_per_key.is_some() {
+            if let Some(obj) = status.as_object_mut() {
+                obj.insert(
+                    "trimmed_points".into(),
+                    self.trimmed_points.load(Ordering::Relaxed).into(),
+                );
+            }
+        }
+        if let Some(obj) = status.as_object_mut() {
+            obj.insert("influxdb_up".into(), self.health.is_up().into());
+        }
+        if let (Some(breaker), Some(obj)) = (&self.breaker, status.as_object_mut()) {
+            obj.insert("circuit_breaker".into(), breaker.state_name().into());
+        }
+        if let (Some(buffer), Some(obj)) = (&self.buffer, status.as_object_mut()) {
+            obj.insert("buffered_points".into(), buffer.len().into());
+        }
+        if let (Some(archive), Some(obj)) = (&self.archive, status.as_object_mut()) {
+            obj.insert("archive_dropped_points".into(), archive.dropped().into());
+        }
+        if let (false, Some(obj)) = (self.replicas.is_empty(), status.as_object_mut()) {
+            obj.insert(
+                "replicas".into(),
+                self.replicas
+                    .iter()
+                    .map(|r| {
+                        serde_json::json!({
+                            "target": r.target(),
+                            "dropped": r.dropped(),
+                            "last_error": r.last_error(),
+                        })
+                    })
+                    .collect(),
+            );
+        }
+        if let (Some((time, error)), Some(obj)) =
+            (&*self.last_gc.read().unwrap(), status.as_object_mut())
+        {
+            obj.insert(
+                "last_gc".into(),
+                serde_json::json!({
+                    "time": humantime::format_rfc3339_millis(*time).to_string(),
+                    "error": error,
+                }),
+            );
+        }
+        status
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "influxdb.put",
+            skip_all,
+            fields(
+                storage = %self.config.name,
+                measurement = Empty,
+                query = Empty,
+                points = 1,
+                latency_us = Empty,
+            )
+        )
+    )]
+    async fn put(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        value: Value,
+        timestamp: Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        self.check_open()?;
+        self.count_metric("puts");
+        let timestamp = self.police_timestamp(&key, timestamp)?;
+        // the value to cache once inserted
+        let to_cache = self
+            .latest_cache
+            .as_ref()
+            .map(|_| (key.clone(), value.clone()));
+        let entry = self.entries_cache.as_ref().map(|_| key.clone());
+        // ignore the values republished by the change feed, already stored
+        if let (Some(feed), Some(k)) = (&self.change_feed, &key) {
+            if feed.is_echo(k, &value.payload.contiguous()) {
+                debug!("Ignore value of {:?} republished by the change feed", k);
+                return Ok(StorageInsertionResult::Outdated);
+            }
+        }
+        // likewise for the replayed values
+        if let (Some(replayer), Some(k)) = (&self.replayer, &key) {
+            if replayer.is_echo(k, &value.payload.contiguous()) {
+                debug!("Ignore value of {:?} replayed by the storage", k);
+                return Ok(StorageInsertionResult::Outdated);
+            }
+        }
+        // skip the values identical to the previous one of the key
+        let written = match &self.dedup {
+            Some(dedup) => {
+                let fingerprint = dedup.fingerprint(&value);
+                if dedup.is_duplicate(&key, &fingerprint, &timestamp) {
+                    debug!("Skip PUT of {:?} with an unchanged value", key);
+                    return Ok(StorageInsertionResult::Outdated);
+                }
+                Some((key.clone(), fingerprint))
+            }
+            None => None,
+        };
+        if key.as_ref() == Some(&self.none_key) {
+            fail!(
+                Schema,
+                "Key {} of storage {} is the key reserved for the points with no key (`{}` property)",
+                self.none_key,
+                self.config.name,
+                PROP_STORAGE_NONE_KEY
+            )
+        }
+        let measurement = key.unwrap_or_else(|| self.none_key.clone());
+        check_key(measurement.as_str())?;
+        trace_field("measurement", &measurement);
+
+        // Note: assume that uhlc timestamp was generated by a clock using UNIX_EPOCH (that's the case by default)
+        let influx_time = timestamp.get_time().to_duration().as_nanos();
+
+        // while InfluxDB is unreachable, buffer the point (without the checks requiring queries) or fail fast
+        // (the buffer is flushed into the storage's database: the routed keys are not buffered)
+        let buffering = !self.health.is_up();
+        if buffering && (self.buffer.is_none() || self.route_of(measurement.as_str()).is_some()) {
+            self.check_health()?;
+        }
+
+        // with latest only, ignore sample if a more recent point is stored
+        if self.latest_only
+            && !buffering
+            && self
+                .has_newer_point(measurement.as_str(), &timestamp)
+                .await?
+        {
+            debug!(
+                "Received a value for {:?} with timestamp older than the stored one; ignore it",
+                measurement
+            );
+            return Ok(StorageInsertionResult::Outdated);
+        }
+
+        // ignore sample if covered by a deletion of this measurement
+        // (not checked in write only mode, to avoid an extra query per PUT)
+        if !self.latest_only
+            && !self.settings().write_only
+            && !buffering
+            && self.is_deleted(measurement.as_str(), &timestamp).await?
+        {
+            debug!(
+                "Received a value for {:?} with timestamp older than its deletion; ignore it",
+                measurement
+            );
+            return Ok(StorageInsertionResult::Outdated);
+        }
+
+        // apply the conflict policy if points of other writers are stored at the same time
+        // (not checked while buffering)
+        let mut conflict = false;
+        if self.conflict_policy != ConflictPolicy::LastWritten && !buffering {
+            let stored = self.timestamps_at(measurement.as_str(), &timestamp).await?;
+            let outdated = stored.contains(&timestamp)
+                || match self.conflict_policy {
+                    ConflictPolicy::NewestWins => stored.iter().any(|t| *t > timestamp),
+                    ConflictPolicy::FirstWins => !stored.is_empty(),
+                    _ => false,
+                };
+            if outdated {
+                debug!(
+                    "Received a value for {:?} conflicting with a stored point at the same time; ignore it",
+                    measurement
+                );
+                return Ok(StorageInsertionResult::Outdated);
+            }
+            conflict = self.conflict_policy == ConflictPolicy::KeepBoth && !stored.is_empty();
+        }
+
+        if !buffering {
+            self.check_max_series(measurement.as_str()).await?;
+        }
+
+        // the point is going to be written: take its tokens
+        if let Some(limiter) = &self.rate_limiter {
+            if let Err(limit) = limiter.try_acquire(value.payload.len()) {
+                self.stats.record_rate_limited();
+                self.count_metric("rate_limited");
+                match self.on_rate_limit {
+                    OnRateLimit::Refuse => fail!(
+                        Capacity,
+                        "PUT of {:?} on storage {} exceeds the `{}` rate limit",
+                        measurement,
+                        self.config.name,
+                        limit
+                    ),
+                    OnRateLimit::Drop => {
+                        debug!(
+                            "Drop PUT of {:?} exceeding the `{}` rate limit",
+                            measurement, limit
+                        );
+                        return Ok(StorageInsertionResult::Outdated);
+                    }
+                }
+            }
+        }
+        // and cancel the scheduled drop of the measurement
+        self.cancel_measurement_drop(measurement.as_str()).await;
+
+        if buffering {
+            if let (Some(buffer), Some((name, query))) =
+                (&self.buffer, self.long_key_point(measurement.as_str()))
+            {
+                if !buffer.push(query, self.priority_of(&measurement)) {
+                    fail!(
+                        Capacity,
+                        "InfluxDB is unreachable and the buffer of storage {} is full",
+                        self.config.name
+                    )
+                }
+                self.add_long_key(name, measurement.as_str());
+            }
+        } else {
+            self.register_long_key(measurement.as_str()).await?;
+        }
+
+        let payload = value.payload.contiguous();
+        let hash = self.value_hash.map(|h| h.compute(&payload));
+        // offload the oversized payloads to the blob store
+        let blob = match &self.offload {
+            Some((store, threshold)) if payload.len() > *threshold => {
+                match store.put(&payload).await {
+                    Ok(blob) => Some(blob),
+                    Err(e) => return Err(self.record_error(e).into()),
+                }
+            }
+            _ => None,
+        };
+        // encode the value into the fields of its point (with an empty value if offloaded)
+        let EncodedValue {
+            value: strvalue,
+            base64,
+            compression,
+            numeric: numeric_value,
+            fields,
+        } = match &blob {
+            Some(_) => EncodedValue {
+                value: String::new(),
+                base64: false,
+                compression: "",
+                numeric: None,
+                fields: Vec::new(),
+            },
+            None => self.codec.encode(payload, &value.encoding),
+        };
+
+        // Note: tags are stored as strings in InfluxDB, while fileds are typed.
+        // For simpler/faster deserialization, we store encoding, timestamp and base64 as fields.
+        // while the kind is stored as a tag to be indexed by InfluxDB and have faster queries on it.
+        let mut query = add_put_fields(
+            self.new_write_query(influx_time, measurement.as_str()),
+            &timestamp,
+            &value.encoding,
+            base64,
+        );
+        if conflict {
+            query = query.add_tag(CONFLICT_TAG, timestamp.get_id().to_string());
+        }
+        if self.schema == Schema::Compat {
+            query = query.add_field(LEGACY_ENCODING_FIELD, value.encoding.to_string());
+        }
+        // with compression enabled the field is always written (empty if not compressed), so the
+        // downsampling tiers' last("compression") always comes from the same point as last("value")
+        if self.compression.is_some() {
+            query = query.add_field(COMPRESSION_FIELD, compression);
+        }
+        if let Some(hash) = hash {
+            query = query.add_field(VALUE_HASH_FIELD, hash);
+        }
+        // (for the same reason, the blob fields are always written with offload enabled)
+        if self.offload.is_some() {
+            let (reference, hash) = match &blob {
+                Some(b) => (b.reference.as_str(), b.hash.as_str()),
+                None => ("", ""),
+            };
+            query = query
+                .add_field(BLOB_REF_FIELD, reference)
+                .add_field(BLOB_HASH_FIELD, hash);
+        }
+        if let Some(f) = numeric_value {
+            query = query.add_field(NUMERIC_VALUE_FIELD, f);
+        }
+        for (name, f) in fields {
+            query = query.add_field(name, f);
+        }
+        // split the values too large for InfluxDB in several points at the same time
+        let mut queries = if self.chunk_size > 0 && strvalue.len() > self.chunk_size {
+            let chunks = split_value(&strvalue, self.chunk_size);
+            debug!(
+                "Split value of {:?} in {} chunks",
+                measurement,
+                chunks.len()
+            );
+            chunks
+                .iter()
+                .enumerate()
+                .map(|(i, chunk)| {
+                    query
+                        .clone()
+                        .add_tag(CHUNK_INDEX_TAG, i as u64)
+                        .add_field(CHUNK_COUNT_FIELD, chunks.len() as u64)
+                        .add_field("value", *chunk)
+                })
+                .collect::<Vec<_>>()
+        } else {
+            vec![query.add_field("value", strvalue)]
+        };
+        // the update of the key's index point, written with the value (but not replicated nor archived)
+        let index_point = self
+            .key_index
+            .as_ref()
+            .and_then(|index| index.point(measurement.as_str(), &timestamp));
+        if let (true, Some(buffer)) = (buffering, &self.buffer) {
+            debug!("Buffer {:?} while InfluxDB is unreachable", measurement);
+            let priority = self.priority_of(&measurement);
+            for query in queries {
+                if let Some(archive) = &self.archive {
+                    archive.push(query.clone());
+                }
+                for replica in &self.replicas {
+                    replica.push(query.clone());
+                }
+                if !buffer.push(query, priority) {
+                    fail!(
+                        Capacity,
+                        "InfluxDB is unreachable and the buffer of storage {} is full",
+                        self.config.name
+                    )
+                }
+            }
+            if let (Some(index), Some(point)) = (&self.key_index, index_point) {
+                if buffer.push(point, priority) {
+                    index.record(measurement.as_str(), &timestamp);
+                }
+            }
+            self.record_created(measurement.as_str());
+            if let (Some(cache), Some((key, value))) = (&mut self.latest_cache, to_cache) {
+                cache.put(key, StoredData { value, timestamp });
+            }
+            if let (Some(cache), Some(key)) = (&self.entries_cache, entry) {
+                cache.update(key, timestamp);
+            }
+            if let (Some(dedup), Some((key, fingerprint))) = (&self.dedup, written) {
+                dedup.record(key, fingerprint, timestamp);
+            }
+            return Ok(StorageInsertionResult::Inserted);
+        }
+        debug!("Put {:?} with Influx query: {:?}", measurement, queries);
+        trace_field("query", &format_args!("{queries:?}"));
+        self.inject_write_fault().await?;
+        let start = Instant::now();
+        // (the index point is written in the same request, but not replicated nor archived)
+        let data_points = queries.len();
+        if let Some(point) = &index_point {
+            queries.push(point.clone());
+        }
+        let result = self.client_for(measurement.as_str()).write(&queries).await;
+        queries.truncate(data_points);
+        let latency = self.observe_query_latency(start);
+        self.log_slow_query(latency, &format_args!("{queries:?}"), None);
+        if let Err(e) = result {
+            Err(self
+                .record_error(InfluxDbError::from_client(
+                    format!("Failed to put Value for {measurement:?} in InfluxDb storage"),
+                    e,
+                ))
+                .into())
+        } else {
+            self.stats.record_write();
+            self.record_created(measurement.as_str());
+            if let (Some(index), Some(_)) = (&self.key_index, &index_point) {
+                index.record(measurement.as_str(), &timestamp);
+            }
+            // with latest only, remove the previous points (PUT or DEL)
+            if self.latest_only {
+                let (from, filter) = self.key_selection(measurement.as_str());
+                let query = InfluxRQuery::new(
+                    Delete::new(from, filter.and(Condition::time(Cmp::Lt, influx_time)))
+                        .to_string(),
+                );
+                debug!(
+                    "Remove previous points of {:?} with Influx query: {:?}",
+                    measurement, query
+                );
+                if let Err(e) = self.client_for(measurement.as_str()).query(&query).await {
+                    warn!(
+                        "Failed to remove previous points of {:?} from InfluxDb storage : {}",
+                        measurement, e
+                    )
+                }
+                for replica in &self.replicas {
+                    replica.push_query(query.clone());
+                }
+            }
+            for query in queries {
+                for replica in &self.replicas {
+                    replica.push(query.clone());
+                }
+                if let Some(archive) = &self.archive {
+                    archive.push(query);
+                }
+            }
+            if let (Some(cache), Some((key, value))) = (&mut self.latest_cache, to_cache) {
+                cache.put(key, StoredData { value, timestamp });
+            }
+            if let (Some(cache), Some(key)) = (&self.entries_cache, entry) {
+                cache.update(key, timestamp);
+            }
+            if let (Some(dedup), Some((key, fingerprint))) = (&self.dedup, written) {
+                dedup.record(key, fingerprint, timestamp);
+            }
+            Ok(StorageInsertionResult::Inserted)
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "influxdb.delete",
+            skip_all,
+            fields(
+                storage = %self.config.name,
+                measurement = Empty,
+                query = Empty,
+                latency_us = Empty,
+            )
+        )
+    )]
+    async fn delete(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        timestamp: Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        self.check_open()?;
+        self.count_metric("deletes");
+        let timestamp = self.police_timestamp(&key, timestamp)?;
+        if let Some(cache) = &mut self.latest_cache {
+            cache.remove(&key);
+        }
+        if let Some(dedup) = &self.dedup {
+            dedup.forget(&key);
+        }
+        let entry = self.entries_cache.as_ref().map(|_| key.clone());
+        let measurement = key.unwrap_or_else(|| self.none_key.clone());
+        check_key(measurement.as_str())?;
+        trace_field("measurement", &measurement);
+        self.check_health()?;
+
+        // first write the points buffered before this DELETE (or being flushed), so none of them
+        // is written after it
+        if let Some(buffer) = &self.buffer {
+            flush_buffer(&*self.client.data(), buffer, self.events.as_ref()).await;
+            if buffer.len() > 0 {
+                fail!(
+                    Connection,
+                    "Failed to write the points buffered before the deletion of {:?} in storage {}",
+                    measurement,
+                    self.config.name
+                )
+            }
+        }
+
+        // Note: assume that uhlc timestamp was generated by a clock using UNIX_EPOCH (that's the case by default)
+        let influx_time = timestamp.get_time().to_duration().as_nanos();
+
+        self.register_long_key(measurement.as_str()).await?;
+
+        // start of the deleted window, if tombstones are bounded
+        let tombstone_start = self
+            .settings()
+            .tombstone_window
+            .map(|w| influx_time.saturating_sub(w.as_nanos()));
+
+        // delete all points from the measurement that are older than this DELETE message
+        // (in case more recent PUT have been recevived un-ordered), or drop it at once,
+        // or keep them with the "mark" strategy
+        if self.delete_strategy != DeleteStrategy::Mark {
+            let (from, filter) = self.key_selection(measurement.as_str());
+            let (query, client) = match (&self.delete_strategy, tombstone_start) {
+                // (dropped with the admin credentials, like the delayed drops, unless routed)
+                (DeleteStrategy::Drop, _) => {
+                    let (m, k) = self.drop_target(measurement.as_str());
+                    let client = match self.route_of(measurement.as_str()) {
+                        Some(route) => route.client.data(),
+                        None => self.admin_client.data(),
+                    };
+                    (drop_query(&m, k.as_deref()), client)
+                }
+                (_, Some(start)) => (
+                    InfluxRQuery::new(
+                        Delete::new(
+                            from,
+                            filter
+                                .and(Condition::time(Cmp::Ge, start))
+                                .and(Condition::time(Cmp::Lt, influx_time)),
+                        )
+                        .to_string(),
+                    ),
+                    self.client_for(measurement.as_str()),
+                ),
+                (_, None) => (
+                    InfluxRQuery::new(
+                        Delete::new(from, filter.and(Condition::time(Cmp::Lt, influx_time)))
+                            .to_string(),
+                    ),
+                    self.client_for(measurement.as_str()),
+                ),
+            };
+            debug!("Delete {:?} with Influx query: {:?}", measurement, query);
+            trace_field("query", &format_args!("{query:?}"));
+            self.inject_write_fault().await?;
+            let start = Instant::now();
+            let result = client.query(&query).await;
+            let latency = self.observe_query_latency(start);
+            self.log_slow_query(latency, &format_args!("{query:?}"), None);
+            if let Err(e) = result {
+                return Err(self
+                    .record_error(InfluxDbError::from_client(
+                        format!("Failed to delete points for measurement '{measurement}' from InfluxDb storage"),
+                        e,
+                    ))
+                    .into());
+            }
+            for replica in &self.replicas {
+                replica.push_query(query.clone());
+            }
+        }
+        // dropped at once: no deletion marker, nor delayed drop
+        if self.delete_strategy == DeleteStrategy::Drop {
+            if let Some(pending) = self.pending_drops.remove(measurement.as_str()) {
+                pending.handle.defuse();
+            }
+            if let Some(events) = &self.events {
+                let (m, k) = self.drop_target(measurement.as_str());
+                events.emit(
+                    "measurement_dropped",
+                    serde_json::json!({"measurement": m, "key": k}),
+                );
+            }
+            if let Some(index) = &self.key_index {
+                let query = index.remove(measurement.as_str());
+                if let Err(e) = self.client_for(measurement.as_str()).query(&query).await {
+                    warn!(
+                        "Failed to remove {:?} from the key index of storage {}: {}",
+                        measurement, self.config.name, e
+                    );
+                }
+            }
+            if let (Some(cache), Some(key)) = (&self.entries_cache, entry) {
+                cache.update(key, timestamp);
+            }
+            return Ok(StorageInsertionResult::Deleted);
+        }
+        // store a point (with timestamp) with "delete" tag, thus we don't re-introduce an older point later
+        let mut query = self
+            .new_write_query(influx_time, measurement.as_str())
+            .add_tag("kind", "DEL")
+            .add_field("timestamp", timestamp.to_string())
+            .add_field("encoding_prefix", 0_u8)
+            .add_field("encoding_suffix", "")
+            .add_field("base64", false)
+            .add_field("value", "");
+        if let Some(start) = tombstone_start {
+            query = query.add_field("tombstone_start", start as i64);
+        }
+        if self.schema == Schema::Compat {
+            query = query.add_field(LEGACY_ENCODING_FIELD, "");
+        }
+        debug!(
+            "Mark measurement {} as deleted at time {}",
+            measurement, influx_time
+        );
+        let index_point = self
+            .key_index
+            .as_ref()
+            .and_then(|index| index.point(measurement.as_str(), &timestamp));
+        // (the index point is written in the same request, but not replicated)
+        let mut points = vec![query];
+        if let Some(point) = &index_point {
+            points.push(point.clone());
+        }
+        let result = self.client_for(measurement.as_str()).write(&points).await;
+        if let Err(e) = result {
+            return Err(self
+                .record_error(InfluxDbError::from_client(
+                    format!("Failed to mark measurement {measurement:?} as deleted"),
+                    e,
+                ))
+                .into());
+        }
+        self.stats.record_write();
+        self.record_created(measurement.as_str());
+        if let (Some(index), Some(_)) = (&self.key_index, &index_point) {
+            index.record(measurement.as_str(), &timestamp);
+        }
+        for replica in &self.replicas {
+            replica.push(points[0].clone());
+        }
+        // schedule the drop of measurement later in the future, if it's empty (not with the "mark" strategy)
+        // (forgetting the drops already run, but not those still running)
+        if self.delete_strategy == DeleteStrategy::Tombstone {
+            self.pending_drops.retain(|_, pending| {
+                pending
+                    .state
+                    .try_lock()
+                    .map_or(true, |state| *state != DropState::Done)
+            });
+            let state = Arc::new(AsyncMutex::new(DropState::Scheduled));
+            let handle = self
+                .schedule_measurement_drop(measurement.as_str(), state.clone())
+                .await;
+            if let Some(previous) = self
+                .pending_drops
+                .insert(measurement.to_string(), PendingDrop { handle, state })
+            {
+                // superseded by the new drop
+                previous.handle.defuse();
+            }
+        }
+        if let (Some(cache), Some(key)) = (&self.entries_cache, entry) {
+            cache.update(key, timestamp);
+        }
+        Ok(StorageInsertionResult::Deleted)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "influxdb.get",
+            skip_all,
+            fields(
+                storage = %self.config.name,
+                key = ?key,
+                parameters = %parameters,
+                query = Empty,
+                points = Empty,
+                latency_us = Empty,
+            )
+        )
+    )]
+    async fn get(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        parameters: &str,
+    ) -> ZResult<Vec<StoredData>> {
+        self.count_metric("gets");
+        if let Some(operation) = key
+            .as_ref()
+            .and_then(|k| k.as_str().strip_prefix(ADMIN_KEY_PREFIX))
+        {
+            if self.admin_operations {
+                return self.admin_operation(operation, parameters).await;
+            }
+        }
+        if self.allow_raw_queries && key.as_ref().map_or(false, |k| k.as_str() == RAW_QUERY_KEY) {
+            return self.raw_query(parameters).await;
+        }
+        let settings = self.settings();
+        if settings.write_only {
+            fail!(
+                QueryParse,
+                "Storage {} is write only (`{}` property): GET is not supported",
+                self.config.name,
+                PROP_STORAGE_WRITE_ONLY
+            )
+        }
+        self.check_open()?;
+        self.check_health()?;
+        let measurement = match key.clone() {
+            Some(k) => k,
+            None => self.none_key.clone(),
+        };
+        // the databases to query (more than the storage's one if some keys are routed elsewhere)
+        let clients = self.read_clients(&measurement);
+        // convert the key expression into an Influx regex (or the hashed measurement name of a too long key)
+        let regex = self.key_regex(&measurement);
+
+        let mut parameters = GetParameters::parse(parameters, settings.strict_parameters)?;
+
+        // reply the latest value from the cache if possible
+        // (the cache is per key: a key expression with wildcards may match keys that aren't cached)
+        let cacheable =
+            parameters.is_latest_only() && self.read_external.is_none() && !measurement.is_wild();
+        // with the "mark" strategy the deleted points remain: get the latest point including the deletions,
+        // and don't reply the keys whose latest point is a deletion (likewise for the latest point at "_at" time)
+        let hide_deleted = (self.delete_strategy == DeleteStrategy::Mark
+            && parameters.is_latest_only())
+            || (parameters.at.is_some() && !parameters.include_deletes);
+        if hide_deleted {
+            parameters.include_deletes = true;
+        }
+        if cacheable {
+            // the cached value might have been garbage collected since
+            let ttl = settings.ttl;
+            if let Some(data) = self
+                .latest_cache
+                .as_mut()
+                .and_then(|c| c.get(&key))
+                .filter(|d| !ttl.map_or(false, |ttl| is_expired(&d.timestamp, ttl)))
+            {
+                debug!("Reply cached latest value for {:?}", key);
+                return Ok(vec![data]);
+            }
+        }
+        if parameters.coverage {
+            return self.get_coverage(&measurement, &parameters);
+        }
+        if let (Some(agg), Some(window)) = (parameters.agg, parameters.window) {
+            return self
+                .get_aggregation(&clients, &regex, &parameters, agg, window)
+                .await;
+        }
+        let max_samples = parameters.max_samples;
+
+        // read from the coarsest downsampling tier suitable for the time range, if any
+        // (only in the storage's database)
+        let rp = match (parameters.limit, max_samples) {
+            (None, None)
+                if self
+                    .routes
+                    .iter()
+                    .all(|r| !r.key_expr.intersects(&measurement)) =>
+            {
+                self.select_tier(&parameters)
+                    .map(|t| t.retention.name.clone())
+                    .unwrap_or_default()
+            }
+            _ => String::new(),
+        };
+        let rp_prefix = match rp.as_str() {
+            "" => String::new(),
+            rp => format!("{}.", quote_ident(rp)),
+        };
+
+        // the Influx query, with clauses constructed from the parameters
+        let columns = self.get_columns();
+        let influx_query_str = match (&self.layout, max_samples) {
+            (_, Some(n)) => self.downsampling_query(&regex, &parameters, n)?,
+            (Layout::MeasurementPerKey, None) => {
+                // with the key index, select the matching measurements rather than a regex on all of them
+                let indexed = match &self.key_index {
+                    Some(index) if measurement.is_wild() => index
+                        .entries(Some(&*measurement))
+                        .into_iter()
+                        .filter(|(key, _)| key != self.none_key.as_str())
+                        .map(|(key, _)| {
+                            Source::in_retention_policy(&rp, &self.key_mapper.measurement(&key))
+                        })
+                        .collect::<Vec<_>>(),
+                    _ => Vec::new(),
+                };
+                let from = match indexed.len() {
+                    1..=MAX_INDEXED_MEASUREMENTS => Source::list(indexed),
+                    _ => Source::expr(format!("{rp_prefix}{regex}")),
+                };
+                select_from_parameters(&columns, from, &parameters, None).to_string()
+            }
+            (Layout::SingleMeasurement(m), None) => select_from_parameters(
+                &columns,
+                Source::in_retention_policy(&rp, m),
+                &parameters,
+                Some(&regex),
+            )
+            .to_string(),
+        };
+
+        debug!("Get {:?} with Influx query: {}", key, influx_query_str);
+        // for each serie, the decoded points
+        let series = match settings.max_points_per_chunk {
+            // paginate the queries returning all the points of a time range
+            Some(page_size)
+                if parameters.time_range.is_some()
+                    && parameters.limit.is_none()
+                    && max_samples.is_none() =>
+            {
+                let mut series: Vec<(String, Vec<StoredData>)> = Vec::new();
+                let mut offset = 0;
+                loop {
+                    let page = self
+                        .query_stored_data(
+                            &clients,
+                            &format!("{influx_query_str} LIMIT {page_size} OFFSET {offset}"),
+                        )
+                        .await?;
+                    // the last page of a serie has less points than the limit
+                    let is_last_page = page.iter().all(|(_, count, _)| *count < page_size);
+                    for (serie_name, _, mut data) in page {
+                        match series.iter_mut().find(|(s, _)| *s == serie_name) {
+                            Some((_, d)) => {
+                                // a value whose chunks are split by the page boundary is in both pages
+                                if let (Some(last), Some(first)) = (d.last(), data.first()) {
+                                    if last.timestamp == first.timestamp {
+                                        data.remove(0);
+                                    }
+                                }
+                                d.append(&mut data)
+                            }
+                            None => series.push((serie_name, data)),
+                        }
+                    }
+                    if is_last_page {
+                        break;
+                    }
+                    offset += page_size;
+                }
+                series
+            }
+            _ => self
+                .query_stored_data(&clients, &influx_query_str)
+                .await?
+                .into_iter()
+                .map(|(serie_name, _, data)| (serie_name, data))
+                .collect(),
+        };
+
+        let mut result = Vec::new();
+        let mut dedup = ReplyDedup::new(settings.dedup_window);
+        for (serie_name, data) in series {
+            // get the key expression from the serie name
+            let ke = match self.keyexpr_from_serie(&serie_name) {
+                Ok(k) => k,
+                Err(e) => {
+                    error!("Error replying with serie '{}' : {}", serie_name, e);
+                    continue;
+                }
+            };
+            // the regex may match the truncated measurement name of a too long key not matching the key expression
+            if ke.as_ref().map_or(false, |k| !k.intersects(&measurement)) {
+                debug!(
+                    "Serie '{}' doesn't match {}; don't reply it",
+                    serie_name, measurement
+                );
+                continue;
+            }
+            debug!("Replying {} values for {:?}", data.len(), ke);
+            for d in data {
+                if hide_deleted && d.value.encoding.suffix() == DELETE_ENCODING_SUFFIX {
+                    debug!("Latest point of {:?} is a deletion; don't reply it", ke);
+                    continue;
+                }
+                if dedup.is_duplicate(&ke, &d.timestamp) {
+                    debug!(
+                        "Skip duplicate value for {:?} with timestamp {}",
+                        ke, d.timestamp
+                    );
+                    continue;
+                }
+                result.push(d);
+            }
+        }
+        if cacheable && result.len() == 1 {
+            if let Some(cache) = &mut self.latest_cache {
+                cache.put(key, result[0].clone());
+            }
+        }
+        Ok(result)
+    }
+
+    async fn get_all_entries(&self) -> ZResult<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
+        // nothing to align with other storages in write only mode
+        if self.settings().write_only {
+            return Ok(Vec::new());
+        }
+        if let Some(cache) = &self.entries_cache {
+            if let Some(entries) = cache.entries() {
+                return Ok(entries);
+            }
+            // reconcile the cache with InfluxDB
+            let started = (Instant::now(), SystemTime::now());
+            let entries = self.latest_entries(None).await?;
+            cache.reconcile(&entries, started);
+            return Ok(cache.entries().unwrap_or(entries));
+        }
+        self.latest_entries(None).await
+    }
+}
+
+impl Drop for InfluxDbStorage {
+    fn drop(&mut self) {
+        debug!("Closing InfluxDB storage");
+        // a fallback if not shut down by the volume: blocks the dropping thread
+        let deadline = Instant::now() + self.settings().shutdown_timeout;
+        task::block_on(self.shutdown(deadline));
+    }
+}
+
+// The storage given to zenoh, shared with its volume to shut it down (see `InfluxDbVolume::shutdown`)
+struct SharedStorage(Arc<AsyncMutex<InfluxDbStorage>>);
+
+#[async_trait]
+impl Storage for SharedStorage {
+    fn get_admin_status(&self) -> serde_json::Value {
+        task::block_on(self.0.lock()).get_admin_status()
+    }
+
+    async fn put(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        value: Value,
+        timestamp: Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        self.0.lock().await.put(key, value, timestamp).await
+    }
+
+    async fn delete(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        timestamp: Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        self.0.lock().await.delete(key, timestamp).await
+    }
+
+    async fn get(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        parameters: &str,
+    ) -> ZResult<Vec<StoredData>> {
+        self.0.lock().await.get(key, parameters).await
+    }
+
+    async fn get_all_entries(&self) -> ZResult<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
+        self.0.lock().await.get_all_entries().await
+    }
+}
+
+// The lifecycle of the drop scheduled after the deletion of a key
+#[derive(PartialEq)]
+enum DropState {
+    Scheduled,
+    // by a new PUT of the key
+    Cancelled,
+    // run (whether the measurement was dropped or not)
+    Done,
+}
+
+// A scheduled drop: its state is locked while the drop runs, so a PUT cancelling it waits for its end
+struct PendingDrop {
+    handle: TimedHandle,
+    state: Arc<AsyncMutex<DropState>>,
+}
+
+// Scheduled dropping of a measurement after a timeout, if it's empty.
+// The drop itself is run by the maintenance queue.
+struct TimedMeasurementDrop {
+    client: SharedClient,
+    measurement: String,
+    // with a single measurement layout, only the series of this key are dropped
+    key: Option<String>,
+    state: Arc<AsyncMutex<DropState>>,
+    events: Option<Events>,
+    maintenance: MaintenanceQueue,
+}
+
+#[async_trait]
+impl Timed for TimedMeasurementDrop {
+    async fn run(&mut self) {
+        let name = match &self.key {
+            Some(key) => format!("drop series of {} in measurement {}", key, self.measurement),
+            None => format!("drop measurement {}", self.measurement),
+        };
+        let client = self.client.data();
+        let measurement = self.measurement.clone();
+        let key = self.key.clone();
+        let state = self.state.clone();
+        let events = self.events.clone();
+        let job = async move {
+            let mut state = state.lock().await;
+            if *state == DropState::Scheduled {
+                drop_measurement_if_empty(client, measurement, key, events).await;
+            }
+            *state = DropState::Done;
+        };
+        // waiting for a free slot of a full queue in this callback would block all the events of the
+        // shared Timer: wait in a task of its own
+        let maintenance = self.maintenance.clone();
+        task::spawn(async move { maintenance.submit_waiting(name, job).await });
+    }
+}
+
+// The query dropping a measurement, or only the series of a key with a single measurement
+fn drop_query(measurement: &str, key: Option<&str>) -> InfluxRQuery {
+    match key {
+        Some(k) => InfluxRQuery::new(format!(
+            r#"DROP SERIES FROM {} WHERE "{KEY_TAG}"={}"#,
+            quote_ident(measurement),
+            quote_literal(k)
+        )),
+        None => InfluxRQuery::new(format!("DROP MEASUREMENT {}", quote_ident(measurement))),
+    }
+}
+
+async fn drop_measurement_if_empty(
+    client: Arc<dyn InfluxClient>,
+    measurement: String,
+    key: Option<String>,
+    events: Option<Events>,
+) {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct QueryResult {
+        kind: String,
+    }
+
+    let filter = match &key {
+        Some(k) => format!(r#""{KEY_TAG}"={} AND "#, quote_literal(k)),
+        None => String::new(),
+    };
+
+    // check if there is at least 1 point without "DEL" kind in the measurement
+    let query = InfluxRQuery::new(format!(
+        r#"SELECT "kind" FROM {} WHERE {}kind!='DEL' LIMIT 1"#,
+        quote_ident(&measurement),
+        filter
+    ));
+    match client.json_query(query).await {
+        Ok(mut result) => {
+            match result.deserialize_next::<QueryResult>() {
+                Ok(qr) => {
+                    if !qr.series.is_empty() {
+                        debug!("Measurement {} contains new values inserted after deletion; don't drop it", measurement);
+                        return;
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to check if measurement '{}' is empty (can't drop it) : {}",
+                        measurement, e
+                    );
+                    return;
+                }
+            }
+        }
+        Err(e) => {
+            warn!(
+                "Failed to check if measurement '{}' is empty (can't drop it) : {}",
+                measurement, e
+            );
+            return;
+        }
+    }
+
+    // drop the measurement (or only the key's series)
+    let query = drop_query(&measurement, key.as_deref());
+    debug!(
+        "Drop measurement {} after timeout with Influx query: {:?}",
+        measurement, query
+    );
+    match client.query(&query).await {
+        Ok(_) => {
+            if let Some(events) = events {
+                events.emit(
+                    "measurement_dropped",
+                    serde_json::json!({"measurement": measurement, "key": key}),
+                );
+            }
+        }
+        Err(e) => warn!(
+            "Failed to drop measurement '{}' from InfluxDb storage : {}",
+            measurement, e
+        ),
+    }
+}
+
+// Periodic verification that the objects created by a storage still exist (drift detection),
+// repairing or warning depending on the `on_drift` policy.
+// The verification itself is run by the maintenance queue.
+struct TimedVerification {
+    client: SharedClient,
+    managed: ManagedObjects,
+    on_drift: OnDrift,
+    maintenance: MaintenanceQueue,
+}
+
+#[async_trait]
+impl Timed for TimedVerification {
+    async fn run(&mut self) {
+        let name = match &self.managed.database {
+            Some(db) => format!("verify objects of database {db}"),
+            None => "verify retention policies and continuous queries".into(),
+        };
+        self.maintenance.submit(
+            name,
+            verify_managed_objects(self.client.get(), self.managed.clone(), self.on_drift),
+        );
+    }
+}
+
+async fn verify_managed_objects(client: Client, managed: ManagedObjects, on_drift: OnDrift) {
+    if let Some(db) = &managed.database {
+        match is_db_existing(&client, db).await {
+            Ok(true) => debug!("Verified InfluxDB database {} still exists", db),
+            Ok(false) => match on_drift {
+                OnDrift::Warn => error!(
+                    "InfluxDB database {} created by zenoh doesn't exist anymore!",
+                    db
+                ),
+                OnDrift::Repair => {
+                    warn!(
+                        "InfluxDB database {} created by zenoh doesn't exist anymore: re-create it",
+                        db
+                    );
+                    if let Err(e) = create_db(&client, db, managed.storage_username.clone()).await {
+                        error!("Failed to re-create InfluxDB database {} : {}", db, e);
+                    }
+                }
+            },
+            Err(e) => warn!("Failed to verify InfluxDB database {} : {}", db, e),
+        }
+    }
+
+    // the retention policies, by database
+    let mut databases: Vec<&String> = managed
+        .retention_policies
+        .iter()
+        .map(|(db, _)| db)
+        .collect();
+    databases.sort();
+    databases.dedup();
+    for db in databases {
+        let existing = match show_retention_policies_details(&client, db).await {
+            Ok(existing) => existing,
+            Err(e) => {
+                warn!("{}", e);
+                continue;
+            }
+        };
+        for (_, rp) in managed.retention_policies.iter().filter(|(d, _)| d == db) {
+            let drift = match existing.iter().find(|e| e.name == rp.name) {
+                None => "doesn't exist anymore".to_string(),
+                Some(e) => match e.drift(rp) {
+                    Some(drift) => drift,
+                    None => {
+                        debug!(
+                            "Verified InfluxDB retention policy {} on database {}",
+                            rp.name, db
+                        );
+                        continue;
+                    }
+                },
+            };
+            match on_drift {
+                OnDrift::Warn => error!(
+                    "InfluxDB retention policy {} created by zenoh on database {} {}!",
+                    rp.name, db, drift
+                ),
+                OnDrift::Repair => {
+                    warn!(
+                        "InfluxDB retention policy {} created by zenoh on database {} {}: set it again",
+                        rp.name, db, drift
+                    );
+                    if let Err(e) = set_retention_policy(&client, db, rp).await {
+                        error!("{}", e);
+                    }
+                }
+            }
+        }
+    }
+    if managed.continuous_queries.is_empty() {
+        return;
+    }
+    let existing = match show_continuous_queries(&client).await {
+        Ok(existing) => existing,
+        Err(e) => {
+            warn!("{}", e);
+            return;
+        }
+    };
+    for cq in &managed.continuous_queries {
+        if existing.contains(&(cq.db.clone(), cq.name.clone())) {
+            debug!(
+                "Verified InfluxDB continuous query {} still exists on database {}",
+                cq.name, cq.db
+            );
+            continue;
+        }
+        match on_drift {
+            OnDrift::Warn => error!(
+                "InfluxDB continuous query {} created by zenoh on database {} doesn't exist anymore: downsampling stopped!",
+                cq.name, cq.db
+            ),
+            OnDrift::Repair => {
+                warn!(
+                    "InfluxDB continuous query {} created by zenoh on database {} doesn't exist anymore: re-create it",
+                    cq.name, cq.db
+                );
+                if let Err(e) = set_continuous_query(&client, &cq.db, &cq.name, &cq.select).await {
+                    error!("{}", e);
+                }
+            }
+        }
+    }
+}
+
+// Periodic refresh of the series cardinality of a storage's databases.
+// The queries are run by the maintenance queue.
+struct TimedCardinalityCheck {
+    clients: Vec<SharedClient>,
+    series_cardinality: Arc<AtomicU64>,
+    maintenance: MaintenanceQueue,
+}
+
+#[async_trait]
+impl Timed for TimedCardinalityCheck {
+    async fn run(&mut self) {
+        let clients: Vec<_> = self.clients.iter().map(|c| c.data()).collect();
+        let series_cardinality = self.series_cardinality.clone();
+        self.maintenance.submit(
+            format!("check series cardinality of {}", database_names(&clients)),
+            async move {
+                match get_total_series_cardinality(&clients).await {
+                    Ok(n) => series_cardinality.store(n, Ordering::Relaxed),
+                    Err(e) => warn!("{}", e),
+                }
+            },
+        );
+    }
+}
+
+// Periodic counting of the measurements, series and points of a storage's databases.
+// The queries are run by the maintenance queue.
+struct TimedStatsRefresh {
+    clients: Vec<SharedClient>,
+    // with a single measurement layout, the measurement of all the keys
+    measurement: Option<String>,
+    stats: Arc<StorageStats>,
+    maintenance: MaintenanceQueue,
+}
+
+#[async_trait]
+impl Timed for TimedStatsRefresh {
+    async fn run(&mut self) {
+        let clients: Vec<_> = self.clients.iter().map(|c| c.data()).collect();
+        let measurement = self.measurement.clone();
+        let stats = self.stats.clone();
+        self.maintenance.submit(
+            format!("count points of {}", database_names(&clients)),
+            async move {
+                let counts = async {
+                    let (mut measurements, mut series, mut points) = (0, 0, 0);
+                    for client in &clients {
+                        measurements += show_measurements(&**client)
+                            .await?
+                            .iter()
+                            .filter(|m| *m != KEY_NAMES_MEASUREMENT && *m != KEY_INDEX_MEASUREMENT)
+                            .count() as u64;
+                        series += get_series_cardinality(&**client).await?;
+                        points += count_points(&**client, measurement.clone()).await?;
+                    }
+                    ZResult::Ok((measurements, series, points))
+                };
+                match counts.await {
+                    Ok((measurements, series, points)) => {
+                        stats.set_counts(measurements, series, points)
+                    }
+                    Err(e) => warn!("{}", e),
+                }
+            },
+        );
+    }
+}
+
+// Migrate in place the points written with a previous schema version, returning the number of migrated points.
+// The fields of the current schema are written on the points (with the same time and tags), their other fields
+// being kept: the points remain readable by the previous versions in "compat" schema.
+async fn migrate_points(client: &dyn InfluxClient, measurement: Option<String>) -> ZResult<u64> {
+    #[derive(Deserialize, Debug)]
+    struct OldPoint {
+        time: String,
+        encoding: Option<String>,
+        encoding_prefix: Option<u8>,
+        schema_version: Option<u64>,
+    }
+    let from = match &measurement {
+        None => "/.*/".to_string(),
+        Some(m) => quote_ident(m),
+    };
+    let mut migrated = 0;
+    let mut offset = 0;
+    loop {
+        let query = InfluxRQuery::new(format!(
+            r#"SELECT "{LEGACY_ENCODING_FIELD}", "encoding_prefix", "{SCHEMA_VERSION_FIELD}" FROM {from} WHERE kind!='' GROUP BY * LIMIT {MIGRATION_BATCH_SIZE} OFFSET {offset}"#
+        ));
+        let series = match client.json_query(query).await {
+            Ok(mut result) => {
+                match result.deserialize_next_tagged::<HashMap<String, String>, OldPoint>() {
+                    Ok(qr) => qr.series,
+                    Err(e) => fail_client!(
+                        e,
+                        "Failed to parse points of InfluxDb database {} to migrate",
+                        client.database_name()
+                    ),
+                }
+            }
+            Err(e) => fail_client!(
+                e,
+                "Failed to read points of InfluxDb database {} to migrate",
+                client.database_name()
+            ),
+        };
+        let is_last_batch = series.iter().all(|s| s.values.len() < MIGRATION_BATCH_SIZE);
+
+        let mut queries = Vec::new();
+        for serie in series {
+            for point in serie.values {
+                if point.schema_version.is_some() {
+                    continue;
+                }
+                // the encoding fields of the current schema, from the legacy encoding
+                let encoding = match (point.encoding_prefix, &point.encoding) {
+                    (Some(_), _) => None,
+                    (None, Some(legacy)) => Some(Encoding::from(legacy.clone())),
+                    // not a zenoh point
+                    (None, None) => continue,
+                };
+                let time = match humantime::parse_rfc3339(&point.time)
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                {
+                    Some(t) => t.as_nanos(),
+                    None => {
+                        warn!(
+                            r#"Failed to decode time of Influx point {} with time="{}": not migrated"#,
+                            serie.name, point.time
+                        );
+                        continue;
+                    }
+                };
+                let mut query =
+                    InfluxWQuery::new(InfluxTimestamp::Nanoseconds(time), serie.name.as_str())
+                        .add_field(SCHEMA_VERSION_FIELD, SCHEMA_VERSION);
+                // (the tags not set on the point are given as empty by the GROUP BY)
+                for (tag, value) in serie.tags.iter().filter(|(_, v)| !v.is_empty()) {
+                    query = query.add_tag(tag.as_str(), value.as_str());
+                }
+                if let Some(encoding) = encoding {
+                    query = query
+                        .add_field("encoding_prefix", u8::from(*encoding.prefix()))
+                        .add_field("encoding_suffix", encoding.suffix());
+                }
+                queries.push(query);
+            }
+        }
+        if !queries.is_empty() {
+            let count = queries.len() as u64;
+            if let Err(e) = client.write(&queries).await {
+                fail_client!(
+                    e,
+                    "Failed to migrate points of InfluxDb database {}",
+                    client.database_name()
+                )
+            }
+            migrated += count;
+        }
+        if is_last_batch {
+            break;
+        }
+        offset += MIGRATION_BATCH_SIZE;
+    }
+    Ok(migrated)
+}
+
+// Count the PUT points of the storage (in all measurements, or in the single measurement)
+async fn count_points(client: &dyn InfluxClient, measurement: Option<String>) -> ZResult<u64> {
+    #[derive(Deserialize, Debug)]
+    struct Count {
+        count: u64,
+    }
+    let from = match &measurement {
+        None => "/.*/".to_string(),
+        Some(m) => quote_ident(m),
+    };
+    let query = InfluxRQuery::new(format!(
+        r#"SELECT count("timestamp") FROM {from} WHERE kind='PUT'"#
+    ));
+    match client.json_query(query).await {
+        Ok(mut result) => match result.deserialize_next::<Count>() {
+            Ok(qr) => Ok(qr
+                .series
+                .iter()
+                .flat_map(|s| s.values.iter())
+                .map(|c| c.count)
+                .sum()),
+            Err(e) => fail_client!(
+                e,
+                "Failed to parse points count of InfluxDb database {}",
+                client.database_name()
+            ),
+        },
+        Err(e) => fail_client!(
+            e,
+            "Failed to count points of InfluxDb database {}",
+            client.database_name()
+        ),
+    }
+}
+
+// Periodic trimming of the history, keeping only the newest `max_history` PUT points per key.
+// The trimming itself is run by the maintenance queue.
+struct TimedHistoryTrim {
+    client: SharedClient,
+    // with a single measurement layout, the measurement of all the keys
+    measurement: Option<String>,
+    max_history: u64,
+    trimmed_points: Arc<AtomicU64>,
+    maintenance: MaintenanceQueue,
+}
+
+#[async_trait]
+impl Timed for TimedHistoryTrim {
+    async fn run(&mut self) {
+        let client = self.client.data();
+        let measurement = self.measurement.clone();
+        let max_history = self.max_history;
+        let trimmed_points = self.trimmed_points.clone();
+        self.maintenance.submit(
+            format!("trim history of {}", client.database_name()),
+            async move {
+                match trim_history(&*client, measurement, max_history).await {
+                    Ok(0) => {}
+                    Ok(n) => {
+                        debug!(
+                            "Trimmed {} points from InfluxDb database {}",
+                            n,
+                            client.database_name()
+                        );
+                        trimmed_points.fetch_add(n, Ordering::Relaxed);
+                    }
+                    Err(e) => warn!("{}", e),
+                }
+            },
+        );
+    }
+}
+
+// Delete the PUT points beyond the newest `max_history` ones of each key, returning the number of deleted points.
+// The DEL points are kept, to still block the re-insertion of deleted values.
+async fn trim_history(
+    client: &dyn InfluxClient,
+    measurement: Option<String>,
+    max_history: u64,
+) -> ZResult<u64> {
+    #[derive(Deserialize, Debug)]
+    struct Count {
+        count: u64,
+    }
+    #[derive(Deserialize, Debug)]
+    struct KeyTag {
+        key: String,
+    }
+    #[derive(Deserialize, Debug)]
+    struct Point {
+        timestamp: String,
+    }
+
+    // count the PUT points per key (i.e. per measurement or per key tag)
+    let query = InfluxRQuery::new(match &measurement {
+        None => r#"SELECT count("timestamp") AS "count" FROM /.*/ WHERE kind='PUT'"#.to_string(),
+        Some(m) => format!(
+            r#"SELECT count("timestamp") AS "count" FROM {} WHERE kind='PUT' GROUP BY "{KEY_TAG}""#,
+            quote_ident(m)
+        ),
+    });
+    let counts: Vec<(String, u64)> = match client.json_query(query).await {
+        Ok(mut result) => {
+            let counts = match &measurement {
+                None => result.deserialize_next::<Count>().map(|r| {
+                    r.series
+                        .into_iter()
+                        .map(|s| (s.name, s.values.iter().map(|c| c.count).sum()))
+                        .collect()
+                }),
+                Some(_) => result.deserialize_next_tagged::<KeyTag, Count>().map(|r| {
+                    r.series
+                        .into_iter()
+                        .map(|s| (s.tags.key, s.values.iter().map(|c| c.count).sum()))
+                        .collect()
+                }),
+            };
+            counts.map_err(|e| {
+                InfluxDbError::from_client(
+                    format!(
+                        "Failed to parse points count of InfluxDb database {}",
+                        client.database_name()
+                    ),
+                    e,
+                )
+            })?
+        }
+        Err(e) => fail_client!(
+            e,
+            "Failed to count points of InfluxDb database {}",
+            client.database_name()
+        ),
+    };
+
+    let mut trimmed = 0;
+    for (serie, count) in counts.into_iter().filter(|(_, c)| *c > max_history) {
+        let (from, filter) = match &measurement {
+            None => (quote_ident(&serie), String::new()),
+            Some(m) => (
+                quote_ident(m),
+                format!(r#""{KEY_TAG}"={} AND "#, quote_literal(&serie)),
+            ),
+        };
+        // the oldest point to keep
+        let query = InfluxRQuery::new(format!(
+            r#"SELECT "timestamp" FROM {from} WHERE {filter}kind='PUT' ORDER BY time DESC LIMIT 1 OFFSET {}"#,
+            max_history - 1
+        ));
+        let oldest = match client.json_query(query).await {
+            Ok(mut result) => match result.deserialize_next::<Point>() {
+                Ok(r) => r
+                    .series
+                    .into_iter()
+                    .flat_map(|s| s.values)
+                    .find_map(|p| Timestamp::from_str(&p.timestamp).ok()),
+                Err(e) => fail_client!(e, "Failed to parse the points of {}", serie),
+            },
+            Err(e) => fail_client!(e, "Failed to get the points of {}", serie),
+        };
+        let oldest = match oldest {
+            Some(t) => t,
+            None => continue,
+        };
+        let influx_time = oldest.get_time().to_duration().as_nanos();
+        let query = InfluxRQuery::new(format!(
+            r#"DELETE FROM {from} WHERE {filter}kind='PUT' AND time < {influx_time}"#
+        ));
+        debug!("Trim history of {} with Influx query: {:?}", serie, query);
+        if let Err(e) = client.query(&query).await {
+            fail_client!(e, "Failed to trim history of {}", serie)
+        }
+        trimmed += count - max_history;
+    }
+    Ok(trimmed)
+}
+
+// Periodic check of the connectivity to InfluxDB.
+// The check itself is run by the maintenance queue.
+struct TimedHealthCheck {
+    client: SharedClient,
+    health: Arc<Health>,
+    maintenance: MaintenanceQueue,
+}
+
+#[async_trait]
+impl Timed for TimedHealthCheck {
+    async fn run(&mut self) {
+        let client = self.client.get();
+        let health = self.health.clone();
+        self.maintenance
+            .submit("check InfluxDB health".into(), async move {
+                match client.ping().await {
+                    Ok((_, version)) => {
+                        // also refresh the inventory of the databases (possibly not allowed)
+                        let databases = match show_databases(&client).await {
+                            Ok(dbs) => Some(dbs),
+                            Err(e) => {
+                                debug!("{}", e);
+                                None
+                            }
+                        };
+                        health.report_up(version, databases)
+                    }
+                    Err(e) => health.report_down(e.to_string()),
+                }
+            });
+    }
+}
+
+// Periodic re-reading of a credentials file, to apply the rotated credentials.
+// The reading itself is run by the maintenance queue.
+struct TimedCredentialsReload {
+    path: PathBuf,
+    client: SharedClient,
+    maintenance: MaintenanceQueue,
+}
+
+#[async_trait]
+impl Timed for TimedCredentialsReload {
+    async fn run(&mut self) {
+        self.maintenance.submit(
+            format!("reload credentials from {}", self.path.display()),
+            reload_credentials(self.path.clone(), self.client.clone()),
+        );
+    }
+}
+
+// Periodic flush of the points buffered while InfluxDB was unreachable.
+// The flush itself is run by the maintenance queue.
+struct TimedBufferFlush {
+    client: SharedClient,
+    health: Arc<Health>,
+    buffer: Arc<WriteBuffer>,
+    events: Option<Events>,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<metrics::StorageMetrics>,
+    maintenance: MaintenanceQueue,
+}
+
+#[async_trait]
+impl Timed for TimedBufferFlush {
+    async fn run(&mut self) {
+        if !self.health.is_up() || self.buffer.len() == 0 {
+            return;
+        }
+        let client = self.client.data();
+        let buffer = self.buffer.clone();
+        let events = self.events.clone();
+        #[cfg(feature = "metrics")]
+        let metrics = self.metrics.clone();
+        self.maintenance.submit(
+            format!("flush buffered points of {}", client.database_name()),
+            async move {
+                let _batches = flush_buffer(&*client, &buffer, events.as_ref()).await;
+                #[cfg(feature = "metrics")]
+                for _ in 0.._batches {
+                    metrics.inc("buffer_flushes");
+                }
+            },
+        );
+    }
+}
+
+// Write the buffered points by batches, until the buffer is empty or a write fails.
+// Returns the number of batches written.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "influxdb.flush",
+        skip_all,
+        fields(db = %client.database_name(), points = Empty)
+    )
+)]
+async fn flush_buffer(
+    client: &dyn InfluxClient,
+    buffer: &WriteBuffer,
+    events: Option<&Events>,
+) -> usize {
+    let _flushing = buffer.lock_flush().await;
+    let mut batches = 0;
+    let mut flushed = 0;
+    loop {
+        let points = buffer.take(BUFFER_FLUSH_BATCH_SIZE);
+        if points.is_empty() {
+            break;
+        }
+        debug!(
+            "Flush {} buffered points to InfluxDb database {}",
+            points.len(),
+            client.database_name()
+        );
+        let queries: Vec<InfluxWQuery> = points.iter().map(|(_, q)| q.clone()).collect();
+        if let Err(e) = client.write(&queries).await {
+            warn!(
+                "Failed to flush buffered points to InfluxDb database {} : {}",
+                client.database_name(),
+                e
+            );
+            if let Some(events) = events {
+                events.emit(
+                    "buffer_flush_failed",
+                    serde_json::json!({
+                        "db": client.database_name(),
+                        "points": points.len() + buffer.len(),
+                        "error": e.to_string(),
+                    }),
+                );
+            }
+            buffer.restore(points);
+            break;
+        }
+        batches += 1;
+        flushed += points.len();
+    }
+    trace_field("points", &flushed);
+    batches
+}
+
+// Record a field declared by the current tracing span (only with the "tracing" feature)
+fn trace_field(_name: &str, _value: &dyn std::fmt::Display) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record(_name, tracing::field::display(_value));
+}
+
+// Periodic garbage collection of the points older than the TTL,
+// and compaction of the deletion markers older than the tombstone TTL.
+// The collection itself is run by the maintenance queue.
+struct TimedGarbageCollection {
+    client: SharedClient,
+    // with a single measurement layout, the measurement of all the keys
+    measurement: Option<String>,
+    settings: SharedSettings,
+    last_gc: Arc<RwLock<Option<(SystemTime, Option<String>)>>>,
+    // if true, the deletion markers are not removed after `tombstone_ttl`: they're the only record of the deletions
+    // (with the "mark" delete strategy)
+    keep_markers: bool,
+    events: Option<Events>,
+    maintenance: MaintenanceQueue,
+}
+
+#[async_trait]
+impl Timed for TimedGarbageCollection {
+    async fn run(&mut self) {
+        let client = self.client.data();
+        let from = match &self.measurement {
+            None => Source::all(),
+            Some(m) => Source::measurement(m),
+        };
+        let mut queries = Vec::new();
+        // only the zenoh points (not the lookup of hashed measurement names, nor the external points)
+        let settings = self.settings.read().unwrap().clone();
+        let tombstone_ttl = settings.tombstone_ttl.filter(|_| !self.keep_markers);
+        if settings.ttl.is_none() && tombstone_ttl.is_none() {
+            return;
+        }
+        if let Some(ttl) = settings.ttl {
+            let filter = Where::new()
+                .and(Condition::tag_ne("kind", ""))
+                .and(Condition::time(
+                    Cmp::Lt,
+                    format!("now() - {}", influx_duration(ttl)),
+                ));
+            queries.push(InfluxRQuery::new(
+                Delete::new(from.clone(), filter).to_string(),
+            ));
+        }
+        if let Some(tombstone_ttl) = tombstone_ttl {
+            let filter = Where::new()
+                .and(Condition::tag_eq("kind", "DEL"))
+                .and(Condition::time(
+                    Cmp::Lt,
+                    format!("now() - {}", influx_duration(tombstone_ttl)),
+                ));
+            queries.push(InfluxRQuery::new(Delete::new(from, filter).to_string()));
+        }
+        let last_gc = self.last_gc.clone();
+        let events = self.events.clone();
+        self.maintenance.submit(
+            format!("garbage collect {}", client.database_name()),
+            async move {
+                let mut error = None;
+                for query in queries {
+                    debug!("Garbage collect with Influx query: {:?}", query);
+                    if let Err(e) = client.query(&query).await {
+                        warn!(
+                            "Failed to garbage collect the expired points of InfluxDb database {} : {}",
+                            client.database_name(),
+                            e
+                        );
+                        error = Some(e.to_string());
+                    }
+                }
+                if let Some(events) = events {
+                    events.emit(
+                        "gc",
+                        serde_json::json!({"db": client.database_name(), "error": error}),
+                    );
+                }
+                *last_gc.write().unwrap() = Some((SystemTime::now(), error));
+            },
+        );
+    }
+}
+
+// Periodic removal of the offloaded payloads no longer referenced by the points of the storage's databases
+// (deleted, trimmed, expired...). A blob is shared by the points of a same payload: it's only removed once no
+// point references it. The collection itself is run by the maintenance queue.
+struct TimedBlobCollection {
+    clients: Vec<SharedClient>,
+    // with a single measurement layout, the measurement of all the keys
+    measurement: Option<String>,
+    store: Arc<BlobStore>,
+    maintenance: MaintenanceQueue,
+}
+
+#[async_trait]
+impl Timed for TimedBlobCollection {
+    async fn run(&mut self) {
+        let clients: Vec<_> = self.clients.iter().map(|c| c.data()).collect();
+        let from = match &self.measurement {
+            None => Source::all(),
+            Some(m) => Source::measurement(m),
+        };
+        let store = self.store.clone();
+        self.maintenance.submit(
+            format!("collect the blobs of {}", database_names(&clients)),
+            async move {
+                match collect_blobs(&clients, from, &store, BLOB_GC_GRACE).await {
+                    Ok(0) => (),
+                    Ok(n) => debug!(
+                        "Removed {} unreferenced blobs of InfluxDb databases {}",
+                        n,
+                        database_names(&clients)
+                    ),
+                    Err(e) => warn!(
+                        "Failed to collect the unreferenced blobs of InfluxDb databases {} : {}",
+                        database_names(&clients),
+                        e
+                    ),
+                }
+            },
+        );
+    }
+}
+
+// Remove the blobs older than `grace` that no point of the databases references, returning their number.
+// The blobs are listed before the references: the ones of the PUTs in progress are more recent than `grace`.
+async fn collect_blobs(
+    clients: &[Arc<dyn InfluxClient>],
+    from: Source,
+    store: &BlobStore,
+    grace: Duration,
+) -> ZResult<usize> {
+    let now = SystemTime::now();
+    let mut unreferenced: Vec<String> = store
+        .list()
+        .await?
+        .into_iter()
+        .filter(|(_, modified)| {
+            now.duration_since(*modified)
+                .map_or(false, |age| age >= grace)
+        })
+        .map(|(reference, _)| reference)
+        .collect();
+    if unreferenced.is_empty() {
+        return Ok(0);
+    }
+    let referenced = get_blob_references(clients, from).await?;
+    unreferenced.retain(|reference| !referenced.contains(reference));
+    for reference in &unreferenced {
+        store.remove(reference).await?;
+    }
+    Ok(unreferenced.len())
+}
+
+// The blobs referenced by the points of the databases
+async fn get_blob_references(
+    clients: &[Arc<dyn InfluxClient>],
+    from: Source,
+) -> ZResult<HashSet<String>> {
+    #[derive(Deserialize)]
+    struct Reference {
+        distinct: String,
+    }
+    let query = Select::new(&format!("DISTINCT({})", quote_ident(BLOB_REF_FIELD)), from)
+        .filter(Where::new().and(Condition::field_ne(BLOB_REF_FIELD, "")))
+        .to_string();
+    let mut references = HashSet::new();
+    for client in clients {
+        match client.json_query(InfluxRQuery::new(query.clone())).await {
+            Ok(mut result) => match result.deserialize_next::<Reference>() {
+                Ok(qr) => references.extend(
+                    qr.series
+                        .into_iter()
+                        .flat_map(|s| s.values)
+                        .map(|r| r.distinct),
+                ),
+                Err(e) => fail_client!(
+                    e,
+                    "Failed to parse the blob references of InfluxDb database {}",
+                    client.database_name()
+                ),
+            },
+            Err(e) => fail_client!(
+                e,
+                "Failed to get the blob references of InfluxDb database {}",
+                client.database_name()
+            ),
+        }
+    }
+    Ok(references)
+}
+
+// Returns true if the timestamp is older than now - ttl
+fn is_expired(timestamp: &Timestamp, ttl: Duration) -> bool {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(now) => timestamp.get_time().to_duration() + ttl < now,
+        Err(_) => false,
+    }
+}
+
+// The names of the databases, for the logs
+fn database_names(clients: &[Arc<dyn InfluxClient>]) -> String {
+    clients
+        .iter()
+        .map(|c| c.database_name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// The series cardinality of all the databases of a storage
+async fn get_total_series_cardinality(clients: &[Arc<dyn InfluxClient>]) -> ZResult<u64> {
+    let mut total = 0;
+    for client in clients {
+        total += get_series_cardinality(&**client).await?;
+    }
+    Ok(total)
+}
+
+async fn get_series_cardinality(client: &dyn InfluxClient) -> ZResult<u64> {
+    #[derive(Deserialize, Debug)]
+    struct Cardinality {
+        count: u64,
+    }
+    let query = InfluxRQuery::new("SHOW SERIES CARDINALITY");
+    match client.json_query(query).await {
+        Ok(mut result) => match result.deserialize_next::<Cardinality>() {
+            Ok(qr) => Ok(qr
+                .series
+                .iter()
+                .flat_map(|s| s.values.iter())
+                .map(|c| c.count)
+                .sum()),
+            Err(e) => fail_client!(
+                e,
+                "Failed to parse series cardinality of InfluxDb database {}",
+                client.database_name()
+            ),
+        },
+        Err(e) => fail_client!(
+            e,
+            "Failed to get series cardinality of InfluxDb database {}",
+            client.database_name()
+        ),
+    }
+}
+
+// The types of the fields of each measurement ("integer", "unsigned", "float", "boolean" or "string")
+async fn show_field_keys(
+    client: &dyn InfluxClient,
+) -> ZResult<HashMap<String, HashMap<String, String>>> {
+    #[derive(Deserialize)]
+    struct FieldKey {
+        #[serde(rename = "fieldKey")]
+        field_key: String,
+        #[serde(rename = "fieldType")]
+        field_type: String,
+    }
+    let query = InfluxRQuery::new("SHOW FIELD KEYS");
+    debug!("List field keys with Influx query: {:?}", query);
+    match client.json_query(query).await {
+        Ok(mut result) => match result.deserialize_next::<FieldKey>() {
+            Ok(field_keys) => Ok(field_keys
+                .series
+                .into_iter()
+                .map(|s| {
+                    let types = s
+                        .values
+                        .into_iter()
+                        .map(|f| (f.field_key, f.field_type))
+                        .collect();
+                    (s.name, types)
+                })
+                .collect()),
+            Err(e) => fail_client!(
+                e,
+                "Failed to parse field keys of InfluxDb database {}",
+                client.database_name()
+            ),
+        },
+        Err(e) => fail_client!(
+            e,
+            "Failed to list field keys of InfluxDb database {}",
+            client.database_name()
+        ),
+    }
+}
+
+async fn show_measurements(client: &dyn InfluxClient) -> ZResult<Vec<String>> {
+    list_measurements(client, InfluxRQuery::new("SHOW MEASUREMENTS")).await
+}
+
+// List a page of the measurements (in alphabetical order)
+async fn show_measurements_page(
+    client: &dyn InfluxClient,
+    limit: usize,
+    offset: usize,
+) -> ZResult<Vec<String>> {
+    list_measurements(
+        client,
+        InfluxRQuery::new(format!("SHOW MEASUREMENTS LIMIT {limit} OFFSET {offset}")),
+    )
+    .await
+}
+
+async fn list_measurements(client: &dyn InfluxClient, query: InfluxRQuery) -> ZResult<Vec<String>> {
+    #[derive(Deserialize)]
+    struct Measurement {
+        name: String,
+    }
+    debug!("List measurements with Influx query: {:?}", query);
+    match client.json_query(query).await {
+        Ok(mut result) => match result.deserialize_next::<Measurement>() {
+            Ok(measurements) => Ok(measurements
+                .series
+                .into_iter()
+                .flat_map(|s| s.values)
+                .map(|m| m.name)
+                .collect()),
+            Err(e) => fail_client!(
+                e,
+                "Failed to parse list of measurements of InfluxDb database {}",
+                client.database_name()
+            ),
+        },
+        Err(e) => fail_client!(
+            e,
+            "Failed to list measurements of InfluxDb database {}",
+            client.database_name()
+        ),
+    }
+}
+
+fn generate_db_name() -> String {
+    format!("zenoh_db_{}", Uuid::new_v4().simple())
+}
+
+async fn show_databases(client: &Client) -> ZResult<Vec<String>> {
+    #[derive(Deserialize)]
+    struct Database {
+        name: String,
+    }
+    let query = InfluxRQuery::new("SHOW DATABASES");
+    debug!("List databases with Influx query: {:?}", query);
+    match client.json_query(query).await {
+        Ok(mut result) => match result.deserialize_next::<Database>() {
+            Ok(dbs) => {
+                let mut result: Vec<String> = Vec::new();
+                for serie in dbs.series {
+                    for db in serie.values {
+                        result.push(db.name);
+                    }
+                }
+                Ok(result)
+            }
+            Err(e) => fail_client!(e, "Failed to parse list of existing InfluxDb databases"),
+        },
+        Err(e) => fail_client!(e, "Failed to list existing InfluxDb databases"),
+    }
+}
+
+async fn is_db_existing(client: &Client, db_name: &str) -> ZResult<bool> {
+    let dbs = show_databases(client).await?;
+    Ok(dbs.iter().any(|e| e == db_name))
+}
+
+// Create a non-admin user for a database (to be granted access on it by create_db()),
+// returning its generated credentials
+async fn provision_user(client: &Client, db_name: &str) -> ZResult<(String, String)> {
+    let username = format!("zenoh_{db_name}");
+    let password = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let query = InfluxRQuery::new(format!(
+        "CREATE USER {} WITH PASSWORD '{}'",
+        quote_ident(&username),
+        password
+    ));
+    debug!("Create Influx user {} for database {}", username, db_name);
+    if let Err(e) = client.query(&query).await {
+        fail_client!(
+            e,
+            "Failed to create InfluxDb user '{}' for database '{}'",
+            username,
+            db_name
+        )
+    }
+    Ok((username, password))
+}
+
+async fn create_db(
+    client: &Client,
+    db_name: &str,
+    storage_username: Option<String>,
+) -> ZResult<()> {
+    let query = InfluxRQuery::new(format!("CREATE DATABASE {}", quote_ident(db_name)));
+    debug!("Create Influx database: {}", db_name);
+    if let Err(e) = client.query(&query).await {
+        fail_client!(e, "Failed to create new InfluxDb database '{}'", db_name)
+    }
+
+    // is a username is specified for storage access, grant him access to the database
+    if let Some(username) = storage_username {
+        let query = InfluxRQuery::new(format!(
+            "GRANT ALL ON {} TO {}",
+            quote_ident(db_name),
+            quote_ident(&username)
+        ));
+        debug!(
+            "Grant access to {} on Influx database: {}",
+            username, db_name
+        );
+        if let Err(e) = client.query(&query).await {
+            fail_client!(
+                e,
+                "Failed grant access to {} on Influx database '{}'",
+                username,
+                db_name
+            )
+        }
+    }
+    Ok(())
+}
+
+// A retention policy as listed by SHOW RETENTION POLICIES
+#[derive(Deserialize)]
+struct ExistingRetentionPolicy {
+    name: String,
+    // in the Go format (e.g. "168h0m0s"), "0s" for an infinite duration
+    duration: String,
+    #[serde(rename = "shardGroupDuration")]
+    shard_group_duration: String,
+    #[serde(rename = "replicaN")]
+    replica_n: u64,
+    default: bool,
+}
+
+impl ExistingRetentionPolicy {
+    // How the retention policy differs from the expected one, if it does
+    fn drift(&self, expected: &RetentionPolicy) -> Option<String> {
+        let duration = parse_go_duration(&self.duration).filter(|d| !d.is_zero());
+        if duration != expected.duration {
+            return Some(format!("has a duration of {}", self.duration));
+        }
+        if self.replica_n != expected.replication {
+            return Some(format!("has a replication of {}", self.replica_n));
+        }
+        if let Some(shard_duration) = expected.shard_duration {
+            if parse_go_duration(&self.shard_group_duration) != Some(shard_duration) {
+                return Some(format!(
+                    "has a shard duration of {}",
+                    self.shard_group_duration
+                ));
+            }
+        }
+        if expected.default && !self.default {
+            return Some("is not the default retention policy anymore".into());
+        }
+        None
+    }
+}
+
+// Parse a duration in the Go format used by InfluxDB (e.g. "1h30m0s", "500ms")
+fn parse_go_duration(s: &str) -> Option<Duration> {
+    let mut result = Duration::ZERO;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let value: f64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let nanos = match &rest[..unit] {
+            "h" => 3_600_000_000_000.0,
+            "m" => 60_000_000_000.0,
+            "s" => 1_000_000_000.0,
+            "ms" => 1_000_000.0,
+            "us" | "µs" => 1_000.0,
+            "ns" => 1.0,
+            _ => return None,
+        };
+        rest = &rest[unit..];
+        result += Duration::from_nanos((value * nanos).round() as u64);
+    }
+    Some(result)
+}
+
+async fn show_retention_policies_details(
+    client: &Client,
+    db_name: &str,
+) -> ZResult<Vec<ExistingRetentionPolicy>> {
+    let query = InfluxRQuery::new(format!(
+        "SHOW RETENTION POLICIES ON {}",
+        quote_ident(db_name)
+    ));
+    debug!("List retention policies with Influx query: {:?}", query);
+    match client.json_query(query).await {
+        Ok(mut result) => match result.deserialize_next::<ExistingRetentionPolicy>() {
+            Ok(rps) => Ok(rps.series.into_iter().flat_map(|s| s.values).collect()),
+            Err(e) => fail_client!(
+                e,
+                "Failed to parse retention policies of InfluxDb database '{}'",
+                db_name
+            ),
+        },
+        Err(e) => fail_client!(
+            e,
+            "Failed to list retention policies of InfluxDb database '{}'",
+            db_name
+        ),
+    }
+}
+
+// The (database, name) of all the continuous queries of the server
+async fn show_continuous_queries(client: &Client) -> ZResult<Vec<(String, String)>> {
+    #[derive(Deserialize)]
+    struct ContinuousQuery {
+        name: String,
+    }
+    let query = InfluxRQuery::new("SHOW CONTINUOUS QUERIES");
+    debug!("List continuous queries with Influx query: {:?}", query);
+    match client.json_query(query).await {
+        Ok(mut result) => match result.deserialize_next::<ContinuousQuery>() {
+            Ok(cqs) => Ok(cqs
+                .series
+                .into_iter()
+                .flat_map(|s| {
+                    let db = s.name;
+                    s.values.into_iter().map(move |cq| (db.clone(), cq.name))
+                })
+                .collect()),
+            Err(e) => fail_client!(e, "Failed to parse continuous queries of InfluxDb"),
+        },
+        Err(e) => fail_client!(e, "Failed to list continuous queries of InfluxDb"),
+    }
+}
+
+async fn show_retention_policies(client: &Client, db_name: &str) -> ZResult<Vec<String>> {
+    Ok(show_retention_policies_details(client, db_name)
+        .await?
+        .into_iter()
+        .map(|rp| rp.name)
+        .collect())
+}
+
+// Create the retention policy (or alter it if already existing) as the default one of the database
+async fn set_retention_policy(client: &Client, db_name: &str, rp: &RetentionPolicy) -> ZResult<()> {
+    let action = if show_retention_policies(client, db_name)
+        .await?
+        .contains(&rp.name)
+    {
+        "ALTER"
+    } else {
+        "CREATE"
+    };
+    let query = InfluxRQuery::new(format!(
+        "{action} RETENTION POLICY {} ON {} {}{}",
+        quote_ident(&rp.name),
+        quote_ident(db_name),
+        rp.clauses(),
+        if rp.default { " DEFAULT" } else { "" }
+    ));
+    debug!("Set retention policy with Influx query: {:?}", query);
+    if let Err(e) = client.query(&query).await {
+        fail_client!(
+            e,
+            "Failed to set retention policy '{}' on InfluxDb database '{}'",
+            rp.name,
+            db_name
+        )
+    }
+    Ok(())
+}
+
+// The continuous query writing the last point of each key per interval into the tier's retention policy.
+// The tags (kind, key...) are kept with "GROUP BY *", so the points can be read as the original ones.
+fn downsampling_continuous_query(
+    db_name: &str,
+    tier: &Tier,
+    layout: &Layout,
+    compat: bool,
+    numeric_values: bool,
+) -> String {
+    let mut columns: Vec<String> = GET_COLUMNS
+        .iter()
+        // (the tags are kept by the GROUP BY)
+        .filter(|c| {
+            **c != "kind" && **c != CHUNK_INDEX_TAG && (compat || **c != LEGACY_ENCODING_FIELD)
+        })
+        .map(|c| format!("last({0}) AS {0}", quote_ident(c)))
+        .collect();
+    if numeric_values {
+        columns.push(format!(
+            "mean({0}) AS {0}",
+            quote_ident(NUMERIC_VALUE_FIELD)
+        ));
+    }
+    let (into, from) = match layout {
+        Layout::MeasurementPerKey => (":MEASUREMENT".to_string(), "/.*/".to_string()),
+        Layout::SingleMeasurement(m) => (quote_ident(m), quote_ident(m)),
+    };
+    format!(
+        "SELECT {} INTO {}.{}.{into} FROM {from} GROUP BY time({}), *",
+        columns.join(", "),
+        quote_ident(db_name),
+        quote_ident(&tier.retention.name),
+        influx_duration(tier.interval)
+    )
+}
+
+// Create a continuous query, re-creating it if it already exists (possibly with another query)
+async fn set_continuous_query(
+    client: &Client,
+    db_name: &str,
+    name: &str,
+    select: &str,
+) -> ZResult<()> {
+    let create = InfluxRQuery::new(format!(
+        "CREATE CONTINUOUS QUERY {} ON {} BEGIN {select} END",
+        quote_ident(name),
+        quote_ident(db_name)
+    ));
+    debug!("Create continuous query with Influx query: {:?}", create);
+    if client.query(&create).await.is_ok() {
+        return Ok(());
+    }
+    let drop = InfluxRQuery::new(format!(
+        "DROP CONTINUOUS QUERY {} ON {}",
+        quote_ident(name),
+        quote_ident(db_name)
+    ));
+    debug!("Re-create continuous query after Influx query: {:?}", drop);
+    if let Err(e) = client.query(&drop).await {
+        fail_client!(
+            e,
+            "Failed to drop continuous query '{}' on InfluxDb database '{}'",
+            name,
+            db_name
+        )
+    }
+    if let Err(e) = client.query(&create).await {
+        fail_client!(
+            e,
+            "Failed to create continuous query '{}' on InfluxDb database '{}'",
+            name,
+            db_name
+        )
+    }
+    Ok(())
+}
+
+// The kind tag and the timestamp, encoding and base64 fields of a PUT point
+fn add_put_fields(
+    query: InfluxWQuery,
+    timestamp: &Timestamp,
+    encoding: &Encoding,
+    base64: bool,
+) -> InfluxWQuery {
+    query
+        .add_tag("kind", "PUT")
+        .add_field("timestamp", timestamp_field(timestamp))
+        .add_field("encoding_prefix", u8::from(*encoding.prefix()))
+        .add_field("encoding_suffix", encoding.suffix())
+        .add_field("base64", base64)
+}
+
+// The "timestamp" field of a point: still one String per point (owned by the field of the write query),
+// allocated at the max length of a timestamp rather than grown while formatted
+fn timestamp_field(timestamp: &Timestamp) -> String {
+    use std::fmt::Write;
+    let mut result = String::with_capacity(TIMESTAMP_FIELD_CAPACITY);
+    write!(result, "{timestamp}").unwrap();
+    result
+}
+
+// Only accept a single SELECT (without INTO) or SHOW statement (but SHOW USERS and SHOW GRANTS)
+fn check_read_only_query(query: &str) -> ZResult<()> {
+    let words = query_words(query);
+    let read_only = match words.first().map(String::as_str) {
+        Some("SELECT") => !words.iter().any(|w| w == "INTO"),
+        Some("SHOW") => !matches!(
+            words.get(1).map(String::as_str),
+            Some("USERS") | Some("GRANTS")
+        ),
+        _ => false,
+    };
+    if !read_only || query.contains(';') {
+        fail!(
+            QueryParse,
+            "Raw query {:?} is refused: only a single SELECT (without INTO) or SHOW statement (but SHOW USERS and SHOW GRANTS) is allowed",
+            query
+        )
+    }
+    Ok(())
+}
+
+// The unquoted words of an InfluxQL query, uppercased: the quoted identifiers and strings are skipped, and
+// the words are split on the quotes and punctuation too (e.g. `INTO"db"."rp"."m"` has the word INTO)
+fn query_words(query: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut escaped = false;
+    for c in query.chars() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            word.push(c.to_ascii_uppercase());
+        } else {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            if c == '"' || c == '\'' {
+                quote = Some(c);
+            }
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+// Parse a "key,timestamp,value" CSV row, the value being the rest of the row (unquoted if double-quoted)
+fn parse_csv_row(row: &str) -> Option<(&str, &str, String)> {
+    let (key, rest) = row.split_once(',')?;
+    let (timestamp, value) = rest.split_once(',')?;
+    let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(v) => v.replace("\"\"", "\""),
+        None => value.to_string(),
+    };
+    Some((key.trim(), timestamp.trim(), value))
+}
+
+// The file of the `path` parameter of an admin operation, relative to the directory of the `property` property: it
+// must have no "..", and is checked to be under the directory once canonicalized (following the symbolic links)
+fn confined_path(
+    dir: Option<&Path>,
+    path: &str,
+    property: &str,
+    operation: &str,
+) -> ZResult<PathBuf> {
+    let dir = match dir {
+        Some(dir) => dir,
+        None => fail!(
+            QueryParse,
+            "The `path` parameter of the {} operation requires the `{}` property",
+            operation,
+            property
+        ),
+    };
+    let relative = Path::new(path);
+    let file_name = match relative.file_name() {
+        Some(name) if relative.components().all(|c| matches!(c, Component::Normal(_))) => name,
+        _ => fail!(
+            QueryParse,
+            "Invalid `path` {:?} for {} operation: it must be a file relative to the `{}` directory, without \"..\"",
+            path,
+            operation,
+            property
+        ),
+    };
+    let dir = dir.canonicalize().map_err(|e| {
+        influx_error!(
+            QueryParse,
+            "Invalid `{}` directory {:?}: {}",
+            property,
+            dir,
+            e
+        )
+    })?;
+    let file = dir.join(relative);
+    // (the file may not exist yet, but its directory must)
+    let file = match file.canonicalize() {
+        Ok(file) => file,
+        Err(_) => file
+            .parent()
+            .unwrap_or(&dir)
+            .canonicalize()
+            .map_err(|e| {
+                influx_error!(
+                    QueryParse,
+                    "Invalid `path` {:?} for {} operation: {}",
+                    path,
+                    operation,
+                    e
+                )
+            })?
+            .join(file_name),
+    };
+    if !file.starts_with(&dir) {
+        fail!(
+            QueryParse,
+            "Invalid `path` {:?} for {} operation: it's not under the `{}` directory",
+            path,
+            operation,
+            property
+        )
+    }
+    Ok(file)
+}
+
+// Decode the %XX escapes (and '+' as space) of a selector parameter
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(b) => {
+                        result.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => result.push(b'%'),
+                }
+            }
+            b'+' => result.push(b' '),
+            b => result.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+// Check that a key can be used as a measurement name or tag value, and be read back unchanged
+fn check_key(key: &str) -> ZResult<()> {
+    if key.contains(|c: char| c == '\n' || c == '\r') {
+        fail!(
+            Schema,
+            "Key {:?} contains a line break, which is not supported by InfluxDB",
+            key
+        )
+    }
+    Ok(())
+}
+
+// Returns an InfluxDB regex (see https://docs.influxdata.com/influxdb/v1.8/query_language/explore-data/#regular-expressions)
+// matching the same keys than the list of key expressions. Per chunk (separated by "/"):
+//   - "**" matches any number of chunks (including none), "*" matches exactly one chunk,
+//     and "$*" matches any characters (maybe none) within a chunk.
+//   - The wildcards never match a verbatim chunk (starting with '@'), which is only matched by itself.
+//   - The other characters are literals: the regex special characters are escaped with '\'.
+// The expressions are alternatives of a group, surrounded with '/^' and '$/'.
+// E.g. "a/**/b$*" is translated into "/^(?:a(?:\/[^\/@][^\/]*)*\/b[^\/]*)$/".
+fn key_exprs_to_influx_regex(path_exprs: &[&keyexpr]) -> String {
+    // a chunk which is not verbatim
+    const CHUNK: &str = r"[^\/@][^\/]*";
+    let mut result = String::with_capacity(2 * path_exprs[0].len() + 8);
+    result.push_str("/^(?:");
+    for (i, path_expr) in path_exprs.iter().enumerate() {
+        if i != 0 {
+            result.push('|');
+        }
+        let chunks: Vec<&str> = path_expr.as_str().split('/').collect();
+        // if true, the next chunk is preceded by a "/" separator
+        let mut separator = false;
+        for (j, chunk) in chunks.iter().enumerate() {
+            match *chunk {
+                "**" if chunks.len() == 1 => {
+                    result.push_str(&format!(r"{CHUNK}(?:\/{CHUNK})*"));
+                }
+                // including the separator with the next chunk
+                "**" if j == 0 => {
+                    result.push_str(&format!(r"(?:{CHUNK}\/)*"));
+                    separator = false;
+                }
+                // including the separator with the previous chunk
+                "**" => {
+                    result.push_str(&format!(r"(?:\/{CHUNK})*"));
+                    separator = true;
+                }
+                _ => {
+                    if separator {
+                        result.push_str(r"\/");
+                    }
+                    push_chunk_regex(&mut result, chunk, CHUNK);
+                    separator = true;
+                }
+            }
+        }
+    }
+    result.push_str(")$/");
+    result
+}
+
+// Append the regex of a key expression's chunk (other than "**") to the result
+fn push_chunk_regex(result: &mut String, chunk: &str, any_chunk: &str) {
+    if chunk == "*" {
+        result.push_str(any_chunk);
+        return;
+    }
+    let mut chars = chunk.chars().peekable();
+    let mut first = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '$' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if first {
+                    // not matching a verbatim chunk
+                    result.push_str(&format!("(?:{any_chunk})?"));
+                } else {
+                    result.push_str(r"[^\/]*");
+                }
+            }
+            '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '^' | '$' | '|' => {
+                result.push('\\');
+                result.push(c);
+            }
+            _ => result.push(c),
+        }
+        first = false;
+    }
+}
+
+// Append a verbatim name to an Influx regex, escaping its special characters and separators
+fn push_escaped_regex(result: &mut String, name: &str) {
+    for c in name.chars() {
+        match c {
+            '\\' | '/' | '.' | '+' | '*' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '^' | '$'
+            | '|' => {
+                result.push('\\');
+                result.push(c);
+            }
+            _ => result.push(c),
+        }
+    }
+}
+
+// Load the mapping of the hashed measurement names to their keys (see `max_measurement_length` property)
+async fn load_long_keys(client: &Client) -> ZResult<HashMap<String, String>> {
+    #[derive(Deserialize, Debug)]
+    struct KeyName {
+        measurement: String,
+        key: String,
+    }
+
+    let query = InfluxRQuery::new(format!(
+        r#"SELECT "measurement", "key" FROM {}"#,
+        quote_ident(KEY_NAMES_MEASUREMENT)
+    ));
+    match client.json_query(query).await {
+        Ok(mut result) => match result.deserialize_next::<KeyName>() {
+            Ok(qr) => Ok(qr
+                .series
+                .into_iter()
+                .flat_map(|s| s.values)
+                .map(|k| (k.measurement, k.key))
+                .collect()),
+            Err(err) => fail_client!(err, "Failed to load the names of long keys"),
+        },
+        Err(err) => fail_client!(err, "Failed to load the names of long keys"),
+    }
+}
+
+// The expected JSon type of the points written by zenoh
+#[derive(Deserialize, Debug)]
+struct ZenohPoint {
+    // NOTE: not returned by downsampling queries
+    #[serde(default)]
+    kind: String,
+    timestamp: String,
+    // absent in points written by previous versions
+    encoding_prefix: Option<u8>,
+    encoding_suffix: Option<String>,
+    // only present in points written by previous versions, or in "compat" schema
+    encoding: Option<String>,
+    base64: bool,
+    // the algorithm compressing the (base64) value, absent or empty if not compressed
+    #[serde(default)]
+    compression: Option<String>,
+    // only present in the chunks of a value split in several points
+    #[serde(default)]
+    chunk_index: Option<String>,
+    #[serde(default)]
+    chunk_count: Option<u64>,
+    // only present (and not empty) in the points of the payloads offloaded to the blob store
+    #[serde(default)]
+    blob_ref: Option<String>,
+    #[serde(default)]
+    blob_hash: Option<String>,
+    // only present with a `value_hash` setting
+    #[serde(default)]
+    value_hash: Option<String>,
+    value: String,
+}
+
+impl ChunkedPoint for ZenohPoint {
+    fn chunk(&self) -> Option<(usize, usize)> {
+        let index = self.chunk_index.as_ref()?.parse().ok()?;
+        Some((index, self.chunk_count? as usize))
+    }
+
+    fn zenoh_timestamp(&self) -> &str {
+        &self.timestamp
+    }
+
+    fn take_value(&mut self) -> String {
+        std::mem::take(&mut self.value)
+    }
+
+    fn set_value(&mut self, value: String) {
+        self.value = value;
+    }
+}
+
+impl OffloadedPoint for ZenohPoint {
+    fn blob(&self) -> Option<BlobRef> {
+        match (&self.blob_ref, &self.blob_hash) {
+            (Some(reference), Some(hash)) if !reference.is_empty() => Some(BlobRef {
+                reference: reference.clone(),
+                hash: hash.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl HashedPoint for ZenohPoint {
+    fn value_hash(&self) -> Option<String> {
+        self.value_hash.clone()
+    }
+}
+
+// The rows of the points possibly not written by zenoh
+impl ChunkedPoint for serde_json::Map<String, serde_json::Value> {
+    fn chunk(&self) -> Option<(usize, usize)> {
+        let index = self.get(CHUNK_INDEX_TAG)?.as_str()?.parse().ok()?;
+        let count = self.get(CHUNK_COUNT_FIELD)?.as_u64()?;
+        Some((index, count as usize))
+    }
+
+    fn zenoh_timestamp(&self) -> &str {
+        self.get("timestamp").and_then(|t| t.as_str()).unwrap_or("")
+    }
+
+    fn take_value(&mut self) -> String {
+        match self.remove("value") {
+            Some(serde_json::Value::String(s)) => s,
+            _ => String::new(),
+        }
+    }
+
+    fn set_value(&mut self, value: String) {
+        self.insert("value".into(), value.into());
+    }
+}
+
+impl HashedPoint for serde_json::Map<String, serde_json::Value> {
+    fn value_hash(&self) -> Option<String> {
+        self.get(VALUE_HASH_FIELD)
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    }
+}
+
+impl OffloadedPoint for serde_json::Map<String, serde_json::Value> {
+    fn blob(&self) -> Option<BlobRef> {
+        let str_field = |name: &str| self.get(name).and_then(|v| v.as_str());
+        match (str_field(BLOB_REF_FIELD), str_field(BLOB_HASH_FIELD)) {
+            (Some(reference), Some(hash)) if !reference.is_empty() => Some(BlobRef {
+                reference: reference.to_string(),
+                hash: hash.to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+// Decode a point written by zenoh. Returns None (with a warning) if it can't be decoded
+fn decode_zenoh_point(serie_name: &str, zpoint: ZenohPoint) -> ZResult<Option<StoredData>> {
+    // get the encoding, from the current schema or else from the legacy one
+    let encoding = match (zpoint.encoding_prefix, zpoint.encoding) {
+        (Some(prefix), _) => {
+            let encoding_prefix = prefix
+                .try_into()
+                .map_err(|_| influx_error!(Serialization, "Unknown encoding {}", prefix))?;
+            match zpoint.encoding_suffix {
+                Some(suffix) if !suffix.is_empty() => {
+                    Encoding::WithSuffix(encoding_prefix, suffix.into())
+                }
+                _ => Encoding::Exact(encoding_prefix),
+            }
+        }
+        (None, Some(legacy)) => Encoding::from(legacy),
+        (None, None) => {
+            warn!(
+                r#"No encoding in Influx point {} with timestamp="{}""#,
+                serie_name, zpoint.timestamp
+            );
+            return Ok(None);
+        }
+    };
+    // mark the deletions (only returned with "_include_deletes")
+    let encoding = if zpoint.kind == "DEL" {
+        Encoding::WithSuffix(*encoding.prefix(), DELETE_ENCODING_SUFFIX.into())
+    } else {
+        encoding
+    };
+    // get the payload (the points are self-describing: decoded by any codec)
+    let payload =
+        match StringCodec.decode(zpoint.value, zpoint.base64, zpoint.compression.as_deref()) {
+            Ok(v) => ZBuf::from(v),
+            Err(e) => {
+                warn!(
+                    r#"Failed to decode zenoh Value from Influx point {} with timestamp="{}": {}"#,
+                    serie_name, zpoint.timestamp, e
+                );
+                return Ok(None);
+            }
+        };
+    // get the timestamp
+    let timestamp = match Timestamp::from_str(&zpoint.timestamp) {
+        Ok(t) => t,
+        Err(e) => {
+            warn!(
+                r#"Failed to decode zenoh Timestamp from Influx point {} with timestamp="{}": {:?}"#,
+                serie_name, zpoint.timestamp, e
+            );
+            return Ok(None);
+        }
+    };
+    let value = Value::new(payload).encoding(encoding);
+    Ok(Some(StoredData { value, timestamp }))
+}
+
+// Decode a point that might not be written by zenoh nor have all the zenoh fields.
+// With `value_field` ("read_external" mode), a point without "timestamp" field is a point written by another tool:
+// the value is the `value_field` field and the timestamp is synthesized from the Influx time.
+// If not `strict`, the missing zenoh fields get default values.
+fn decode_row(
+    serie_name: &str,
+    row: serde_json::Map<String, serde_json::Value>,
+    value_field: Option<&str>,
+    strict: bool,
+) -> ZResult<Option<StoredData>> {
+    // a point written by zenoh has a "timestamp" field
+    if let Some(timestamp) = row.get("timestamp").and_then(|t| t.as_str()) {
+        if !strict {
+            let zpoint = lenient_zenoh_point(&row, timestamp.to_string());
+            return decode_zenoh_point(serie_name, zpoint);
+        }
+        return match serde_json::from_value::<ZenohPoint>(serde_json::Value::Object(row)) {
+            Ok(zpoint) => decode_zenoh_point(serie_name, zpoint),
+            Err(e) => {
+                warn!(
+                    "Failed to decode zenoh point from serie {}: {}",
+                    serie_name, e
+                );
+                Ok(None)
+            }
+        };
+    }
+
+    let time = match row.get("time").and_then(|t| t.as_str()) {
+        Some(t) => t,
+        None => {
+            warn!("No time in Influx point from serie {}", serie_name);
+            return Ok(None);
+        }
+    };
+    let timestamp = match timestamp_from_influx_time(time) {
+        Ok(t) => t,
+        Err(e) => {
+            warn!(
+                r#"Failed to decode time of Influx point {} with time="{}": {}"#,
+                serie_name, time, e
+            );
+            return Ok(None);
+        }
+    };
+    let value_field = match value_field {
+        Some(f) => f,
+        None if !strict => {
+            let zpoint = lenient_zenoh_point(&row, timestamp.to_string());
+            return decode_zenoh_point(serie_name, zpoint);
+        }
+        None => {
+            warn!(
+                r#"No "timestamp" field in Influx point {} with time="{}""#,
+                serie_name, time
+            );
+            return Ok(None);
+        }
+    };
+    let value = match row.get(value_field) {
+        Some(serde_json::Value::String(s)) => Value::from(s.as_str()),
+        Some(serde_json::Value::Number(n)) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => Value::from(i),
+            (None, Some(f)) => Value::from(f),
+            _ => Value::from(n.to_string()),
+        },
+        Some(v) if !v.is_null() => json_value(v.clone()),
+        _ => {
+            debug!(
+                r#"No field "{}" in Influx point {} with time="{}""#,
+                value_field, serie_name, time
+            );
+            return Ok(None);
+        }
+    };
+    Ok(Some(StoredData { value, timestamp }))
+}
+
+// Build a zenoh point from a row, with default values for the missing fields ("strict_schema": false)
+fn lenient_zenoh_point(
+    row: &serde_json::Map<String, serde_json::Value>,
+    timestamp: String,
+) -> ZenohPoint {
+    let str_field = |name: &str| row.get(name).and_then(|v| v.as_str()).map(String::from);
+    let encoding = str_field(LEGACY_ENCODING_FIELD);
+    let encoding_prefix = match row.get("encoding_prefix").and_then(|v| v.as_u64()) {
+        Some(p) => u8::try_from(p).ok(),
+        // default to an empty encoding if there is none
+        None if encoding.is_none() => Some(0),
+        None => None,
+    };
+    ZenohPoint {
+        kind: str_field("kind").unwrap_or_else(|| "PUT".to_string()),
+        timestamp,
+        encoding_prefix,
+        encoding_suffix: str_field("encoding_suffix"),
+        encoding,
+        base64: row.get("base64").and_then(|v| v.as_bool()).unwrap_or(false),
+        compression: str_field(COMPRESSION_FIELD),
+        chunk_index: None,
+        chunk_count: None,
+        blob_ref: None,
+        blob_hash: None,
+        value_hash: None,
+        value: match row.get("value") {
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(v) if !v.is_null() => v.to_string(),
+            _ => String::new(),
+        },
+    }
+}
+
+// Synthesize a zenoh timestamp from the (RFC3339) time of an Influx point
+fn timestamp_from_influx_time(time: &str) -> Result<Timestamp, String> {
+    let t = humantime::parse_rfc3339(time).map_err(|e| e.to_string())?;
+    let d = t.duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?;
+    Ok(Timestamp::new(
+        NTP64::from(d),
+        *new_reception_timestamp().get_id(),
+    ))
+}
+
+// A JSON value to reply to queries
+fn json_value(json: serde_json::Value) -> Value {
+    Value::new(ZBuf::from(json.to_string().into_bytes()))
+        .encoding(Encoding::Exact(KnownEncoding::AppJson))
+}
+
+// Returns the SELECT query of a GET, with the WHERE, GROUP BY, ORDER BY and LIMIT clauses from the parameters.
+// With a single measurement layout, `key_regex` filters the key tag and the points are grouped by key.
+fn select_from_parameters(
+    columns: &str,
+    from: Source,
+    p: &GetParameters,
+    key_regex: Option<&str>,
+) -> Select {
+    let latest_only = p.time_range.is_none();
+    let (limit, order) = (p.limit, p.order);
+    let mut select = Select::new(columns, from).filter(where_from_parameters(p, key_regex));
+    if key_regex.is_some() {
+        select = select.group_by_tag(KEY_TAG);
+    }
+    if latest_only && limit.is_none() && order.is_none() {
+        //No time selection, return only latest values
+        select = select.order_by_time("DESC").limit(1);
+    } else if limit.is_some() || order.is_some() {
+        // without time selection, the limit applies to the latest values
+        let default_order = if latest_only { "DESC" } else { "ASC" };
+        select = select.order_by_time(order.unwrap_or(default_order));
+        if let Some(n) = limit {
+            select = select.limit(n);
+        } else if latest_only {
+            select = select.limit(1);
+        }
+    }
+    select
+}
+
+// Returns the WHERE clause of a GET query: excluding the deletions (unless "_include_deletes"), filtering the key tag with `key_regex`
+// (single measurement layout), and the time range of the "_time" parameter.
+fn where_from_parameters(p: &GetParameters, key_regex: Option<&str>) -> Where {
+    use zenoh::selector::{TimeBound, TimeRange};
+    let mut result = if p.include_deletes {
+        // (kind!='' excludes non-zenoh measurements, but not the deletions)
+        Where::new().and(Condition::tag_ne("kind", ""))
+    } else {
+        Where::new().and(Condition::tag_ne("kind", "DEL"))
+    };
+    if let Some(regex) = key_regex {
+        result.push(Condition::tag_matches(KEY_TAG, regex));
+    }
+    // the latest points at a time (without time range)
+    if let Some(t) = &p.at {
+        result.push(Condition::time(Cmp::Le, time_literal(t)));
+    }
+    if let Some(TimeRange(start, stop)) = &p.time_range {
+        match start {
+            TimeBound::Inclusive(t) => result.push(Condition::time(Cmp::Ge, time_literal(t))),
+            TimeBound::Exclusive(t) => result.push(Condition::time(Cmp::Gt, time_literal(t))),
+            TimeBound::Unbounded => {}
+        }
+        match stop {
+            TimeBound::Inclusive(t) => result.push(Condition::time(Cmp::Le, time_literal(t))),
+            TimeBound::Exclusive(t) => result.push(Condition::time(Cmp::Lt, time_literal(t))),
+            TimeBound::Unbounded => {}
+        }
+    }
+    result
+}
+
+// The InfluxQL literal of a time expression
+fn time_literal(t: &TimeExpr) -> String {
+    let mut result = String::new();
+    write_timeexpr(&mut result, t);
+    result
+}
+
+// Returns the GROUP BY time() interval and offset to get at most `max_samples` windows over the "_time" range
+fn downsampling_interval(p: &GetParameters, max_samples: u64) -> ZResult<(Duration, Duration)> {
+    use zenoh::selector::{TimeBound, TimeRange};
+    let (start, stop) = match &p.time_range {
+        Some(TimeRange(start, stop)) => (start, stop),
+        None => fail!(
+            QueryParse,
+            "{} selector parameter requires a _time range",
+            PARAM_MAX_SAMPLES
+        ),
+    };
+    let start = match start {
+        TimeBound::Inclusive(t) | TimeBound::Exclusive(t) => resolve_timeexpr(t),
+        TimeBound::Unbounded => fail!(
+            QueryParse,
+            "{} selector parameter requires a _time range with a start",
+            PARAM_MAX_SAMPLES
+        ),
+    };
+    let stop = match stop {
+        TimeBound::Inclusive(t) | TimeBound::Exclusive(t) => resolve_timeexpr(t),
+        TimeBound::Unbounded => SystemTime::now(),
+    };
+    let range = stop.duration_since(start).unwrap_or_default().as_nanos();
+    let n = max_samples as u128;
+    // round up, so the range fits in max_samples windows
+    let interval = ((range + n - 1) / n).max(1);
+    // align the windows on the start of the range
+    let start_ns = start
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let offset = start_ns % interval;
+    Ok((
+        Duration::from_nanos(interval as u64),
+        Duration::from_nanos(offset as u64),
+    ))
+}
+
+// Resolve a TimeExpr to an absolute time
+fn resolve_timeexpr(t: &TimeExpr) -> SystemTime {
+    match t {
+        TimeExpr::Fixed(t) => *t,
+        TimeExpr::Now { offset_secs } if *offset_secs >= 0.0 => {
+            SystemTime::now() + Duration::from_secs_f64(*offset_secs)
+        }
+        TimeExpr::Now { offset_secs } => SystemTime::now() - Duration::from_secs_f64(-*offset_secs),
+    }
+}
+
+// Format a duration as an InfluxQL duration literal, with the largest exact unit
+fn influx_duration(d: Duration) -> String {
+    let ns = d.as_nanos();
+    for (unit, unit_ns) in [
+        ("w", 604_800_000_000_000_u128),
+        ("d", 86_400_000_000_000),
+        ("h", 3_600_000_000_000),
+        ("m", 60_000_000_000),
+        ("s", 1_000_000_000),
+        ("ms", 1_000_000),
+        ("u", 1_000),
+    ] {
+        if ns % unit_ns == 0 {
+            return format!("{}{unit}", ns / unit_ns);
+        }
+    }
+    format!("{ns}ns")
+}
+
+// Write a time expression as an InfluxQL time, keeping the sub-second precision
+// (e.g. "now(-0.5s)" is written as "now() - 500ms")
+fn write_timeexpr(s: &mut String, t: &TimeExpr) {
+    use humantime::format_rfc3339_nanos;
+    use std::fmt::Write;
+    match t {
+        TimeExpr::Fixed(t) => write!(s, "'{}'", format_rfc3339_nanos(*t)),
+        TimeExpr::Now { offset_secs } if *offset_secs == 0.0 => write!(s, "now()"),
+        TimeExpr::Now { offset_secs } => {
+            let offset = influx_duration(Duration::from_secs_f64(offset_secs.abs()));
+            if *offset_secs < 0.0 {
+                write!(s, "now() - {offset}")
+            } else {
+                write!(s, "now() + {offset}")
+            }
+        }
+    }
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client::MockClient;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use zenoh_backend_traits::config::GarbageCollectionConfig;
+
+    fn timeexpr(t: &TimeExpr) -> String {
+        let mut s = String::new();
+        write_timeexpr(&mut s, t);
+        s
+    }
+
+    #[test]
+    fn write_timeexpr_now() {
+        assert_eq!(timeexpr(&TimeExpr::Now { offset_secs: 0.0 }), "now()");
+        assert_eq!(
+            timeexpr(&TimeExpr::Now { offset_secs: -60.0 }),
+            "now() - 1m"
+        );
+        assert_eq!(
+            timeexpr(&TimeExpr::Now {
+                offset_secs: 7200.0
+            }),
+            "now() + 2h"
+        );
+        assert_eq!(
+            timeexpr(&TimeExpr::Now { offset_secs: -90.0 }),
+            "now() - 90s"
+        );
+    }
+
+    #[test]
+    fn write_timeexpr_sub_second() {
+        assert_eq!(
+            timeexpr(&TimeExpr::Now { offset_secs: -0.5 }),
+            "now() - 500ms"
+        );
+        assert_eq!(
+            timeexpr(&TimeExpr::Now { offset_secs: 1.5 }),
+            "now() + 1500ms"
+        );
+        assert_eq!(
+            timeexpr(&TimeExpr::Now { offset_secs: -0.25 }),
+            "now() - 250ms"
+        );
+        assert_eq!(
+            timeexpr(&TimeExpr::Now {
+                offset_secs: -0.000001
+            }),
+            "now() - 1u"
+        );
+        assert_eq!(
+            timeexpr(&TimeExpr::from_str("now(-0.5s)").unwrap()),
+            "now() - 500ms"
+        );
+    }
+
+    #[test]
+    fn write_timeexpr_fixed() {
+        let t = UNIX_EPOCH + Duration::from_secs(1_672_531_200);
+        assert_eq!(
+            timeexpr(&TimeExpr::Fixed(t)),
+            "'2023-01-01T00:00:00.000000000Z'"
+        );
+        assert_eq!(
+            timeexpr(&TimeExpr::Fixed(t + Duration::from_nanos(500_000_001))),
+            "'2023-01-01T00:00:00.500000001Z'"
+        );
+    }
+
+    #[test]
+    fn influx_durations() {
+        assert_eq!(influx_duration(Duration::from_secs(604_800)), "1w");
+        assert_eq!(influx_duration(Duration::from_secs(86_400 * 3)), "3d");
+        assert_eq!(influx_duration(Duration::from_millis(1500)), "1500ms");
+        assert_eq!(influx_duration(Duration::from_micros(3)), "3u");
+        assert_eq!(influx_duration(Duration::from_nanos(7)), "7ns");
+    }
+
+    fn regex(key_exprs: &[&str]) -> String {
+        let key_exprs: Vec<&keyexpr> = key_exprs
+            .iter()
+            .map(|ke| keyexpr::new(*ke).unwrap())
+            .collect();
+        key_exprs_to_influx_regex(&key_exprs)
+    }
+
+    #[test]
+    fn regex_of_keys() {
+        assert_eq!(regex(&["demo/example/a"]), r"/^(?:demo\/example\/a)$/");
+        // the regex special characters are escaped
+        assert_eq!(regex(&["a.b+c(d)"]), r"/^(?:a\.b\+c\(d\))$/");
+        assert_eq!(regex(&["a|b"]), r"/^(?:a\|b)$/");
+        // verbatim chunks are matched as-is
+        assert_eq!(regex(&["demo/@v/a"]), r"/^(?:demo\/@v\/a)$/");
+        assert_eq!(
+            regex(&["a/b", "c/**"]),
+            r"/^(?:a\/b|c(?:\/[^\/@][^\/]*)*)$/"
+        );
+    }
+
+    #[test]
+    fn regex_of_wildcards() {
+        // the wildcards don't match the verbatim chunks (starting with '@')
+        assert_eq!(regex(&["demo/*/a"]), r"/^(?:demo\/[^\/@][^\/]*\/a)$/");
+        assert_eq!(regex(&["**"]), r"/^(?:[^\/@][^\/]*(?:\/[^\/@][^\/]*)*)$/");
+        // "**" matches 0 or more chunks, with their separators
+        assert_eq!(regex(&["demo/**"]), r"/^(?:demo(?:\/[^\/@][^\/]*)*)$/");
+        assert_eq!(regex(&["**/a"]), r"/^(?:(?:[^\/@][^\/]*\/)*a)$/");
+        assert_eq!(regex(&["demo/**/a"]), r"/^(?:demo(?:\/[^\/@][^\/]*)*\/a)$/");
+        // "$*" matches 0 or more characters of a chunk
+        assert_eq!(regex(&["a/b$*"]), r"/^(?:a\/b[^\/]*)$/");
+        assert_eq!(regex(&["a/$*b"]), r"/^(?:a\/(?:[^\/@][^\/]*)?b)$/");
+    }
+
+    // A chunk of a key, with the characters escaped in the regexes (but not starting with '@')
+    const KEY_CHUNK: &str = r"[a-zA-Z0-9_ .+()\[\]{}^|\\,=é日-]{1,12}";
+
+    // The InfluxQL regex of key expressions, as a Rust regex
+    fn rust_regex(key_exprs: &[&str]) -> regex::Regex {
+        let influx = regex(key_exprs);
+        let pattern = influx
+            .strip_prefix('/')
+            .and_then(|r| r.strip_suffix('/'))
+            .unwrap()
+            .replace(r"\/", "/");
+        regex::Regex::new(&pattern).unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn keys_match_their_regex(chunks in vec(KEY_CHUNK, 1..5)) {
+            let key = chunks.join("/");
+            let regex = rust_regex(&[&key]);
+            prop_assert!(regex.is_match(&key), "{} doesn't match {:?}", regex, key);
+            prop_assert!(!regex.is_match(&format!("{key}x")));
+            prop_assert!(!regex.is_match(&format!("x{key}")));
+            prop_assert!(!regex.is_match(&format!("{key}/x")));
+        }
+
+        #[test]
+        fn wildcards_match_their_keys(
+            prefix in vec(KEY_CHUNK, 1..3),
+            suffix in vec(KEY_CHUNK, 1..4),
+        ) {
+            let prefix = prefix.join("/");
+            let key = format!("{prefix}/{}", suffix.join("/"));
+            // "*" matches a single chunk, "**" any number of them
+            prop_assert_eq!(
+                rust_regex(&[&format!("{prefix}/*")]).is_match(&key),
+                suffix.len() == 1
+            );
+            prop_assert!(rust_regex(&[&format!("{prefix}/**")]).is_match(&key));
+            prop_assert!(!rust_regex(&[&format!("{prefix}x/**")]).is_match(&key));
+        }
+
+        #[test]
+        fn timestamp_fields_round_trip(time in any::<u64>()) {
+            let timestamp = Timestamp::new(NTP64(time), *new_reception_timestamp().get_id());
+            let field = timestamp_field(&timestamp);
+            prop_assert!(field.len() <= TIMESTAMP_FIELD_CAPACITY, "{}", field);
+            prop_assert_eq!(Timestamp::from_str(&field).unwrap(), timestamp);
+        }
+    }
+
+    #[test]
+    fn percent_decoding() {
+        assert_eq!(percent_decode("abc"), "abc");
+        assert_eq!(percent_decode("a%20b"), "a b");
+        assert_eq!(percent_decode("a+b"), "a b");
+        assert_eq!(percent_decode("%2Fdemo%2fa"), "/demo/a");
+        assert_eq!(percent_decode("%41"), "A");
+        assert_eq!(percent_decode("%C3%A9"), "é");
+        // the invalid escapes are kept
+        assert_eq!(percent_decode("%zz"), "%zz");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%4"), "%4");
+        // the invalid UTF-8 sequences are replaced
+        assert_eq!(percent_decode("a%FF"), "a\u{FFFD}");
+    }
+
+    #[test]
+    fn csv_rows() {
+        assert_eq!(
+            parse_csv_row("demo/a,7183000000000000000/1f,hello"),
+            Some(("demo/a", "7183000000000000000/1f", "hello".to_string()))
+        );
+        // the value is the rest of the row
+        assert_eq!(
+            parse_csv_row("demo/a,t,x,y"),
+            Some(("demo/a", "t", "x,y".to_string()))
+        );
+        assert_eq!(
+            parse_csv_row(r#"demo/a,t,"he said ""hi"", twice""#),
+            Some(("demo/a", "t", r#"he said "hi", twice"#.to_string()))
+        );
+        assert_eq!(
+            parse_csv_row(" demo/a , t ,v"),
+            Some(("demo/a", "t", "v".to_string()))
+        );
+        assert_eq!(
+            parse_csv_row("demo/a,t,"),
+            Some(("demo/a", "t", String::new()))
+        );
+        assert_eq!(parse_csv_row("demo/a,t"), None);
+        assert_eq!(parse_csv_row("demo/a"), None);
+    }
+
+    #[test]
+    fn read_only_queries() {
+        for query in [
+            "SELECT * FROM m",
+            "select * from m",
+            "  SHOW MEASUREMENTS",
+            "show databases",
+            r#"SELECT "into" FROM m"#,
+            "SELECT value FROM m WHERE kind='INTO'",
+            "SHOW FIELD KEYS FROM m",
+        ] {
+            assert!(check_read_only_query(query).is_ok(), "{query:?}");
+        }
+        for query in [
+            "",
+            "SELECT * INTO other FROM m",
+            "select * into other from m",
+            r#"SELECT * INTO"otherdb"."autogen"."m" FROM m"#,
+            r#"SELECT *INTO"m2"FROM m"#,
+            r#"SELECT "value" INTO"m2" FROM "m""#,
+            "SELECT\tvalue\nINTO m2 FROM m",
+            "SHOW USERS",
+            "show grants for u",
+            "DROP DATABASE db",
+            "DELETE FROM m",
+            "CREATE USER u WITH PASSWORD 'p'",
+            "SELECT * FROM m; DROP DATABASE db",
+            "SHOW MEASUREMENTS;",
+        ] {
+            assert!(check_read_only_query(query).is_err(), "{query:?}");
+        }
+    }
+
+    #[test]
+    fn confined_paths() {
+        let dir = std::env::temp_dir().join(format!("zenoh-influxdb-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let confined = |path: &str| {
+            confined_path(Some(dir.as_path()), path, PROP_STORAGE_EXPORT_DIR, "export")
+        };
+        let canonical = dir.canonicalize().unwrap();
+        assert_eq!(confined("a.lp").unwrap(), canonical.join("a.lp"));
+        assert_eq!(confined("sub/a.lp").unwrap(), canonical.join("sub/a.lp"));
+        for path in [
+            "",
+            "/etc/passwd",
+            "../a.lp",
+            "sub/../../a.lp",
+            "./a.lp",
+            "missing/a.lp",
+        ] {
+            assert!(confined(path).is_err(), "{path:?}");
+        }
+        // without the directory, no path is accepted
+        assert!(confined_path(None, "a.lp", PROP_STORAGE_EXPORT_DIR, "export").is_err());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("/tmp", dir.join("link")).unwrap();
+            assert!(confined("link/a.lp").is_err());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // A storage with the default properties, running its queries on the mock
+    fn mock_storage(mock: &Arc<MockClient>) -> InfluxDbStorage {
+        let client = SharedClient::new(Client::new("http://localhost:8086", "test"), None)
+            .with_data_client(mock.clone());
+        InfluxDbStorage {
+            config: StorageConfig {
+                name: "test".to_string(),
+                key_expr: OwnedKeyExpr::from_str("demo/**").unwrap(),
+                complete: false,
+                strip_prefix: None,
+                volume_id: "influxdb".to_string(),
+                volume_cfg: serde_json::json!({}),
+                garbage_collection_config: GarbageCollectionConfig::default(),
+                replica_config: None,
+            },
+            admin_client: client.clone(),
+            client,
+            routes: Vec::new(),
+            on_closure: OnClosure::DoNothing,
+            closure_protection: None,
+            provisioned_user: None,
+            created_measurements: None,
+            layout: Layout::MeasurementPerKey,
+            settings: Arc::new(RwLock::new(Arc::new(
+                Settings::from_config(&serde_json::Map::new(), "test", false).unwrap(),
+            ))),
+            max_series: None,
+            on_max_series: OnMaxSeries::Warn,
+            rate_limiter: None,
+            on_rate_limit: OnRateLimit::Refuse,
+            clock_skew: (None, None),
+            on_clock_skew: OnClockSkew::Reject,
+            conflict_policy: ConflictPolicy::LastWritten,
+            delete_strategy: DeleteStrategy::Tombstone,
+            priorities: Vec::new(),
+            series_cardinality: Arc::new(AtomicU64::new(0)),
+            max_history_per_key: None,
+            stats: Arc::new(StorageStats::new()),
+            trimmed_points: Arc::new(AtomicU64::new(0)),
+            last_gc: Arc::new(RwLock::new(None)),
+            tiers: Vec::new(),
+            archive: None,
+            replicas: Vec::new(),
+            health: Arc::new(Health::new()),
+            buffer: None,
+            breaker: None,
+            closed: false,
+            pending_drops: HashMap::new(),
+            schema: Schema::Current,
+            long_keys: None,
+            key_mapper: Arc::new(PerKeyMapper),
+            read_external: None,
+            none_key: OwnedKeyExpr::from_str(NONE_KEY).unwrap(),
+            numeric_values: false,
+            compression: None,
+            codec: Box::new(StringCodec),
+            chunk_size: DEFAULT_CHUNK_SIZE as usize,
+            alignment_concurrency: DEFAULT_ALIGNMENT_CONCURRENCY as usize,
+            offload: None,
+            value_hash: None,
+            on_corrupted: OnCorrupted::Skip,
+            latest_cache: None,
+            entries_cache: None,
+            dedup: None,
+            change_feed: None,
+            replayer: None,
+            key_index: None,
+            events: None,
+            latest_only: false,
+            admin_operations: false,
+            export_dir: None,
+            import_dir: None,
+            v2_target: None,
+            allow_raw_queries: false,
+            #[cfg(feature = "fault_injection")]
+            faults: None,
+            #[cfg(feature = "metrics")]
+            metrics: metrics::StorageMetrics::register("test"),
+            timer: Timer::default(),
+            maintenance: MaintenanceQueue::new(1, 16).unwrap(),
+        }
+    }
+
+    fn key(k: &str) -> Option<OwnedKeyExpr> {
+        Some(OwnedKeyExpr::from_str(k).unwrap())
+    }
+
+    fn later(timestamp: &Timestamp) -> Timestamp {
+        after(timestamp, Duration::from_secs(1))
+    }
+
+    fn after(timestamp: &Timestamp, duration: Duration) -> Timestamp {
+        let time = timestamp.get_time().to_duration() + duration;
+        Timestamp::new(NTP64::from(time), *timestamp.get_id())
+    }
+
+    // The points of a key written so far
+    fn written_points(mock: &MockClient, key: &str) -> Vec<String> {
+        mock.queries()
+            .into_iter()
+            .filter(|q| q.starts_with(&format!("{key},kind=PUT ")))
+            .collect()
+    }
+
+    fn is_connection_error(e: &zenoh::Error) -> bool {
+        matches!(
+            e.downcast_ref::<InfluxDbError>(),
+            Some(InfluxDbError::Connection(_))
+        )
+    }
+
+    #[test]
+    fn put_writes_a_point() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        let timestamp = new_reception_timestamp();
+        let result = task::block_on(storage.put(key("demo/a"), Value::from("hello"), timestamp));
+        assert!(matches!(result, Ok(StorageInsertionResult::Inserted)));
+
+        let queries = mock.queries();
+        assert_eq!(queries.len(), 2, "{queries:?}");
+        // the check of the deletions of the key, then the point
+        assert_eq!(
+            queries[0],
+            r#"SELECT "timestamp" FROM "demo/a" WHERE "kind"='DEL' ORDER BY time DESC LIMIT 1"#
+        );
+        assert!(queries[1].starts_with("demo/a,kind=PUT "), "{}", queries[1]);
+        assert!(queries[1].contains(&format!(r#"timestamp="{timestamp}""#)));
+        assert!(queries[1].contains(r#"value="hello""#));
+        assert!(queries[1].contains("base64=false"));
+    }
+
+    #[test]
+    fn put_after_deletion() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        let timestamp = new_reception_timestamp();
+        mock.reply(
+            r#""kind"='DEL'"#,
+            serde_json::json!([{
+                "name": "demo/a",
+                "columns": ["time", "timestamp"],
+                "values": [["2023-01-01T00:00:00Z", later(&timestamp).to_string()]],
+            }]),
+        );
+        let result = task::block_on(storage.put(key("demo/a"), Value::from("hello"), timestamp));
+        assert!(matches!(result, Ok(StorageInsertionResult::Outdated)));
+        // the point is not written
+        assert_eq!(mock.queries().len(), 1);
+    }
+
+    #[test]
+    fn outdated_put_keeps_the_scheduled_drop() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        let timestamp = new_reception_timestamp();
+        let deletion = later(&timestamp);
+        let result = task::block_on(storage.delete(key("demo/a"), deletion));
+        assert!(matches!(result, Ok(StorageInsertionResult::Deleted)));
+        assert!(storage.pending_drops.contains_key("demo/a"));
+        mock.reply(
+            r#""kind"='DEL'"#,
+            serde_json::json!([{
+                "name": "demo/a",
+                "columns": ["time", "timestamp"],
+                "values": [["2023-01-01T00:00:00Z", deletion.to_string()]],
+            }]),
+        );
+        // a PUT older than the deletion doesn't cancel the drop
+        let result = task::block_on(storage.put(key("demo/a"), Value::from("hello"), timestamp));
+        assert!(matches!(result, Ok(StorageInsertionResult::Outdated)));
+        assert!(storage.pending_drops.contains_key("demo/a"));
+        // a newer one does
+        let result =
+            task::block_on(storage.put(key("demo/a"), Value::from("hello"), later(&deletion)));
+        assert!(matches!(result, Ok(StorageInsertionResult::Inserted)));
+        assert!(storage.pending_drops.is_empty());
+    }
+
+    #[test]
+    fn rejected_put_takes_no_tokens() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        storage.rate_limiter = RateLimiter::new(Some(1), None);
+        let timestamp = new_reception_timestamp();
+        // the invalid and the outdated PUTs don't take the token of the valid one
+        let result = task::block_on(storage.put(key(NONE_KEY), Value::from("hello"), timestamp));
+        assert!(result.is_err());
+        mock.reply(
+            r#"FROM "demo/a" WHERE "kind"='DEL'"#,
+            serde_json::json!([{
+                "name": "demo/a",
+                "columns": ["time", "timestamp"],
+                "values": [["2023-01-01T00:00:00Z", later(&timestamp).to_string()]],
+            }]),
+        );
+        let result = task::block_on(storage.put(key("demo/a"), Value::from("hello"), timestamp));
+        assert!(matches!(result, Ok(StorageInsertionResult::Outdated)));
+        let result = task::block_on(storage.put(key("demo/b"), Value::from("hello"), timestamp));
+        assert!(matches!(result, Ok(StorageInsertionResult::Inserted)));
+        // the next one exceeds the limit
+        let result = task::block_on(storage.put(key("demo/b"), Value::from("hello"), timestamp));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn dedup_of_unchanged_values() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        storage.dedup = Some(PutDedup::new(Duration::from_secs(60), None));
+        let start = new_reception_timestamp();
+        let mut put = |value: &str, timestamp: Timestamp| {
+            task::block_on(storage.put(key("demo/a"), Value::from(value), timestamp)).unwrap()
+        };
+        assert!(matches!(put("on", start), StorageInsertionResult::Inserted));
+        assert!(matches!(
+            put("on", after(&start, Duration::from_secs(1))),
+            StorageInsertionResult::Outdated
+        ));
+        assert_eq!(written_points(&mock, "demo/a").len(), 1);
+        // a changed value is written
+        assert!(matches!(
+            put("off", after(&start, Duration::from_secs(2))),
+            StorageInsertionResult::Inserted
+        ));
+        // and an unchanged one once per interval (keyframe)
+        assert!(matches!(
+            put("off", after(&start, Duration::from_secs(30))),
+            StorageInsertionResult::Outdated
+        ));
+        assert!(matches!(
+            put("off", after(&start, Duration::from_secs(62))),
+            StorageInsertionResult::Inserted
+        ));
+        assert_eq!(written_points(&mock, "demo/a").len(), 3);
+        assert_eq!(storage.dedup.as_ref().unwrap().skipped(), 2);
+
+        // the latest value is forgotten on DELETE
+        let deletion = after(&start, Duration::from_secs(63));
+        task::block_on(storage.delete(key("demo/a"), deletion)).unwrap();
+        let result = task::block_on(storage.put(
+            key("demo/a"),
+            Value::from("off"),
+            after(&start, Duration::from_secs(64)),
+        ));
+        assert!(matches!(result, Ok(StorageInsertionResult::Inserted)));
+        assert_eq!(written_points(&mock, "demo/a").len(), 4);
+    }
+
+    #[test]
+    fn deadband_of_numeric_values() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        storage.dedup = Some(PutDedup::new(Duration::from_secs(60), Some(0.5)));
+        let start = new_reception_timestamp();
+        let mut put = |value: &str, secs: u64| {
+            let timestamp = after(&start, Duration::from_secs(secs));
+            task::block_on(storage.put(key("demo/a"), Value::from(value), timestamp)).unwrap()
+        };
+        assert!(matches!(put("21.0", 0), StorageInsertionResult::Inserted));
+        assert!(matches!(put("21.4", 1), StorageInsertionResult::Outdated));
+        // compared with the latest written value (21.0), not the latest received one
+        assert!(matches!(put("21.6", 2), StorageInsertionResult::Inserted));
+        assert!(matches!(put("21.2", 3), StorageInsertionResult::Outdated));
+        assert!(matches!(put("20.9", 4), StorageInsertionResult::Inserted));
+        // a keyframe once per interval
+        assert!(matches!(put("21.0", 65), StorageInsertionResult::Inserted));
+        // the non numeric values are only deduplicated when identical
+        assert!(matches!(put("n/a", 66), StorageInsertionResult::Inserted));
+        assert!(matches!(put("n/a", 67), StorageInsertionResult::Outdated));
+
+        let points = written_points(&mock, "demo/a");
+        assert_eq!(points.len(), 5, "{points:?}");
+        assert!(points[1].contains(r#"value="21.6""#));
+        assert!(points[2].contains(r#"value="20.9""#));
+    }
+
+    #[test]
+    fn conflict_policies() {
+        // 2 writers' timestamps at the same time
+        let time = new_reception_timestamp().to_string();
+        let time = &time[..time.find('/').unwrap()];
+        let first: Timestamp = format!("{time}/1").parse().unwrap();
+        let second: Timestamp = format!("{time}/2").parse().unwrap();
+        assert!(first < second);
+        for (policy, stored, put, inserted) in [
+            (ConflictPolicy::LastWritten, &second, &first, true),
+            (ConflictPolicy::NewestWins, &second, &first, false),
+            (ConflictPolicy::NewestWins, &first, &second, true),
+            (ConflictPolicy::FirstWins, &first, &second, false),
+            (ConflictPolicy::KeepBoth, &first, &second, true),
+            // the same point, whatever the policy
+            (ConflictPolicy::KeepBoth, &second, &second, false),
+        ] {
+            let mock = Arc::new(MockClient::new("test"));
+            let mut storage = mock_storage(&mock);
+            storage.conflict_policy = policy;
+            mock.reply(
+                r#""kind"='PUT' AND time ="#,
+                serde_json::json!([{
+                    "name": "demo/a",
+                    "columns": ["time", "timestamp"],
+                    "values": [["2023-01-01T00:00:00Z", stored.to_string()]],
+                }]),
+            );
+            let result = task::block_on(storage.put(key("demo/a"), Value::from("hello"), *put));
+            let points = written_points(&mock, "demo/a");
+            if inserted {
+                assert!(matches!(result, Ok(StorageInsertionResult::Inserted)));
+                assert_eq!(points.len(), 1);
+                // only the kept conflicting point is tagged with its writer
+                let tagged = points[0].contains(&format!(",{CONFLICT_TAG}={}", put.get_id()));
+                assert_eq!(tagged, storage.conflict_policy == ConflictPolicy::KeepBoth);
+            } else {
+                assert!(matches!(result, Ok(StorageInsertionResult::Outdated)));
+                assert!(points.is_empty());
+            }
+            // the stored points are only queried with a policy
+            let checked = mock.queries().iter().any(|q| q.contains(r#""kind"='PUT'"#));
+            assert_eq!(
+                checked,
+                storage.conflict_policy != ConflictPolicy::LastWritten
+            );
+        }
+    }
+
+    #[test]
+    fn clock_skew_policing() {
+        let now = new_reception_timestamp();
+        let future = after(&now, Duration::from_secs(7200));
+        let past = Timestamp::new(
+            NTP64::from(now.get_time().to_duration() - Duration::from_secs(7200)),
+            *now.get_id(),
+        );
+
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        storage.clock_skew = (
+            Some(Duration::from_secs(60)),
+            Some(Duration::from_secs(3600)),
+        );
+        for timestamp in [future, past] {
+            let result =
+                task::block_on(storage.put(key("demo/a"), Value::from("hello"), timestamp));
+            assert!(result.is_err());
+            assert!(task::block_on(storage.delete(key("demo/a"), timestamp)).is_err());
+        }
+        assert!(mock.queries().is_empty());
+        let result = task::block_on(storage.put(key("demo/a"), Value::from("hello"), now));
+        assert!(matches!(result, Ok(StorageInsertionResult::Inserted)));
+
+        // clamped to the closest bound
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        storage.clock_skew = (
+            Some(Duration::from_secs(60)),
+            Some(Duration::from_secs(3600)),
+        );
+        storage.on_clock_skew = OnClockSkew::Clamp;
+        let result = task::block_on(storage.put(key("demo/a"), Value::from("hello"), future));
+        assert!(matches!(result, Ok(StorageInsertionResult::Inserted)));
+        let points = written_points(&mock, "demo/a");
+        assert_eq!(points.len(), 1);
+        assert!(!points[0].contains(&future.to_string()));
+        let time: u128 = points[0].rsplit(' ').next().unwrap().parse().unwrap();
+        let bound = now.get_time().to_duration() + Duration::from_secs(60);
+        assert!(time > now.get_time().to_duration().as_nanos());
+        assert!(time <= (bound + Duration::from_secs(1)).as_nanos());
+    }
+
+    #[test]
+    fn put_failure() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        mock.fail_next(1);
+        let result = task::block_on(storage.put(
+            key("demo/a"),
+            Value::from("hello"),
+            new_reception_timestamp(),
+        ));
+        assert!(is_connection_error(&result.unwrap_err()));
+        // not retried by the storage
+        assert_eq!(mock.queries().len(), 1);
+        assert!(matches!(
+            task::block_on(storage.put(
+                key("demo/a"),
+                Value::from("hello"),
+                new_reception_timestamp()
+            )),
+            Ok(StorageInsertionResult::Inserted)
+        ));
+    }
+
+    #[test]
+    fn get_latest() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        let timestamp = new_reception_timestamp();
+        mock.reply(
+            "SELECT",
+            serde_json::json!([{
+                "name": "demo/a",
+                "columns": ["time", "kind", "timestamp", "encoding_prefix", "encoding_suffix", "base64", "value"],
+                "values": [[
+                    "2023-01-01T00:00:00Z",
+                    "PUT",
+                    timestamp.to_string(),
+                    u8::from(KnownEncoding::TextPlain),
+                    "",
+                    false,
+                    "hello",
+                ]],
+            }]),
+        );
+        let data = task::block_on(storage.get(key("demo/*"), "")).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].timestamp, timestamp);
+        assert_eq!(&*data[0].value.payload.contiguous(), b"hello");
+        assert_eq!(
+            data[0].value.encoding,
+            Encoding::Exact(KnownEncoding::TextPlain)
+        );
+
+        let queries = mock.queries();
+        assert_eq!(queries.len(), 1, "{queries:?}");
+        assert!(
+            queries[0].contains(r"FROM /^(?:demo\/[^\/@][^\/]*)$/"),
+            "{}",
+            queries[0]
+        );
+        assert!(queries[0].ends_with("ORDER BY time DESC LIMIT 1"));
+    }
+
+    #[test]
+    fn compressed_values() {
+        for compression in [Compression::Zstd, Compression::Lz4] {
+            let mock = Arc::new(MockClient::new("test"));
+            let mut storage = mock_storage(&mock);
+            let name = compression.name();
+            storage.compression = Some(compression);
+            storage.codec = Box::new(CompressedCodec {
+                compression: Compression::from_name(name).unwrap(),
+                threshold: 16,
+                inner: Box::new(StringCodec),
+            });
+            let timestamp = new_reception_timestamp();
+            let payload = "hello ".repeat(100);
+            for (k, value) in [("demo/a", payload.as_str()), ("demo/b", "hello")] {
+                let result = task::block_on(storage.put(key(k), Value::from(value), timestamp));
+                assert!(matches!(result, Ok(StorageInsertionResult::Inserted)));
+            }
+            // only the payloads larger than the threshold are compressed (the field is always written)
+            let small = &written_points(&mock, "demo/b")[0];
+            assert!(small.contains(r#"compression="""#), "{small}");
+            assert!(small.contains(r#"value="hello""#));
+            let large = &written_points(&mock, "demo/a")[0];
+            assert!(
+                large.contains(&format!(r#"compression="{name}""#)),
+                "{large}"
+            );
+            assert!(large.contains("base64=true"));
+            let value = large.split(r#"value=""#).nth(1).unwrap();
+            let value = &value[..value.find('"').unwrap()];
+            assert!(value.len() < payload.len());
+
+            // and decompressed on GET
+            mock.reply(
+                "SELECT",
+                serde_json::json!([{
+                    "name": "demo/a",
+                    "columns": ["time", "kind", "timestamp", "encoding_prefix", "encoding_suffix", "base64", "value", "compression"],
+                    "values": [[
+                        "2023-01-01T00:00:00Z",
+                        "PUT",
+                        timestamp.to_string(),
+                        u8::from(KnownEncoding::TextPlain),
+                        "",
+                        true,
+                        value,
+                        name,
+                    ]],
+                }]),
+            );
+            let data = task::block_on(storage.get(key("demo/*"), "")).unwrap();
+            assert_eq!(data.len(), 1);
+            assert_eq!(&*data[0].value.payload.contiguous(), payload.as_bytes());
+        }
+    }
+
+    #[test]
+    fn latest_cache_of_keys_only() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        storage.latest_cache = Some(LatestCache::new(8));
+        let timestamp = new_reception_timestamp();
+        mock.reply(
+            "SELECT",
+            serde_json::json!([{
+                "name": "demo/a",
+                "columns": ["time", "kind", "timestamp", "encoding_prefix", "encoding_suffix", "base64", "value"],
+                "values": [[
+                    "2023-01-01T00:00:00Z",
+                    "PUT",
+                    timestamp.to_string(),
+                    u8::from(KnownEncoding::TextPlain),
+                    "",
+                    false,
+                    "hello",
+                ]],
+            }]),
+        );
+        // a single reply to a wildcard GET is not cached, nor read from the cache
+        for _ in 0..2 {
+            let data = task::block_on(storage.get(key("demo/*"), "")).unwrap();
+            assert_eq!(data.len(), 1);
+        }
+        assert_eq!(mock.queries().len(), 2);
+        assert_eq!(storage.latest_cache.as_ref().unwrap().len(), 0);
+
+        for _ in 0..2 {
+            let data = task::block_on(storage.get(key("demo/a"), "")).unwrap();
+            assert_eq!(data.len(), 1);
+            assert_eq!(data[0].timestamp, timestamp);
+        }
+        assert_eq!(mock.queries().len(), 3);
+        assert_eq!(storage.latest_cache.as_ref().unwrap().len(), 1);
+        // a cached key doesn't answer a wildcard GET
+        task::block_on(storage.get(key("demo/*"), "")).unwrap();
+        assert_eq!(mock.queries().len(), 4);
+    }
+
+    #[test]
+    fn wildcard_get_of_long_keys() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        let long_keys = Arc::new(HashingMapper {
+            max_length: 64,
+            names: RwLock::new(HashMap::new()),
+        });
+        // a 3 chunks key, whose hashed measurement name has only 2 chunks
+        let long_key = format!("demo/{}/a", "y".repeat(64));
+        let name = long_keys.hashed_name(&long_key).unwrap();
+        long_keys
+            .names
+            .write()
+            .unwrap()
+            .insert(name.clone(), long_key.clone());
+        storage.key_mapper = long_keys.clone();
+        storage.long_keys = Some(long_keys);
+        let timestamp = new_reception_timestamp();
+        mock.reply(
+            "SELECT",
+            serde_json::json!([{
+                "name": name,
+                "columns": ["time", "kind", "timestamp", "encoding_prefix", "encoding_suffix", "base64", "value"],
+                "values": [[
+                    "2023-01-01T00:00:00Z",
+                    "PUT",
+                    timestamp.to_string(),
+                    u8::from(KnownEncoding::TextPlain),
+                    "",
+                    false,
+                    "hello",
+                ]],
+            }]),
+        );
+
+        // the hashed measurement name is selected, though the key expression's regex doesn't match it
+        let data = task::block_on(storage.get(key("demo/*/a"), "")).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].timestamp, timestamp);
+        let queries = mock.queries();
+        let escaped = name.replace('/', r"\/");
+        assert!(
+            queries[0].contains(&format!(r"FROM /^(?:demo\/[^\/@][^\/]*\/a|{escaped})$/")),
+            "{}",
+            queries[0]
+        );
+
+        // the regex of a key expression not matching the long key matches its hashed measurement name:
+        // its points are not replied
+        let data = task::block_on(storage.get(key("demo/*"), "")).unwrap();
+        assert!(data.is_empty());
+        let queries = mock.queries();
+        assert!(!queries[1].contains(&escaped), "{}", queries[1]);
+    }
+
+    #[test]
+    fn chunked_value_in_single_measurement() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        storage.layout = Layout::SingleMeasurement("zenoh".to_string());
+        storage.key_mapper = Arc::new(SingleMeasurementMapper {
+            measurement: "zenoh".to_string(),
+        });
+        storage.chunk_size = 5;
+        let timestamp = new_reception_timestamp();
+        let result =
+            task::block_on(storage.put(key("demo/a"), Value::from("hello world!"), timestamp));
+        assert!(matches!(result, Ok(StorageInsertionResult::Inserted)));
+        // a point per chunk, in the shared measurement with the key tag
+        let queries = mock.queries();
+        let lines = queries.last().unwrap().lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3, "{lines:?}");
+        for (i, line) in lines.iter().enumerate() {
+            assert!(line.starts_with("zenoh,"), "{line}");
+            assert!(line.contains("key=demo/a"), "{line}");
+            assert!(line.contains(&format!("chunk_index={i}")), "{line}");
+            assert!(line.contains("chunk_count=3"), "{line}");
+        }
+
+        // the latest point is the last chunk: the other ones are queried, grouped by key too
+        let columns = serde_json::json!([
+            "time",
+            "kind",
+            "timestamp",
+            "encoding_prefix",
+            "encoding_suffix",
+            "base64",
+            "chunk_index",
+            "chunk_count",
+            "value"
+        ]);
+        let chunk = |i: usize, value: &str| {
+            serde_json::json!([
+                "2023-01-01T00:00:00Z",
+                "PUT",
+                timestamp.to_string(),
+                u8::from(KnownEncoding::TextPlain),
+                "",
+                false,
+                i.to_string(),
+                3,
+                value
+            ])
+        };
+        mock.reply(
+            r#""chunk_index"!=''"#,
+            serde_json::json!([{
+                "name": "zenoh",
+                "tags": {"key": "demo/a"},
+                "columns": columns,
+                "values": [chunk(0, "hello"), chunk(1, " worl"), chunk(2, "d!")],
+            }]),
+        );
+        mock.reply(
+            "LIMIT 1",
+            serde_json::json!([{
+                "name": "zenoh",
+                "tags": {"key": "demo/a"},
+                "columns": columns,
+                "values": [chunk(2, "d!")],
+            }]),
+        );
+        let data = task::block_on(storage.get(key("demo/a"), "")).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].timestamp, timestamp);
+        assert_eq!(&*data[0].value.payload.contiguous(), b"hello world!");
+        let queries = mock.queries();
+        let chunks_query = queries.last().unwrap();
+        assert!(
+            chunks_query.contains(r#"FROM "zenoh" WHERE "key"='demo/a' AND "chunk_index"!=''"#),
+            "{chunks_query}"
+        );
+        assert!(
+            chunks_query.ends_with(r#"GROUP BY "key""#),
+            "{chunks_query}"
+        );
+    }
+
+    #[test]
+    fn get_failure() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        mock.fail_next(1);
+        let result = task::block_on(storage.get(key("demo/a"), ""));
+        assert!(is_connection_error(&result.unwrap_err()));
+        // no points
+        assert!(task::block_on(storage.get(key("demo/a"), ""))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn delete_writes_a_marker() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        let timestamp = new_reception_timestamp();
+        let influx_time = timestamp.get_time().to_duration().as_nanos();
+        let result = task::block_on(storage.delete(key("demo/a"), timestamp));
+        assert!(matches!(result, Ok(StorageInsertionResult::Deleted)));
+
+        let queries = mock.queries();
+        assert_eq!(queries.len(), 2, "{queries:?}");
+        // the older points are deleted, then the deletion marker is written
+        assert_eq!(
+            queries[0],
+            format!(r#"DELETE FROM "demo/a" WHERE time < {influx_time}"#)
+        );
+        assert!(queries[1].starts_with("demo/a,kind=DEL "), "{}", queries[1]);
+        assert!(queries[1].contains(&format!(r#"timestamp="{timestamp}""#)));
+        // the measurement is dropped later, if empty
+        assert!(storage.pending_drops.contains_key("demo/a"));
+    }
+
+    #[test]
+    fn delete_failure() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        mock.fail_next(1);
+        let result = task::block_on(storage.delete(key("demo/a"), new_reception_timestamp()));
+        assert!(is_connection_error(&result.unwrap_err()));
+        // no deletion marker is written, nor the drop scheduled
+        assert_eq!(mock.queries().len(), 1);
+        assert!(storage.pending_drops.is_empty());
+        assert_eq!(
+            storage.stats.to_json()["last_error"]["kind"],
+            serde_json::json!("connection")
+        );
+    }
+
+    #[test]
+    fn compact_requires_a_horizon() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        assert!(task::block_on(storage.admin_operation("compact", "")).is_err());
+        assert!(mock.queries().is_empty());
+
+        task::block_on(storage.admin_operation("compact", "before=now(-1h)")).unwrap();
+        let queries = mock.queries();
+        assert_eq!(queries.len(), 1, "{queries:?}");
+        assert!(
+            queries[0].starts_with("DELETE FROM /.*/ WHERE kind='DEL' AND time < now() - "),
+            "{}",
+            queries[0]
+        );
+
+        // the deletion markers are the only record of the deletions with the "mark" strategy
+        storage.delete_strategy = DeleteStrategy::Mark;
+        assert!(task::block_on(storage.admin_operation("compact", "before=now(-1h)")).is_err());
+        assert_eq!(mock.queries().len(), 1);
+    }
+
+    #[test]
+    fn undelete_removes_the_markers() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        assert!(task::block_on(storage.admin_operation("undelete", "")).is_err());
+        assert!(
+            task::block_on(storage.admin_operation("undelete", "key=demo/a&at=now()")).is_err()
+        );
+        assert!(mock.queries().is_empty());
+
+        mock.reply(
+            "count(",
+            serde_json::json!([{
+                "name": "demo/a",
+                "columns": ["time", "count"],
+                "values": [["1970-01-01T00:00:00Z", 2]],
+            }]),
+        );
+        let data = task::block_on(storage.admin_operation("undelete", "key=demo/a")).unwrap();
+        let result: serde_json::Value =
+            serde_json::from_slice(&data[0].value.payload.contiguous()).unwrap();
+        assert_eq!(result, serde_json::json!({ "undeleted": 2 }));
+        let queries = mock.queries();
+        assert_eq!(queries.len(), 2, "{queries:?}");
+        // the deletion markers are counted, then removed
+        assert!(queries[0].starts_with(r#"SELECT count("timestamp") FROM "#));
+        assert!(queries[0].ends_with(" WHERE kind='DEL'"), "{}", queries[0]);
+        assert!(queries[1].starts_with("DELETE FROM "));
+        assert!(queries[1].ends_with(" WHERE kind='DEL'"), "{}", queries[1]);
+
+        // only the ones older than a time
+        task::block_on(storage.admin_operation("undelete", "key=demo/a&before=now(-1h)")).unwrap();
+        let queries = mock.queries();
+        assert_eq!(queries.len(), 4, "{queries:?}");
+        assert!(
+            queries[3].contains(" WHERE kind='DEL' AND time < now() - "),
+            "{}",
+            queries[3]
+        );
+    }
+
+    #[test]
+    fn trim_all_databases() {
+        let mock = Arc::new(MockClient::new("test"));
+        let routed = Arc::new(MockClient::new("routed"));
+        let mut storage = mock_storage(&mock);
+        storage.routes.push(Route {
+            key_expr: OwnedKeyExpr::from_str("demo/routed/**").unwrap(),
+            client: SharedClient::new(Client::new("http://localhost:8086", "routed"), None)
+                .with_data_client(routed.clone()),
+            admin_client: SharedClient::new(Client::new("http://localhost:8086", "routed"), None),
+        });
+        let entries = EntriesCache::new(Duration::from_secs(60));
+        entries.reconcile(&[], (Instant::now(), SystemTime::now()));
+        storage.entries_cache = Some(entries);
+
+        task::block_on(storage.admin_operation("trim", "before=now(-1h)")).unwrap();
+        for queries in [mock.queries(), routed.queries()] {
+            assert_eq!(queries.len(), 1, "{queries:?}");
+            assert!(
+                queries[0].starts_with("DELETE FROM /.*/ WHERE kind!='' AND time < now() - "),
+                "{}",
+                queries[0]
+            );
+        }
+        // the trimmed keys are listed again from InfluxDB
+        assert!(storage.entries_cache.as_ref().unwrap().entries().is_none());
+
+        // likewise for compact
+        task::block_on(storage.admin_operation("compact", "before=now(-1h)")).unwrap();
+        assert_eq!(mock.queries().len(), 2);
+        assert_eq!(routed.queries().len(), 2);
+    }
+
+    #[test]
+    fn delete_flushes_the_buffer() {
+        let mock = Arc::new(MockClient::new("test"));
+        let mut storage = mock_storage(&mock);
+        let buffer = Arc::new(WriteBuffer::new(16));
+        storage.buffer = Some(buffer.clone());
+        let timestamp = new_reception_timestamp();
+        let influx_time = timestamp.get_time().to_duration().as_nanos();
+        let buffered = InfluxWQuery::new(InfluxTimestamp::Nanoseconds(influx_time - 1), "demo/a")
+            .add_tag("kind", "PUT")
+            .add_field("value", "buffered");
+        assert!(buffer.push(buffered.clone(), Priority::Normal));
+
+        // the DELETE fails if the buffered points can't be written first, and they're kept
+        mock.fail_next(1);
+        let result = task::block_on(storage.delete(key("demo/a"), timestamp));
+        assert!(is_connection_error(&result.unwrap_err()));
+        assert_eq!(mock.queries().len(), 1);
+        assert_eq!(buffer.len(), 1);
+
+        let result = task::block_on(storage.delete(key("demo/a"), timestamp));
+        assert!(matches!(result, Ok(StorageInsertionResult::Deleted)));
+        let queries = mock.queries();
+        assert_eq!(queries.len(), 4, "{queries:?}");
+        // the buffered point is written before the older points are deleted
+        assert!(queries[1].starts_with("demo/a,kind=PUT "), "{}", queries[1]);
+        assert!(queries[1].contains(r#"value="buffered""#));
+        assert_eq!(
+            queries[2],
+            format!(r#"DELETE FROM "demo/a" WHERE time < {influx_time}"#)
+        );
+        assert!(queries[3].starts_with("demo/a,kind=DEL "), "{}", queries[3]);
+        assert_eq!(buffer.len(), 0);
+    }
+
+    // Wait for a job of the maintenance queue to be run
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "Maintenance job not run");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    // Run the scheduled drop of the measurement of demo/a, returning the queries
+    fn run_drop(mock: &Arc<MockClient>) -> Vec<String> {
+        let storage = mock_storage(mock);
+        let state = Arc::new(AsyncMutex::new(DropState::Scheduled));
+        let mut timed = TimedMeasurementDrop {
+            client: storage.client.clone(),
+            measurement: "demo/a".to_string(),
+            key: None,
+            state: state.clone(),
+            events: None,
+            maintenance: storage.maintenance.clone(),
+        };
+        task::block_on(timed.run());
+        wait_for(|| *task::block_on(state.lock()) == DropState::Done);
+        mock.queries()
+    }
+
+    #[test]
+    fn scheduled_drop() {
+        let mock = Arc::new(MockClient::new("test"));
+        assert_eq!(
+            run_drop(&mock),
+            vec![
+                r#"SELECT "kind" FROM "demo/a" WHERE kind!='DEL' LIMIT 1"#.to_string(),
+                r#"DROP MEASUREMENT "demo/a""#.to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn scheduled_drop_of_a_rewritten_key() {
+        let mock = Arc::new(MockClient::new("test"));
+        mock.reply(
+            "kind!='DEL'",
+            serde_json::json!([{
+                "name": "demo/a",
+                "columns": ["time", "kind"],
+                "values": [["2023-01-01T00:00:00Z", "PUT"]],
+            }]),
+        );
+        // a point was written after the deletion: the measurement is kept
+        assert_eq!(run_drop(&mock).len(), 1);
+    }
+
+    #[test]
+    fn scheduled_drop_with_a_full_queue() {
+        let mock = Arc::new(MockClient::new("test"));
+        let storage = mock_storage(&mock);
+        // the single maintenance thread is busy until the release, and its queue is full
+        let maintenance = MaintenanceQueue::new(1, 1).unwrap();
+        let (start, started) = async_std::channel::bounded::<()>(1);
+        let (release, released) = async_std::channel::bounded::<()>(1);
+        maintenance.submit("busy".to_string(), async move {
+            let _ = start.send(()).await;
+            let _ = released.recv().await;
+        });
+        task::block_on(started.recv()).unwrap();
+        maintenance.submit("queued".to_string(), async {});
+        let state = Arc::new(AsyncMutex::new(DropState::Scheduled));
+        let mut timed = TimedMeasurementDrop {
+            client: storage.client.clone(),
+            measurement: "demo/a".to_string(),
+            key: None,
+            state: state.clone(),
+            events: None,
+            maintenance: maintenance.clone(),
+        };
+        // the Timer's callback returns without waiting for a free slot
+        let now = Instant::now();
+        task::block_on(timed.run());
+        assert!(now.elapsed() < Duration::from_secs(1));
+        assert!(*task::block_on(state.lock()) == DropState::Scheduled);
+
+        task::block_on(release.send(())).unwrap();
+        wait_for(|| *task::block_on(state.lock()) == DropState::Done);
+        assert_eq!(mock.queries().len(), 2);
+    }
+
+    #[test]
+    fn cardinality_of_all_databases() {
+        let maintenance = MaintenanceQueue::new(1, 16).unwrap();
+        let mut clients = Vec::new();
+        for (db, count) in [("test", 3), ("routed", 4)] {
+            let mock = Arc::new(MockClient::new(db));
+            mock.reply(
+                "CARDINALITY",
+                serde_json::json!([{"name": db, "columns": ["count"], "values": [[count]]}]),
+            );
+            clients.push(
+                SharedClient::new(Client::new("http://localhost:8086", db), None)
+                    .with_data_client(mock),
+            );
+        }
+        let series_cardinality = Arc::new(AtomicU64::new(0));
+        let mut timed = TimedCardinalityCheck {
+            clients,
+            series_cardinality: series_cardinality.clone(),
+            maintenance,
+        };
+        task::block_on(timed.run());
+        wait_for(|| series_cardinality.load(Ordering::Relaxed) == 7);
+    }
+
+    #[test]
+    fn garbage_collection() {
+        let mock = Arc::new(MockClient::new("test"));
+        let storage = mock_storage(&mock);
+        let mut settings = Settings::from_config(&serde_json::Map::new(), "test", false).unwrap();
+        settings.ttl = Some(Duration::from_secs(3600));
+        settings.tombstone_ttl = Some(Duration::from_secs(86_400));
+        let last_gc = Arc::new(RwLock::new(None));
+        let mut timed = TimedGarbageCollection {
+            client: storage.client.clone(),
+            measurement: None,
+            settings: Arc::new(RwLock::new(Arc::new(settings))),
+            last_gc: last_gc.clone(),
+            keep_markers: false,
+            events: None,
+            maintenance: storage.maintenance.clone(),
+        };
+        task::block_on(timed.run());
+        wait_for(|| last_gc.read().unwrap().is_some());
+        assert_eq!(
+            mock.queries(),
+            vec![
+                r#"DELETE FROM /.*/ WHERE "kind"!='' AND time < now() - 1h"#.to_string(),
+                r#"DELETE FROM /.*/ WHERE "kind"='DEL' AND time < now() - 1d"#.to_string(),
+            ]
+        );
+        assert_eq!(last_gc.read().unwrap().as_ref().unwrap().1, None);
+
+        // a failed query is reported as the error of the last collection
+        *last_gc.write().unwrap() = None;
+        mock.fail_next(1);
+        task::block_on(timed.run());
+        wait_for(|| last_gc.read().unwrap().is_some());
+        assert!(last_gc.read().unwrap().as_ref().unwrap().1.is_some());
+
+        // with the "mark" delete strategy, the deletion markers are kept
+        *last_gc.write().unwrap() = None;
+        timed.keep_markers = true;
+        task::block_on(timed.run());
+        wait_for(|| last_gc.read().unwrap().is_some());
+        assert_eq!(
+            mock.queries().last().unwrap(),
+            r#"DELETE FROM /.*/ WHERE "kind"!='' AND time < now() - 1h"#
+        );
+        assert_eq!(mock.queries().len(), 5);
+    }
+
+    #[test]
+    fn blob_collection() {
+        let dir = std::env::temp_dir().join(format!("zenoh-influxdb-{}", Uuid::new_v4()));
+        let store = BlobStore::Directory(dir.clone());
+        let mock = Arc::new(MockClient::new("test"));
+        let clients: Vec<Arc<dyn InfluxClient>> = vec![mock.clone()];
+        task::block_on(async {
+            let kept = store.put(b"kept").await.unwrap();
+            let removed = store.put(b"removed").await.unwrap();
+            mock.reply(
+                "DISTINCT",
+                serde_json::json!([{
+                    "name": "demo/a",
+                    "columns": ["time", "distinct"],
+                    "values": [["1970-01-01T00:00:00Z", kept.reference]],
+                }]),
+            );
+            // the recent blobs are kept, referenced or not
+            let grace = Duration::from_secs(3600);
+            assert_eq!(
+                collect_blobs(&clients, Source::all(), &store, grace)
+                    .await
+                    .unwrap(),
+                0
+            );
+            assert!(mock.queries().is_empty());
+
+            let collected = collect_blobs(&clients, Source::all(), &store, Duration::ZERO).await;
+            assert_eq!(collected.unwrap(), 1);
+            assert!(mock.queries()[0].contains(r#"WHERE "blob_ref"!=''"#));
+            assert!(store.get(&kept).await.is_ok());
+            assert!(store.get(&removed).await.is_err());
+
+            // nothing is removed if the references can't be read
+            mock.fail_next(1);
+            assert!(
+                collect_blobs(&clients, Source::all(), &store, Duration::ZERO)
+                    .await
+                    .is_err()
+            );
+            assert!(store.get(&kept).await.is_ok());
+        });
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
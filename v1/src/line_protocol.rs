@@ -0,0 +1,299 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::InfluxDbError;
+use influxdb::{Query, Timestamp as InfluxTimestamp, Type, WriteQuery as InfluxWQuery};
+use std::collections::HashMap;
+use std::time::UNIX_EPOCH;
+
+// The max size of a chunk of exported lines replied over zenoh
+pub(crate) const EXPORT_CHUNK_SIZE: usize = 1024 * 1024;
+
+// The header of an annotated line protocol export (as read by `influx -import`)
+pub(crate) fn export_header(databases: &[String]) -> String {
+    let mut header = String::from("# DDL\n");
+    for db in databases {
+        header.push_str(&format!("CREATE DATABASE \"{db}\"\n"));
+    }
+    header.push_str("# DML\n");
+    header
+}
+
+// The annotation switching the database of the following lines
+pub(crate) fn context_database(db: &str) -> String {
+    format!("# CONTEXT-DATABASE:{db}\n")
+}
+
+// Convert a point read with `SELECT * ... GROUP BY *` into a line of line protocol, with its fields typed as given
+// by `SHOW FIELD KEYS` ("integer", "unsigned", "float", "boolean" or "string"). Returns None if it has no field.
+pub(crate) fn to_line(
+    measurement: &str,
+    tags: &HashMap<String, String>,
+    row: &serde_json::Map<String, serde_json::Value>,
+    field_types: Option<&HashMap<String, String>>,
+) -> Result<Option<String>, InfluxDbError> {
+    let time = match row.get("time").and_then(|t| t.as_str()) {
+        Some(time) => time,
+        None => fail!(
+            Serialization,
+            "No time on Influx point of measurement {}",
+            measurement
+        ),
+    };
+    let time = match humantime::parse_rfc3339(time)
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    {
+        Some(t) => t.as_nanos(),
+        None => fail!(
+            Serialization,
+            r#"Failed to decode time of Influx point {} with time="{}""#,
+            measurement,
+            time
+        ),
+    };
+    let mut query = InfluxWQuery::new(InfluxTimestamp::Nanoseconds(time), measurement);
+    // (the tags not set on the point are given as empty by the GROUP BY)
+    for (tag, value) in tags.iter().filter(|(_, v)| !v.is_empty()) {
+        query = query.add_tag(tag.as_str(), value.as_str());
+    }
+    let mut has_fields = false;
+    for (name, value) in row.iter().filter(|(name, _)| name.as_str() != "time") {
+        let field_type = field_types.and_then(|t| t.get(name)).map(String::as_str);
+        let field = match value {
+            serde_json::Value::Null => continue,
+            serde_json::Value::Bool(b) => Type::Boolean(*b),
+            serde_json::Value::String(s) => Type::Text(s.clone()),
+            serde_json::Value::Number(n) => match (field_type, n.as_i64(), n.as_u64()) {
+                (Some("integer"), Some(i), _) => Type::SignedInteger(i),
+                (Some("unsigned"), _, Some(u)) => Type::UnsignedInteger(u),
+                _ => match n.as_f64() {
+                    Some(f) => Type::Float(f),
+                    None => continue,
+                },
+            },
+            v => Type::Text(v.to_string()),
+        };
+        query = query.add_field(name.as_str(), field);
+        has_fields = true;
+    }
+    if !has_fields {
+        return Ok(None);
+    }
+    match query.build() {
+        Ok(q) => Ok(Some(q.get())),
+        Err(e) => fail!(
+            Serialization,
+            "Failed to export Influx point of measurement {}: {}",
+            measurement,
+            e
+        ),
+    }
+}
+
+// A point parsed from a line of line protocol (its time is not needed to decode a zenoh point)
+pub(crate) struct Line {
+    pub(crate) measurement: String,
+    pub(crate) tags: HashMap<String, String>,
+    pub(crate) fields: serde_json::Map<String, serde_json::Value>,
+}
+
+// Split an annotated line protocol content, read line by line, in lines of line protocol (the string field values
+// may contain newlines), without the empty lines, comments and DDL statements
+#[derive(Default)]
+pub(crate) struct LineSplitter {
+    // the line being read, continued on the next line within a string field value (or after an escaping '\\')
+    pending: String,
+    // the section of the pending line: 0 for the measurement and tags, 1 for the fields, 2 for the time
+    section: usize,
+    escaped: bool,
+    in_string: bool,
+    in_ddl: bool,
+}
+
+impl LineSplitter {
+    // Read the next line of the content (without its newline), returning the line of line protocol it completes
+    pub(crate) fn push(&mut self, line: &str) -> Option<String> {
+        if self.in_string || self.escaped {
+            self.pending.push('\n');
+            self.escaped = false;
+        }
+        for c in line.chars() {
+            match c {
+                _ if self.escaped => self.escaped = false,
+                '\\' => self.escaped = true,
+                '"' if self.section == 1 => self.in_string = !self.in_string,
+                ' ' if !self.in_string => self.section += 1,
+                _ => {}
+            }
+        }
+        self.pending.push_str(line);
+        if self.in_string || self.escaped {
+            return None;
+        }
+        self.section = 0;
+        let line = std::mem::take(&mut self.pending);
+        let line = line.trim();
+        match line {
+            "# DDL" => self.in_ddl = true,
+            "# DML" => self.in_ddl = false,
+            _ => {}
+        }
+        if line.is_empty() || line.starts_with('#') || self.in_ddl {
+            return None;
+        }
+        Some(line.to_string())
+    }
+
+    // The last line, if the content ends within a string field value
+    pub(crate) fn finish(self) -> Option<String> {
+        let line = self.pending.trim();
+        (!line.is_empty()).then(|| line.to_string())
+    }
+}
+
+// Parse a line of line protocol: "<measurement>[,<tag>=<value>...] <field>=<value>[,<field>=<value>...] [<time>]"
+pub(crate) fn parse_line(line: &str) -> Result<Line, InfluxDbError> {
+    let (series, rest) = match split_once_unescaped(line, ' ', false) {
+        (series, Some(rest)) => (series, rest),
+        (_, None) => fail!(Serialization, "No fields in line {:?}", line),
+    };
+    let (fields, _time) = split_once_unescaped(rest, ' ', true);
+
+    let mut series = split_unescaped(series, ',', false).into_iter();
+    let measurement = unescape(series.next().unwrap_or_default());
+    if measurement.is_empty() {
+        fail!(Serialization, "No measurement in line {:?}", line)
+    }
+    let mut tags = HashMap::new();
+    for tag in series {
+        match split_once_unescaped(tag, '=', false) {
+            (name, Some(value)) => tags.insert(unescape(name), unescape(value)),
+            (_, None) => fail!(Serialization, "Invalid tag {:?} in line {:?}", tag, line),
+        };
+    }
+    let mut values = serde_json::Map::new();
+    for field in split_unescaped(fields, ',', true) {
+        let value = match split_once_unescaped(field, '=', true) {
+            (name, Some(value)) => parse_field_value(value).map(|v| (unescape(name), v)),
+            (_, None) => None,
+        };
+        match value {
+            Some((name, value)) => values.insert(name, value),
+            None => fail!(
+                Serialization,
+                "Invalid field {:?} in line {:?}",
+                field,
+                line
+            ),
+        };
+    }
+    Ok(Line {
+        measurement,
+        tags,
+        fields: values,
+    })
+}
+
+// Split at the first separator not escaped (nor in a double-quoted string if `quotes`)
+fn split_once_unescaped(s: &str, separator: char, quotes: bool) -> (&str, Option<&str>) {
+    let mut escaped = false;
+    let mut in_string = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' if quotes => in_string = !in_string,
+            c if c == separator && !in_string => return (&s[..i], Some(&s[i + c.len_utf8()..])),
+            _ => {}
+        }
+    }
+    (s, None)
+}
+
+fn split_unescaped(s: &str, separator: char, quotes: bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = Some(s);
+    while let Some(s) = rest {
+        let (part, tail) = split_once_unescaped(s, separator, quotes);
+        parts.push(part);
+        rest = tail;
+    }
+    parts
+}
+
+fn unescape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next)) if matches!(next, ',' | '=' | ' ' | '"' | '\\') => {
+                result.push(next);
+                chars.next();
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+// Parse a field value: a double-quoted string, a boolean, an integer ("<n>i"), an unsigned ("<n>u") or a float
+fn parse_field_value(s: &str) -> Option<serde_json::Value> {
+    if let Some(s) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return Some(unescape(s).into());
+    }
+    match s {
+        "t" | "T" | "true" | "True" | "TRUE" => Some(true.into()),
+        "f" | "F" | "false" | "False" | "FALSE" => Some(false.into()),
+        _ => match (s.strip_suffix('i'), s.strip_suffix('u')) {
+            (Some(i), _) => i.parse::<i64>().ok().map(Into::into),
+            (_, Some(u)) => u.parse::<u64>().ok().map(Into::into),
+            _ => s
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(serde_json::Value::Number),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(content: &str) -> Vec<String> {
+        let mut splitter = LineSplitter::default();
+        let mut lines: Vec<String> = content
+            .split('\n')
+            .filter_map(|l| splitter.push(l))
+            .collect();
+        lines.extend(splitter.finish());
+        lines
+    }
+
+    #[test]
+    fn split_annotated_lines() {
+        let content = "# DDL\nCREATE DATABASE \"db\"\n# DML\n# CONTEXT-DATABASE: db\n\n\
+            m,kind=PUT value=\"a b\nc\" 1\nm value=\"\\\"\" 2\nm\\\n2 value=1 3\nm value=\"unterminated";
+        assert_eq!(
+            split(content),
+            vec![
+                "m,kind=PUT value=\"a b\nc\" 1",
+                "m value=\"\\\"\" 2",
+                "m\\\n2 value=1 3",
+                "m value=\"unterminated",
+            ]
+        );
+    }
+}
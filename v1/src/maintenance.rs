@@ -0,0 +1,100 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use async_std::channel::{bounded, Receiver, SendError, Sender, TrySendError};
+use log::{debug, warn};
+use std::future::Future;
+use std::pin::Pin;
+use zenoh::Result as ZResult;
+use zenoh_core::zerror;
+
+// default number of threads running the maintenance jobs
+pub(crate) const DEFAULT_MAINTENANCE_THREADS: usize = 1;
+// default max number of maintenance jobs waiting to be run
+pub(crate) const DEFAULT_MAINTENANCE_QUEUE_SIZE: usize = 256;
+
+type MaintenanceJob = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+// A queue of background jobs (measurement drops, GC, health checks...) run by a small pool of
+// dedicated threads, so that maintenance work doesn't run on the tasks of the put/get operations.
+// The threads stop once all clones of the queue are dropped.
+#[derive(Clone)]
+pub(crate) struct MaintenanceQueue {
+    tx: Sender<(String, MaintenanceJob)>,
+}
+
+impl MaintenanceQueue {
+    pub(crate) fn new(threads: usize, capacity: usize) -> ZResult<MaintenanceQueue> {
+        let (tx, rx) = bounded(capacity.max(1));
+        for i in 0..threads.max(1) {
+            let rx = rx.clone();
+            std::thread::Builder::new()
+                .name(format!("influxdb-maintenance-{i}"))
+                .spawn(move || async_std::task::block_on(run_jobs(rx)))
+                .map_err(|e| zerror!("Failed to spawn InfluxDB maintenance thread: {}", e))?;
+        }
+        Ok(MaintenanceQueue { tx })
+    }
+
+    // Enqueue a periodic job. If the queue is full the job is dropped: maintenance is best-effort
+    // and will be re-attempted by the next periodic run.
+    pub(crate) fn submit<F>(&self, name: String, job: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let job: MaintenanceJob = Box::pin(job);
+        match self.tx.try_send((name, job)) {
+            Ok(()) => {}
+            Err(TrySendError::Full((name, _))) => {
+                warn!(
+                    "InfluxDB maintenance queue is full; dropping job '{}'",
+                    name
+                )
+            }
+            Err(TrySendError::Closed((name, _))) => {
+                warn!(
+                    "InfluxDB maintenance queue is closed; dropping job '{}'",
+                    name
+                )
+            }
+        }
+    }
+
+    // Enqueue a one-shot job (scheduled drop, migration...), that would never be re-attempted if
+    // dropped: if the queue is full, wait for a free slot.
+    pub(crate) async fn submit_waiting<F>(&self, name: String, job: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let job: MaintenanceJob = Box::pin(job);
+        if let Err(SendError((name, _))) = self.tx.send((name, job)).await {
+            warn!(
+                "InfluxDB maintenance queue is closed; dropping job '{}'",
+                name
+            )
+        }
+    }
+
+    // Stop accepting jobs: the threads stop once the already queued jobs are run
+    pub(crate) fn close(&self) {
+        self.tx.close();
+    }
+}
+
+async fn run_jobs(rx: Receiver<(String, MaintenanceJob)>) {
+    while let Ok((name, job)) = rx.recv().await {
+        debug!("Run maintenance job '{}'", name);
+        job.await;
+    }
+}
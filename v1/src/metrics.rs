@@ -0,0 +1,193 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use async_std::io::{ReadExt, WriteExt};
+use async_std::net::TcpListener;
+use log::{debug, warn};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, Weak};
+use std::time::Duration;
+
+// The counted events, with their metric name and help
+const COUNTERS: [(&str, &str); 8] = [
+    ("puts", "PUT operations received by the storage"),
+    ("gets", "GET operations received by the storage"),
+    ("deletes", "DELETE operations received by the storage"),
+    (
+        "buffer_flushes",
+        "batches of buffered points flushed to InfluxDB",
+    ),
+    ("errors", "InfluxDB queries that failed"),
+    ("corrupted_values", "values read with a hash mismatch"),
+    ("rate_limited", "PUT operations exceeding the rate limits"),
+    (
+        "clock_skewed",
+        "PUT and DELETE operations with a timestamp out of the skew bounds",
+    ),
+];
+
+// Upper bounds (in seconds) of the buckets of the query latency histogram
+const LATENCY_BUCKETS: [f64; 10] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0, 5.0];
+
+lazy_static::lazy_static!(
+    // the metrics of all the storages of the process, by storage name
+    static ref REGISTRY: RwLock<Vec<(String, Weak<StorageMetrics>)>> = RwLock::new(Vec::new());
+);
+
+// The metrics of a storage (only built with the "metrics" feature)
+pub(crate) struct StorageMetrics {
+    counters: [AtomicU64; COUNTERS.len()],
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    latency_count: AtomicU64,
+    latency_sum_micros: AtomicU64,
+}
+
+impl StorageMetrics {
+    // Create the metrics of a storage, exported until they are dropped
+    pub(crate) fn register(storage_name: &str) -> Arc<StorageMetrics> {
+        let metrics = Arc::new(StorageMetrics {
+            counters: Default::default(),
+            latency_buckets: Default::default(),
+            latency_count: AtomicU64::new(0),
+            latency_sum_micros: AtomicU64::new(0),
+        });
+        let mut registry = REGISTRY.write().unwrap();
+        registry.retain(|(_, m)| m.strong_count() > 0);
+        registry.push((storage_name.to_string(), Arc::downgrade(&metrics)));
+        metrics
+    }
+
+    // Increment a counter (one of COUNTERS names)
+    pub(crate) fn inc(&self, name: &str) {
+        if let Some(i) = COUNTERS.iter().position(|(n, _)| *n == name) {
+            self.counters[i].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn observe_latency(&self, latency: Duration) {
+        let secs = latency.as_secs_f64();
+        for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            if secs <= *bound {
+                self.latency_buckets[i].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.latency_count.fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_micros
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+// The metrics of all the storages in OpenMetrics text format
+pub(crate) fn render() -> String {
+    let storages: Vec<(String, Arc<StorageMetrics>)> = REGISTRY
+        .read()
+        .unwrap()
+        .iter()
+        .filter_map(|(name, m)| m.upgrade().map(|m| (name.clone(), m)))
+        .collect();
+    let mut s = String::new();
+    for (i, (name, help)) in COUNTERS.iter().enumerate() {
+        let _ = writeln!(s, "# TYPE zenoh_influxdb_{name} counter");
+        let _ = writeln!(s, "# HELP zenoh_influxdb_{name} Number of {help}.");
+        for (storage, m) in &storages {
+            let _ = writeln!(
+                s,
+                r#"zenoh_influxdb_{name}_total{{storage="{}"}} {}"#,
+                escape_label(storage),
+                m.counters[i].load(Ordering::Relaxed)
+            );
+        }
+    }
+    let _ = writeln!(s, "# TYPE zenoh_influxdb_query_latency_seconds histogram");
+    let _ = writeln!(
+        s,
+        "# HELP zenoh_influxdb_query_latency_seconds Latency of the InfluxDB queries."
+    );
+    for (storage, m) in &storages {
+        let storage = escape_label(storage);
+        for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            let _ = writeln!(
+                s,
+                r#"zenoh_influxdb_query_latency_seconds_bucket{{storage="{storage}",le="{bound}"}} {}"#,
+                m.latency_buckets[i].load(Ordering::Relaxed)
+            );
+        }
+        let count = m.latency_count.load(Ordering::Relaxed);
+        let _ = writeln!(
+            s,
+            r#"zenoh_influxdb_query_latency_seconds_bucket{{storage="{storage}",le="+Inf"}} {count}"#
+        );
+        let _ = writeln!(
+            s,
+            r#"zenoh_influxdb_query_latency_seconds_count{{storage="{storage}"}} {count}"#
+        );
+        let _ = writeln!(
+            s,
+            r#"zenoh_influxdb_query_latency_seconds_sum{{storage="{storage}"}} {}"#,
+            m.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1e6
+        );
+    }
+    s.push_str("# EOF\n");
+    s
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Serve the metrics over HTTP on the given address (e.g. "127.0.0.1:9464"), for any path
+pub(crate) async fn serve(address: String) {
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!(
+                "Failed to serve InfluxDB backend metrics on {} : {}",
+                address, e
+            );
+            return;
+        }
+    };
+    debug!("Serve InfluxDB backend metrics on http://{}", address);
+    loop {
+        let mut stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                warn!("Failed to accept metrics connection on {} : {}", address, e);
+                continue;
+            }
+        };
+        async_std::task::spawn(async move {
+            // the request itself doesn't matter: read its head and reply the metrics
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 16 * 1024 {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let body = render();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/openmetrics-text; version=1.0.0; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
@@ -0,0 +1,341 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::client::InfluxClient;
+use async_std::channel::{bounded, Receiver, Sender, TrySendError};
+use async_std::future::timeout;
+use async_std::task::JoinHandle;
+use influxdb::{Client, Query, ReadQuery, WriteQuery};
+use log::{debug, warn};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+// An operation to forward to the mirror database
+enum Forwarded {
+    Point(WriteQuery),
+    // e.g. a DELETE query
+    Query(ReadQuery),
+}
+
+// A best-effort forwarding of the operations to a mirror database (archive or replica), with batched points.
+// The operations are dropped if the mirror is too slow or fails: they remain in the main database.
+// The forwarding task flushes the pending points and stops once the Mirror is dropped.
+pub(crate) struct Mirror {
+    // the URL and database of the mirror
+    target: String,
+    tx: Sender<Forwarded>,
+    // the number of operations not forwarded (queue full or failure)
+    dropped: Arc<AtomicU64>,
+    last_error: Arc<RwLock<Option<String>>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl Mirror {
+    pub(crate) fn new(
+        client: Client,
+        batch_size: usize,
+        max_bytes: Option<usize>,
+        flush_interval: Duration,
+        linger: Option<Duration>,
+        queue_size: usize,
+    ) -> Mirror {
+        let target = format!("{} ({})", client.database_url(), client.database_name());
+        Mirror::start(
+            target,
+            Arc::new(client),
+            batch_size,
+            max_bytes,
+            flush_interval,
+            linger,
+            queue_size,
+        )
+    }
+
+    fn start(
+        target: String,
+        client: Arc<dyn InfluxClient>,
+        batch_size: usize,
+        max_bytes: Option<usize>,
+        flush_interval: Duration,
+        linger: Option<Duration>,
+        queue_size: usize,
+    ) -> Mirror {
+        let (tx, rx) = bounded(queue_size.max(1));
+        let dropped = Arc::new(AtomicU64::new(0));
+        let last_error = Arc::new(RwLock::new(None));
+        let task = async_std::task::spawn(forward(
+            client,
+            rx,
+            batch_size.max(1),
+            max_bytes,
+            flush_interval,
+            linger,
+            dropped.clone(),
+            last_error.clone(),
+        ));
+        Mirror {
+            target,
+            tx,
+            dropped,
+            last_error,
+            task: Some(task),
+        }
+    }
+
+    // Stop accepting operations, and wait (at most the timeout) for the pending ones to be forwarded
+    pub(crate) async fn close(&mut self, max_wait: Duration) {
+        self.tx.close();
+        if let Some(task) = self.task.take() {
+            if timeout(max_wait, task).await.is_err() {
+                warn!(
+                    "Timeout forwarding the pending operations to InfluxDB mirror {}",
+                    self.target
+                );
+            }
+        }
+    }
+
+    pub(crate) fn push(&self, query: WriteQuery) {
+        self.send(Forwarded::Point(query))
+    }
+
+    pub(crate) fn push_query(&self, query: ReadQuery) {
+        self.send(Forwarded::Query(query))
+    }
+
+    fn send(&self, op: Forwarded) {
+        match self.tx.try_send(op) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) | Err(TrySendError::Closed(_)) => {
+                // warn only once per 1000 dropped operations
+                if self.dropped.fetch_add(1, Ordering::Relaxed) % 1000 == 0 {
+                    warn!(
+                        "InfluxDB mirror queue for {} is full; dropping points",
+                        self.target
+                    );
+                }
+            }
+        }
+    }
+
+    pub(crate) fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn last_error(&self) -> Option<String> {
+        self.last_error.read().unwrap().clone()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn forward(
+    client: Arc<dyn InfluxClient>,
+    rx: Receiver<Forwarded>,
+    batch_size: usize,
+    max_bytes: Option<usize>,
+    flush_interval: Duration,
+    linger: Option<Duration>,
+    dropped: Arc<AtomicU64>,
+    last_error: Arc<RwLock<Option<String>>>,
+) {
+    let mut batch = Vec::with_capacity(batch_size);
+    // the size of the current batch in line protocol (only computed with max_bytes)
+    let mut batch_bytes = 0;
+    // the time to flush the current batch, at most flush_interval after its first point
+    let mut max_deadline: Option<Instant> = None;
+    // the same, or earlier if no point is received during linger (idle queue)
+    let mut deadline: Option<Instant> = None;
+    loop {
+        let received = match deadline {
+            Some(d) => timeout(d.saturating_duration_since(Instant::now()), rx.recv()).await,
+            None => Ok(rx.recv().await),
+        };
+        // a query to run after the points received before it
+        let mut query = None;
+        let closed = match received {
+            Ok(Ok(Forwarded::Point(point))) => {
+                let now = Instant::now();
+                if batch.is_empty() {
+                    max_deadline = Some(now + flush_interval);
+                }
+                deadline = match linger {
+                    Some(linger) => max_deadline.map(|max| max.min(now + linger)),
+                    None => max_deadline,
+                };
+                if max_bytes.is_some() {
+                    batch_bytes += point.build().map_or(0, |q| q.get().len());
+                }
+                batch.push(point);
+                false
+            }
+            Ok(Ok(Forwarded::Query(q))) => {
+                query = Some(q);
+                false
+            }
+            Ok(Err(_)) => true,
+            // flush interval elapsed
+            Err(_) => false,
+        };
+        let expired = deadline.map_or(false, |d| Instant::now() >= d);
+        let full = batch.len() >= batch_size || max_bytes.map_or(false, |max| batch_bytes >= max);
+        if !batch.is_empty() && (closed || expired || query.is_some() || full) {
+            let points = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+            batch_bytes = 0;
+            max_deadline = None;
+            deadline = None;
+            let count = points.len() as u64;
+            debug!(
+                "Forward {} points to InfluxDB mirror {}",
+                count,
+                client.database_name()
+            );
+            if let Err(e) = client.write(&points).await {
+                dropped.fetch_add(count, Ordering::Relaxed);
+                warn!(
+                    "Failed to forward {} points to InfluxDB mirror {} : {}",
+                    count,
+                    client.database_name(),
+                    e
+                );
+                *last_error.write().unwrap() = Some(e.to_string());
+            }
+        }
+        if let Some(q) = query {
+            debug!(
+                "Forward query to InfluxDB mirror {} : {:?}",
+                client.database_name(),
+                q
+            );
+            if let Err(e) = client.query(&q).await {
+                dropped.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    "Failed to forward query to InfluxDB mirror {} : {}",
+                    client.database_name(),
+                    e
+                );
+                *last_error.write().unwrap() = Some(e.to_string());
+            }
+        }
+        if closed {
+            break;
+        }
+    }
+    debug!(
+        "Stop forwarding to InfluxDB mirror {}",
+        client.database_name()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::MockClient;
+    use influxdb::Timestamp;
+
+    fn point(i: u64) -> WriteQuery {
+        WriteQuery::new(Timestamp::Nanoseconds(i as u128), "demo/a").add_field("value", i)
+    }
+
+    // The number of points of each batch written so far
+    fn batches(mock: &MockClient) -> Vec<usize> {
+        mock.queries()
+            .iter()
+            .map(|q| q.split('\n').count())
+            .collect()
+    }
+
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "Points not forwarded");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn mirror(
+        mock: &Arc<MockClient>,
+        max_bytes: Option<usize>,
+        flush_interval: Duration,
+        linger: Option<Duration>,
+    ) -> Mirror {
+        Mirror::start(
+            "mock".to_string(),
+            mock.clone(),
+            100,
+            max_bytes,
+            flush_interval,
+            linger,
+            100,
+        )
+    }
+
+    #[test]
+    fn linger_flushes_an_idle_batch() {
+        let mock = Arc::new(MockClient::new("mirror"));
+        let started = Instant::now();
+        let mut mirror = mirror(
+            &mock,
+            None,
+            Duration::from_secs(60),
+            Some(Duration::from_millis(50)),
+        );
+        for i in 0..3 {
+            mirror.push(point(i));
+        }
+        // well before the flush interval
+        wait_for(|| !mock.queries().is_empty());
+        assert!(started.elapsed() < Duration::from_secs(60));
+        assert_eq!(batches(&mock), vec![3]);
+
+        // the points received before a query are forwarded first
+        mirror.push(point(3));
+        mirror.push_query(ReadQuery::new(r#"DELETE FROM "demo/a""#));
+        wait_for(|| mock.queries().len() == 3);
+        assert_eq!(mock.queries()[2], r#"DELETE FROM "demo/a""#);
+        assert_eq!(batches(&mock), vec![3, 1, 1]);
+        async_std::task::block_on(mirror.close(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn flush_interval_without_linger() {
+        let mock = Arc::new(MockClient::new("mirror"));
+        let mut mirror = mirror(&mock, None, Duration::from_millis(100), None);
+        mirror.push(point(0));
+        mirror.push(point(1));
+        wait_for(|| !mock.queries().is_empty());
+        assert_eq!(batches(&mock), vec![2]);
+        async_std::task::block_on(mirror.close(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn batches_by_bytes() {
+        let mock = Arc::new(MockClient::new("mirror"));
+        let size = point(0).build().unwrap().get().len();
+        // the batches are flushed as soon as they reach the size of 2 points
+        let mut mirror = mirror(&mock, Some(2 * size), Duration::from_secs(60), None);
+        for i in 0..5 {
+            mirror.push(point(i));
+        }
+        wait_for(|| mock.queries().len() == 2);
+        assert_eq!(batches(&mock), vec![2, 2]);
+        // the last one on close
+        async_std::task::block_on(mirror.close(Duration::from_secs(1)));
+        assert_eq!(batches(&mock), vec![2, 2, 1]);
+    }
+}
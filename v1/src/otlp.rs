@@ -0,0 +1,64 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::{runtime, trace, Resource};
+use tracing_subscriber::layer::SubscriberExt;
+use zenoh::Result as ZResult;
+use zenoh_core::{bail, zerror};
+
+// default service name of the exported spans
+const DEFAULT_SERVICE_NAME: &str = "zenoh-backend-influxdb";
+
+// Export the tracing spans of the backend to an OpenTelemetry collector, over OTLP/HTTP.
+// The configuration is an object with:
+//   - "endpoint" (required): the OTLP/HTTP traces endpoint, e.g. "http://localhost:4318/v1/traces"
+//   - "service_name" (optional): the service name of the spans
+pub(crate) fn install(config: &serde_json::Value) -> ZResult<()> {
+    let endpoint = match config.get("endpoint") {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        _ => bail!("`otlp.endpoint` property must be a string"),
+    };
+    let service_name = match config.get("service_name") {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        None => DEFAULT_SERVICE_NAME.to_string(),
+        Some(_) => bail!("`otlp.service_name` property must be a string"),
+    };
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .http()
+                .with_http_client(surf::Client::new())
+                .with_endpoint(&endpoint),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                service_name,
+            )])),
+        )
+        .install_batch(runtime::AsyncStd)
+        .map_err(|e| zerror!("Failed to install the OTLP exporter to {}: {}", endpoint, e))?;
+    let subscriber =
+        tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    tracing::subscriber::set_global_default(subscriber).map_err(|e| {
+        zerror!(
+            "Failed to install the OTLP exporter: a tracing subscriber is already set ({})",
+            e
+        )
+    })?;
+    Ok(())
+}
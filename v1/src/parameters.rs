@@ -0,0 +1,306 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::{
+    AGGREGATIONS, PARAM_AGG, PARAM_AT, PARAM_COVERAGE, PARAM_INCLUDE_DELETES, PARAM_LIMIT,
+    PARAM_MAX_SAMPLES, PARAM_ORDER, PARAM_TIME, PARAM_WINDOW,
+};
+use log::warn;
+use std::str::FromStr;
+use std::time::Duration;
+use zenoh::selector::{TimeExpr, TimeRange};
+use zenoh::Result as ZResult;
+use zenoh_core::{bail, zerror};
+
+// The selector parameters of a GET understood by the storage
+pub(crate) struct GetParameters<'a> {
+    pub(crate) time_range: Option<TimeRange>,
+    pub(crate) limit: Option<u64>,
+    // "ASC" or "DESC"
+    pub(crate) order: Option<&'static str>,
+    pub(crate) agg: Option<&'a str>,
+    pub(crate) window: Option<Duration>,
+    pub(crate) max_samples: Option<u64>,
+    pub(crate) coverage: bool,
+    pub(crate) include_deletes: bool,
+    // the time of the replied latest points (ignoring the later points and deletions)
+    pub(crate) at: Option<TimeExpr>,
+}
+
+impl<'a> GetParameters<'a> {
+    // Parse and validate the selector parameters.
+    // The unknown parameters starting with '_' (reserved for zenoh) are refused if `strict`, or ignored with a warning.
+    pub(crate) fn parse(p: &'a str, strict: bool) -> ZResult<GetParameters<'a>> {
+        let mut result = GetParameters {
+            time_range: None,
+            limit: None,
+            order: None,
+            agg: None,
+            window: None,
+            max_samples: None,
+            coverage: false,
+            include_deletes: false,
+            at: None,
+        };
+        for kv in p.split('&').filter(|kv| !kv.is_empty()) {
+            let (name, value) = kv.split_once('=').unwrap_or((kv, ""));
+            match name {
+                PARAM_TIME => {
+                    result.time_range = Some(TimeRange::from_str(value).map_err(|e| {
+                        zerror!(
+                            "Invalid value for {} selector parameter: '{}' ({})",
+                            name,
+                            value,
+                            e
+                        )
+                    })?)
+                }
+                PARAM_LIMIT => result.limit = Some(parse_u64(name, value)?),
+                PARAM_ORDER => {
+                    result.order = match value {
+                        "asc" => Some("ASC"),
+                        "desc" => Some("DESC"),
+                        _ => bail!(
+                            r#"Invalid value for {} selector parameter: '{}' (must be "asc" or "desc")"#,
+                            name,
+                            value
+                        ),
+                    }
+                }
+                PARAM_AGG => {
+                    if !AGGREGATIONS.contains(&value) {
+                        bail!(
+                            "Invalid value for {} selector parameter: '{}' (must be one of {:?})",
+                            name,
+                            value,
+                            AGGREGATIONS
+                        )
+                    }
+                    result.agg = Some(value)
+                }
+                PARAM_WINDOW => {
+                    let window = humantime::parse_duration(value).map_err(|e| {
+                        zerror!(
+                            "Invalid value for {} selector parameter: '{}' ({})",
+                            name,
+                            value,
+                            e
+                        )
+                    })?;
+                    if window.is_zero() {
+                        bail!(
+                            "Invalid value for {} selector parameter: must be a non-zero duration",
+                            name
+                        )
+                    }
+                    result.window = Some(window)
+                }
+                PARAM_MAX_SAMPLES => match parse_u64(name, value)? {
+                    0 => bail!(
+                        "Invalid value for {} selector parameter: must be positive",
+                        name
+                    ),
+                    n => result.max_samples = Some(n),
+                },
+                PARAM_COVERAGE => result.coverage = parse_bool(name, value)?,
+                PARAM_INCLUDE_DELETES => result.include_deletes = parse_bool(name, value)?,
+                PARAM_AT => {
+                    result.at = Some(TimeExpr::from_str(value).map_err(|e| {
+                        zerror!(
+                            "Invalid value for {} selector parameter: '{}' ({})",
+                            name,
+                            value,
+                            e
+                        )
+                    })?)
+                }
+                _ if name.starts_with('_') => {
+                    if strict {
+                        bail!("Unsupported selector parameter: {}", name)
+                    }
+                    warn!("Ignoring unsupported selector parameter: {}", name)
+                }
+                // other parameters are for the application
+                _ => {}
+            }
+        }
+
+        if result.agg.is_some() {
+            if result.window.is_none() {
+                bail!(
+                    "{} selector parameter requires a {}",
+                    PARAM_AGG,
+                    PARAM_WINDOW
+                )
+            }
+            if result.time_range.is_none() {
+                bail!(
+                    "{} selector parameter requires a {} range",
+                    PARAM_AGG,
+                    PARAM_TIME
+                )
+            }
+        }
+        if result.at.is_some()
+            && (result.time_range.is_some() || result.agg.is_some() || result.coverage)
+        {
+            bail!(
+                "{} selector parameter can't be used with {}, {} nor {}",
+                PARAM_AT,
+                PARAM_TIME,
+                PARAM_AGG,
+                PARAM_COVERAGE
+            )
+        }
+        if result.max_samples.is_some() && result.time_range.is_none() {
+            bail!(
+                "{} selector parameter requires a {} range",
+                PARAM_MAX_SAMPLES,
+                PARAM_TIME
+            )
+        }
+        Ok(result)
+    }
+
+    // True if only the latest value is requested (the default, without any parameter)
+    pub(crate) fn is_latest_only(&self) -> bool {
+        self.time_range.is_none()
+            && self.limit.is_none()
+            && self.order.is_none()
+            && self.agg.is_none()
+            && self.max_samples.is_none()
+            && !self.coverage
+            && !self.include_deletes
+            && self.at.is_none()
+    }
+}
+
+fn parse_u64(name: &str, value: &str) -> ZResult<u64> {
+    value.parse::<u64>().map_err(|_| {
+        zerror!(
+            "Invalid value for {} selector parameter: '{}' (must be a positive integer)",
+            name,
+            value
+        )
+        .into()
+    })
+}
+
+fn parse_bool(name: &str, value: &str) -> ZResult<bool> {
+    match value {
+        "" | "true" => Ok(true),
+        "false" => Ok(false),
+        _ => bail!(
+            r#"Invalid value for {} selector parameter: '{}' (must be "true" or "false")"#,
+            name,
+            value
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(p: &str) -> ZResult<GetParameters> {
+        GetParameters::parse(p, true)
+    }
+
+    #[test]
+    fn no_parameters() {
+        for p in ["", "&", "foo=bar", "foo&bar=1"] {
+            assert!(parse(p).unwrap().is_latest_only(), "{p:?}");
+        }
+    }
+
+    #[test]
+    fn time_limit_order() {
+        let params = parse("_time=[now(-1h)..now()]&_limit=10&_order=desc").unwrap();
+        assert!(params.time_range.is_some());
+        assert_eq!(params.limit, Some(10));
+        assert_eq!(params.order, Some("DESC"));
+        assert!(!params.is_latest_only());
+        assert_eq!(parse("_order=asc").unwrap().order, Some("ASC"));
+
+        assert!(parse("_time=yesterday").is_err());
+        assert!(parse("_limit=-1").is_err());
+        assert!(parse("_limit=").is_err());
+        assert!(parse("_order=ASC").is_err());
+    }
+
+    #[test]
+    fn aggregations() {
+        let params = parse("_time=[now(-1h)..]&_agg=mean&_window=1m").unwrap();
+        assert_eq!(params.agg, Some("mean"));
+        assert_eq!(params.window, Some(Duration::from_secs(60)));
+        for agg in AGGREGATIONS {
+            assert!(parse(&format!("_time=[now(-1h)..]&_agg={agg}&_window=1m")).is_ok());
+        }
+
+        assert!(parse("_time=[now(-1h)..]&_agg=median&_window=1m").is_err());
+        // an aggregation requires a window and a time range
+        assert!(parse("_time=[now(-1h)..]&_agg=mean").is_err());
+        assert!(parse("_agg=mean&_window=1m").is_err());
+        assert!(parse("_time=[now(-1h)..]&_agg=mean&_window=0s").is_err());
+        assert!(parse("_time=[now(-1h)..]&_agg=mean&_window=soon").is_err());
+    }
+
+    #[test]
+    fn max_samples() {
+        let params = parse("_time=[now(-1h)..]&_max_samples=100").unwrap();
+        assert_eq!(params.max_samples, Some(100));
+
+        assert!(parse("_time=[now(-1h)..]&_max_samples=0").is_err());
+        // max_samples requires a time range
+        assert!(parse("_max_samples=100").is_err());
+    }
+
+    #[test]
+    fn booleans() {
+        assert!(parse("_coverage").unwrap().coverage);
+        assert!(parse("_coverage=true").unwrap().coverage);
+        assert!(!parse("_coverage=false").unwrap().coverage);
+        assert!(parse("_include_deletes").unwrap().include_deletes);
+        assert!(!parse("_include_deletes=false").unwrap().include_deletes);
+
+        assert!(parse("_coverage=1").is_err());
+        assert!(parse("_include_deletes=yes").is_err());
+    }
+
+    #[test]
+    fn at() {
+        let params = parse("_at=now(-1h)").unwrap();
+        assert!(params.at.is_some());
+        assert!(!params.is_latest_only());
+        assert!(parse("_at=2023-01-01T00:00:00Z").is_ok());
+
+        assert!(parse("_at=later").is_err());
+        // at can't be used with a time range, an aggregation nor coverage
+        assert!(parse("_at=now(-1h)&_time=[now(-2h)..]").is_err());
+        assert!(parse("_at=now(-1h)&_time=[now(-2h)..]&_agg=mean&_window=1m").is_err());
+        assert!(parse("_at=now(-1h)&_coverage").is_err());
+    }
+
+    #[test]
+    fn unknown_parameters() {
+        assert!(GetParameters::parse("_unknown=1", true).is_err());
+        assert!(GetParameters::parse("_unknown=1", false)
+            .unwrap()
+            .is_latest_only());
+        // the parameters not starting with '_' are for the application
+        assert!(GetParameters::parse("unknown=1", true)
+            .unwrap()
+            .is_latest_only());
+    }
+}
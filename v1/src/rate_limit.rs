@@ -0,0 +1,94 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::{PROP_STORAGE_MAX_BYTES_PER_SEC, PROP_STORAGE_MAX_POINTS_PER_SEC};
+use std::sync::Mutex;
+use std::time::Instant;
+
+// A token bucket refilled with `rate` tokens per second, allowing bursts of up to 1 second of tokens
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> TokenBucket {
+        TokenBucket {
+            rate: rate as f64,
+            tokens: rate as f64,
+        }
+    }
+
+    fn refill(&mut self, elapsed_secs: f64) {
+        self.tokens = (self.tokens + elapsed_secs * self.rate).min(self.rate);
+    }
+
+    // (a request larger than a burst only requires a full bucket)
+    fn has(&self, n: f64) -> bool {
+        self.tokens >= n.min(self.rate)
+    }
+
+    fn take(&mut self, n: f64) {
+        self.tokens -= n.min(self.rate);
+    }
+}
+
+// The rate limits of the PUT on a storage (`max_points_per_sec` and `max_bytes_per_sec` properties)
+pub(crate) struct RateLimiter {
+    // the buckets of the points and of the bytes, and the time of their last refill
+    state: Mutex<(Option<TokenBucket>, Option<TokenBucket>, Instant)>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(
+        max_points_per_sec: Option<u64>,
+        max_bytes_per_sec: Option<u64>,
+    ) -> Option<RateLimiter> {
+        if max_points_per_sec.is_none() && max_bytes_per_sec.is_none() {
+            return None;
+        }
+        Some(RateLimiter {
+            state: Mutex::new((
+                max_points_per_sec.map(TokenBucket::new),
+                max_bytes_per_sec.map(TokenBucket::new),
+                Instant::now(),
+            )),
+        })
+    }
+
+    // Take the tokens of a point of `bytes` bytes. Returns the name of the exceeded limit if they're not available
+    // (then no token is taken).
+    pub(crate) fn try_acquire(&self, bytes: usize) -> Result<(), &'static str> {
+        let mut state = self.state.lock().unwrap();
+        let (points, payload, last_refill) = &mut *state;
+        let elapsed = last_refill.elapsed().as_secs_f64();
+        *last_refill = Instant::now();
+        for bucket in [points.as_mut(), payload.as_mut()].into_iter().flatten() {
+            bucket.refill(elapsed);
+        }
+        if points.as_ref().map_or(false, |b| !b.has(1.0)) {
+            return Err(PROP_STORAGE_MAX_POINTS_PER_SEC);
+        }
+        if payload.as_ref().map_or(false, |b| !b.has(bytes as f64)) {
+            return Err(PROP_STORAGE_MAX_BYTES_PER_SEC);
+        }
+        if let Some(b) = points {
+            b.take(1.0);
+        }
+        if let Some(b) = payload {
+            b.take(bytes as f64);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::events::open_session;
+use log::{debug, info, warn};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zenoh::buffers::buffer::SplitBuffer;
+use zenoh::prelude::{OwnedKeyExpr, Value};
+use zenoh::time::Timestamp;
+use zenoh::Result as ZResult;
+use zenoh::Session;
+use zenoh_core::AsyncResolve;
+
+// max number of replayed values not yet received back by the storage
+const MAX_ECHOES: usize = 100_000;
+// interval between 2 checks of the end of a pause
+const PAUSE_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+// A value to replay: the key to publish it on, the key under which the storage receives it back (if it
+// subscribes to it), its original timestamp and its value
+pub(crate) struct ReplayedPoint {
+    pub(crate) key: OwnedKeyExpr,
+    pub(crate) echo: Option<OwnedKeyExpr>,
+    pub(crate) timestamp: Timestamp,
+    pub(crate) value: Value,
+}
+
+// The pacing of a replay
+pub(crate) struct ReplayPacing {
+    // the factor dividing the original inter-sample timing
+    pub(crate) speed: f64,
+    // the max number of values published per second
+    pub(crate) max_rate: Option<f64>,
+}
+
+// The progress of a running replay
+struct ReplayProgress {
+    name: String,
+    total: usize,
+    published: Arc<AtomicU64>,
+}
+
+// The replays of stored points in zenoh (`replay` admin operation): the values are republished in timestamp order,
+// with their original inter-sample timing scaled by a speed factor. The replays stop on demand, or once the Replayer
+// is dropped.
+pub(crate) struct Replayer {
+    session: Arc<Session>,
+    // incremented to stop the running replays
+    generation: Arc<AtomicU64>,
+    running: Arc<AtomicU64>,
+    published: Arc<AtomicU64>,
+    // set to pause the running replays (their timing resumes where it was paused)
+    paused: Arc<AtomicBool>,
+    // the progress of the running replays, by id
+    progress: Arc<Mutex<BTreeMap<u64, ReplayProgress>>>,
+    next_id: AtomicU64,
+    // the keys and payload hashes of the replayed values, to ignore them when received back by the storage
+    echoes: Arc<Mutex<HashSet<(OwnedKeyExpr, u64)>>>,
+}
+
+impl Replayer {
+    pub(crate) async fn start(connect: &[String]) -> ZResult<Replayer> {
+        Ok(Replayer {
+            session: Arc::new(open_session(connect).await?),
+            generation: Arc::new(AtomicU64::new(0)),
+            running: Arc::new(AtomicU64::new(0)),
+            published: Arc::new(AtomicU64::new(0)),
+            paused: Arc::new(AtomicBool::new(false)),
+            progress: Arc::new(Mutex::new(BTreeMap::new())),
+            next_id: AtomicU64::new(0),
+            echoes: Arc::new(Mutex::new(HashSet::new())),
+        })
+    }
+
+    // Replay the points (sorted by timestamp) in the background, with their original timing scaled by the pacing
+    pub(crate) fn replay(&self, name: String, points: Vec<ReplayedPoint>, pacing: ReplayPacing) {
+        let session = self.session.clone();
+        let generation = self.generation.clone();
+        let running = self.running.clone();
+        let published = self.published.clone();
+        let paused = self.paused.clone();
+        let echoes = self.echoes.clone();
+        let started_generation = generation.load(Ordering::Relaxed);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let replay_published = Arc::new(AtomicU64::new(0));
+        let progress = self.progress.clone();
+        progress.lock().unwrap().insert(
+            id,
+            ReplayProgress {
+                name: name.clone(),
+                total: points.len(),
+                published: replay_published.clone(),
+            },
+        );
+        let min_interval = pacing
+            .max_rate
+            .map(|rate| Duration::from_secs_f64(1.0 / rate));
+        running.fetch_add(1, Ordering::Relaxed);
+        async_std::task::spawn(async move {
+            info!("Start replay {} of {} values", name, points.len());
+            let mut start = Instant::now();
+            let first = points.first().map(|p| p.timestamp.get_time().to_duration());
+            let mut last_publication: Option<Instant> = None;
+            let mut count = 0u64;
+            for point in points {
+                // wait for the end of a pause, shifting the timing of the next points
+                if paused.load(Ordering::Relaxed) {
+                    let pause = Instant::now();
+                    while paused.load(Ordering::Relaxed)
+                        && generation.load(Ordering::Relaxed) == started_generation
+                    {
+                        async_std::task::sleep(PAUSE_CHECK_INTERVAL).await;
+                    }
+                    start += pause.elapsed();
+                }
+                if generation.load(Ordering::Relaxed) != started_generation {
+                    info!("Stop replay {} after {} values", name, count);
+                    break;
+                }
+                // wait for the (scaled) time of the point since the first one,
+                // and at least the min interval since the previous publication
+                if let Some(first) = first {
+                    let offset = point
+                        .timestamp
+                        .get_time()
+                        .to_duration()
+                        .saturating_sub(first);
+                    let mut due =
+                        start + Duration::from_secs_f64(offset.as_secs_f64() / pacing.speed);
+                    if let (Some(last), Some(interval)) = (last_publication, min_interval) {
+                        due = due.max(last + interval);
+                    }
+                    let now = Instant::now();
+                    if due > now {
+                        async_std::task::sleep(due - now).await;
+                    }
+                }
+                last_publication = Some(Instant::now());
+                if let Some(echo) = point.echo {
+                    let mut echoes = echoes.lock().unwrap();
+                    if echoes.len() >= MAX_ECHOES {
+                        echoes.clear();
+                    }
+                    echoes.insert((echo, payload_hash(&point.value.payload.contiguous())));
+                }
+                debug!("Replay value of {}", point.key);
+                match session.put(&point.key, point.value).res_async().await {
+                    Ok(()) => {
+                        count += 1;
+                        published.fetch_add(1, Ordering::Relaxed);
+                        replay_published.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => warn!("Failed to replay value of {}: {}", point.key, e),
+                }
+            }
+            debug!("End of replay {}", name);
+            progress.lock().unwrap().remove(&id);
+            running.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+
+    // Stop the running replays (also resuming the later ones)
+    pub(crate) fn stop(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    // Pause or resume the running replays
+    pub(crate) fn pause(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    // The progress of the running replays, e.g. {"paused": false, "replays": [{"name": "...", "published": 10, "total": 100}]}
+    pub(crate) fn progress(&self) -> serde_json::Value {
+        let replays: Vec<serde_json::Value> = self
+            .progress
+            .lock()
+            .unwrap()
+            .values()
+            .map(|p| {
+                serde_json::json!({
+                    "name": p.name,
+                    "published": p.published.load(Ordering::Relaxed),
+                    "total": p.total,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "paused": self.paused.load(Ordering::Relaxed),
+            "replays": replays,
+        })
+    }
+
+    // If the value was replayed, forget it and return true: the storage must ignore it
+    pub(crate) fn is_echo(&self, key: &OwnedKeyExpr, payload: &[u8]) -> bool {
+        self.echoes
+            .lock()
+            .unwrap()
+            .remove(&(key.clone(), payload_hash(payload)))
+    }
+
+    // The number of running replays
+    pub(crate) fn running(&self) -> u64 {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    // The number of values replayed
+    pub(crate) fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Replayer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn payload_hash(payload: &[u8]) -> u64 {
+    xxhash_rust::xxh64::xxh64(payload, 0)
+}
@@ -0,0 +1,122 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use zenoh::Result as ZResult;
+use zenoh_core::{bail, zerror};
+
+// A provider of the secrets referenced in the configuration as `{"secret": "<reference>"}`
+// (e.g. the InfluxDB credentials), so they don't need to be stored in the zenoh configuration.
+pub trait SecretProvider: Send + Sync {
+    fn get_secret(&self, reference: &str) -> ZResult<String>;
+}
+
+lazy_static::lazy_static!(
+    static ref DEFAULT_SECRET_PROVIDER: RwLock<Option<Arc<dyn SecretProvider>>> = RwLock::new(None);
+);
+
+// Set the provider used by the volumes without a `vault` configuration.
+// Must be called before the volumes are started.
+pub fn set_secret_provider(provider: Arc<dyn SecretProvider>) {
+    *DEFAULT_SECRET_PROVIDER.write().unwrap() = Some(provider);
+}
+
+pub(crate) fn default_secret_provider() -> Option<Arc<dyn SecretProvider>> {
+    DEFAULT_SECRET_PROVIDER.read().unwrap().clone()
+}
+
+// A HashiCorp Vault KV v2 secrets engine.
+// The references are "<path>#<key>": the key's value in the latest version of the secret at path.
+pub(crate) struct VaultKv2 {
+    address: String,
+    token: String,
+    mount: String,
+    namespace: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VaultResponse {
+    data: VaultData,
+}
+
+#[derive(Deserialize)]
+struct VaultData {
+    data: HashMap<String, serde_json::Value>,
+}
+
+impl VaultKv2 {
+    pub(crate) fn new(
+        address: String,
+        token: String,
+        mount: String,
+        namespace: Option<String>,
+    ) -> VaultKv2 {
+        VaultKv2 {
+            address: address.trim_end_matches('/').to_string(),
+            token,
+            mount: mount.trim_matches('/').to_string(),
+            namespace,
+        }
+    }
+
+    async fn read(&self, path: &str) -> ZResult<HashMap<String, serde_json::Value>> {
+        let url = format!(
+            "{}/v1/{}/data/{}",
+            self.address,
+            self.mount,
+            path.trim_start_matches('/')
+        );
+        let mut request = surf::get(&url).header("X-Vault-Token", self.token.as_str());
+        if let Some(namespace) = &self.namespace {
+            request = request.header("X-Vault-Namespace", namespace.as_str());
+        }
+        let mut response = request
+            .await
+            .map_err(|e| zerror!("Failed to read secret {} from Vault: {}", path, e))?;
+        if !response.status().is_success() {
+            bail!(
+                "Failed to read secret {} from Vault: HTTP status {}",
+                path,
+                response.status()
+            )
+        }
+        let response: VaultResponse = response
+            .body_json()
+            .await
+            .map_err(|e| zerror!("Invalid response from Vault for secret {}: {}", path, e))?;
+        Ok(response.data.data)
+    }
+}
+
+impl SecretProvider for VaultKv2 {
+    fn get_secret(&self, reference: &str) -> ZResult<String> {
+        let (path, key) = match reference.rsplit_once('#') {
+            Some((path, key)) if !path.is_empty() && !key.is_empty() => (path, key),
+            _ => bail!(
+                r#"Invalid Vault secret reference "{}" (expecting "<path>#<key>")"#,
+                reference
+            ),
+        };
+        debug!("Read secret {} from Vault at {}", path, self.address);
+        let secret = async_std::task::block_on(self.read(path))?;
+        match secret.get(key) {
+            Some(serde_json::Value::String(value)) => Ok(value.clone()),
+            Some(_) => bail!("Key {} of Vault secret {} is not a string", key, path),
+            None => bail!("No key {} in Vault secret {}", key, path),
+        }
+    }
+}
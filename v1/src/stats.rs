@@ -0,0 +1,117 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::error::InfluxDbError;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+// The activity of a storage and the size of its data in InfluxDB, reported in its admin status
+pub(crate) struct StorageStats {
+    state: RwLock<StatsState>,
+}
+
+#[derive(Default)]
+struct StatsState {
+    // time of the last successful PUT or DELETE
+    last_write: Option<SystemTime>,
+    // time of the last successful query of points
+    last_read: Option<SystemTime>,
+    last_error: Option<(SystemTime, InfluxDbError)>,
+    // number of values read with a hash mismatch (see `value_hash` property)
+    corrupted_values: u64,
+    // number of PUT exceeding the rate limits (see `max_points_per_sec` and `max_bytes_per_sec` properties)
+    rate_limited: u64,
+    // number of PUT and DELETE with a timestamp out of bounds (see `max_future_skew` and `max_past_skew` properties)
+    clock_skewed: u64,
+    // gathered periodically from InfluxDB
+    counts: Option<StatsCounts>,
+}
+
+struct StatsCounts {
+    time: SystemTime,
+    measurements: u64,
+    series: u64,
+    points: u64,
+}
+
+fn format_time(t: SystemTime) -> String {
+    humantime::format_rfc3339_millis(t).to_string()
+}
+
+impl StorageStats {
+    pub(crate) fn new() -> StorageStats {
+        StorageStats {
+            state: RwLock::new(StatsState::default()),
+        }
+    }
+
+    pub(crate) fn record_write(&self) {
+        self.state.write().unwrap().last_write = Some(SystemTime::now());
+    }
+
+    pub(crate) fn record_read(&self) {
+        self.state.write().unwrap().last_read = Some(SystemTime::now());
+    }
+
+    pub(crate) fn record_error(&self, error: InfluxDbError) {
+        self.state.write().unwrap().last_error = Some((SystemTime::now(), error));
+    }
+
+    pub(crate) fn record_corrupted(&self) {
+        self.state.write().unwrap().corrupted_values += 1;
+    }
+
+    pub(crate) fn record_rate_limited(&self) {
+        self.state.write().unwrap().rate_limited += 1;
+    }
+
+    pub(crate) fn record_clock_skewed(&self) {
+        self.state.write().unwrap().clock_skewed += 1;
+    }
+
+    pub(crate) fn set_counts(&self, measurements: u64, series: u64, points: u64) {
+        self.state.write().unwrap().counts = Some(StatsCounts {
+            time: SystemTime::now(),
+            measurements,
+            series,
+            points,
+        });
+    }
+
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        let state = self.state.read().unwrap();
+        let mut json = serde_json::json!({
+            "last_write": state.last_write.map(format_time),
+            "last_read": state.last_read.map(format_time),
+            "last_error": state.last_error.as_ref().map(|(time, error)| {
+                serde_json::json!({
+                    "time": format_time(*time),
+                    "kind": error.kind(),
+                    "retryable": error.is_retryable(),
+                    "error": error.message(),
+                })
+            }),
+            "corrupted_values": state.corrupted_values,
+            "rate_limited": state.rate_limited,
+            "clock_skewed": state.clock_skewed,
+        });
+        if let (Some(counts), Some(obj)) = (&state.counts, json.as_object_mut()) {
+            obj.insert("measurements".into(), counts.measurements.into());
+            obj.insert("series".into(), counts.series.into());
+            obj.insert("points".into(), counts.points.into());
+            obj.insert("counted_at".into(), format_time(counts.time).into());
+        }
+        json
+    }
+}
@@ -0,0 +1,72 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use rustls::internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use rustls::ClientConfig;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zenoh::Result as ZResult;
+use zenoh_core::{bail, zerror};
+
+// The TLS settings of the connection of a storage to its InfluxDB server
+pub(crate) struct TlsConfig {
+    // the PEM file of additional CA certificates, trusted besides the well-known ones
+    pub(crate) ca_file: Option<PathBuf>,
+    // the PEM files of the client certificate chain and key (for mutual TLS)
+    pub(crate) client_cert: Option<(PathBuf, PathBuf)>,
+}
+
+impl TlsConfig {
+    // An HTTP client using these TLS settings, for the InfluxDB client
+    pub(crate) fn http_client(&self) -> ZResult<surf::Client> {
+        let mut config = ClientConfig::new();
+        config
+            .root_store
+            .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+        if let Some(path) = &self.ca_file {
+            if let Err(()) = config.root_store.add_pem_file(&mut open(path)?) {
+                bail!("Invalid CA certificates file {}", path.display())
+            }
+        }
+        if let Some((cert_file, key_file)) = &self.client_cert {
+            let chain = match certs(&mut open(cert_file)?) {
+                Ok(chain) if !chain.is_empty() => chain,
+                _ => bail!("Invalid client certificate file {}", cert_file.display()),
+            };
+            let key = match pkcs8_private_keys(&mut open(key_file)?) {
+                Ok(mut keys) if !keys.is_empty() => keys.remove(0),
+                _ => match rsa_private_keys(&mut open(key_file)?) {
+                    Ok(mut keys) if !keys.is_empty() => keys.remove(0),
+                    _ => bail!("Invalid client key file {}", key_file.display()),
+                },
+            };
+            config
+                .set_single_client_cert(chain, key)
+                .map_err(|e| zerror!("Invalid client certificate or key: {}", e))?;
+        }
+        surf::Config::new()
+            .set_tls_config(Some(Arc::new(config)))
+            .try_into()
+            .map_err(|e| zerror!("Failed to create HTTP client: {}", e).into())
+    }
+}
+
+fn open(path: &Path) -> ZResult<BufReader<File>> {
+    match File::open(path) {
+        Ok(file) => Ok(BufReader::new(file)),
+        Err(e) => bail!("Failed to open {}: {}", path.display(), e),
+    }
+}
@@ -0,0 +1,126 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use influxdb::{Query, Timestamp as InfluxTimestamp, WriteQuery as InfluxWQuery};
+use zenoh::buffers::buffer::SplitBuffer;
+use zenoh_backend_traits::StoredData;
+
+use crate::codec::encode_string;
+use crate::{InfluxDbError, DELETE_ENCODING_SUFFIX};
+
+// An InfluxDB 2.x bucket, where the `migrate_v2` admin operation rewrites the points of a storage
+// with the schema of the InfluxDB 2.x backend (see `migrate_v2` property).
+pub(crate) struct V2Target {
+    url: String,
+    org_id: String,
+    bucket: String,
+    token: String,
+}
+
+impl V2Target {
+    pub(crate) fn new(url: String, org_id: String, bucket: String, token: String) -> V2Target {
+        V2Target {
+            url: url.trim_end_matches('/').to_string(),
+            org_id,
+            bucket,
+            token,
+        }
+    }
+
+    pub(crate) fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    // Write the points (in line protocol) into the bucket
+    pub(crate) async fn write(&self, lines: &[String]) -> Result<(), InfluxDbError> {
+        let url = surf::Url::parse_with_params(
+            &format!("{}/api/v2/write", self.url),
+            &[
+                ("orgID", self.org_id.as_str()),
+                ("bucket", self.bucket.as_str()),
+                ("precision", "ns"),
+            ],
+        )
+        .map_err(|e| influx_error!(QueryParse, "Invalid InfluxDB 2.x URL {}: {}", self.url, e))?;
+        let response = surf::post(url)
+            .header("Authorization", format!("Token {}", self.token))
+            .body(lines.join("\n"))
+            .await
+            .map_err(|e| {
+                influx_error!(
+                    Connection,
+                    "Failed to write into InfluxDB 2.x bucket {}: {}",
+                    self.bucket,
+                    e
+                )
+            })?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else if status == surf::StatusCode::Unauthorized || status == surf::StatusCode::Forbidden
+        {
+            fail!(
+                Auth,
+                "Write into InfluxDB 2.x bucket {} refused: HTTP status {}",
+                self.bucket,
+                status
+            )
+        } else {
+            fail!(
+                Connection,
+                "Failed to write into InfluxDB 2.x bucket {}: HTTP status {}",
+                self.bucket,
+                status
+            )
+        }
+    }
+}
+
+// The point of a value (or of a deletion marker) with the schema of the InfluxDB 2.x backend, in line protocol.
+// The point's time is the zenoh timestamp's one (in nanoseconds).
+pub(crate) fn v2_line(measurement: &str, data: &StoredData) -> Result<String, InfluxDbError> {
+    let time = data.timestamp.get_time().to_duration().as_nanos();
+    let is_deletion = data.value.encoding.suffix() == DELETE_ENCODING_SUFFIX;
+    let query = InfluxWQuery::new(InfluxTimestamp::Nanoseconds(time), measurement)
+        .add_field("timestamp", data.timestamp.to_string());
+    let query = if is_deletion {
+        query
+            .add_tag("kind", "DEL")
+            .add_field("encoding_prefix", 0_i64)
+            .add_field("encoding_suffix", "")
+            .add_field("base64", false)
+            .add_field("value", "")
+    } else {
+        // encode the value as a string, converting to base64 if the buffer is not a UTF-8 string
+        let (base64, strvalue) = encode_string(data.value.payload.contiguous());
+        query
+            .add_tag("kind", "PUT")
+            // (an u8 is not supported by InfluxDB 2.x)
+            .add_field(
+                "encoding_prefix",
+                u8::from(*data.value.encoding.prefix()) as i64,
+            )
+            .add_field("encoding_suffix", data.value.encoding.suffix())
+            .add_field("base64", base64)
+            .add_field("value", strvalue)
+    };
+    query.build().map(|q| q.get()).map_err(|e| {
+        influx_error!(
+            Serialization,
+            "Failed to encode point of {} for InfluxDB 2.x: {}",
+            measurement,
+            e
+        )
+    })
+}
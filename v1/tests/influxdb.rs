@@ -0,0 +1,228 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// Tests of the storages against an InfluxDB 1.8 server started in a docker container (with testcontainers).
+// Needing docker, they're ignored by default: run them with
+// `cargo test -p zenoh-backend-influxdb-v1 --test influxdb -- --ignored`
+use async_std::task;
+use serde_json::json;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use testcontainers::{clients::Cli, Container, GenericImage};
+use zenoh::buffers::buffer::SplitBuffer;
+use zenoh::prelude::*;
+use zenoh::time::{new_reception_timestamp, Timestamp, NTP64};
+use zenoh_backend_influxdb::InfluxDbBackend;
+use zenoh_backend_traits::config::{GarbageCollectionConfig, StorageConfig, VolumeConfig};
+use zenoh_backend_traits::{Storage, StorageInsertionResult, Volume};
+use zenoh_plugin_trait::Plugin;
+
+const INFLUXDB_PORT: u16 = 8086;
+
+// An InfluxDB 1.8 server, and its URL
+fn server(docker: &Cli) -> (Container<'_, GenericImage>, String) {
+    let container =
+        docker.run(GenericImage::new("influxdb", "1.8").with_exposed_port(INFLUXDB_PORT));
+    let url = format!(
+        "http://127.0.0.1:{}",
+        container.get_host_port_ipv4(INFLUXDB_PORT)
+    );
+    // wait for the server to answer its ping
+    let deadline = Instant::now() + Duration::from_secs(60);
+    task::block_on(async {
+        loop {
+            match surf::get(format!("{url}/ping")).await {
+                Ok(r) if r.status().is_success() => break,
+                _ if Instant::now() < deadline => task::sleep(Duration::from_millis(500)).await,
+                r => panic!("InfluxDB at {url} is not ready: {r:?}"),
+            }
+        }
+    });
+    (container, url)
+}
+
+fn volume(url: &str) -> Box<dyn Volume> {
+    let mut rest = serde_json::Map::new();
+    rest.insert("url".into(), url.into());
+    let config = VolumeConfig {
+        name: "influxdb".into(),
+        backend: None,
+        paths: None,
+        required: true,
+        rest,
+    };
+    InfluxDbBackend::start("influxdb", &config).unwrap()
+}
+
+// A storage of "demo/**" in its own database (dropped on closure), with additional properties
+async fn storage(volume: &dyn Volume, db: &str, properties: serde_json::Value) -> Box<dyn Storage> {
+    let mut volume_cfg = json!({"db": db, "create_db": true, "on_closure": "drop_db"});
+    for (name, value) in properties.as_object().unwrap() {
+        volume_cfg[name] = value.clone();
+    }
+    volume
+        .create_storage(StorageConfig {
+            name: db.to_string(),
+            key_expr: OwnedKeyExpr::from_str("demo/**").unwrap(),
+            complete: false,
+            strip_prefix: None,
+            volume_id: "influxdb".to_string(),
+            volume_cfg,
+            garbage_collection_config: GarbageCollectionConfig::default(),
+            replica_config: None,
+        })
+        .await
+        .unwrap()
+}
+
+fn key(k: &str) -> Option<OwnedKeyExpr> {
+    Some(OwnedKeyExpr::from_str(k).unwrap())
+}
+
+fn later(timestamp: &Timestamp, secs: u64) -> Timestamp {
+    let time = timestamp.get_time().to_duration() + Duration::from_secs(secs);
+    Timestamp::new(NTP64::from(time), *timestamp.get_id())
+}
+
+// The payloads replied to a GET, sorted
+async fn get(storage: &mut Box<dyn Storage>, key_expr: &str, parameters: &str) -> Vec<String> {
+    let mut values: Vec<String> = storage
+        .get(key(key_expr), parameters)
+        .await
+        .unwrap()
+        .iter()
+        .map(|d| String::from_utf8(d.value.payload.contiguous().to_vec()).unwrap())
+        .collect();
+    values.sort();
+    values
+}
+
+// PUT, GET and DELETE on the storage, then a PUT older than the DELETE
+async fn put_get_delete(mut storage: Box<dyn Storage>) {
+    let timestamp = new_reception_timestamp();
+    for (k, v) in [("demo/a", "hello"), ("demo/b", "world")] {
+        assert!(matches!(
+            storage.put(key(k), Value::from(v), timestamp).await,
+            Ok(StorageInsertionResult::Inserted)
+        ));
+    }
+    assert_eq!(get(&mut storage, "demo/a", "").await, ["hello"]);
+    assert_eq!(get(&mut storage, "demo/*", "").await, ["hello", "world"]);
+    assert!(get(&mut storage, "demo/c", "").await.is_empty());
+
+    let deletion = later(&timestamp, 1);
+    assert!(matches!(
+        storage.delete(key("demo/a"), deletion).await,
+        Ok(StorageInsertionResult::Deleted)
+    ));
+    assert!(get(&mut storage, "demo/a", "").await.is_empty());
+    assert_eq!(get(&mut storage, "demo/*", "").await, ["world"]);
+    // the deletion blocks the older values
+    assert!(matches!(
+        storage
+            .put(key("demo/a"), Value::from("old"), timestamp)
+            .await,
+        Ok(StorageInsertionResult::Outdated)
+    ));
+    assert!(matches!(
+        storage
+            .put(key("demo/a"), Value::from("new"), later(&timestamp, 2))
+            .await,
+        Ok(StorageInsertionResult::Inserted)
+    ));
+    assert_eq!(get(&mut storage, "demo/a", "").await, ["new"]);
+
+    let entries = storage.get_all_entries().await.unwrap();
+    for k in ["demo/a", "demo/b"] {
+        assert!(
+            entries.iter().any(|(e, _)| e.as_deref() == Some(k)),
+            "{k} not in {entries:?}"
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs docker"]
+fn measurement_per_key() {
+    let docker = Cli::default();
+    let (_container, url) = server(&docker);
+    let volume = volume(&url);
+    task::block_on(async {
+        put_get_delete(storage(&*volume, "measurement_per_key", json!({})).await).await;
+    });
+}
+
+#[test]
+#[ignore = "needs docker"]
+fn single_measurement() {
+    let docker = Cli::default();
+    let (_container, url) = server(&docker);
+    let volume = volume(&url);
+    task::block_on(async {
+        let properties = json!({"layout": "single_measurement"});
+        put_get_delete(storage(&*volume, "single_measurement", properties).await).await;
+    });
+}
+
+#[test]
+#[ignore = "needs docker"]
+fn history() {
+    let docker = Cli::default();
+    let (_container, url) = server(&docker);
+    let volume = volume(&url);
+    task::block_on(async {
+        let mut storage = storage(&*volume, "history", json!({})).await;
+        let timestamp = new_reception_timestamp();
+        for i in 0..3 {
+            storage
+                .put(
+                    key("demo/a"),
+                    Value::from(format!("v{i}")),
+                    later(&timestamp, i),
+                )
+                .await
+                .unwrap();
+        }
+        // the latest value, or all of them in a time range
+        assert_eq!(get(&mut storage, "demo/a", "").await, ["v2"]);
+        assert_eq!(
+            get(&mut storage, "demo/a", "_time=[now(-1h)..]").await,
+            ["v0", "v1", "v2"]
+        );
+    });
+}
+
+#[test]
+#[ignore = "needs docker"]
+fn chunked_values() {
+    let docker = Cli::default();
+    let (_container, url) = server(&docker);
+    let volume = volume(&url);
+    task::block_on(async {
+        for layout in ["measurement_per_key", "single_measurement"] {
+            let properties = json!({"layout": layout, "chunk_size": 1000});
+            let mut storage = storage(&*volume, &format!("chunked_{layout}"), properties).await;
+            let value = "zenoh é日 ".repeat(500);
+            storage
+                .put(
+                    key("demo/a"),
+                    Value::from(value.as_str()),
+                    new_reception_timestamp(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(get(&mut storage, "demo/a", "").await, [value]);
+        }
+    });
+}
@@ -18,14 +18,17 @@ use base64::{engine::general_purpose::STANDARD as b64_std_engine, Engine};
 use chrono::{NaiveDateTime, Utc};
 use futures::prelude::*;
 use influxdb2::api::buckets::ListBucketsRequest;
+use influxdb2::api::organization::ListOrganizationRequest;
+use influxdb2::models::retention_rule::Type as RetentionRuleType;
 use influxdb2::models::Query;
-use influxdb2::models::{DataPoint, PostBucketRequest};
+use influxdb2::models::{DataPoint, PostBucketRequest, RetentionRule};
 use influxdb2::Client;
 use influxdb2::FromDataPoint;
 use log::warn;
 use zenoh::buffers::ZBuf;
 use zenoh_plugin_trait::{plugin_long_version, plugin_version, Plugin};
 
+use std::borrow::Cow;
 use std::convert::{TryFrom, TryInto};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -53,7 +56,12 @@ pub const PROP_TOKEN: &str = "token";
 // Properties used by the Storage
 pub const PROP_STORAGE_DB: &str = "db";
 pub const PROP_STORAGE_CREATE_DB: &str = "create_db";
+pub const PROP_STORAGE_CREATE_BUCKET: &str = "create_bucket";
+pub const PROP_STORAGE_RETENTION_SECONDS: &str = "retention_seconds";
+pub const PROP_STORAGE_ORG: &str = "org";
 pub const PROP_STORAGE_ON_CLOSURE: &str = "on_closure";
+pub const PROP_STORAGE_ROUTING: &str = "routing";
+pub const PROP_STORAGE_PROVISION_CREDENTIALS: &str = "provision_credentials";
 
 // Special key for None (when the prefix being stripped exactly matches the key)
 pub const NONE_KEY: &str = "@@none_key@@";
@@ -67,10 +75,45 @@ lazy_static::lazy_static!(
 
 type Config<'a> = &'a serde_json::Map<String, serde_json::Value>;
 
-fn get_private_conf<'a>(config: Config<'a>, credit: &str) -> ZResult<Option<&'a String>> {
+// Resolve a credential value: either a literal string, or a reference to a file
+// (`{"file": "/run/secrets/influx_pass"}`) or to an environment variable (`{"env": "INFLUX_PASSWORD"}`)
+fn resolve_secret(value: &serde_json::Value, credit: &str) -> ZResult<String> {
+    match value {
+        serde_json::Value::String(v) => Ok(v.clone()),
+        serde_json::Value::Object(reference) if reference.len() == 1 => {
+            match reference.iter().next() {
+                Some((k, serde_json::Value::String(path))) if k == "file" => {
+                    let secret = std::fs::read_to_string(path).map_err(|e| {
+                        zerror!("Failed to read `{}` from file {}: {}", credit, path, e)
+                    })?;
+                    Ok(secret.trim_end_matches(['\n', '\r']).to_string())
+                }
+                Some((k, serde_json::Value::String(var))) if k == "env" => Ok(std::env::var(var)
+                    .map_err(|e| {
+                    zerror!(
+                        "Failed to read `{}` from environment variable {}: {}",
+                        credit,
+                        var,
+                        e
+                    )
+                })?),
+                _ => bail!(
+                    r#"Optional property `{}` must be a string, or a {{"file": "<path>"}} or {{"env": "<variable>"}} reference"#,
+                    credit
+                ),
+            }
+        }
+        _ => bail!(
+            r#"Optional property `{}` must be a string, or a {{"file": "<path>"}} or {{"env": "<variable>"}} reference"#,
+            credit
+        ),
+    }
+}
+
+fn get_private_conf(config: Config, credit: &str) -> ZResult<Option<String>> {
     match config.get_private(credit) {
         PrivacyGetResult::NotFound => Ok(None),
-        PrivacyGetResult::Private(serde_json::Value::String(v)) => Ok(Some(v)),
+        PrivacyGetResult::Private(v) => Ok(Some(resolve_secret(v, credit)?)),
         PrivacyGetResult::Public(serde_json::Value::String(v)) => {
             log::warn!(
                 r#"Value "{}" is given for `{}` publicly (i.e. is visible by anyone who can fetch the router configuration). 
@@ -82,8 +125,10 @@ fn get_private_conf<'a>(config: Config<'a>, credit: &str) -> ZResult<Option<&'a
                 credit,
                 v
             );
-            Ok(Some(v))
+            Ok(Some(v.clone()))
         }
+        // a reference is not a secret by itself: no need to make it private
+        PrivacyGetResult::Public(v) => Ok(Some(resolve_secret(v, credit)?)),
         PrivacyGetResult::Both {
             public: serde_json::Value::String(public),
             private: serde_json::Value::String(private),
@@ -96,11 +141,9 @@ fn get_private_conf<'a>(config: Config<'a>, credit: &str) -> ZResult<Option<&'a
                 credit,
                 if public == private { "" } else { "not " }
             );
-            Ok(Some(private))
-        }
-        _ => {
-            bail!("Optional property `{}` must be a string", credit)
+            Ok(Some(private.clone()))
         }
+        PrivacyGetResult::Both { private, .. } => Ok(Some(resolve_secret(private, credit)?)),
     }
 }
 
@@ -113,10 +156,7 @@ fn extract_credentials(config: Config) -> ZResult<Option<InfluxDbCredentials>> {
         //     org_id.clone(),
         //     token.clone(),
         // ))),
-        (Some(org_id), Some(token)) => Ok(Some(InfluxDbCredentials {
-            org_id: org_id.clone(),
-            token: token.clone(),
-        })),
+        (Some(org_id), Some(token)) => Ok(Some(InfluxDbCredentials { org_id, token })),
         _ => {
             log::error!("Couldn't get token and org");
             bail!(
@@ -240,18 +280,48 @@ impl Volume for InfluxDbVolume {
                 )
             }
         };
+        // "create_bucket" is an alias of "create_db"
+        let mut createdb = false;
+        for prop in [PROP_STORAGE_CREATE_DB, PROP_STORAGE_CREATE_BUCKET] {
+            match volume_cfg.get(prop) {
+                None | Some(serde_json::Value::Bool(false)) => {}
+                Some(serde_json::Value::Bool(true)) => createdb = true,
+                Some(_) => bail!(
+                    "`{}` property of InfluxDBv2 storage `{}` must be a boolean",
+                    prop,
+                    &config.name
+                ),
+            }
+        }
         let (db, createdb) = match volume_cfg.get(PROP_STORAGE_DB) {
-            Some(serde_json::Value::String(s)) => (
-                s.clone(),
-                match volume_cfg.get(PROP_STORAGE_CREATE_DB) {
-                    None | Some(serde_json::Value::Bool(false)) => false,
-                    Some(serde_json::Value::Bool(true)) => true,
-                    Some(_) => todo!(),
-                },
-            ),
+            Some(serde_json::Value::String(s)) => (s.clone(), createdb),
             None => (generate_db_name(), true),
             Some(v) => bail!("Invalid value for ${PROP_STORAGE_DB} config property: ${v}"),
         };
+        // the retention of the created bucket (0 = infinite)
+        let retention_seconds = match volume_cfg.get(PROP_STORAGE_RETENTION_SECONDS) {
+            None => 0,
+            Some(serde_json::Value::Number(n))
+                if n.as_u64().map_or(false, |s| s <= i32::MAX as u64) =>
+            {
+                n.as_u64().unwrap() as i32
+            }
+            Some(_) => bail!(
+                "`{}` property of InfluxDBv2 storage `{}` must be a positive integer (up to {})",
+                PROP_STORAGE_RETENTION_SECONDS,
+                &config.name,
+                i32::MAX
+            ),
+        };
+        let org = match volume_cfg.get(PROP_STORAGE_ORG) {
+            None => None,
+            Some(serde_json::Value::String(s)) => Some(s.clone()),
+            Some(_) => bail!(
+                "`{}` property of InfluxDBv2 storage `{}` must be a string",
+                PROP_STORAGE_ORG,
+                &config.name
+            ),
+        };
 
         // The Influx client on database used to write/query on this storage
         let url = match &self.admin_status.rest.get(PROP_BACKEND_URL) {
@@ -264,11 +334,45 @@ impl Volume for InfluxDbVolume {
             }
         };
 
-        let creds = match extract_credentials(volume_cfg)? {
+        let admin_creds = match &self.credentials {
             Some(creds) => creds,
-            _ => bail!("No credentials specified to access database '{}'", db),
+            None => bail!("No credentials specified to access database '{}'", db),
         };
-        let client = match std::panic::catch_unwind(|| {
+        let routing = get_routing_conf(volume_cfg, &config.name)?;
+        // with provisioning and no token for the storage, a token only granted on the bucket is created with it
+        let provisioning = match volume_cfg.get(PROP_STORAGE_PROVISION_CREDENTIALS) {
+            None | Some(serde_json::Value::Bool(false)) => false,
+            Some(serde_json::Value::Bool(true)) => {
+                get_private_conf(volume_cfg, PROP_TOKEN)?.is_none()
+            }
+            Some(_) => bail!(
+                "`{}` property of InfluxDBv2 storage `{}` must be a boolean",
+                PROP_STORAGE_PROVISION_CREDENTIALS,
+                &config.name
+            ),
+        };
+        if provisioning && !routing.is_empty() {
+            bail!(
+                "`{}` property of InfluxDBv2 storage `{}` can't be used with `{}`",
+                PROP_STORAGE_PROVISION_CREDENTIALS,
+                &config.name,
+                PROP_STORAGE_ROUTING
+            )
+        }
+        let mut creds = if provisioning {
+            // the bucket is checked (and created) with the admin token, until the token is provisioned
+            InfluxDbCredentials {
+                org_id: get_private_conf(volume_cfg, PROP_BACKEND_ORG_ID)?
+                    .unwrap_or_else(|| admin_creds.org_id.clone()),
+                token: admin_creds.token.clone(),
+            }
+        } else {
+            match extract_credentials(volume_cfg)? {
+                Some(creds) => creds,
+                _ => bail!("No credentials specified to access database '{}'", db),
+            }
+        };
+        let mut client = match std::panic::catch_unwind(|| {
             Client::new(url.clone(), creds.org_id.clone(), creds.token.clone())
         }) {
             Ok(client) => client,
@@ -276,23 +380,70 @@ impl Volume for InfluxDbVolume {
         };
 
         //check if db exists, if it doesn't create one if user has set createdb=true in config
-        match async_std::task::block_on(async { is_db_existing(&client, &db).await }) {
-            Ok(res) => {
-                if !res && createdb {
-                    // try to create db using user credentials
-                    match async_std::task::block_on(async {
-                        create_db(&self.admin_client, &creds.org_id, &db).await
-                    }) {
-                        Ok(res) => {
-                            if !res {
-                                bail!("Database '{}' wasnt't created in InfluxDBv2 storage", db)
-                            }
-                        }
-                        Err(e) => bail!("Failed to create InfluxDBv2 Storage : {:?}", e),
-                    }
-                }
+        let created = async_std::task::block_on(ensure_db(
+            &self.admin_client,
+            &client,
+            &db,
+            createdb,
+            org.as_deref(),
+            &creds.org_id,
+            retention_seconds,
+        ))?;
+
+        let mut provisioned = None;
+        if provisioning {
+            if !created {
+                bail!(
+                    "No credentials specified to access database '{}' (`{}` only applies to the databases created by the storage)",
+                    db,
+                    PROP_STORAGE_PROVISION_CREDENTIALS
+                )
             }
-            Err(e) => bail!("Failed to create InfluxDBv2 Storage : {:?}", e),
+            let (id, token) = async_std::task::block_on(provision_token(
+                &self.admin_client,
+                &url,
+                &admin_creds.token,
+                &db,
+            ))?;
+            creds.token = token;
+            client = match std::panic::catch_unwind(|| {
+                Client::new(url.clone(), creds.org_id.clone(), creds.token.clone())
+            }) {
+                Ok(client) => client,
+                Err(e) => bail!("Error in creating client for InfluxDBv2 storage: {:?}", e),
+            };
+            provisioned = Some(ProvisionedToken {
+                url: url.clone(),
+                admin_token: admin_creds.token.clone(),
+                id,
+            });
+        }
+
+        // the clients of the buckets the keys are routed to (the ones created here are applied `on_closure`)
+        let mut routes = Vec::new();
+        for route in routing {
+            let route_org = route.org.as_ref().unwrap_or(&creds.org_id);
+            let route_client = match std::panic::catch_unwind(|| {
+                Client::new(url.clone(), route_org.clone(), creds.token.clone())
+            }) {
+                Ok(client) => client,
+                Err(e) => bail!("Error in creating client for InfluxDBv2 storage: {:?}", e),
+            };
+            let created = async_std::task::block_on(ensure_db(
+                &self.admin_client,
+                &route_client,
+                &route.bucket,
+                createdb,
+                route.org.as_deref(),
+                &creds.org_id,
+                retention_seconds,
+            ))?;
+            routes.push(Route {
+                key_expr: route.key_expr,
+                client: route_client,
+                bucket: route.bucket,
+                created,
+            });
         }
 
         config
@@ -301,12 +452,23 @@ impl Volume for InfluxDbVolume {
             .unwrap()
             .entry(PROP_STORAGE_DB)
             .or_insert(db.clone().into());
+        // insert the provisioned token (and the organization) as private properties
+        if provisioned.is_some() {
+            let private = config
+                .volume_cfg
+                .as_object_mut()
+                .unwrap()
+                .entry("private")
+                .or_insert_with(|| serde_json::Value::Object(Default::default()));
+            if let Some(private) = private.as_object_mut() {
+                private
+                    .entry(PROP_BACKEND_ORG_ID)
+                    .or_insert(creds.org_id.clone().into());
+                private.insert(PROP_TOKEN.into(), creds.token.clone().into());
+            }
+        }
 
         // The Influx client on database with backend's credentials (admin), to drop measurements and database
-        let admin_creds = match &self.credentials {
-            Some(creds) => creds,
-            None => bail!("No credentials specified to access database '{}'", db),
-        };
         let admin_client = match std::panic::catch_unwind(|| {
             Client::new(url.clone(), &admin_creds.org_id, &admin_creds.token)
         }) {
@@ -318,7 +480,9 @@ impl Volume for InfluxDbVolume {
             config,
             admin_client,
             client,
+            routes,
             on_closure,
+            provisioned,
             timer: Timer::default(),
         }))
     }
@@ -356,17 +520,135 @@ impl TryFrom<&Properties> for OnClosure {
     }
 }
 
+struct RouteConf {
+    key_expr: OwnedKeyExpr,
+    bucket: String,
+    // the name of the bucket's organization, if not the storage's one
+    org: Option<String>,
+}
+
+// Parse the `routing` property: the buckets (and organizations) of the keys matching some key expressions
+fn get_routing_conf(config: Config, storage_name: &str) -> ZResult<Vec<RouteConf>> {
+    let routes = match config.get(PROP_STORAGE_ROUTING) {
+        None => return Ok(Vec::new()),
+        Some(serde_json::Value::Array(routes)) => routes,
+        Some(_) => bail!(
+            r#"`{}` property of InfluxDBv2 storage `{}` must be an array (e.g. [{{"key_expr": "robot/**", "bucket": "robots"}}])"#,
+            PROP_STORAGE_ROUTING,
+            storage_name
+        ),
+    };
+    let mut result = Vec::with_capacity(routes.len());
+    for route in routes {
+        let route = match route {
+            serde_json::Value::Object(route) => route,
+            _ => bail!(
+                "Invalid route {} in `{}` property of InfluxDBv2 storage `{}`: it must be an object",
+                route,
+                PROP_STORAGE_ROUTING,
+                storage_name
+            ),
+        };
+        let key_expr = match route.get("key_expr") {
+            Some(serde_json::Value::String(k)) => match OwnedKeyExpr::from_str(k) {
+                Ok(k) => k,
+                Err(e) => bail!(
+                    "Invalid `{}.key_expr` property of InfluxDBv2 storage `{}`: {}",
+                    PROP_STORAGE_ROUTING,
+                    storage_name,
+                    e
+                ),
+            },
+            _ => bail!(
+                "`{}.key_expr` property of InfluxDBv2 storage `{}` is required and must be a string",
+                PROP_STORAGE_ROUTING,
+                storage_name
+            ),
+        };
+        let bucket = match route.get("bucket") {
+            Some(serde_json::Value::String(b)) if !b.is_empty() => b.clone(),
+            _ => bail!(
+                "`{}.bucket` property of InfluxDBv2 storage `{}` is required and must be a non-empty string",
+                PROP_STORAGE_ROUTING,
+                storage_name
+            ),
+        };
+        let org = match route.get("org") {
+            None => None,
+            Some(serde_json::Value::String(o)) if !o.is_empty() => Some(o.clone()),
+            Some(_) => bail!(
+                "`{}.org` property of InfluxDBv2 storage `{}` must be a non-empty string",
+                PROP_STORAGE_ROUTING,
+                storage_name
+            ),
+        };
+        result.push(RouteConf {
+            key_expr,
+            bucket,
+            org,
+        });
+    }
+    Ok(result)
+}
+
+struct Route {
+    key_expr: OwnedKeyExpr,
+    client: Client,
+    bucket: String,
+    // if the bucket was created by the storage
+    created: bool,
+}
+
+// A token created for the storage's bucket (see `provision_credentials` property), deleted with the bucket
+struct ProvisionedToken {
+    url: String,
+    admin_token: String,
+    id: String,
+}
+
 struct InfluxDbStorage {
     config: StorageConfig,
     admin_client: Client,
     client: Client,
+    // the first route including a key gives its bucket (by default the storage's one)
+    routes: Vec<Route>,
     on_closure: OnClosure,
+    provisioned: Option<ProvisionedToken>,
     timer: Timer,
 }
 
 impl InfluxDbStorage {
+    // The client and bucket storing a measurement
+    fn bucket_of(&self, measurement: &str) -> ZResult<(&Client, String)> {
+        if let Ok(key) = keyexpr::new(measurement) {
+            if let Some(route) = self.routes.iter().find(|r| r.key_expr.includes(key)) {
+                return Ok((&route.client, route.bucket.clone()));
+            }
+        }
+        Ok((&self.client, get_db_name(self.config.clone())?))
+    }
+
+    // The clients and buckets to query for a key expression: the ones of the routes intersecting it,
+    // and the storage's one unless a route includes it
+    fn buckets_for(&self, key_expr: &str) -> ZResult<Vec<(&Client, String)>> {
+        let key_expr = match keyexpr::new(key_expr) {
+            Ok(ke) => ke,
+            Err(_) => return Ok(vec![(&self.client, get_db_name(self.config.clone())?)]),
+        };
+        let mut result: Vec<(&Client, String)> = self
+            .routes
+            .iter()
+            .filter(|r| r.key_expr.intersects(key_expr))
+            .map(|r| (&r.client, r.bucket.clone()))
+            .collect();
+        if !self.routes.iter().any(|r| r.key_expr.includes(key_expr)) {
+            result.push((&self.client, get_db_name(self.config.clone())?));
+        }
+        Ok(result)
+    }
+
     async fn get_deletion_timestamp(&self, measurement: &str) -> ZResult<Option<Timestamp>> {
-        let db = get_db_name(self.config.clone())?;
+        let (client, db) = self.bucket_of(measurement)?;
         let qs = format!(
             "from(bucket: \"{}\")
                                     |> range(start: {})
@@ -394,9 +676,7 @@ impl InfluxDbStorage {
         let query = Query::new(qs);
         let mut query_result: Vec<ZenohPoint> = vec![];
 
-        match async_std::task::block_on(async {
-            self.client.query::<ZenohPoint>(Some(query)).await
-        }) {
+        match async_std::task::block_on(async { client.query::<ZenohPoint>(Some(query)).await }) {
             Ok(result) => {
                 query_result = result;
             }
@@ -474,12 +754,19 @@ impl Storage for InfluxDbStorage {
 
         // Note: assume that uhlc timestamp was generated by a clock using UNIX_EPOCH (that's the case by default)
         // encode the value as a string to be stored in InfluxDB, converting to base64 if the buffer is not a UTF-8 string
-        let (base64, strvalue) = match String::from_utf8(value.payload.contiguous().into_owned()) {
-            Ok(s) => (false, s),
-            Err(err) => (true, b64_std_engine.encode(err.into_bytes())),
+        // (copying the payload only once: into the string, or while encoding it in base64)
+        let (base64, strvalue) = match value.payload.contiguous() {
+            Cow::Borrowed(bytes) => match std::str::from_utf8(bytes) {
+                Ok(s) => (false, s.to_string()),
+                Err(_) => (true, b64_std_engine.encode(bytes)),
+            },
+            Cow::Owned(bytes) => match String::from_utf8(bytes) {
+                Ok(s) => (false, s),
+                Err(err) => (true, b64_std_engine.encode(err.as_bytes())),
+            },
         };
 
-        let db = get_db_name(self.config.clone())?;
+        let (client, db) = self.bucket_of(measurement.as_str())?;
 
         // Note: tags are stored as strings in InfluxDB, while fileds are typed.
         // For simpler/faster deserialization, we store encoding, timestamp and base64 as fields.
@@ -494,7 +781,7 @@ impl Storage for InfluxDbStorage {
             .build()?];
 
         match async_std::task::block_on(async {
-            self.client.write(&db, stream::iter(zenoh_point)).await
+            client.write(&db, stream::iter(zenoh_point)).await
         }) {
             Ok(_) => Ok(StorageInsertionResult::Inserted),
             Err(e) => bail!(
@@ -515,7 +802,7 @@ impl Storage for InfluxDbStorage {
         // delete all points from the measurement that are older than this DELETE message
         // (in case more recent PUT have been recevived un-ordered)
 
-        let db = get_db_name(self.config.clone())?;
+        let (client, db) = self.bucket_of(measurement.as_str())?;
 
         let start_timestamp = NaiveDateTime::UNIX_EPOCH;
         let stop_timestamp = NaiveDateTime::from_timestamp_opt(
@@ -529,8 +816,7 @@ impl Storage for InfluxDbStorage {
             "Delete {:?} with Influx query in InfluxDBv2 storage",
             measurement
         );
-        if let Err(e) = self
-            .client
+        if let Err(e) = client
             .delete(&db, start_timestamp, stop_timestamp, predicate)
             .await
         {
@@ -558,7 +844,7 @@ impl Storage for InfluxDbStorage {
             stop_timestamp
         );
 
-        if let Err(e) = self.client.write(&db, stream::iter(zenoh_point)).await {
+        if let Err(e) = client.write(&db, stream::iter(zenoh_point)).await {
             bail!(
                 "Failed to mark measurement {:?} as deleted : {} in InfluxDBv2 storage",
                 measurement,
@@ -581,8 +867,6 @@ impl Storage for InfluxDbStorage {
             None => OwnedKeyExpr::from_str(NONE_KEY)?,
         };
 
-        let db = get_db_name(self.config.clone())?;
-
         // the expected JSon type resulting from the query
         // #[derive(Deserialize, Debug)]
         #[derive(Debug, Default, FromDataPoint)]
@@ -596,22 +880,21 @@ impl Storage for InfluxDbStorage {
             base64: bool,
             value: String,
         }
-        #[allow(unused_assignments)]
-        let mut qs: String = String::new();
+        let time_range = time_from_parameters(parameters)?;
 
-        match time_from_parameters(parameters)? {
-            Some((start, stop)) => {
-                qs = format!(
+        // query each bucket the key may be routed to, and merge the results
+        let mut query_result: Vec<ZenohPoint> = vec![];
+        for (client, db) in self.buckets_for(measurement.as_str())? {
+            let qs = match time_range {
+                Some((start, stop)) => format!(
                     "from(bucket: \"{}\")
                                             |> range(start: {}, stop: {})
                                             |> filter(fn: (r) => r._measurement == \"{}\")
                                             |> filter(fn: (r) => r[\"kind\"] == \"PUT\")
                                         ",
                     db, start, stop, measurement
-                );
-            }
-            None => {
-                qs = format!(
+                ),
+                None => format!(
                     "from(bucket: \"{}\")
                                             |> range(start: {})
                                             |> filter(fn: (r) => r._measurement == \"{}\")
@@ -619,33 +902,30 @@ impl Storage for InfluxDbStorage {
                                             |> last()
                                         ",
                     db, 0, measurement
-                );
-            }
-        }
-
-        log::debug!(
-            "Get {:?} with Influx query:{} in InfluxDBv2 storage",
-            key,
-            qs
-        );
+                ),
+            };
 
-        let query = Query::new(qs);
-        let mut query_result: Vec<ZenohPoint> = vec![];
+            log::debug!(
+                "Get {:?} with Influx query:{} in InfluxDBv2 storage",
+                key,
+                qs
+            );
 
-        match async_std::task::block_on(async {
-            self.client.query::<ZenohPoint>(Some(query)).await
-        }) {
-            Ok(result) => {
-                query_result = result;
-            }
-            Err(e) => {
-                log::error!(
-                    "Couldn't get data from database {} in InfluxDBv2 storage with error: {} ",
-                    db,
-                    e
-                );
-            }
-        };
+            let query = Query::new(qs);
+            match async_std::task::block_on(async { client.query::<ZenohPoint>(Some(query)).await })
+            {
+                Ok(result) => {
+                    query_result.extend(result);
+                }
+                Err(e) => {
+                    log::error!(
+                        "Couldn't get data from database {} in InfluxDBv2 storage with error: {} ",
+                        db,
+                        e
+                    );
+                }
+            };
+        }
         let mut result: Vec<StoredData> = vec![];
 
         for zpoint in query_result {
@@ -697,6 +977,11 @@ impl Storage for InfluxDbStorage {
             let value = Value::new(payload).encoding(encoding);
             result.push(StoredData { value, timestamp });
         }
+        // without time range, only the latest of the points of the queried buckets
+        if time_range.is_none() && result.len() > 1 {
+            result.sort_by_key(|d| d.timestamp);
+            result.drain(..result.len() - 1);
+        }
         Ok(result)
     }
 
@@ -728,30 +1013,51 @@ impl Drop for InfluxDbStorage {
                 return;
             }
         };
+        // the storage's bucket, and the routes' buckets it created
+        let storage: &Self = self;
+        let mut buckets = vec![(&storage.client, db.clone())];
+        for route in storage.routes.iter().filter(|r| r.created) {
+            if !buckets.iter().any(|(_, b)| *b == route.bucket) {
+                buckets.push((&route.client, route.bucket.clone()));
+            }
+        }
 
-        match self.on_closure {
+        match storage.on_closure {
             OnClosure::DropDb => {
                 task::block_on(async move {
-                    log::debug!("Close InfluxDBv2 storage, dropping database {}", db);
-                    if let Err(e) = self.admin_client.delete_bucket(&db).await {
-                        log::error!("Failed to drop InfluxDbv2 database '{}' : {}", db, e)
+                    for (_, bucket) in buckets {
+                        log::debug!("Close InfluxDBv2 storage, dropping database {}", bucket);
+                        if let Err(e) = storage.admin_client.delete_bucket(&bucket).await {
+                            log::error!("Failed to drop InfluxDbv2 database '{}' : {}", bucket, e)
+                        }
+                    }
+                    if let Some(provisioned) = &storage.provisioned {
+                        if let Err(e) = delete_token(provisioned).await {
+                            log::error!(
+                                "Failed to delete InfluxDbv2 token of database '{}' : {}",
+                                db,
+                                e
+                            )
+                        }
                     }
                 });
             }
             OnClosure::DropSeries => {
                 task::block_on(async move {
-                    log::debug!(
-                        "Close InfluxDBv2 storage, dropping all series from database {}",
-                        db
-                    );
-                    let start = NaiveDateTime::MIN;
-                    let stop = NaiveDateTime::MAX;
-                    if let Err(e) = self.client.delete(&db, start, stop, None).await {
-                        log::error!(
-                            "Failed to drop all series from InfluxDbv2 database '{}' : {}",
-                            db,
-                            e
-                        )
+                    for (client, bucket) in buckets {
+                        log::debug!(
+                            "Close InfluxDBv2 storage, dropping all series from database {}",
+                            bucket
+                        );
+                        let start = NaiveDateTime::MIN;
+                        let stop = NaiveDateTime::MAX;
+                        if let Err(e) = client.delete(&bucket, start, stop, None).await {
+                            log::error!(
+                                "Failed to drop all series from InfluxDbv2 database '{}' : {}",
+                                bucket,
+                                e
+                            )
+                        }
                     }
                 });
             }
@@ -795,13 +1101,161 @@ async fn is_db_existing(client: &Client, db: &str) -> ZResult<bool> {
     }
 }
 
-async fn create_db(client: &Client, org_id: &str, db: &str) -> ZResult<bool> {
-    let result = client
-        .create_bucket(Some(PostBucketRequest::new(
-            org_id.to_owned(),
-            db.to_owned(),
-        )))
-        .await;
+// Check if a bucket exists and, if not and `create` is true, create it using the admin client,
+// in the organization with the `org` name if any, or else in the `default_org_id` one.
+// Returns true if the bucket was created.
+async fn ensure_db(
+    admin_client: &Client,
+    client: &Client,
+    db: &str,
+    create: bool,
+    org: Option<&str>,
+    default_org_id: &str,
+    retention_seconds: i32,
+) -> ZResult<bool> {
+    match is_db_existing(client, db).await {
+        Ok(true) => Ok(false),
+        Ok(false) if create => {
+            let org_id = match org {
+                Some(name) => resolve_org_id(admin_client, name).await?,
+                None => default_org_id.to_owned(),
+            };
+            match create_db(admin_client, &org_id, db, retention_seconds).await {
+                Ok(true) => Ok(true),
+                Ok(false) => bail!("Database '{}' wasnt't created in InfluxDBv2 storage", db),
+                Err(e) => bail!("Failed to create InfluxDBv2 Storage : {:?}", e),
+            }
+        }
+        Ok(false) => Ok(false),
+        Err(e) => bail!("Failed to create InfluxDBv2 Storage : {:?}", e),
+    }
+}
+
+// Create a token only allowed to read and write a bucket (with the authorizations API, not exposed by
+// the influxdb2 client), returning its ID and value
+async fn provision_token(
+    admin_client: &Client,
+    url: &str,
+    admin_token: &str,
+    db: &str,
+) -> ZResult<(String, String)> {
+    let request = ListBucketsRequest {
+        name: Some(db.to_owned()),
+        ..ListBucketsRequest::default()
+    };
+    let bucket = admin_client
+        .list_buckets(Some(request))
+        .await?
+        .buckets
+        .into_iter()
+        .find(|b| b.name == db);
+    let (bucket_id, org_id) = match bucket {
+        Some(b) => match (b.id, b.org_id) {
+            (Some(id), Some(org_id)) => (id, org_id),
+            _ => bail!("InfluxDBv2 bucket '{}' has no ID or organization ID", db),
+        },
+        None => bail!("InfluxDBv2 bucket '{}' not found", db),
+    };
+    let permission = |action: &str| {
+        serde_json::json!({
+            "action": action,
+            "resource": {"type": "buckets", "id": bucket_id, "orgID": org_id},
+        })
+    };
+    let body = serde_json::json!({
+        "orgID": org_id,
+        "description": format!("zenoh storage on bucket {db}"),
+        "permissions": [permission("read"), permission("write")],
+    });
+    let response = reqwest::Client::new()
+        .post(format!(
+            "{}/api/v2/authorizations",
+            url.trim_end_matches('/')
+        ))
+        .header("Authorization", format!("Token {admin_token}"))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| {
+            zerror!(
+                "Failed to create InfluxDBv2 token for bucket '{}': {}",
+                db,
+                e
+            )
+        })?;
+    if !response.status().is_success() {
+        bail!(
+            "Failed to create InfluxDBv2 token for bucket '{}': {}",
+            db,
+            response.status()
+        )
+    }
+    let authorization: serde_json::Value = response.json().await.map_err(|e| {
+        zerror!(
+            "Invalid InfluxDBv2 authorization for bucket '{}': {}",
+            db,
+            e
+        )
+    })?;
+    match (
+        authorization.get("id").and_then(|v| v.as_str()),
+        authorization.get("token").and_then(|v| v.as_str()),
+    ) {
+        (Some(id), Some(token)) => {
+            log::debug!("Created InfluxDBv2 token {} for bucket {}", id, db);
+            Ok((id.to_owned(), token.to_owned()))
+        }
+        _ => bail!("Invalid InfluxDBv2 authorization for bucket '{}'", db),
+    }
+}
+
+async fn delete_token(provisioned: &ProvisionedToken) -> ZResult<()> {
+    let response = reqwest::Client::new()
+        .delete(format!(
+            "{}/api/v2/authorizations/{}",
+            provisioned.url.trim_end_matches('/'),
+            provisioned.id
+        ))
+        .header(
+            "Authorization",
+            format!("Token {}", provisioned.admin_token),
+        )
+        .send()
+        .await
+        .map_err(|e| zerror!("{}", e))?;
+    if !response.status().is_success() {
+        bail!("{}", response.status())
+    }
+    Ok(())
+}
+
+// Returns the ID of the organization with this name
+async fn resolve_org_id(client: &Client, org: &str) -> ZResult<String> {
+    let request = ListOrganizationRequest {
+        org: Some(org.to_owned()),
+        ..ListOrganizationRequest::default()
+    };
+    let orgs = client.list_organizations(request).await?.orgs;
+    match orgs.into_iter().find(|o| o.name == org).and_then(|o| o.id) {
+        Some(id) => Ok(id),
+        None => bail!("InfluxDBv2 organization '{}' not found", org),
+    }
+}
+
+async fn create_db(
+    client: &Client,
+    org_id: &str,
+    db: &str,
+    retention_seconds: i32,
+) -> ZResult<bool> {
+    let mut request = PostBucketRequest::new(org_id.to_owned(), db.to_owned());
+    if retention_seconds > 0 {
+        request.retention_rules = vec![RetentionRule::new(
+            RetentionRuleType::Expire,
+            retention_seconds,
+        )];
+    }
+    let result = client.create_bucket(Some(request)).await;
     match result {
         Ok(_) => Ok(true),
         Err(_) => Ok(false), //can post error here
@@ -0,0 +1,199 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// Tests of the storages against an InfluxDB 2.x server started in a docker container (with testcontainers).
+// Needing docker, they're ignored by default: run them with
+// `cargo test -p zenoh-backend-influxdb-v2 --test influxdb2 -- --ignored`
+use serde_json::json;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use testcontainers::{clients::Cli, Container, GenericImage};
+use zenoh::buffers::buffer::SplitBuffer;
+use zenoh::prelude::*;
+use zenoh::time::{new_reception_timestamp, Timestamp, NTP64};
+use zenoh_backend_influxdb2::InfluxDbBackend;
+use zenoh_backend_traits::config::{GarbageCollectionConfig, StorageConfig, VolumeConfig};
+use zenoh_backend_traits::{Storage, StorageInsertionResult, Volume};
+use zenoh_plugin_trait::Plugin;
+
+const INFLUXDB_PORT: u16 = 8086;
+const ORG: &str = "zenoh";
+const TOKEN: &str = "zenoh-test-token";
+
+// An InfluxDB 2.x server set up with the ORG organization and the TOKEN admin token, its URL and the ORG's id
+fn server(docker: &Cli) -> (Container<'_, GenericImage>, String, String) {
+    let image = GenericImage::new("influxdb", "2.7")
+        .with_exposed_port(INFLUXDB_PORT)
+        .with_env_var("DOCKER_INFLUXDB_INIT_MODE", "setup")
+        .with_env_var("DOCKER_INFLUXDB_INIT_USERNAME", "zenoh")
+        .with_env_var("DOCKER_INFLUXDB_INIT_PASSWORD", "zenoh-password")
+        .with_env_var("DOCKER_INFLUXDB_INIT_ORG", ORG)
+        .with_env_var("DOCKER_INFLUXDB_INIT_BUCKET", "zenoh")
+        .with_env_var("DOCKER_INFLUXDB_INIT_ADMIN_TOKEN", TOKEN);
+    let container = docker.run(image);
+    let url = format!(
+        "http://127.0.0.1:{}",
+        container.get_host_port_ipv4(INFLUXDB_PORT)
+    );
+    // the setup is done when the organization can be read with the admin token
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let org_id = tokio_runtime().block_on(async {
+        let client = reqwest::Client::new();
+        loop {
+            let reply = client
+                .get(format!("{url}/api/v2/orgs?org={ORG}"))
+                .header("Authorization", format!("Token {TOKEN}"))
+                .send()
+                .await;
+            match reply {
+                Ok(r) if r.status().is_success() => {
+                    let orgs: serde_json::Value = r.json().await.unwrap();
+                    break orgs["orgs"][0]["id"].as_str().unwrap().to_string();
+                }
+                _ if Instant::now() < deadline => {
+                    tokio::time::sleep(Duration::from_millis(500)).await
+                }
+                r => panic!("InfluxDB at {url} is not set up: {r:?}"),
+            }
+        }
+    });
+    (container, url, org_id)
+}
+
+// The backend's HTTP client (reqwest) needs a tokio runtime to be entered
+fn tokio_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn volume(url: &str, org_id: &str) -> Box<dyn Volume> {
+    let mut rest = serde_json::Map::new();
+    rest.insert("url".into(), url.into());
+    rest.insert("org_id".into(), org_id.into());
+    rest.insert("token".into(), TOKEN.into());
+    let config = VolumeConfig {
+        name: "influxdb2".into(),
+        backend: None,
+        paths: None,
+        required: true,
+        rest,
+    };
+    InfluxDbBackend::start("influxdb2", &config).unwrap()
+}
+
+// A storage of "demo/**" in its own bucket (dropped on closure), with additional properties
+async fn storage(volume: &dyn Volume, db: &str, properties: serde_json::Value) -> Box<dyn Storage> {
+    let mut volume_cfg = json!({"db": db, "create_db": true, "on_closure": "drop_db"});
+    for (name, value) in properties.as_object().unwrap() {
+        volume_cfg[name] = value.clone();
+    }
+    volume
+        .create_storage(StorageConfig {
+            name: db.to_string(),
+            key_expr: OwnedKeyExpr::from_str("demo/**").unwrap(),
+            complete: false,
+            strip_prefix: None,
+            volume_id: "influxdb2".to_string(),
+            volume_cfg,
+            garbage_collection_config: GarbageCollectionConfig::default(),
+            replica_config: None,
+        })
+        .await
+        .unwrap()
+}
+
+fn key(k: &str) -> Option<OwnedKeyExpr> {
+    Some(OwnedKeyExpr::from_str(k).unwrap())
+}
+
+fn later(timestamp: &Timestamp, secs: u64) -> Timestamp {
+    let time = timestamp.get_time().to_duration() + Duration::from_secs(secs);
+    Timestamp::new(NTP64::from(time), *timestamp.get_id())
+}
+
+// The payloads replied to a GET, sorted
+async fn get(storage: &mut Box<dyn Storage>, key_expr: &str) -> Vec<String> {
+    let mut values: Vec<String> = storage
+        .get(key(key_expr), "")
+        .await
+        .unwrap()
+        .iter()
+        .map(|d| String::from_utf8(d.value.payload.contiguous().to_vec()).unwrap())
+        .collect();
+    values.sort();
+    values
+}
+
+#[test]
+#[ignore = "needs docker"]
+fn put_get_delete() {
+    let docker = Cli::default();
+    let (_container, url, org_id) = server(&docker);
+    let runtime = tokio_runtime();
+    let _guard = runtime.enter();
+    let volume = volume(&url, &org_id);
+    async_std::task::block_on(async {
+        let mut storage = storage(&*volume, "put_get_delete", json!({})).await;
+        let timestamp = new_reception_timestamp();
+        for (k, v) in [("demo/a", "hello"), ("demo/b", "world")] {
+            assert!(matches!(
+                storage.put(key(k), Value::from(v), timestamp).await,
+                Ok(StorageInsertionResult::Inserted)
+            ));
+        }
+        assert_eq!(get(&mut storage, "demo/a").await, ["hello"]);
+        assert_eq!(get(&mut storage, "demo/*").await, ["hello", "world"]);
+
+        let deletion = later(&timestamp, 1);
+        assert!(matches!(
+            storage.delete(key("demo/a"), deletion).await,
+            Ok(StorageInsertionResult::Deleted)
+        ));
+        assert!(get(&mut storage, "demo/a").await.is_empty());
+        assert_eq!(get(&mut storage, "demo/*").await, ["world"]);
+        // the deletion blocks the older values
+        assert!(matches!(
+            storage
+                .put(key("demo/a"), Value::from("old"), timestamp)
+                .await,
+            Ok(StorageInsertionResult::Outdated)
+        ));
+    });
+}
+
+#[test]
+#[ignore = "needs docker"]
+fn bucket_in_a_named_org() {
+    let docker = Cli::default();
+    let (_container, url, org_id) = server(&docker);
+    let runtime = tokio_runtime();
+    let _guard = runtime.enter();
+    let volume = volume(&url, &org_id);
+    async_std::task::block_on(async {
+        // the `org` property is resolved to the id of an organization (here the volume's one, by its name)
+        let properties = json!({"org": ORG});
+        let mut storage = storage(&*volume, "bucket_in_a_named_org", properties).await;
+        storage
+            .put(
+                key("demo/a"),
+                Value::from("hello"),
+                new_reception_timestamp(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(get(&mut storage, "demo/a").await, ["hello"]);
+    });
+}